@@ -0,0 +1,146 @@
+//! What ScreenCaptureKit records: a whole display or a single window. The
+//! window's app is what's heard, and with `capture_video` only the window is
+//! filmed.
+
+use recorder_core::RecorderError;
+use screencapturekit::prelude::*;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Bounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl From<CGRect> for Bounds {
+    fn from(rect: CGRect) -> Self {
+        Self {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayInfo {
+    id: u32,
+    title: String,
+    bounds: Bounds,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowInfo {
+    id: u32,
+    title: String,
+    app: String,
+    bounds: Bounds,
+}
+
+/// A stream filter for the chosen target, the size video is captured at and
+/// what recordings of it are grouped by.
+pub struct Target {
+    pub filter: SCContentFilter,
+    pub width: u32,
+    pub height: u32,
+    /// The window's app, `display` for a display
+    pub profile: String,
+}
+
+/// The display or window to capture, the main display when neither is given.
+pub fn resolve(display_id: Option<u32>, window_id: Option<u32>) -> Result<Target, RecorderError> {
+    let content =
+        SCShareableContent::get().map_err(|e| RecorderError::CaptureFailed(e.to_string()))?;
+    if let Some(window_id) = window_id {
+        if display_id.is_some() {
+            return Err(RecorderError::InvalidArgument(
+                "Pick either a display or a window".to_string(),
+            ));
+        }
+        let window = content
+            .windows()
+            .into_iter()
+            .find(|window| window.window_id() == window_id)
+            .ok_or_else(|| RecorderError::NotFound("That window is gone".to_string()))?;
+        let frame = window.frame();
+        return Ok(Target {
+            filter: SCContentFilter::create().with_window(&window).build(),
+            width: frame.width as u32,
+            height: frame.height as u32,
+            profile: window
+                .owning_application()
+                .map(|app| app.application_name())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "window".to_string()),
+        });
+    }
+
+    let displays = content.displays();
+    let display = match display_id {
+        Some(id) => displays
+            .into_iter()
+            .find(|display| display.display_id() == id),
+        None => displays.into_iter().next(),
+    }
+    .ok_or_else(|| RecorderError::DeviceNotFound("No display found".to_string()))?;
+    Ok(Target {
+        width: display.width(),
+        height: display.height(),
+        filter: SCContentFilter::create()
+            .with_display(&display)
+            .with_excluding_windows(&[])
+            .build(),
+        profile: "display".to_string(),
+    })
+}
+
+#[tauri::command]
+pub fn list_displays() -> Result<Vec<DisplayInfo>, RecorderError> {
+    let content =
+        SCShareableContent::get().map_err(|e| RecorderError::CaptureFailed(e.to_string()))?;
+    Ok(content
+        .displays()
+        .into_iter()
+        .enumerate()
+        .map(|(index, display)| DisplayInfo {
+            id: display.display_id(),
+            title: format!(
+                "Display {} ({}×{})",
+                index + 1,
+                display.width(),
+                display.height()
+            ),
+            bounds: display.frame().into(),
+        })
+        .collect())
+}
+
+/// On-screen windows with a title, sorted by app.
+#[tauri::command]
+pub fn list_windows() -> Result<Vec<WindowInfo>, RecorderError> {
+    let content =
+        SCShareableContent::get().map_err(|e| RecorderError::CaptureFailed(e.to_string()))?;
+    let mut windows: Vec<WindowInfo> = content
+        .windows()
+        .into_iter()
+        .filter(|window| window.is_on_screen())
+        .filter_map(|window| {
+            // Menu bar items and other chrome come without a title
+            let title = window.title().filter(|title| !title.is_empty())?;
+            Some(WindowInfo {
+                id: window.window_id(),
+                title,
+                app: window
+                    .owning_application()
+                    .map(|app| app.application_name())
+                    .unwrap_or_default(),
+                bounds: window.frame().into(),
+            })
+        })
+        .collect();
+    windows.sort_by_key(|window| (window.app.to_lowercase(), window.title.to_lowercase()));
+    Ok(windows)
+}
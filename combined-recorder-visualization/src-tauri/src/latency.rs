@@ -0,0 +1,95 @@
+//! How far the mic lags system audio, kept in `latency.json` in the app
+//! config dir and applied to every recording. Set by hand or measured with
+//! `calibrate_latency`, which plays a clap through the speakers.
+
+use crate::{capture_target, AppState};
+use parking_lot::Mutex;
+use recorder_core::{align, CpalSource, Latency, RecorderError, ScreenCaptureSource};
+use screencapturekit::prelude::*;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+pub struct LatencyStore {
+    store: PathBuf,
+    latency: Mutex<Latency>,
+}
+
+impl LatencyStore {
+    pub fn load(app: &AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_config_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("latency.json");
+        let latency = std::fs::read_to_string(&store)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            latency: Mutex::new(latency),
+        }
+    }
+
+    pub fn get(&self) -> Latency {
+        *self.latency.lock()
+    }
+
+    fn set(&self, latency: Latency) -> Result<(), String> {
+        if let Some(dir) = self.store.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(&latency).map_err(|e| e.to_string())?;
+        std::fs::write(&self.store, json).map_err(|e| e.to_string())?;
+        *self.latency.lock() = latency;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn get_latency(store: State<'_, LatencyStore>) -> Latency {
+    store.get()
+}
+
+/// Applies from the next recording on.
+#[tauri::command]
+pub fn set_latency(store: State<'_, LatencyStore>, latency: Latency) -> Result<(), RecorderError> {
+    if !latency.system_ms.is_finite() || !latency.mic_ms.is_finite() {
+        return Err(RecorderError::InvalidArgument(
+            "Latency has to be a number of milliseconds".to_string(),
+        ));
+    }
+    if latency.system_ms < 0.0 || latency.mic_ms < 0.0 {
+        return Err(RecorderError::InvalidArgument(
+            "Latency can't be negative".to_string(),
+        ));
+    }
+    store.set(latency).map_err(RecorderError::IoError)
+}
+
+/// Measures and saves the latency. Takes about two seconds with the
+/// speakers on, and can't run while recording.
+#[tauri::command]
+pub async fn calibrate_latency(app: AppHandle) -> Result<Latency, RecorderError> {
+    if app.state::<AppState>().0.lock().core.is_recording() {
+        return Err(RecorderError::Busy(
+            "Stop recording before calibrating".to_string(),
+        ));
+    }
+    let target = capture_target::resolve(None, None)?;
+    let config =
+        ScreenCaptureSource::configure(SCStreamConfiguration::new(), recorder_core::SAMPLE_RATE);
+    let system = ScreenCaptureSource::new(&target.filter, &config, recorder_core::SAMPLE_RATE);
+    let mic = CpalSource::default_input(recorder_core::SAMPLE_RATE)
+        .map_err(RecorderError::DeviceNotFound)?;
+
+    let latency = tauri::async_runtime::spawn_blocking(move || {
+        align::calibrate(Box::new(system), Box::new(mic))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    app.state::<LatencyStore>()
+        .set(latency)
+        .map_err(RecorderError::IoError)?;
+    Ok(latency)
+}
@@ -1,38 +1,62 @@
+mod capture_target;
+mod latency;
+mod output;
+mod peaks;
+mod status;
+mod tray;
+mod video;
+
 use anyhow::Result;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use hound::{WavSpec, WavWriter};
 use parking_lot::Mutex;
+use recorder_core::{
+    loudness, recording_lock, AudioLevels, BufferLimits, BufferOverrun, CpalSource,
+    FinalizeOptions, MixFn, Mixer, OverflowPolicy, RecorderError, RecordingOptions,
+    ScreenCaptureSource, WavFile,
+};
 use screencapturekit::prelude::*;
 use serde::Serialize;
-use std::collections::VecDeque;
-use std::fs::File;
-use std::io::BufWriter;
-use std::path::PathBuf;
+use status::{RecorderStatus, StatusMachine};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager, State};
 
 #[derive(Debug, Clone, Serialize)]
-struct AudioLevels {
-    mic_level: f32,
-    system_level: f32,
-    mixed_level: f32,
+struct PauseState {
+    paused: bool,
+    // Time that made it into the file, pauses excluded
+    elapsed_ms: u64,
+    paused_ms: u64,
+    // None while recording
+    reason: Option<PauseReason>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum PauseReason {
+    /// From the window or the tray
+    User,
+    /// A source stalled under `OverflowPolicy::AutoPause`
+    BufferOverrun(BufferOverrun),
 }
 
 struct SharedRecorder {
-    system_stream: Option<SCStream>,
-    mic_stream: Option<cpal::Stream>,
-    file_path: Option<PathBuf>,
-    writer: Option<Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>>,
-
-    // Buffers for mixing
-    system_buffer: Arc<Mutex<VecDeque<f32>>>,
-    mic_buffer: Arc<Mutex<VecDeque<f32>>>,
-
-    // Level tracking for visualization
-    system_level: Arc<Mutex<f32>>,
-    mic_level: Arc<Mutex<f32>>,
-    last_levels_update: Arc<Mutex<Instant>>,
+    // Sources, mixer and file, levels included
+    core: recorder_core::Recorder,
+
+    // Held for the whole recording so other playground apps keep off the mic
+    recording_lock: Option<recording_lock::RecordingLock>,
+
+    // Pause tracking, the streams keep running while paused
+    paused: Arc<AtomicBool>,
+    started_at: Option<Instant>,
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+    pause_reason: Option<PauseReason>,
+
+    // Screen video encoder, with `capture_video`
+    video: Option<video::VideoRecorder>,
 }
 
 pub struct AppState(Mutex<SharedRecorder>);
@@ -40,310 +64,385 @@ pub struct AppState(Mutex<SharedRecorder>);
 impl AppState {
     pub fn new() -> Self {
         Self(Mutex::new(SharedRecorder {
-            system_stream: None,
-            mic_stream: None,
-            file_path: None,
-            writer: None,
-            system_buffer: Arc::new(Mutex::new(VecDeque::new())),
-            mic_buffer: Arc::new(Mutex::new(VecDeque::new())),
-            system_level: Arc::new(Mutex::new(0.0)),
-            mic_level: Arc::new(Mutex::new(0.0)),
-            last_levels_update: Arc::new(Mutex::new(Instant::now())),
+            core: recorder_core::Recorder::default(),
+            recording_lock: None,
+            paused: Arc::new(AtomicBool::new(false)),
+            started_at: None,
+            paused_at: None,
+            paused_total: Duration::ZERO,
+            pause_reason: None,
+            video: None,
         }))
     }
 }
 
-struct Mixer {
-    system_buffer: Arc<Mutex<VecDeque<f32>>>,
-    mic_buffer: Arc<Mutex<VecDeque<f32>>>,
-    writer: Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>,
-    app_handle: AppHandle,
-    system_level: Arc<Mutex<f32>>,
-    mic_level: Arc<Mutex<f32>>,
-    last_levels_update: Arc<Mutex<Instant>>,
-}
-
-impl Mixer {
-    fn mix_available(&self) {
-        let mut sys = self.system_buffer.lock();
-        let mut mic = self.mic_buffer.lock();
-        let mut writer_lock = self.writer.lock();
-
-        if let Some(writer) = writer_lock.as_mut() {
-            let mut mixed_sum = 0.0f32;
-            let mut mixed_count = 0u32;
-
-            while sys.len() >= 2 && mic.len() >= 2 {
-                let s1 = sys.pop_front().unwrap();
-                let s2 = sys.pop_front().unwrap();
-                let m1 = mic.pop_front().unwrap();
-                let m2 = mic.pop_front().unwrap();
-
-                let mixed_1 = (s1 + m1) / 2.0;
-                let mixed_2 = (s2 + m2) / 2.0;
-
-                mixed_sum += (mixed_1 * mixed_1 + mixed_2 * mixed_2) / 2.0;
-                mixed_count += 1;
-
-                let _ = writer.write_sample(mixed_1);
-                let _ = writer.write_sample(mixed_2);
-            }
-
-            // Emit audio levels every 50ms
-            if mixed_count > 0 {
-                let mut last_update = self.last_levels_update.lock();
-                if last_update.elapsed() >= Duration::from_millis(50) {
-                    let mixed_rms = (mixed_sum / mixed_count as f32).sqrt();
-                    let mic_rms = *self.mic_level.lock();
-                    let sys_rms = *self.system_level.lock();
-
-                    let levels = AudioLevels {
-                        mic_level: mic_rms,
-                        system_level: sys_rms,
-                        mixed_level: mixed_rms,
-                    };
-
-                    let _ = self.app_handle.emit("audio-levels", &levels);
-                    *last_update = Instant::now();
-                }
-            }
+impl SharedRecorder {
+    fn pause_state(&self) -> PauseState {
+        let current_pause = self.paused_at.map_or(Duration::ZERO, |at| at.elapsed());
+        let paused = self.paused_total + current_pause;
+        let total = self.started_at.map_or(Duration::ZERO, |at| at.elapsed());
+        PauseState {
+            paused: self.paused_at.is_some(),
+            elapsed_ms: total.saturating_sub(paused).as_millis() as u64,
+            paused_ms: paused.as_millis() as u64,
+            reason: self.pause_reason.clone(),
         }
     }
 }
 
-struct SystemAudioOutputHandler {
-    buffer: Arc<Mutex<VecDeque<f32>>>,
-    mixer_trigger: Arc<Mixer>,
-    system_level: Arc<Mutex<f32>>,
-}
+/// Writes the mix, counting its frames into `written`, and reports levels,
+/// peaks, `buffer-overrun` and `clipping-detected` as audio comes in. An
+/// overrun under `OverflowPolicy::AutoPause` pauses, once however many
+/// follow.
+fn mix_fn(
+    app: &AppHandle,
+    paused: Arc<AtomicBool>,
+    sample_rate: u32,
+    written: Arc<AtomicU64>,
+) -> MixFn {
+    let app_handle = app.clone();
+    let mut peaks = peaks::PeakAggregator::new(sample_rate);
+    // Set from spawning the pause until it has happened
+    let mut pausing = false;
+    Box::new(move |mixer: &Mixer, writer: &mut WavFile| {
+        // Audio captured while paused is thrown away so resuming picks up live
+        if paused.load(Ordering::Relaxed) {
+            pausing = false;
+            mixer.clear();
+            return;
+        }
+        mixer.mix_into(writer, |system, mic, mixed| {
+            peaks.push(system, mic, mixed);
+            written.fetch_add(1, Ordering::Relaxed);
+        });
 
-impl SCStreamOutputTrait for SystemAudioOutputHandler {
-    fn did_output_sample_buffer(&self, sample: CMSampleBuffer, of_type: SCStreamOutputType) {
-        if let SCStreamOutputType::Audio = of_type {
-            if let Some(buffer_list) = sample.audio_buffer_list() {
-                let mut samples = Vec::new();
-                let num_buffers = buffer_list.num_buffers();
-
-                if num_buffers == 1 {
-                    let buffer = buffer_list.get(0).unwrap();
-                    let data = buffer.data();
-                    let f32_samples: &[f32] = unsafe {
-                        std::slice::from_raw_parts(data.as_ptr() as *const f32, data.len() / 4)
-                    };
-                    samples.extend_from_slice(f32_samples);
-                } else {
-                    let mut channel_data = Vec::new();
-                    for i in 0..num_buffers {
-                        let buffer = buffer_list.get(i).unwrap();
-                        let data = buffer.data();
-                        let f32_samples: &[f32] = unsafe {
-                            std::slice::from_raw_parts(data.as_ptr() as *const f32, data.len() / 4)
-                        };
-                        channel_data.push(f32_samples);
-                    }
-                    if !channel_data.is_empty() {
-                        let len = channel_data[0].len();
-                        for i in 0..len {
-                            for channel in &channel_data {
-                                if i < channel.len() {
-                                    samples.push(channel[i]);
-                                }
-                            }
-                        }
-                    }
-                }
-
-                if !samples.is_empty() {
-                    // Track system audio RMS level
-                    let sum: f32 = samples.iter().map(|s| s * s).sum();
-                    let rms = (sum / samples.len() as f32).sqrt();
-                    *self.system_level.lock() = rms;
-
-                    self.buffer.lock().extend(samples);
-                    self.mixer_trigger.mix_available();
-                }
+        if let Some(waveform) = peaks.take_ready() {
+            let _ = app_handle.emit("audio-waveform", &waveform);
+        }
+        if let Some(levels) = mixer.levels().report() {
+            let _ = app_handle.emit("audio-levels", &levels);
+        }
+        if let Some(clipping) = mixer.clipping() {
+            let _ = app_handle.emit("clipping-detected", &clipping);
+        }
+        for overrun in mixer.overruns() {
+            let _ = app_handle.emit("buffer-overrun", &overrun);
+            if overrun.policy == OverflowPolicy::AutoPause && !pausing {
+                pausing = true;
+                // Not from the mixing thread, stopping waits for it
+                let app = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let state = app.state::<AppState>();
+                    let status = app.state::<StatusMachine>();
+                    let _ = pause(&app, &state, &status, PauseReason::BufferOverrun(overrun));
+                });
             }
         }
-    }
+    })
 }
 
+/// Starts recording mic and system audio. With `capture_video` the screen is
+/// recorded too, and stopping produces an MP4 with the mixed audio. Captures
+/// the main display unless a `display_id` or `window_id` from
+/// `list_displays` / `list_windows` is given. `overflow_policy` decides what
+/// happens when one source stalls, dropping the oldest audio by default.
+/// `options` picks the file's bit depth, sample rate and channels, 32-bit
+/// float 48 kHz stereo by default and 16-bit for mono voice memos, and with
+/// `limiter` whether loud mixes are turned down rather than clipped.
 #[tauri::command]
-async fn start_recording(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
-    let mut recorder = state.0.lock();
-    if recorder.system_stream.is_some() || recorder.mic_stream.is_some() {
-        return Err("Already recording".to_string());
-    }
-
-    // --- SETUP WAV WRITER ---
-    let audio_dir = app.path().app_data_dir().unwrap_or_else(|_| PathBuf::from("."));
-    std::fs::create_dir_all(&audio_dir).map_err(|e| e.to_string())?;
-    let file_path = audio_dir.join("combined_audio.wav");
-
-    let spec = WavSpec {
-        channels: 2,
-        sample_rate: 48000,
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
-    };
+async fn start_recording(
+    app: AppHandle,
+    status: State<'_, StatusMachine>,
+    capture_video: Option<bool>,
+    display_id: Option<u32>,
+    window_id: Option<u32>,
+    overflow_policy: Option<OverflowPolicy>,
+    options: Option<RecordingOptions>,
+) -> Result<String, RecorderError> {
+    status.begin_start(&app)?;
+    // Looking up the capture target, probing devices and creating files
+    // block, so not on the command thread
+    let start_app = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        start(
+            &start_app,
+            &mut start_app.state::<AppState>().0.lock(),
+            capture_video.unwrap_or(false),
+            display_id,
+            window_id,
+            overflow_policy.unwrap_or_default(),
+            options.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| RecorderError::Other(e.to_string()))
+    .and_then(|result| result);
+    status.finish(&app, &result, RecorderStatus::Recording);
+    result
+}
 
-    let writer = WavWriter::create(&file_path, spec).map_err(|e| e.to_string())?;
-    let writer_arc = Arc::new(Mutex::new(Some(writer)));
-
-    let mixer = Arc::new(Mixer {
-        system_buffer: recorder.system_buffer.clone(),
-        mic_buffer: recorder.mic_buffer.clone(),
-        writer: writer_arc.clone(),
-        app_handle: app.clone(),
-        system_level: recorder.system_level.clone(),
-        mic_level: recorder.mic_level.clone(),
-        last_levels_update: recorder.last_levels_update.clone(),
-    });
+fn start(
+    app: &AppHandle,
+    recorder: &mut SharedRecorder,
+    capture_video: bool,
+    display_id: Option<u32>,
+    window_id: Option<u32>,
+    overflow_policy: OverflowPolicy,
+    options: RecordingOptions,
+) -> Result<String, RecorderError> {
+    options.validate().map_err(RecorderError::InvalidArgument)?;
 
     // --- SETUP SYSTEM AUDIO (ScreenCaptureKit) ---
-    let content = SCShareableContent::get().map_err(|e| e.to_string())?;
-    let display = content.displays().first().cloned().ok_or_else(|| "No display found".to_string())?;
-    let filter = SCContentFilter::create().with_display(&display).with_excluding_windows(&[]).build();
-    let config = SCStreamConfiguration::new()
-        .with_captures_audio(true)
-        .with_sample_rate(48000)
-        .with_channel_count(2);
-
-    let system_handler = SystemAudioOutputHandler {
-        buffer: recorder.system_buffer.clone(),
-        mixer_trigger: mixer.clone(),
-        system_level: recorder.system_level.clone(),
-    };
+    let target = capture_target::resolve(display_id, window_id)?;
 
-    let mut system_stream = SCStream::new(&filter, &config);
-    system_stream.add_output_handler(system_handler, SCStreamOutputType::Audio);
-    system_stream.start_capture().map_err(|e| e.to_string())?;
-
-    // --- SETUP MIC AUDIO (cpal) ---
-    let host = cpal::default_host();
-    let device = host.default_input_device().ok_or("No input device available")?;
-
-    let supported_configs = device.supported_input_configs().map_err(|e| e.to_string())?;
-
-    let mic_config_support = supported_configs
-        .filter(|c| c.sample_format() == cpal::SampleFormat::F32)
-        .find(|c| c.min_sample_rate() <= 48000 && c.max_sample_rate() >= 48000)
-        .or_else(|| device.supported_input_configs().ok()?.next())
-        .ok_or("Could not find any suitable input config")?;
-
-    let mic_channels = mic_config_support.channels();
-    let mic_source_sr = if mic_config_support.min_sample_rate() <= 48000
-        && mic_config_support.max_sample_rate() >= 48000
-    {
-        48000
+    // --- SETUP WAV WRITER ---
+    let file_path = app
+        .state::<output::OutputStore>()
+        .next_path(app, &target.profile)?;
+    let lock = recording_lock::acquire("combined-recorder-visualization", &file_path)
+        .map_err(RecorderError::Busy)?;
+    let mut config =
+        ScreenCaptureSource::configure(SCStreamConfiguration::new(), options.sample_rate);
+    let latency = app.state::<latency::LatencyStore>().get();
+    let written = Arc::new(AtomicU64::new(0));
+
+    let video = if capture_video {
+        let (width, height) = video::encodable_size(target.width, target.height);
+        config = config
+            .with_width(width)
+            .with_height(height)
+            .with_pixel_format(PixelFormat::BGRA)
+            .with_minimum_frame_interval(&CMTime::new(1, video::FPS as i32));
+        Some(
+            video::VideoRecorder::start(
+                &output::video_path(&file_path),
+                width as usize,
+                height as usize,
+                recorder.paused.clone(),
+                video::AudioTrack {
+                    frames: written.clone(),
+                    sample_rate: options.sample_rate,
+                    delay: Duration::from_secs_f64(
+                        latency.delays(options.sample_rate).0 as f64 / options.sample_rate as f64,
+                    ),
+                },
+            )
+            .map_err(RecorderError::CaptureFailed)?,
+        )
     } else {
-        mic_config_support.max_sample_rate()
+        None
     };
 
-    let mic_config = mic_config_support.with_sample_rate(mic_source_sr);
-    eprintln!("Selected Mic: {} channels, {} Hz", mic_channels, mic_source_sr);
-
-    let mic_buffer_clone = recorder.mic_buffer.clone();
-    let mic_level_clone = recorder.mic_level.clone();
-    let mixer_clone = mixer.clone();
-
-    // Resampling state for nearest-neighbor interpolation
-    let mut total_in = 0u64;
-    let mut total_out = 0u64;
-    let target_sr_val = 48000.0f64;
-    let source_sr_val = mic_source_sr as f64;
-
-    let mic_stream = device
-        .build_input_stream(
-            &mic_config.into(),
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                let mut mic_buf = mic_buffer_clone.lock();
-                let mut level_sum = 0.0f32;
-                let mut level_count = 0u32;
-
-                for frame in data.chunks(mic_channels as usize) {
-                    total_in += 1;
-                    while (total_out as f64 * source_sr_val) < (total_in as f64 * target_sr_val) {
-                        if mic_channels == 1 {
-                            let s = frame[0];
-                            level_sum += s * s;
-                            level_count += 1;
-                            mic_buf.push_back(s);
-                            mic_buf.push_back(s);
-                        } else {
-                            let l = frame[0];
-                            let r = frame[1];
-                            level_sum += (l * l + r * r) / 2.0;
-                            level_count += 1;
-                            mic_buf.push_back(l);
-                            mic_buf.push_back(r);
-                        }
-                        total_out += 1;
-                    }
-                }
-
-                if level_count > 0 {
-                    let rms = (level_sum / level_count as f32).sqrt();
-                    *mic_level_clone.lock() = rms;
-                }
-
-                drop(mic_buf);
-                mixer_clone.mix_available();
-            },
-            move |err| {
-                eprintln!("Mic stream error: {}", err);
-            },
-            None,
-        )
-        .map_err(|e| e.to_string())?;
+    let mut system_source = ScreenCaptureSource::new(&target.filter, &config, options.sample_rate);
+    let video = video.map(|(video, handler)| {
+        // The audio too, for when it starts
+        system_source.add_output_handler(handler.clone(), SCStreamOutputType::Audio);
+        system_source.add_output_handler(handler, SCStreamOutputType::Screen);
+        video
+    });
 
-    mic_stream.play().map_err(|e| e.to_string())?;
+    // --- SETUP MIC AUDIO (cpal) ---
+    let mic_source =
+        CpalSource::default_input(options.sample_rate).map_err(RecorderError::DeviceNotFound)?;
 
-    recorder.system_stream = Some(system_stream);
-    recorder.mic_stream = Some(mic_stream);
-    recorder.file_path = Some(file_path.clone());
-    recorder.writer = Some(writer_arc);
+    recorder.core.mixer.set_latency(latency);
+    recorder.core.mixer.set_buffer_limits(BufferLimits {
+        policy: overflow_policy,
+        ..Default::default()
+    });
+    recorder
+        .core
+        .start(
+            &file_path,
+            &options,
+            Box::new(system_source),
+            Box::new(mic_source),
+            mix_fn(app, recorder.paused.clone(), options.sample_rate, written),
+        )
+        .map_err(RecorderError::CaptureFailed)?;
+    recorder.recording_lock = Some(lock);
+    recorder.paused.store(false, Ordering::Relaxed);
+    recorder.started_at = Some(Instant::now());
+    recorder.paused_at = None;
+    recorder.paused_total = Duration::ZERO;
+    recorder.pause_reason = None;
+    recorder.video = video;
 
     Ok(file_path.to_string_lossy().to_string())
 }
 
+/// Stops recording and returns the file. With `finalize_options.normalize`
+/// the audio is brought to `target_lufs` first, reporting
+/// `normalize-progress` from 0 to 1 as it goes. A failed normalization is
+/// reported as `normalize-failed` and the recording kept as it was.
 #[tauri::command]
-async fn stop_recording(state: State<'_, AppState>) -> Result<String, String> {
-    let mut recorder = state.0.lock();
+async fn stop_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    status: State<'_, StatusMachine>,
+    finalize_options: Option<FinalizeOptions>,
+) -> Result<String, RecorderError> {
+    status.begin_stop(&app)?;
+    let stopped = stop(&mut state.0.lock());
+    let result = match stopped {
+        // Rewrites and muxes whole files, so not on the command thread and
+        // not holding the state
+        Ok((audio, video)) => {
+            let finalize_app = app.clone();
+            let finalize_options = finalize_options.unwrap_or_default();
+            tauri::async_runtime::spawn_blocking(move || {
+                finalize(&finalize_app, &audio, video, finalize_options)
+            })
+            .await
+            .map_err(|e| RecorderError::Other(e.to_string()))
+            .and_then(|result| result)
+        }
+        Err(e) => Err(e),
+    };
+    status.finish(&app, &result, RecorderStatus::Idle);
+    recorder_updater::resume_deferred_install(&app);
+    result
+}
 
-    if let Some(stream) = recorder.system_stream.take() {
-        let _ = stream.stop_capture();
-    }
+/// Stops the sources and the screen video, returning the audio file and the
+/// finished video.
+fn stop(recorder: &mut SharedRecorder) -> Result<(PathBuf, Option<video::Video>), RecorderError> {
+    // Finished whatever happens to the audio, so ffmpeg isn't left running
+    let video = recorder.video.take().map(|video| video.finish());
+    // Also clears buffers and resets levels
+    let audio = recorder.core.stop();
+
+    recorder.recording_lock.take();
+    recorder.paused.store(false, Ordering::Relaxed);
+    recorder.started_at = None;
+    recorder.paused_at = None;
+    recorder.pause_reason = None;
+    Ok((audio.map_err(RecorderError::IoError)?, video.transpose()?))
+}
 
-    if let Some(stream) = recorder.mic_stream.take() {
-        let _ = stream.pause();
+fn finalize(
+    app: &AppHandle,
+    audio: &Path,
+    video: Option<video::Video>,
+    finalize_options: FinalizeOptions,
+) -> Result<String, RecorderError> {
+    if finalize_options.normalize {
+        // Before the video, which takes its audio from the file
+        let normalized = loudness::normalize(audio, finalize_options.target_lufs, |progress| {
+            let _ = app.emit("normalize-progress", progress);
+        });
+        if let Err(e) = normalized {
+            eprintln!("Failed to normalize {}: {}", audio.display(), e);
+            let _ = app.emit("normalize-failed", e);
+        }
     }
 
-    if let Some(writer_arc) = recorder.writer.take() {
-        let mut writer_lock = writer_arc.lock();
-        if let Some(writer) = writer_lock.take() {
-            writer.finalize().map_err(|e| e.to_string())?;
-        }
+    if let Some(video) = video {
+        let output = output::muxed_path(audio);
+        video::mux(&video, audio, &output)?;
+        let _ = std::fs::remove_file(&video.path);
+        return Ok(output.to_string_lossy().to_string());
     }
 
-    // Clear buffers and reset levels
-    recorder.system_buffer.lock().clear();
-    recorder.mic_buffer.lock().clear();
-    *recorder.system_level.lock() = 0.0;
-    *recorder.mic_level.lock() = 0.0;
+    Ok(audio.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn pause_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    status: State<'_, StatusMachine>,
+) -> Result<(), RecorderError> {
+    pause(&app, &state, &status, PauseReason::User)
+}
+
+/// Emits `recording-paused` with `reason` in the payload.
+fn pause(
+    app: &AppHandle,
+    state: &AppState,
+    status: &StatusMachine,
+    reason: PauseReason,
+) -> Result<(), RecorderError> {
+    status.pause(app)?;
+    let mut recorder = state.0.lock();
+    recorder.paused.store(true, Ordering::Relaxed);
+    recorder.paused_at = Some(Instant::now());
+    recorder.pause_reason = Some(reason);
+    let _ = app.emit("recording-paused", recorder.pause_state());
+    Ok(())
+}
 
-    if let Some(path) = &recorder.file_path {
-        return Ok(path.to_string_lossy().to_string());
+#[tauri::command]
+fn resume_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    status: State<'_, StatusMachine>,
+) -> Result<(), RecorderError> {
+    status.resume(&app)?;
+    let mut recorder = state.0.lock();
+    if let Some(paused_at) = recorder.paused_at.take() {
+        recorder.paused_total += paused_at.elapsed();
     }
+    recorder.pause_reason = None;
+    recorder.paused.store(false, Ordering::Relaxed);
+    let _ = app.emit("recording-paused", recorder.pause_state());
+    Ok(())
+}
+
+/// The `audio-levels` of the last ten seconds, oldest first, for a window
+/// that opens mid-recording to draw before new ones arrive. Empty when not
+/// recording.
+#[tauri::command]
+fn get_level_history(state: State<'_, AppState>) -> Vec<AudioLevels> {
+    state.0.lock().core.mixer.levels().history()
+}
 
-    Err("Not recording".to_string())
+/// Installs the update announced by `update-available`, once the recording
+/// stops if one is running. Returns whether the install started right away.
+#[tauri::command]
+fn install_update(app: AppHandle, status: State<'_, StatusMachine>) -> Result<bool, RecorderError> {
+    if recorder_updater::pending_version(&app).is_none() {
+        return Err(RecorderError::NotFound("No update is pending".to_string()));
+    }
+    let recording = !matches!(
+        status.get(),
+        RecorderStatus::Idle | RecorderStatus::Error { .. }
+    );
+    Ok(recorder_updater::install_when_idle(&app, recording))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .manage(AppState::new())
+        .manage(StatusMachine::new())
+        .manage(recorder_updater::PendingUpdate::default())
+        .setup(|app| {
+            app.manage(latency::LatencyStore::load(app.handle()));
+            app.manage(output::OutputStore::load(app.handle()));
+            tray::init(app.handle())?;
+            recorder_updater::check_in_background(app.handle(), "combined-recorder-visualization");
+            Ok(())
+        })
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![start_recording, stop_recording])
+        .plugin(tauri_plugin_window_state::Builder::default().build())
+        .plugin(recorder_updater::plugin())
+        .invoke_handler(tauri::generate_handler![
+            start_recording,
+            stop_recording,
+            pause_recording,
+            resume_recording,
+            get_level_history,
+            status::get_status,
+            capture_target::list_displays,
+            capture_target::list_windows,
+            latency::get_latency,
+            latency::set_latency,
+            latency::calibrate_latency,
+            output::get_output_settings,
+            output::set_output_settings,
+            install_update
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,129 @@
+//! Where recordings go and what they're called, kept in `output.json` in the
+//! app data dir. File names come from a template with the tokens `{date}`,
+//! `{time}`, `{profile}` (the captured window's app, `display` for a whole
+//! display) and `{counter}`. The screen video and the MP4 take the audio's
+//! name.
+
+use parking_lot::Mutex;
+use recorder_core::{filename, RecorderError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputSettings {
+    /// The app data dir when unset
+    pub output_dir: Option<PathBuf>,
+    pub filename_template: String,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            output_dir: None,
+            filename_template: "combined-{date}-{time}".to_string(),
+        }
+    }
+}
+
+/// The screen video for the audio at `audio`, until it's muxed.
+pub fn video_path(audio: &Path) -> PathBuf {
+    audio.with_extension("video.mp4")
+}
+
+/// The MP4 the audio at `audio` and its video end up in.
+pub fn muxed_path(audio: &Path) -> PathBuf {
+    audio.with_extension("mp4")
+}
+
+// Also taken when a video recording already has that name
+fn taken(path: &Path) -> bool {
+    path.is_file() || video_path(path).is_file() || muxed_path(path).is_file()
+}
+
+pub struct OutputStore {
+    store: PathBuf,
+    settings: Mutex<OutputSettings>,
+}
+
+impl OutputStore {
+    pub fn load(app: &AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("output.json");
+        let settings = std::fs::read_to_string(&store)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            settings: Mutex::new(settings),
+        }
+    }
+
+    fn save(&self, settings: &OutputSettings) -> Result<(), RecorderError> {
+        if let Some(dir) = self.store.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+        Ok(std::fs::write(&self.store, json)?)
+    }
+
+    /// Path for a new recording's audio, creating the folder if needed.
+    pub fn next_path(&self, app: &AppHandle, profile: &str) -> Result<PathBuf, RecorderError> {
+        let settings = self.settings.lock().clone();
+        let dir = match settings.output_dir {
+            Some(dir) => dir,
+            None => app
+                .path()
+                .app_data_dir()
+                .unwrap_or_else(|_| PathBuf::from(".")),
+        };
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(filename::next_path(
+            &dir,
+            &settings.filename_template,
+            profile,
+            taken,
+        ))
+    }
+}
+
+#[tauri::command]
+pub fn get_output_settings(store: State<'_, OutputStore>) -> OutputSettings {
+    store.settings.lock().clone()
+}
+
+/// Sets the folder and file name template for the next recordings.
+#[tauri::command]
+pub fn set_output_settings(
+    store: State<'_, OutputStore>,
+    output_dir: Option<PathBuf>,
+    filename_template: String,
+) -> Result<(), RecorderError> {
+    if !filename::is_valid(&filename_template) {
+        return Err(RecorderError::InvalidArgument(format!(
+            "Not a usable file name template: {}",
+            filename_template
+        )));
+    }
+    if let Some(dir) = &output_dir {
+        if !dir.is_dir() {
+            return Err(RecorderError::NotFound(
+                "The output folder doesn't exist".to_string(),
+            ));
+        }
+    }
+
+    let settings = OutputSettings {
+        output_dir,
+        filename_template,
+    };
+    store.save(&settings)?;
+    *store.settings.lock() = settings;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Min/max peaks behind the scrolling waveform. The mixer feeds every frame
+//! in; finished bins wait in a bounded ring until the next `audio-waveform`
+//! event picks them up.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+pub const BINS_PER_SECOND: u32 = 100;
+/// Bins sent per event, 50ms worth
+const BINS_PER_EVENT: usize = 5;
+/// Older bins are dropped if the frontend falls this far behind
+const RING_CAPACITY: usize = BINS_PER_SECOND as usize;
+
+/// `[min, max]` of one bin, over both channels.
+type Peak = [f32; 2];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WaveformPeaks {
+    pub bins_per_second: u32,
+    pub mic: Vec<Peak>,
+    pub system: Vec<Peak>,
+    pub mixed: Vec<Peak>,
+}
+
+#[derive(Clone, Copy)]
+struct Bin {
+    mic: Peak,
+    system: Peak,
+    mixed: Peak,
+}
+
+impl Bin {
+    const EMPTY: Bin = Bin {
+        mic: [f32::MAX, f32::MIN],
+        system: [f32::MAX, f32::MIN],
+        mixed: [f32::MAX, f32::MIN],
+    };
+}
+
+fn widen(peak: &mut Peak, frame: [f32; 2]) {
+    for sample in frame {
+        peak[0] = peak[0].min(sample);
+        peak[1] = peak[1].max(sample);
+    }
+}
+
+pub struct PeakAggregator {
+    current: Bin,
+    frames_in_bin: u32,
+    frames_per_bin: u32,
+    ring: VecDeque<Bin>,
+}
+
+impl PeakAggregator {
+    /// Bins frames arriving at `sample_rate`.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            current: Bin::EMPTY,
+            frames_in_bin: 0,
+            frames_per_bin: (sample_rate / BINS_PER_SECOND).max(1),
+            ring: VecDeque::with_capacity(RING_CAPACITY),
+        }
+    }
+
+    pub fn push(&mut self, system: [f32; 2], mic: [f32; 2], mixed: [f32; 2]) {
+        widen(&mut self.current.system, system);
+        widen(&mut self.current.mic, mic);
+        widen(&mut self.current.mixed, mixed);
+        self.frames_in_bin += 1;
+
+        if self.frames_in_bin == self.frames_per_bin {
+            if self.ring.len() == RING_CAPACITY {
+                self.ring.pop_front();
+            }
+            self.ring.push_back(self.current);
+            self.current = Bin::EMPTY;
+            self.frames_in_bin = 0;
+        }
+    }
+
+    /// Drains the finished bins once there are enough for an event.
+    pub fn take_ready(&mut self) -> Option<WaveformPeaks> {
+        if self.ring.len() < BINS_PER_EVENT {
+            return None;
+        }
+        let bins: Vec<Bin> = self.ring.drain(..).collect();
+        Some(WaveformPeaks {
+            bins_per_second: BINS_PER_SECOND,
+            mic: bins.iter().map(|bin| bin.mic).collect(),
+            system: bins.iter().map(|bin| bin.system).collect(),
+            mixed: bins.iter().map(|bin| bin.mixed).collect(),
+        })
+    }
+}
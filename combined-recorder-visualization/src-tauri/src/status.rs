@@ -0,0 +1,116 @@
+//! What the recorder is doing. Commands move it along here instead of
+//! checking which streams happen to be set, and every change goes out as
+//! `recording-status-changed`. It's kept apart from the recorder's lock so
+//! `get_status` answers while a start or stop is still in progress.
+
+use crate::tray;
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum RecorderStatus {
+    Idle,
+    Starting,
+    Recording,
+    Paused,
+    Stopping,
+    /// The last start or stop failed. A new recording can still be started
+    Error {
+        message: String,
+    },
+}
+
+pub struct StatusMachine(Mutex<RecorderStatus>);
+
+fn notify(app: &AppHandle, status: RecorderStatus) {
+    tray::refresh(app, &status);
+    let _ = app.emit("recording-status-changed", status);
+}
+
+impl StatusMachine {
+    pub fn new() -> Self {
+        Self(Mutex::new(RecorderStatus::Idle))
+    }
+
+    pub fn get(&self) -> RecorderStatus {
+        self.0.lock().clone()
+    }
+
+    fn set(&self, app: &AppHandle, status: RecorderStatus) {
+        *self.0.lock() = status.clone();
+        notify(app, status);
+    }
+
+    /// Moves on to whatever `next` makes of the current status, or fails
+    /// with its reason.
+    fn advance(
+        &self,
+        app: &AppHandle,
+        next: impl FnOnce(&RecorderStatus) -> Result<RecorderStatus, RecorderError>,
+    ) -> Result<(), RecorderError> {
+        let mut current = self.0.lock();
+        let status = next(&current)?;
+        *current = status.clone();
+        drop(current);
+        notify(app, status);
+        Ok(())
+    }
+
+    pub fn begin_start(&self, app: &AppHandle) -> Result<(), RecorderError> {
+        self.advance(app, |status| match status {
+            RecorderStatus::Idle | RecorderStatus::Error { .. } => Ok(RecorderStatus::Starting),
+            RecorderStatus::Stopping => Err(RecorderError::Busy(
+                "Still stopping the last recording".to_string(),
+            )),
+            _ => Err(RecorderError::AlreadyRecording),
+        })
+    }
+
+    pub fn begin_stop(&self, app: &AppHandle) -> Result<(), RecorderError> {
+        self.advance(app, |status| match status {
+            RecorderStatus::Recording | RecorderStatus::Paused => Ok(RecorderStatus::Stopping),
+            RecorderStatus::Starting => Err(RecorderError::Busy("Still starting".to_string())),
+            RecorderStatus::Stopping => Err(RecorderError::Busy("Already stopping".to_string())),
+            _ => Err(RecorderError::NotRecording),
+        })
+    }
+
+    pub fn pause(&self, app: &AppHandle) -> Result<(), RecorderError> {
+        self.advance(app, |status| match status {
+            RecorderStatus::Recording => Ok(RecorderStatus::Paused),
+            RecorderStatus::Paused => Err(RecorderError::Other("Already paused".to_string())),
+            _ => Err(RecorderError::NotRecording),
+        })
+    }
+
+    pub fn resume(&self, app: &AppHandle) -> Result<(), RecorderError> {
+        self.advance(app, |status| match status {
+            RecorderStatus::Paused => Ok(RecorderStatus::Recording),
+            _ => Err(RecorderError::Other("Not paused".to_string())),
+        })
+    }
+
+    /// Ends a `begin_start` or `begin_stop` with `done` on success.
+    pub fn finish<T>(
+        &self,
+        app: &AppHandle,
+        result: &Result<T, RecorderError>,
+        done: RecorderStatus,
+    ) {
+        let status = match result {
+            Ok(_) => done,
+            Err(e) => RecorderStatus::Error {
+                message: e.to_string(),
+            },
+        };
+        self.set(app, status);
+    }
+}
+
+#[tauri::command]
+pub fn get_status(status: State<'_, StatusMachine>) -> RecorderStatus {
+    status.get()
+}
@@ -0,0 +1,156 @@
+//! Menu bar icon that turns red while recording, with the elapsed time as
+//! its title and Start, Pause and Stop items that call the same commands as
+//! the window. Follows the `status` machine, so it can't drift from it.
+
+use crate::status::{RecorderStatus, StatusMachine};
+use crate::AppState;
+use recorder_core::tray_icon::{self, ICON_SIZE};
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+const TRAY_ID: &str = "main";
+
+pub struct TrayMenu {
+    start_item: MenuItem<Wry>,
+    pause_item: MenuItem<Wry>,
+    stop_item: MenuItem<Wry>,
+    idle_icon: Image<'static>,
+    recording_icon: Image<'static>,
+}
+
+/// The red dot shown while recording.
+fn render_recording_icon() -> Image<'static> {
+    Image::new_owned(tray_icon::recording_dot(1.0), ICON_SIZE, ICON_SIZE)
+}
+
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let start_item = MenuItem::with_id(
+        app,
+        "start-recording",
+        "Start Recording",
+        true,
+        None::<&str>,
+    )?;
+    let pause_item = MenuItem::with_id(app, "pause-recording", "Pause", false, None::<&str>)?;
+    let stop_item =
+        MenuItem::with_id(app, "stop-recording", "Stop Recording", false, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit_item = PredefinedMenuItem::quit(app, None)?;
+    let menu = Menu::with_items(
+        app,
+        &[&start_item, &pause_item, &stop_item, &separator, &quit_item],
+    )?;
+
+    let idle_icon = app.default_window_icon().unwrap().clone();
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(idle_icon.clone())
+        .icon_as_template(true)
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id.as_ref()))
+        .build(app)?;
+
+    app.manage(TrayMenu {
+        start_item,
+        pause_item,
+        stop_item,
+        idle_icon,
+        recording_icon: render_recording_icon(),
+    });
+
+    // Keeps the elapsed time in the menu bar ticking
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(1));
+        if app.state::<StatusMachine>().get() == RecorderStatus::Recording {
+            show_elapsed(&app, "");
+        }
+    });
+    Ok(())
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    let app = app.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let result = match id.as_str() {
+            "start-recording" => crate::start_recording(
+                app.clone(),
+                app.state(),
+                app.state(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .map(|_| ()),
+            "pause-recording" if app.state::<StatusMachine>().get() == RecorderStatus::Paused => {
+                crate::resume_recording(app.clone(), app.state(), app.state())
+            }
+            "pause-recording" => crate::pause_recording(app.clone(), app.state(), app.state()),
+            "stop-recording" => crate::stop_recording(app.clone(), app.state(), app.state(), None)
+                .await
+                .map(|_| ()),
+            _ => return,
+        };
+        if let Err(e) = result {
+            eprintln!("Tray action {} failed: {}", id, e);
+        }
+    });
+}
+
+fn format_elapsed(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Sets the title to the recorded time followed by `suffix`. Skipped while a
+/// start or stop holds the recorder, the next tick catches up.
+fn show_elapsed(app: &AppHandle, suffix: &str) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let state = app.state::<AppState>();
+    let Some(recorder) = state.0.try_lock() else {
+        return;
+    };
+    let elapsed = format_elapsed(recorder.pause_state().elapsed_ms);
+    let _ = tray.set_title(Some(format!("{}{}", elapsed, suffix)));
+}
+
+/// Brings the icon, title and menu in line with `status`.
+pub fn refresh(app: &AppHandle, status: &RecorderStatus) {
+    let (Some(menu), Some(tray)) = (app.try_state::<TrayMenu>(), app.tray_by_id(TRAY_ID)) else {
+        return;
+    };
+    let recording = matches!(status, RecorderStatus::Recording | RecorderStatus::Paused);
+    let idle = matches!(status, RecorderStatus::Idle | RecorderStatus::Error { .. });
+
+    let _ = menu.start_item.set_enabled(idle);
+    let _ = menu.pause_item.set_enabled(recording);
+    let _ = menu.pause_item.set_text(match status {
+        RecorderStatus::Paused => "Resume",
+        _ => "Pause",
+    });
+    let _ = menu.stop_item.set_enabled(recording);
+
+    let icon = if idle {
+        &menu.idle_icon
+    } else {
+        &menu.recording_icon
+    };
+    let _ = tray.set_icon(Some(icon.clone()));
+    let _ = tray.set_icon_as_template(idle);
+    match status {
+        RecorderStatus::Recording => show_elapsed(app, ""),
+        RecorderStatus::Paused => show_elapsed(app, " paused"),
+        _ => {
+            let _ = tray.set_title(None::<&str>);
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! Screen video alongside the audio, put on the audio file's timeline.
+//! ScreenCaptureKit stamps screen frames and system audio with the same host
+//! clock, so a frame's presentation time says where it belongs relative to
+//! the first audio in the file. A writer thread fills a constant `FPS` with
+//! the frames by those times, repeating the last one while the screen doesn't
+//! change and leaving out what was captured while paused, like the audio.
+//!
+//! The mixer corrects drift between system audio and the mic by dropping a
+//! frame now and then, so over a long recording the file ends up a little
+//! shorter than the host clock says. The frames it writes are counted, and
+//! when recording stops the video's timestamps are scaled to match as it's
+//! muxed with the audio into one MP4.
+
+use parking_lot::Mutex;
+use screencapturekit::prelude::*;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+pub const FPS: u32 = 30;
+
+/// How long a frame may take from its presentation time to the writer, so
+/// the slots it belongs in aren't filled with the one before.
+const CAPTURE_LATENCY: f64 = 0.1;
+
+/// Larger differences between the audio and video lengths are a stall
+/// rather than drift, and left alone.
+const MAX_DRIFT: f64 = 0.01;
+
+/// Rounds a capture size down to even dimensions, which h264 needs for
+/// yuv420p.
+pub fn encodable_size(width: u32, height: u32) -> (u32, u32) {
+    ((width & !1).max(2), (height & !1).max(2))
+}
+
+// CLOCK_UPTIME_RAW, the host clock ScreenCaptureKit stamps samples with
+const HOST_CLOCK: u32 = 8;
+
+extern "C" {
+    fn clock_gettime_nsec_np(clock_id: u32) -> u64;
+}
+
+/// Now on the host clock, in seconds.
+fn host_now() -> f64 {
+    unsafe { clock_gettime_nsec_np(HOST_CLOCK) as f64 / 1e9 }
+}
+
+fn seconds(time: CMTime) -> Option<f64> {
+    (time.timescale > 0).then(|| time.value as f64 / time.timescale as f64)
+}
+
+/// The recording's audio, which the video is put in step with.
+pub struct AudioTrack {
+    /// Frames the mixer has written to the file so far
+    pub frames: Arc<AtomicU64>,
+    pub sample_rate: u32,
+    /// Silence the mixer put ahead of system audio, see `Latency::delays`
+    pub delay: Duration,
+}
+
+/// One BGRA frame without row padding.
+struct Frame {
+    /// Presentation time on the host clock, in seconds
+    pts: f64,
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+/// Keeps the most recent screen frame for the writer thread, and notes when
+/// the first audio arrived. Registered for both the screen and the audio
+/// output of the stream.
+#[derive(Clone)]
+pub struct VideoOutputHandler {
+    latest: Arc<Mutex<Option<Frame>>>,
+    audio_start: Arc<Mutex<Option<f64>>>,
+}
+
+impl SCStreamOutputTrait for VideoOutputHandler {
+    fn did_output_sample_buffer(&self, sample: CMSampleBuffer, of_type: SCStreamOutputType) {
+        let Some(pts) = seconds(sample.presentation_timestamp()) else {
+            return;
+        };
+        match of_type {
+            SCStreamOutputType::Audio => {
+                self.audio_start.lock().get_or_insert(pts);
+            }
+            SCStreamOutputType::Screen => {
+                // Frames where nothing changed come without an image
+                let Some(pixel_buffer) = sample.image_buffer() else {
+                    return;
+                };
+                let Ok(guard) = pixel_buffer.lock_read_only() else {
+                    return;
+                };
+                let (width, height) = (guard.width(), guard.height());
+                let stride = guard.bytes_per_row();
+                let pixels = guard.as_slice();
+
+                let row = width * 4;
+                let mut data = Vec::with_capacity(row * height);
+                for y in 0..height {
+                    data.extend_from_slice(&pixels[y * stride..y * stride + row]);
+                }
+                *self.latest.lock() = Some(Frame {
+                    pts,
+                    width,
+                    height,
+                    data,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Feeds ffmpeg one frame for every slot of the constant frame rate.
+struct Encoder {
+    stdin: ChildStdin,
+    width: usize,
+    height: usize,
+    frame: Vec<u8>,
+    /// Frames written so far
+    slots: u64,
+}
+
+impl Encoder {
+    /// Repeats the current frame in every slot starting before `time`
+    /// seconds into the file.
+    fn fill_to(&mut self, time: f64) -> std::io::Result<()> {
+        let until = (time * FPS as f64).ceil();
+        while (self.slots as f64) < until {
+            self.stdin.write_all(&self.frame)?;
+            self.slots += 1;
+        }
+        Ok(())
+    }
+
+    fn show(&mut self, frame: Frame) {
+        // A display switching resolution mid-recording is dropped
+        if frame.width == self.width && frame.height == self.height {
+            self.frame = frame.data;
+        }
+    }
+}
+
+/// The ffmpeg encoder and the thread feeding it.
+pub struct VideoRecorder {
+    path: PathBuf,
+    child: Child,
+    writer: Option<JoinHandle<u64>>,
+    stop: Arc<AtomicBool>,
+    audio: AudioTrack,
+}
+
+impl Drop for VideoRecorder {
+    // Also ends ffmpeg if the recording failed to start after the encoder,
+    // waiting for it so the file is closed and the process reaped
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+/// A finished screen video and how it lines up with the audio.
+pub struct Video {
+    pub path: PathBuf,
+    /// Where the first frame goes in the audio file
+    offset: Duration,
+    /// Audio seconds per video second
+    scale: f64,
+}
+
+impl VideoRecorder {
+    /// Starts encoding `width`x`height` video to `path`, in step with
+    /// `audio`. Returns the handler to register for the stream's screen and
+    /// audio output.
+    pub fn start(
+        path: &Path,
+        width: usize,
+        height: usize,
+        paused: Arc<AtomicBool>,
+        audio: AudioTrack,
+    ) -> Result<(Self, VideoOutputHandler), String> {
+        let ffmpeg = recorder_core::ffmpeg::locate()
+            .ok_or("Capturing video needs ffmpeg, install it with Homebrew")?;
+        let mut child = Command::new(ffmpeg)
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "bgra"])
+            .args(["-s", &format!("{}x{}", width, height)])
+            .args(["-r", &FPS.to_string(), "-i", "-"])
+            // The hardware encoder keeps up with full resolution at 30 fps
+            .args(["-c:v", "h264_videotoolbox", "-b:v", "8M"])
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        let stdin = child.stdin.take().ok_or("ffmpeg has no stdin")?;
+
+        let handler = VideoOutputHandler {
+            latest: Arc::new(Mutex::new(None)),
+            audio_start: Arc::new(Mutex::new(None)),
+        };
+        let stop = Arc::new(AtomicBool::new(false));
+        let writer = std::thread::spawn({
+            let handler = handler.clone();
+            let stop = stop.clone();
+            move || {
+                let mut encoder = Encoder {
+                    stdin,
+                    width,
+                    height,
+                    // Black until the first frame arrives
+                    frame: vec![0u8; width * height * 4],
+                    slots: 0,
+                };
+                if let Err(e) = write_frames(&mut encoder, &handler, &paused, &stop) {
+                    eprintln!("Video encoder stopped: {}", e);
+                }
+                // Dropping stdin tells ffmpeg the input ended
+                encoder.slots
+            }
+        });
+
+        Ok((
+            Self {
+                path: path.to_path_buf(),
+                child,
+                writer: Some(writer),
+                stop,
+                audio,
+            },
+            handler,
+        ))
+    }
+
+    /// Stops feeding frames and waits for ffmpeg to close the file.
+    pub fn finish(mut self) -> Result<Video, String> {
+        self.stop.store(true, Ordering::Relaxed);
+        let slots = self
+            .writer
+            .take()
+            .map_or(0, |writer| writer.join().unwrap_or(0));
+        let status = self.child.wait().map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err(format!("Video encoding failed ({})", status));
+        }
+
+        let video_seconds = slots as f64 / FPS as f64;
+        let audio_seconds = self.audio.frames.load(Ordering::Relaxed) as f64
+            / self.audio.sample_rate as f64
+            - self.audio.delay.as_secs_f64();
+        let scale = audio_seconds / video_seconds;
+        Ok(Video {
+            path: self.path.clone(),
+            offset: self.audio.delay,
+            scale: if (scale - 1.0).abs() <= MAX_DRIFT {
+                scale
+            } else {
+                1.0
+            },
+        })
+    }
+}
+
+/// Puts the handler's frames into `encoder` by their presentation times
+/// until `stop`, counting from the first audio and leaving out pauses.
+fn write_frames(
+    encoder: &mut Encoder,
+    handler: &VideoOutputHandler,
+    paused: &AtomicBool,
+    stop: &AtomicBool,
+) -> std::io::Result<()> {
+    let interval = Duration::from_secs(1) / FPS;
+    let mut paused_total = 0.0;
+    let mut paused_since = None;
+    loop {
+        let stopping = stop.load(Ordering::Relaxed);
+        if !stopping {
+            std::thread::sleep(interval);
+        }
+        let now = host_now();
+        let Some(audio_start) = *handler.audio_start.lock() else {
+            if stopping {
+                return Ok(());
+            }
+            continue;
+        };
+        let file_time = |host: f64, paused_total: f64| host - audio_start - paused_total;
+
+        if paused.load(Ordering::Relaxed) {
+            // Nothing goes into the file, like the audio, but resuming
+            // shows the screen as it is by then
+            if paused_since.is_none() {
+                encoder.fill_to(file_time(now, paused_total))?;
+                paused_since = Some(now);
+            }
+            if let Some(latest) = handler.latest.lock().take() {
+                encoder.show(latest);
+            }
+        } else {
+            if let Some(since) = paused_since.take() {
+                paused_total += now - since;
+            }
+            if let Some(latest) = handler.latest.lock().take() {
+                encoder.fill_to(file_time(latest.pts, paused_total))?;
+                encoder.show(latest);
+            }
+            // Frames still on their way may belong before now
+            let until = if stopping { now } else { now - CAPTURE_LATENCY };
+            encoder.fill_to(file_time(until, paused_total))?;
+        }
+        if stopping {
+            return Ok(());
+        }
+    }
+}
+
+/// Combines the screen video and the mixed audio into `output`, keeping the
+/// video as encoded but moving and scaling its timestamps onto the audio's.
+pub fn mux(video: &Video, audio: &Path, output: &Path) -> Result<(), String> {
+    let ffmpeg = recorder_core::ffmpeg::locate().ok_or("ffmpeg not found")?;
+    let result = Command::new(ffmpeg)
+        .args(["-y", "-loglevel", "error"])
+        .args(["-itsoffset", &format!("{:.6}", video.offset.as_secs_f64())])
+        .args(["-itsscale", &format!("{:.9}", video.scale)])
+        .arg("-i")
+        .arg(&video.path)
+        .arg("-i")
+        .arg(audio)
+        .args(["-map", "0:v", "-map", "1:a", "-c:v", "copy"])
+        .args(["-c:a", "aac", "-b:a", "192k", "-shortest"])
+        .args(["-movflags", "+faststart"])
+        .arg(output)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !result.status.success() {
+        return Err(format!(
+            "Muxing failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+    Ok(())
+}
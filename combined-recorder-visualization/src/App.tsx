@@ -1,24 +1,84 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import "./App.css";
 import WaveformVisualization from "./WaveformVisualization";
 
+interface DisplayInfo {
+  id: number;
+  title: string;
+}
+
+interface WindowInfo {
+  id: number;
+  title: string;
+  app: string;
+}
+
+type RecorderStatus =
+  | { status: "idle" | "starting" | "recording" | "paused" | "stopping" }
+  | { status: "error"; message: string };
+
+interface RecorderError {
+  kind: string;
+  code: number;
+  message: string;
+}
+
+function errorMessage(e: unknown) {
+  return (e as RecorderError)?.message ?? String(e);
+}
+
 function App() {
   const [isRecording, setIsRecording] = useState(false);
   const [filePath, setFilePath] = useState("");
   const [error, setError] = useState("");
   const [status, setStatus] = useState("Ready");
+  const [captureVideo, setCaptureVideo] = useState(false);
+  const [displays, setDisplays] = useState<DisplayInfo[]>([]);
+  const [windows, setWindows] = useState<WindowInfo[]>([]);
+  // "display:<id>" or "window:<id>", empty for the main display
+  const [target, setTarget] = useState("");
+
+  async function refreshTargets() {
+    try {
+      setDisplays(await invoke<DisplayInfo[]>("list_displays"));
+      setWindows(await invoke<WindowInfo[]>("list_windows"));
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  }
+
+  useEffect(() => {
+    refreshTargets();
+    // Picks up a recording still running after the window reloads
+    function follow(recorder: RecorderStatus) {
+      setIsRecording(recorder.status !== "idle" && recorder.status !== "error");
+    }
+    invoke<RecorderStatus>("get_status").then(follow);
+    const unlisten = listen<RecorderStatus>("recording-status-changed", (event) => {
+      follow(event.payload);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
 
   async function startRecording() {
     try {
       setError("");
       setStatus("Starting...");
-      const path = await invoke<string>("start_recording");
+      const [kind, id] = target.split(":");
+      const path = await invoke<string>("start_recording", {
+        captureVideo,
+        displayId: kind === "display" ? Number(id) : null,
+        windowId: kind === "window" ? Number(id) : null,
+      });
       setIsRecording(true);
       setFilePath(path);
-      setStatus("Recording Mic & System Audio");
+      setStatus(captureVideo ? "Recording Screen, Mic & System Audio" : "Recording Mic & System Audio");
     } catch (e) {
-      setError(String(e));
+      setError(errorMessage(e));
       setStatus("Error");
     }
   }
@@ -31,7 +91,7 @@ function App() {
       setFilePath(path);
       setStatus("Saved");
     } catch (e) {
-      setError(String(e));
+      setError(errorMessage(e));
       setStatus("Error");
     }
   }
@@ -56,6 +116,40 @@ function App() {
             Start Recording
           </button>
         )}
+
+        <select
+          className="target-select"
+          value={target}
+          disabled={isRecording}
+          onFocus={refreshTargets}
+          onChange={(e) => setTarget(e.currentTarget.value)}
+        >
+          <option value="">Main display</option>
+          <optgroup label="Displays">
+            {displays.map((display) => (
+              <option key={display.id} value={`display:${display.id}`}>
+                {display.title}
+              </option>
+            ))}
+          </optgroup>
+          <optgroup label="Windows">
+            {windows.map((window) => (
+              <option key={window.id} value={`window:${window.id}`}>
+                {window.app ? `${window.app} — ${window.title}` : window.title}
+              </option>
+            ))}
+          </optgroup>
+        </select>
+
+        <label className="video-toggle">
+          <input
+            type="checkbox"
+            checked={captureVideo}
+            disabled={isRecording}
+            onChange={(e) => setCaptureVideo(e.currentTarget.checked)}
+          />
+          Capture screen video (needs ffmpeg)
+        </label>
       </div>
 
       <div className="visualization-section">
@@ -7,6 +7,17 @@ interface AudioLevels {
   mixed_level: number;
 }
 
+// [min, max] pairs, bins_per_second of them per second of audio
+interface WaveformPeaks {
+  bins_per_second: number;
+  mic: [number, number][];
+  system: [number, number][];
+  mixed: [number, number][];
+}
+
+// Enough history to fill the canvas at one bar per bin
+const MAX_HISTORY = 600;
+
 interface WaveformVisualizationProps {
   isRecording: boolean;
 }
@@ -14,6 +25,7 @@ interface WaveformVisualizationProps {
 const WaveformVisualization: React.FC<WaveformVisualizationProps> = ({ isRecording }) => {
   const canvasRef = useRef<HTMLCanvasElement>(null);
   const animationFrameRef = useRef<number>(0);
+  const historyRef = useRef<[number, number][]>([]);
   const [audioLevels, setAudioLevels] = useState<AudioLevels>({
     mic_level: 0,
     system_level: 0,
@@ -22,18 +34,24 @@ const WaveformVisualization: React.FC<WaveformVisualizationProps> = ({ isRecordi
 
   useEffect(() => {
     let unlisten: (() => void) | null = null;
+    let unlistenWaveform: (() => void) | null = null;
 
     const setupListener = async () => {
       try {
         unlisten = await listen<AudioLevels>('audio-levels', (event) => {
           setAudioLevels(event.payload);
         });
+        unlistenWaveform = await listen<WaveformPeaks>('audio-waveform', (event) => {
+          const history = historyRef.current.concat(event.payload.mixed);
+          historyRef.current = history.slice(-MAX_HISTORY);
+        });
       } catch (error) {
         console.error('Failed to setup audio levels listener:', error);
       }
     };
 
     if (isRecording) {
+      historyRef.current = [];
       setupListener();
     }
 
@@ -41,6 +59,9 @@ const WaveformVisualization: React.FC<WaveformVisualizationProps> = ({ isRecordi
       if (unlisten) {
         unlisten();
       }
+      if (unlistenWaveform) {
+        unlistenWaveform();
+      }
     };
   }, [isRecording]);
 
@@ -77,46 +98,21 @@ const WaveformVisualization: React.FC<WaveformVisualizationProps> = ({ isRecordi
       // Draw single waveform visualization similar to the screenshot
       const centerY = height / 2;
       const maxAmplitude = height * 0.35; // Maximum height of waveform
-      const barWidth = 3;
-      const barSpacing = 1;
-      const numBars = Math.floor(width / (barWidth + barSpacing));
-      
-      // Use audio level to drive the waveform amplitude
-      const baseAmplitude = Math.min(audioLevels.mixed_level * maxAmplitude * 150, maxAmplitude);
-
-      // Draw waveform bars
-      ctx.fillStyle = '#fff';
-
-      const time = Date.now() * 0.001;
+      const barWidth = 1;
+      const numBars = Math.min(width, MAX_HISTORY);
 
-      for (let i = 0; i < numBars; i++) {
-        const x = i * (barWidth + barSpacing);
+      // Newest peaks on the right, scrolling left as more arrive
+      const history = historyRef.current.slice(-numBars);
+      const offset = numBars - history.length;
 
-        // Slow-moving shape wave
-        const wave1 = Math.sin((i * 0.08) + time * 1.2);
-        const wave2 = Math.sin((i * 0.03) + time * 0.8) * 0.6;
-        const shapeWave = (wave1 + wave2) / 1.6;
+      ctx.fillStyle = 'rgba(255, 255, 255, 0.85)';
+      history.forEach(([min, max], i) => {
+        const x = (offset + i) * barWidth;
+        const top = centerY - Math.min(max, 1) * maxAmplitude;
+        const bottom = centerY - Math.max(min, -1) * maxAmplitude;
+        ctx.fillRect(x, top, barWidth, Math.max(bottom - top, 1));
+      });
 
-        // High per-bar randomness to break uniformity
-        const spike = (Math.random() * 2 - 1);
-        const combined = shapeWave * 0.35 + spike * 0.65;
-
-        const amplitude = combined * baseAmplitude;
-
-        // Fade out towards edges
-        const fadeDistance = Math.min(i, numBars - i) / (numBars * 0.08);
-        const fadeFactor = Math.min(fadeDistance, 1);
-        const finalAmplitude = amplitude * fadeFactor;
-
-        const barHeight = Math.abs(finalAmplitude);
-        const barY = centerY - barHeight / 2;
-
-        const opacity = 0.2 + (barHeight / maxAmplitude) * 0.8;
-        ctx.fillStyle = `rgba(255, 255, 255, ${opacity})`;
-
-        ctx.fillRect(x, barY, barWidth, barHeight);
-      }
-      
       // Add a subtle level indicator
       ctx.fillStyle = '#666';
       ctx.font = '12px sans-serif';
@@ -0,0 +1,207 @@
+//! What the mixer writes into. WAV streams straight to disk; the compressed
+//! formats, and any `Layout` other than the capture's own, are captured to a
+//! scratch WAV and transcoded with ffmpeg once the recording stops, so a
+//! failed encode never costs the take. Either WAV is written at the layout's
+//! bit depth, dithered by `SampleEncoder` on the way down.
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+use recorder_core::{BitDepth, SampleEncoder};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SPEC: WavSpec = WavSpec {
+    channels: 2,
+    sample_rate: 48000,
+    bits_per_sample: 32,
+    sample_format: SampleFormat::Float,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Wav,
+    Flac,
+    Mp3,
+    Aac,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Wav => "wav",
+            OutputFormat::Flac => "flac",
+            OutputFormat::Mp3 => "mp3",
+            OutputFormat::Aac => "m4a",
+        }
+    }
+
+    /// Rough final size of one file holding `frames` captured frames.
+    pub fn estimated_size(self, frames: u64, layout: Layout) -> u64 {
+        match self {
+            OutputFormat::Wav => layout.scale(wav_size(frames, layout.bit_depth)),
+            // Typical program material compresses to about half of the WAV
+            OutputFormat::Flac => layout.scale(wav_size(frames, layout.bit_depth)) / 2,
+            // 192 kbit/s is 24000 bytes a second, half a byte per frame at 48 kHz
+            OutputFormat::Mp3 | OutputFormat::Aac => frames / 2,
+        }
+    }
+
+    fn ffmpeg_args(self, bit_depth: BitDepth) -> &'static [&'static str] {
+        match self {
+            // Only converted for another layout, kept at the capture's depth
+            OutputFormat::Wav => match bit_depth {
+                BitDepth::Pcm16 => &["-c:a", "pcm_s16le"],
+                BitDepth::Pcm24 => &["-c:a", "pcm_s24le"],
+                BitDepth::Float32 => &["-c:a", "pcm_f32le"],
+            },
+            OutputFormat::Flac => &["-c:a", "flac"],
+            OutputFormat::Mp3 => &["-c:a", "libmp3lame", "-b:a", "192k"],
+            OutputFormat::Aac => &["-c:a", "aac", "-b:a", "192k"],
+        }
+    }
+}
+
+/// Sample rate, channel count and bit depth of the finished files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Layout {
+    pub sample_rate: u32,
+    /// 1 downmixes to mono, 2 keeps stereo
+    pub channels: u16,
+    /// Of WAV files, the other formats pick their own
+    pub bit_depth: BitDepth,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            sample_rate: SPEC.sample_rate,
+            channels: SPEC.channels,
+            bit_depth: BitDepth::default(),
+        }
+    }
+}
+
+impl Layout {
+    /// Captured as is, the bit depth is taken care of while writing.
+    fn is_native(self) -> bool {
+        self.sample_rate == SPEC.sample_rate && self.channels == SPEC.channels
+    }
+
+    /// Scales a size in the capture layout to this one.
+    fn scale(self, bytes: u64) -> u64 {
+        bytes * u64::from(self.sample_rate) * u64::from(self.channels)
+            / (u64::from(SPEC.sample_rate) * u64::from(SPEC.channels))
+    }
+}
+
+/// Size of a WAV holding `frames` stereo frames at `bit_depth`, header
+/// included. Every format is captured as one of these first.
+pub fn wav_size(frames: u64, bit_depth: BitDepth) -> u64 {
+    44 + frames * u64::from(SPEC.channels) * u64::from(bit_depth.bits() / 8)
+}
+
+pub trait Encoder: Send {
+    fn write_sample(&mut self, sample: f32);
+    /// Flushes and closes the output, transcoding it if the format needs that.
+    fn finish(self: Box<Self>) -> Result<(), String>;
+}
+
+struct WavEncoder {
+    writer: WavWriter<BufWriter<File>>,
+    encoder: SampleEncoder,
+}
+
+impl Encoder for WavEncoder {
+    fn write_sample(&mut self, sample: f32) {
+        let _ = self.encoder.write(&mut self.writer, sample);
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        self.writer.finalize().map_err(|e| e.to_string())
+    }
+}
+
+/// Records to `<target>.part.wav`, then hands that to ffmpeg.
+struct TranscodeEncoder {
+    scratch: WavWriter<BufWriter<File>>,
+    encoder: SampleEncoder,
+    scratch_path: PathBuf,
+    target: PathBuf,
+    format: OutputFormat,
+    layout: Layout,
+}
+
+impl Encoder for TranscodeEncoder {
+    fn write_sample(&mut self, sample: f32) {
+        let _ = self.encoder.write(&mut self.scratch, sample);
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        let this = *self;
+        this.scratch.finalize().map_err(|e| e.to_string())?;
+        let (scratch, target) = (&this.scratch_path, &this.target);
+        transcode(scratch, target, this.format, this.layout).inspect_err(|_| {
+            // Keep the audio as a plain WAV next to where the encode should have gone
+            let _ = std::fs::rename(scratch, target.with_extension("wav"));
+        })?;
+        let _ = std::fs::remove_file(scratch);
+        Ok(())
+    }
+}
+
+fn transcode(
+    source: &Path,
+    target: &Path,
+    format: OutputFormat,
+    layout: Layout,
+) -> Result<(), String> {
+    let output = Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-i"])
+        .arg(source)
+        .args(format.ffmpeg_args(layout.bit_depth))
+        .args(["-ar", &layout.sample_rate.to_string()])
+        .args(["-ac", &layout.channels.to_string()])
+        .arg(target)
+        .output()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => "ffmpeg is needed for this format but wasn't found".to_string(),
+            _ => e.to_string(),
+        })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let _ = std::fs::remove_file(target);
+        Err(format!(
+            "ffmpeg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+pub fn create(
+    path: &Path,
+    format: OutputFormat,
+    layout: Layout,
+) -> Result<Box<dyn Encoder>, String> {
+    let spec = layout.bit_depth.spec(SPEC.sample_rate, SPEC.channels);
+    let encoder = SampleEncoder::new(layout.bit_depth);
+    if format == OutputFormat::Wav && layout.is_native() {
+        let writer = WavWriter::create(path, spec).map_err(|e| e.to_string())?;
+        return Ok(Box::new(WavEncoder { writer, encoder }));
+    }
+
+    let scratch_path = path.with_extension("part.wav");
+    let writer = WavWriter::create(&scratch_path, spec).map_err(|e| e.to_string())?;
+    Ok(Box::new(TranscodeEncoder {
+        scratch: writer,
+        encoder,
+        scratch_path,
+        target: path.to_path_buf(),
+        format,
+        layout,
+    }))
+}
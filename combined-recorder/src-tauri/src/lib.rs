@@ -1,275 +1,441 @@
+mod encoder;
+mod notifications;
+mod permissions;
+mod profiles;
+mod progress;
+mod recorder;
+mod schedules;
+mod sessions;
+mod tray;
+
 use anyhow::Result;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use hound::{WavSpec, WavWriter};
-use parking_lot::Mutex;
+use encoder::OutputFormat;
+use recorder_core::{
+    recording_lock, BitDepth, CaptureSource, CpalSource, Mixer, RecorderError,
+    ScreenCaptureSource, SAMPLE_RATE,
+};
 use screencapturekit::prelude::*;
-use std::collections::VecDeque;
-use std::fs::File;
-use std::io::BufWriter;
+use serde::Serialize;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Manager, State};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State, WindowEvent};
 
-struct SharedRecorder {
-    system_stream: Option<SCStream>,
-    mic_stream: Option<cpal::Stream>,
-    file_path: Option<PathBuf>,
-    writer: Option<Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>>,
-    
-    // Buffers for mixing
-    system_buffer: Arc<Mutex<VecDeque<f32>>>,
-    mic_buffer: Arc<Mutex<VecDeque<f32>>>,
+type Writer = Box<dyn encoder::Encoder>;
+
+enum Output {
+    // The sources averaged into one stereo file. `scale` is 1 over the
+    // number of sources captured, so a single one isn't halved
+    Mixed { writer: Writer, scale: f32 },
+    // Each source in its own stereo file, for editing them separately
+    Multitrack { system: Writer, mic: Writer },
 }
 
-pub struct AppState(Mutex<SharedRecorder>);
-
-impl AppState {
-    pub fn new() -> Self {
-        Self(Mutex::new(SharedRecorder {
-            system_stream: None,
-            mic_stream: None,
-            file_path: None,
-            writer: None,
-            system_buffer: Arc::new(Mutex::new(VecDeque::new())),
-            mic_buffer: Arc::new(Mutex::new(VecDeque::new())),
-        }))
+impl Output {
+    fn write(&mut self, system: [f32; 2], mic: [f32; 2]) {
+        match self {
+            Output::Mixed { writer, scale } => {
+                writer.write_sample((system[0] + mic[0]) * *scale);
+                writer.write_sample((system[1] + mic[1]) * *scale);
+            }
+            Output::Multitrack { system: system_writer, mic: mic_writer } => {
+                for sample in system {
+                    system_writer.write_sample(sample);
+                }
+                for sample in mic {
+                    mic_writer.write_sample(sample);
+                }
+            }
+        }
+    }
+
+    fn finalize(self) -> Result<(), String> {
+        match self {
+            Output::Mixed { writer, .. } => writer.finish(),
+            Output::Multitrack { system, mic } => system.finish().and(mic.finish()),
+        }
     }
 }
 
-struct Mixer {
-    system_buffer: Arc<Mutex<VecDeque<f32>>>,
-    mic_buffer: Arc<Mutex<VecDeque<f32>>>,
-    writer: Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>,
+/// What the core mixer hands its frames to, on its own thread.
+struct Take {
+    output: Output,
+    gains: profiles::Gains,
+    frames_written: Arc<AtomicU64>,
 }
 
-impl Mixer {
-    fn mix_available(&self) {
-        let mut sys = self.system_buffer.lock();
-        let mut mic = self.mic_buffer.lock();
-        let mut writer_lock = self.writer.lock();
-        
-        if let Some(writer) = writer_lock.as_mut() {
-            // We assume stereo (2 channels) for output
-            while sys.len() >= 2 && mic.len() >= 2 {
-                let s1 = sys.pop_front().unwrap();
-                let s2 = sys.pop_front().unwrap();
-                let m1 = mic.pop_front().unwrap();
-                let m2 = mic.pop_front().unwrap();
-                
-                // Simple mixing: average the samples
-                let mixed_1 = (s1 + m1) / 2.0;
-                let mixed_2 = (s2 + m2) / 2.0;
-                
-                let _ = writer.write_sample(mixed_1);
-                let _ = writer.write_sample(mixed_2);
-            }
+impl recorder_core::Output for Take {
+    fn mix(&mut self, mixer: &Mixer) {
+        let (output, gains) = (&mut self.output, self.gains);
+        let scale = |frame: [f32; 2], gain: f32| [frame[0] * gain, frame[1] * gain];
+        let mut frames = 0;
+        // A source that's off isn't captured and comes in as silence
+        mixer.drain(|system, mic| {
+            output.write(scale(system, gains.system), scale(mic, gains.mic));
+            frames += 1;
+        });
+        self.frames_written.fetch_add(frames, Ordering::Relaxed);
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        self.output.finalize()
+    }
+}
+
+struct SharedRecorder {
+    core: recorder_core::Recorder,
+    file_paths: Vec<PathBuf>,
+
+    // Library entry for the running take
+    session_id: Option<String>,
+    started_at: Option<Instant>,
+    progress: Option<tauri::async_runtime::JoinHandle<()>>,
+    sources: profiles::Sources,
+
+    // Held for the whole recording so other playground apps keep off the mic
+    recording_lock: Option<recording_lock::RecordingLock>,
+}
+
+impl SharedRecorder {
+    fn new() -> Self {
+        Self {
+            core: recorder_core::Recorder::default(),
+            file_paths: Vec::new(),
+            session_id: None,
+            started_at: None,
+            progress: None,
+            sources: profiles::Sources::default(),
+            recording_lock: None,
         }
     }
 }
 
-// Handler for ScreenCaptureKit (System Audio)
-struct SystemAudioOutputHandler {
-    buffer: Arc<Mutex<VecDeque<f32>>>,
-    mixer_trigger: Arc<Mixer>,
+/// Everything the main display plays.
+fn system_source() -> Result<Box<dyn CaptureSource>, RecorderError> {
+    let content = SCShareableContent::get().map_err(permissions::capture_error)?;
+    let display = content
+        .displays()
+        .first()
+        .cloned()
+        .ok_or_else(|| RecorderError::DeviceNotFound("No display found".to_string()))?;
+    let filter = SCContentFilter::create().with_display(&display).with_excluding_windows(&[]).build();
+    let config = ScreenCaptureSource::configure(SCStreamConfiguration::new(), SAMPLE_RATE);
+    Ok(Box::new(ScreenCaptureSource::new(&filter, &config, SAMPLE_RATE)))
 }
 
-impl SCStreamOutputTrait for SystemAudioOutputHandler {
-    fn did_output_sample_buffer(&self, sample: CMSampleBuffer, of_type: SCStreamOutputType) {
-        if let SCStreamOutputType::Audio = of_type {
-            if let Some(buffer_list) = sample.audio_buffer_list() {
-                let mut samples = Vec::new();
-                let num_buffers = buffer_list.num_buffers();
-                
-                if num_buffers == 1 {
-                    let buffer = buffer_list.get(0).unwrap();
-                    let data = buffer.data();
-                    let f32_samples: &[f32] = unsafe {
-                        std::slice::from_raw_parts(data.as_ptr() as *const f32, data.len() / 4)
-                    };
-                    samples.extend_from_slice(f32_samples);
-                } else {
-                    let mut channel_data = Vec::new();
-                    for i in 0..num_buffers {
-                        let buffer = buffer_list.get(i).unwrap();
-                        let data = buffer.data();
-                        let f32_samples: &[f32] = unsafe {
-                            std::slice::from_raw_parts(data.as_ptr() as *const f32, data.len() / 4)
-                        };
-                        channel_data.push(f32_samples);
-                    }
-                    if !channel_data.is_empty() {
-                        let len = channel_data[0].len();
-                        for i in 0..len {
-                            for channel in &channel_data {
-                                if i < channel.len() {
-                                    samples.push(channel[i]);
-                                }
-                            }
-                        }
-                    }
-                }
+fn mic_source() -> Result<Box<dyn CaptureSource>, RecorderError> {
+    let mic = CpalSource::default_input(SAMPLE_RATE).map_err(RecorderError::DeviceNotFound)?;
+    Ok(Box::new(mic))
+}
 
-                if !samples.is_empty() {
-                    self.buffer.lock().extend(samples);
-                    self.mixer_trigger.mix_available();
-                }
-            }
-        }
+fn limits(
+    max_duration_secs: Option<u64>,
+    max_size_mb: Option<u64>,
+) -> Result<progress::Limits, RecorderError> {
+    if max_duration_secs == Some(0) || max_size_mb == Some(0) {
+        return Err(RecorderError::InvalidArgument(
+            "Recording limits must be greater than zero".to_string(),
+        ));
     }
+    Ok(progress::Limits {
+        max_duration: max_duration_secs.map(Duration::from_secs),
+        max_bytes: max_size_mb.map(|mb| mb * 1_000_000),
+        ..Default::default()
+    })
+}
+
+/// Starts recording and returns the files being written. `multitrack` keeps
+/// mic and system audio in separate files instead of mixing them; formats
+/// other than WAV are encoded when the recording stops. `bit_depth` is the
+/// WAV's, as in `RecordingOptions`, 32-bit float by default. With a
+/// `max_duration_secs` or `max_size_mb` the recording stops itself once
+/// either is reached.
+#[tauri::command]
+async fn start_recording(
+    recorder: State<'_, recorder::Recorder>,
+    multitrack: Option<bool>,
+    format: Option<OutputFormat>,
+    bit_depth: Option<BitDepth>,
+    max_duration_secs: Option<u64>,
+    max_size_mb: Option<u64>,
+) -> Result<Vec<String>, RecorderError> {
+    let profile = profiles::RecordingProfile {
+        multitrack: multitrack.unwrap_or(false),
+        format: format.unwrap_or_default(),
+        bit_depth: bit_depth.unwrap_or_default(),
+        ..Default::default()
+    };
+    recorder
+        .start(profile, limits(max_duration_secs, max_size_mb)?)
+        .await
 }
 
+/// Like `start_recording`, with everything but the limits taken from the
+/// saved profile `name`.
 #[tauri::command]
-async fn start_recording(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
-    let mut recorder = state.0.lock();
-    if recorder.system_stream.is_some() || recorder.mic_stream.is_some() {
-        return Err("Already recording".to_string());
+async fn start_recording_with_profile(
+    recorder: State<'_, recorder::Recorder>,
+    profiles: State<'_, profiles::ProfileStore>,
+    name: String,
+    max_duration_secs: Option<u64>,
+    max_size_mb: Option<u64>,
+) -> Result<Vec<String>, RecorderError> {
+    let profile = profiles
+        .get(&name)
+        .ok_or_else(|| RecorderError::NotFound("Profile not found".to_string()))?;
+    recorder
+        .start(profile, limits(max_duration_secs, max_size_mb)?)
+        .await
+}
+
+/// Runs on the recorder thread, see `recorder`.
+fn start(
+    app: &AppHandle,
+    recorder: &mut SharedRecorder,
+    profile: profiles::RecordingProfile,
+    limits: progress::Limits,
+) -> Result<Vec<String>, RecorderError> {
+    if recorder.core.is_recording() {
+        return Err(RecorderError::AlreadyRecording);
+    }
+    profile.validate()?;
+    let limits = progress::Limits {
+        low_disk_bytes: (profile.min_free_space_mb > 0)
+            .then_some(profile.min_free_space_mb * 1_000_000),
+        ..limits
+    };
+    let microphone = permissions::Resource::Microphone;
+    if profile.sources.mic && permissions::denied(microphone) {
+        return Err(microphone.denied_error());
     }
 
     // --- SETUP WAV WRITER ---
-    let audio_dir = app.path().app_data_dir().unwrap_or_else(|_| PathBuf::from("."));
-    std::fs::create_dir_all(&audio_dir).map_err(|e| e.to_string())?;
-    let file_path = audio_dir.join("combined_audio.wav");
-
-    let spec = WavSpec {
-        channels: 2,
-        sample_rate: 48000,
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
+    let audio_dir = match &profile.output_dir {
+        Some(dir) => dir.clone(),
+        None => app.path().app_data_dir().unwrap_or_else(|_| PathBuf::from(".")),
     };
-
-    let writer = WavWriter::create(&file_path, spec).map_err(|e| e.to_string())?;
-    let writer_arc = Arc::new(Mutex::new(Some(writer)));
-    
-    let mixer = Arc::new(Mixer {
-        system_buffer: recorder.system_buffer.clone(),
-        mic_buffer: recorder.mic_buffer.clone(),
-        writer: writer_arc.clone(),
-    });
-
-    // --- SETUP SYSTEM AUDIO (ScreenCaptureKit) ---
-    let content = SCShareableContent::get().map_err(|e| e.to_string())?;
-    let display = content.displays().first().cloned().ok_or_else(|| "No display found".to_string())?;
-    let filter = SCContentFilter::create().with_display(&display).with_excluding_windows(&[]).build();
-    let config = SCStreamConfiguration::new()
-        .with_captures_audio(true)
-        .with_sample_rate(48000)
-        .with_channel_count(2);
-
-    let system_handler = SystemAudioOutputHandler {
-        buffer: recorder.system_buffer.clone(),
-        mixer_trigger: mixer.clone(),
+    std::fs::create_dir_all(&audio_dir)?;
+    let (format, layout) = (profile.format, profile.layout());
+    let session_id = sessions::new_id();
+    let names = if profile.multitrack {
+        vec![
+            format!("{}-system", session_id),
+            format!("{}-mic", session_id),
+        ]
+    } else {
+        vec![session_id.clone()]
     };
+    let file_paths: Vec<_> = names
+        .iter()
+        .map(|name| audio_dir.join(name).with_extension(format.extension()))
+        .collect();
+    let lock = recording_lock::acquire("combined-recorder", &file_paths[0])
+        .map_err(RecorderError::Busy)?;
 
-    let mut system_stream = SCStream::new(&filter, &config);
-    system_stream.add_output_handler(system_handler, SCStreamOutputType::Audio);
-    system_stream.start_capture().map_err(|e| e.to_string())?;
-
-    // --- SETUP MIC AUDIO (cpal) ---
-    let host = cpal::default_host();
-    let device = host.default_input_device().ok_or("No input device available")?;
-    
-    let supported_configs = device.supported_input_configs()
-        .map_err(|e| e.to_string())?;
-    
-    // --- MIC CONFIGURATION ---
-    let mic_config_support = supported_configs
-        .filter(|c| c.sample_format() == cpal::SampleFormat::F32)
-        .find(|c| c.min_sample_rate() <= 48000 && c.max_sample_rate() >= 48000)
-        .or_else(|| device.supported_input_configs().ok()?.next())
-        .ok_or("Could not find any suitable input config")?;
-    
-    let mic_channels = mic_config_support.channels();
-    let mic_source_sr = if mic_config_support.min_sample_rate() <= 48000 && mic_config_support.max_sample_rate() >= 48000 {
-        48000
+    let create = |path: &PathBuf| encoder::create(path, format, layout);
+    let output = if profile.multitrack {
+        Output::Multitrack {
+            system: create(&file_paths[0])?,
+            mic: create(&file_paths[1])?,
+        }
     } else {
-        mic_config_support.max_sample_rate()
+        Output::Mixed {
+            writer: create(&file_paths[0])?,
+            scale: 1.0 / profile.sources.names().len() as f32,
+        }
     };
-    
-    let mic_config = mic_config_support.with_sample_rate(mic_source_sr);
-    eprintln!("Selected Mic: {} channels, {} Hz", mic_channels, mic_source_sr);
-
-    let mic_buffer_clone = recorder.mic_buffer.clone();
-    let mixer_clone = mixer.clone();
-    
-    // Resampling state for nearest-neighbor interpolation
-    let mut total_in = 0u64;
-    let mut total_out = 0u64;
-    let target_sr_val = 48000.0f64;
-    let source_sr_val = mic_source_sr as f64;
-
-    let mic_stream = device.build_input_stream(
-        &mic_config.into(),
-        move |data: &[f32], _: &cpal::InputCallbackInfo| {
-            let mut mic_buf = mic_buffer_clone.lock();
-            for frame in data.chunks(mic_channels as usize) {
-                total_in += 1;
-                // Resample to 48000 Hz by repeating or skipping samples
-                while (total_out as f64 * source_sr_val) < (total_in as f64 * target_sr_val) {
-                    if mic_channels == 1 {
-                        mic_buf.push_back(frame[0]);
-                        mic_buf.push_back(frame[0]);
-                    } else if mic_channels >= 2 {
-                        mic_buf.push_back(frame[0]);
-                        mic_buf.push_back(frame[1]);
-                    }
-                    total_out += 1;
-                }
-            }
-            drop(mic_buf);
-            mixer_clone.mix_available();
-        },
-        move |err| {
-            eprintln!("Mic stream error: {}", err);
-        },
-        None,
-    ).map_err(|e| e.to_string())?;
+    let frames_written = Arc::new(AtomicU64::new(0));
+    let take = Take {
+        output,
+        gains: profile.gains,
+        frames_written: frames_written.clone(),
+    };
+
+    // --- SETUP SYSTEM AUDIO (ScreenCaptureKit) AND MIC (cpal) ---
+    let system = profile.sources.system.then(system_source).transpose()?;
+    let mic = profile.sources.mic.then(mic_source).transpose()?;
+    recorder
+        .core
+        .start_output(&file_paths[0], SAMPLE_RATE, system, mic, Box::new(take))
+        .map_err(|e| match profile.sources.system {
+            true => permissions::capture_error(e),
+            false => RecorderError::CaptureFailed(e),
+        })?;
+
+    let started_at = Instant::now();
+    let progress = progress::spawn(
+        app.clone(),
+        frames_written,
+        started_at,
+        format,
+        layout,
+        &file_paths,
+        limits,
+    );
+
+    recorder.file_paths = file_paths.clone();
+    recorder.recording_lock = Some(lock);
+    recorder.session_id = Some(session_id);
+    recorder.started_at = Some(started_at);
+    recorder.progress = Some(progress);
+    recorder.sources = profile.sources;
+
+    Ok(to_strings(&file_paths))
+}
 
-    mic_stream.play().map_err(|e| e.to_string())?;
+fn to_strings(paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+}
 
-    recorder.system_stream = Some(system_stream);
-    recorder.mic_stream = Some(mic_stream);
-    recorder.file_path = Some(file_path.clone());
-    recorder.writer = Some(writer_arc);
+#[derive(Debug, Clone, Serialize)]
+struct RecordingStopped {
+    /// `user`, or `limit-reached` when a limit set at start stopped it
+    reason: &'static str,
+    files: Vec<String>,
+}
 
-    Ok(file_path.to_string_lossy().to_string())
+#[tauri::command]
+async fn stop_recording(
+    recorder: State<'_, recorder::Recorder>,
+) -> Result<Vec<String>, RecorderError> {
+    recorder.stop().await
 }
 
+/// Installs the update announced by `update-available`, once the recording
+/// stops if one is running. Returns whether the install started right away.
 #[tauri::command]
-async fn stop_recording(state: State<'_, AppState>) -> Result<String, String> {
-    let mut recorder = state.0.lock();
-    
-    if let Some(stream) = recorder.system_stream.take() {
-        let _ = stream.stop_capture();
+async fn install_update(
+    app: AppHandle,
+    recorder: State<'_, recorder::Recorder>,
+) -> Result<bool, RecorderError> {
+    if recorder_updater::pending_version(&app).is_none() {
+        return Err(RecorderError::NotFound("No update is pending".to_string()));
     }
-    
-    if let Some(stream) = recorder.mic_stream.take() {
-        let _ = stream.pause(); 
+    recorder.install_update().await
+}
+
+/// Called by the progress ticker once a limit is reached.
+fn stop_at_limit(app: &AppHandle) {
+    app.state::<recorder::Recorder>().stop_at_limit();
+}
+
+/// Runs on the recorder thread, see `recorder`.
+fn stop(
+    app: &AppHandle,
+    recorder: &mut SharedRecorder,
+    reason: &'static str,
+) -> Result<Vec<String>, RecorderError> {
+    if !recorder.core.is_recording() {
+        return Err(RecorderError::NotRecording);
     }
+    // Listed even when finalizing failed, see `finish`
+    let files = finish(recorder, &app.state::<sessions::SessionManager>());
+    recorder_updater::resume_deferred_install(app);
+    tray::refresh_recent(app);
+    let files = files?;
+    let _ = app.emit(
+        "recording-stopped",
+        RecordingStopped {
+            reason,
+            files: files.clone(),
+        },
+    );
+    Ok(files)
+}
 
-    if let Some(writer_arc) = recorder.writer.take() {
-        let mut writer_lock = writer_arc.lock();
-        if let Some(writer) = writer_lock.take() {
-            writer.finalize().map_err(|e| e.to_string())?;
+fn finish(
+    recorder: &mut SharedRecorder,
+    sessions: &sessions::SessionManager,
+) -> Result<Vec<String>, RecorderError> {
+    if let Some(progress) = recorder.progress.take() {
+        progress.abort();
+    }
+
+    // Stops the sources, mixes what they delivered and finalizes the files
+    let finalized = recorder.core.stop();
+
+    let session = (recorder.session_id.take(), recorder.started_at.take());
+    if let (Some(id), Some(started_at)) = session {
+        // A failed encode leaves the audio as WAV, which is still worth listing
+        let files = recorder
+            .file_paths
+            .iter()
+            .map(|path| {
+                if path.exists() {
+                    path.clone()
+                } else {
+                    path.with_extension("wav")
+                }
+            })
+            .collect();
+        let duration_ms = started_at.elapsed().as_millis() as u64;
+        let sources = recorder.sources.names();
+        if let Err(e) = sessions.add(id, files, duration_ms, sources) {
+            eprintln!("Failed to save the recording library: {}", e);
         }
     }
 
-    // Clear buffers
-    recorder.system_buffer.lock().clear();
-    recorder.mic_buffer.lock().clear();
+    // Reset before the finalize error is returned, a failed transcode
+    // mustn't hold the lock and block every later recording
+    recorder.recording_lock.take();
+    finalized?;
 
-    if let Some(path) = &recorder.file_path {
-        return Ok(path.to_string_lossy().to_string());
+    if !recorder.file_paths.is_empty() {
+        return Ok(to_strings(&recorder.file_paths));
     }
-    
-    Err("Not recording".to_string())
+
+    Err(RecorderError::NotRecording)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
-        .manage(AppState::new())
+        .manage(recorder_updater::PendingUpdate::default())
+        .setup(|app| {
+            app.manage(recorder::Recorder::spawn(app.handle().clone()));
+            app.manage(sessions::SessionManager::load(app.handle()));
+            app.manage(profiles::ProfileStore::load(app.handle()));
+            app.manage(schedules::Scheduler::load(app.handle()));
+            tray::init(app.handle())?;
+            schedules::spawn_watcher(app.handle().clone());
+            recorder_updater::check_in_background(app.handle(), "combined-recorder");
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // Closing the window only hides it, so scheduled recordings still start
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == "main" {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        })
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![start_recording, stop_recording])
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_window_state::Builder::default().build())
+        .plugin(recorder_updater::plugin())
+        .invoke_handler(tauri::generate_handler![
+            start_recording,
+            start_recording_with_profile,
+            stop_recording,
+            install_update,
+            permissions::check_permissions,
+            permissions::request_permissions,
+            permissions::open_permission_settings,
+            profiles::save_profile,
+            profiles::list_profiles,
+            profiles::delete_profile,
+            schedules::schedule_recording,
+            schedules::list_schedules,
+            schedules::cancel_schedule,
+            sessions::list_recordings,
+            sessions::delete_recording,
+            sessions::rename_recording
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
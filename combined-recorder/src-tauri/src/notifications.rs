@@ -0,0 +1,64 @@
+//! Native notifications for the recording lifecycle, so starting from the
+//! tray or stopping at a limit is noticed without the window open. Skipped
+//! while the window has focus, it shows the same thing already. The plugin
+//! can't deliver clicks on desktop, so a finished recording's notification
+//! names its path and the tray's Recent Recordings opens it, and the low disk
+//! warning names the folder to clear out instead of opening it.
+
+use recorder_core::RecorderError;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+fn window_focused(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .is_some_and(|window| window.is_focused().unwrap_or(false))
+}
+
+fn show(app: &AppHandle, title: &str, body: &str) {
+    if window_focused(app) {
+        return;
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("Failed to show a notification: {}", e);
+    }
+}
+
+pub fn started(app: &AppHandle, files: &[String]) {
+    show(
+        app,
+        "Recording Started",
+        &format!("Recording to {}", files.join("\n")),
+    );
+}
+
+/// `reason` as sent with `recording-stopped`.
+pub fn stopped(app: &AppHandle, reason: &str, files: &[String]) {
+    let title = match reason {
+        "limit-reached" => "Recording Limit Reached",
+        _ => "Recording Saved",
+    };
+    show(app, title, &format!("Saved to {}", files.join("\n")));
+}
+
+pub fn failed(app: &AppHandle, e: &RecorderError) {
+    show(app, "Recording Failed", &e.to_string());
+}
+
+/// Shown even with the window focused, it doesn't warn about this itself.
+pub fn low_disk(app: &AppHandle, free_bytes: u64, dir: &Path) {
+    let body = format!(
+        "Only {} MB left on the disk holding {}",
+        free_bytes / 1_000_000,
+        dir.display()
+    );
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("Disk Almost Full")
+        .body(body)
+        .show()
+    {
+        eprintln!("Failed to show a notification: {}", e);
+    }
+}
@@ -0,0 +1,219 @@
+//! Microphone and screen recording (TCC) permissions, checked before capture
+//! so the UI can walk the user through granting them instead of showing
+//! whatever SCStream or cpal fail with.
+
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+
+const PRIVACY_PANE: &str = "x-apple.systempreferences:com.apple.preference.security";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Resource {
+    Microphone,
+    ScreenRecording,
+}
+
+impl Resource {
+    /// As serialized.
+    fn name(self) -> &'static str {
+        match self {
+            Resource::Microphone => "microphone",
+            Resource::ScreenRecording => "screen-recording",
+        }
+    }
+
+    /// The Privacy & Security pane where it's granted.
+    pub fn settings_url(self) -> String {
+        let anchor = match self {
+            Resource::Microphone => "Privacy_Microphone",
+            Resource::ScreenRecording => "Privacy_ScreenCapture",
+        };
+        format!("{}?{}", PRIVACY_PANE, anchor)
+    }
+
+    pub fn denied_error(self) -> RecorderError {
+        let message = match self {
+            Resource::Microphone => "Microphone access is turned off",
+            Resource::ScreenRecording => "Screen recording is turned off for this app",
+        };
+        RecorderError::PermissionDenied {
+            resource: self.name().to_string(),
+            message: message.to_string(),
+            settings_url: Some(self.settings_url()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+    Restricted,
+    NotDetermined,
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Permission {
+    status: PermissionStatus,
+    /// Where the user can fix it, only set when not granted
+    settings_url: Option<String>,
+}
+
+impl Permission {
+    fn new(resource: Resource, status: PermissionStatus) -> Self {
+        Self {
+            status,
+            settings_url: (status != PermissionStatus::Granted).then(|| resource.settings_url()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Permissions {
+    /// Both are granted and a recording can start
+    ready: bool,
+    microphone: Permission,
+    screen_recording: Permission,
+}
+
+impl Permissions {
+    fn new(microphone: PermissionStatus, screen_recording: PermissionStatus) -> Self {
+        Self {
+            ready: microphone == PermissionStatus::Granted
+                && screen_recording == PermissionStatus::Granted,
+            microphone: Permission::new(Resource::Microphone, microphone),
+            screen_recording: Permission::new(Resource::ScreenRecording, screen_recording),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn microphone_status() -> PermissionStatus {
+    use objc2_av_foundation::{AVAuthorizationStatus, AVCaptureDevice, AVMediaTypeAudio};
+
+    // SAFETY: reading a framework constant
+    let Some(media_type) = (unsafe { AVMediaTypeAudio }) else {
+        return PermissionStatus::Unsupported;
+    };
+    // SAFETY: audio is a valid media type to ask about
+    match unsafe { AVCaptureDevice::authorizationStatusForMediaType(media_type) } {
+        AVAuthorizationStatus::Authorized => PermissionStatus::Granted,
+        AVAuthorizationStatus::Denied => PermissionStatus::Denied,
+        AVAuthorizationStatus::Restricted => PermissionStatus::Restricted,
+        _ => PermissionStatus::NotDetermined,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn screen_recording_status() -> PermissionStatus {
+    // CoreGraphics can't tell "never asked" from "denied"
+    if objc2_core_graphics::CGPreflightScreenCaptureAccess() {
+        PermissionStatus::Granted
+    } else {
+        PermissionStatus::Denied
+    }
+}
+
+/// Shows the microphone prompt if it hasn't been answered yet. Resolves once
+/// the user has.
+#[cfg(target_os = "macos")]
+async fn request_microphone() {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_av_foundation::{AVCaptureDevice, AVMediaTypeAudio};
+    use parking_lot::Mutex;
+
+    // SAFETY: reading a framework constant
+    let Some(media_type) = (unsafe { AVMediaTypeAudio }) else {
+        return;
+    };
+    let (answered, answer) = tokio::sync::oneshot::channel();
+    // Scoped so the block isn't held across the await, it isn't Send
+    {
+        let answered = Mutex::new(Some(answered));
+        let block = RcBlock::new(move |_granted: Bool| {
+            if let Some(answered) = answered.lock().take() {
+                let _ = answered.send(());
+            }
+        });
+        // SAFETY: audio is a valid media type, and the block only touches
+        // Send + Sync state so it can run on whichever queue AVFoundation picks
+        unsafe { AVCaptureDevice::requestAccessForMediaType_completionHandler(media_type, &block) };
+    }
+    let _ = answer.await;
+}
+
+#[cfg(target_os = "macos")]
+pub fn check() -> Permissions {
+    Permissions::new(microphone_status(), screen_recording_status())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn check() -> Permissions {
+    Permissions::new(PermissionStatus::Unsupported, PermissionStatus::Unsupported)
+}
+
+/// Whether `resource` is known to be off. ScreenCaptureKit fails without
+/// saying why, a microphone without access just records silence.
+#[cfg(target_os = "macos")]
+pub fn denied(resource: Resource) -> bool {
+    let status = match resource {
+        Resource::Microphone => microphone_status(),
+        Resource::ScreenRecording => screen_recording_status(),
+    };
+    matches!(
+        status,
+        PermissionStatus::Denied | PermissionStatus::Restricted
+    )
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn denied(_resource: Resource) -> bool {
+    false
+}
+
+/// For a failing ScreenCaptureKit call, whose error doesn't say whether the
+/// permission is missing.
+pub fn capture_error(e: impl Display) -> RecorderError {
+    if denied(Resource::ScreenRecording) {
+        Resource::ScreenRecording.denied_error()
+    } else {
+        RecorderError::CaptureFailed(e.to_string())
+    }
+}
+
+#[tauri::command]
+pub fn check_permissions() -> Permissions {
+    check()
+}
+
+/// Asks for whatever hasn't been granted yet and returns the result. macOS
+/// only prompts once, after that the user has to go to `settings_url`. A
+/// screen recording grant takes effect after the app restarts.
+#[tauri::command]
+pub async fn request_permissions() -> Permissions {
+    #[cfg(target_os = "macos")]
+    {
+        if microphone_status() == PermissionStatus::NotDetermined {
+            request_microphone().await;
+        }
+        if screen_recording_status() != PermissionStatus::Granted {
+            objc2_core_graphics::CGRequestScreenCaptureAccess();
+        }
+    }
+    check()
+}
+
+/// Opens System Settings at the pane where `resource` is granted.
+#[tauri::command]
+pub fn open_permission_settings(app: AppHandle, resource: Resource) -> Result<(), RecorderError> {
+    app.opener()
+        .open_url(resource.settings_url(), None::<&str>)
+        .map_err(|e| RecorderError::Other(e.to_string()))
+}
@@ -0,0 +1,199 @@
+//! Saved recording setups, kept in `profiles.json` in the app data dir, so a
+//! take can be started by name instead of passing every option again.
+
+use crate::encoder::{Layout, OutputFormat};
+use parking_lot::Mutex;
+use recorder_core::{BitDepth, RecorderError};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+// Beyond this a source is clipping long before it's loud enough
+const MAX_GAIN: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Sources {
+    pub mic: bool,
+    pub system: bool,
+}
+
+impl Default for Sources {
+    fn default() -> Self {
+        Self {
+            mic: true,
+            system: true,
+        }
+    }
+}
+
+impl Sources {
+    /// As listed in the library, `mic` and/or `system`.
+    pub fn names(self) -> Vec<String> {
+        [("mic", self.mic), ("system", self.system)]
+            .into_iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+}
+
+/// Linear gain per source, applied before mixing.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Gains {
+    pub mic: f32,
+    pub system: f32,
+}
+
+impl Default for Gains {
+    fn default() -> Self {
+        Self {
+            mic: 1.0,
+            system: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingProfile {
+    pub name: String,
+    /// Of the finished files, capture always runs at 48 kHz stereo
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Of WAV files, 32-bit float unless set
+    pub bit_depth: BitDepth,
+    pub format: OutputFormat,
+    /// Mic and system audio in separate files, needs both sources
+    pub multitrack: bool,
+    pub sources: Sources,
+    pub gains: Gains,
+    /// Where the files go, the app data dir when unset
+    pub output_dir: Option<PathBuf>,
+    /// Free space on the output disk below which a recording warns, 0 never
+    pub min_free_space_mb: u64,
+}
+
+impl Default for RecordingProfile {
+    fn default() -> Self {
+        let layout = Layout::default();
+        Self {
+            name: String::new(),
+            sample_rate: layout.sample_rate,
+            channels: layout.channels,
+            bit_depth: layout.bit_depth,
+            format: OutputFormat::default(),
+            multitrack: false,
+            sources: Sources::default(),
+            gains: Gains::default(),
+            output_dir: None,
+            min_free_space_mb: 1000,
+        }
+    }
+}
+
+impl RecordingProfile {
+    pub fn layout(&self) -> Layout {
+        Layout {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            bit_depth: self.bit_depth,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), RecorderError> {
+        let invalid = |message: String| Err(RecorderError::InvalidArgument(message));
+        if !(8000..=192000).contains(&self.sample_rate) {
+            return invalid("Sample rate must be between 8 and 192 kHz".to_string());
+        }
+        if !(1..=2).contains(&self.channels) {
+            return invalid("Profiles record mono or stereo".to_string());
+        }
+        if !self.sources.mic && !self.sources.system {
+            return invalid("Pick at least one source".to_string());
+        }
+        if self.multitrack && !(self.sources.mic && self.sources.system) {
+            return invalid("Separate tracks need both sources".to_string());
+        }
+        let in_range = |gain: f32| gain.is_finite() && (0.0..=MAX_GAIN).contains(&gain);
+        if !in_range(self.gains.mic) || !in_range(self.gains.system) {
+            return invalid(format!("Gains must be between 0 and {}", MAX_GAIN));
+        }
+        Ok(())
+    }
+}
+
+pub struct ProfileStore {
+    store: PathBuf,
+    profiles: Mutex<Vec<RecordingProfile>>,
+}
+
+impl ProfileStore {
+    pub fn load(app: &AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("profiles.json");
+        let profiles = std::fs::read_to_string(&store)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            profiles: Mutex::new(profiles),
+        }
+    }
+
+    fn save(&self, profiles: &[RecordingProfile]) -> Result<(), RecorderError> {
+        if let Some(dir) = self.store.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(profiles).map_err(|e| e.to_string())?;
+        Ok(std::fs::write(&self.store, json)?)
+    }
+
+    pub fn get(&self, name: &str) -> Option<RecordingProfile> {
+        self.profiles
+            .lock()
+            .iter()
+            .find(|profile| profile.name == name)
+            .cloned()
+    }
+}
+
+/// Saves `profile`, replacing the one with the same name.
+#[tauri::command]
+pub fn save_profile(
+    store: State<'_, ProfileStore>,
+    profile: RecordingProfile,
+) -> Result<(), RecorderError> {
+    if profile.name.trim().is_empty() {
+        return Err(RecorderError::InvalidArgument(
+            "Profiles need a name".to_string(),
+        ));
+    }
+    profile.validate()?;
+
+    let mut profiles = store.profiles.lock();
+    match profiles.iter_mut().find(|saved| saved.name == profile.name) {
+        Some(saved) => *saved = profile,
+        None => profiles.push(profile),
+    }
+    store.save(&profiles)
+}
+
+#[tauri::command]
+pub fn list_profiles(store: State<'_, ProfileStore>) -> Vec<RecordingProfile> {
+    store.profiles.lock().clone()
+}
+
+#[tauri::command]
+pub fn delete_profile(store: State<'_, ProfileStore>, name: String) -> Result<(), RecorderError> {
+    let mut profiles = store.profiles.lock();
+    let before = profiles.len();
+    profiles.retain(|profile| profile.name != name);
+    if profiles.len() == before {
+        return Err(RecorderError::NotFound("Profile not found".to_string()));
+    }
+    store.save(&profiles)
+}
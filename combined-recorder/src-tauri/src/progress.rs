@@ -0,0 +1,84 @@
+//! Once-a-second `recording-progress` event, so the UI can run a timer and
+//! show the file growing without polling. The same tick enforces the
+//! recording's `Limits` and warns once when the disk runs low.
+
+use crate::encoder::{self, Layout, OutputFormat};
+use crate::notifications;
+use recorder_core::disk::free_space;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Progress {
+    pub elapsed_ms: u64,
+    /// Audio on disk so far, across all files of the take
+    pub bytes_written: u64,
+    /// What the files will come to once encoded
+    pub estimated_size: u64,
+}
+
+/// When an unattended recording should stop by itself, and when it warns
+/// that it may not get there.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    pub max_duration: Option<Duration>,
+    /// Compared against `Progress::bytes_written`, so disk use stays bounded
+    /// whatever the files get encoded to
+    pub max_bytes: Option<u64>,
+    /// Free space on the recording's disk below which to warn, once
+    pub low_disk_bytes: Option<u64>,
+}
+
+impl Limits {
+    fn reached(&self, progress: &Progress) -> bool {
+        self.max_duration
+            .is_some_and(|max| progress.elapsed_ms >= max.as_millis() as u64)
+            || self
+                .max_bytes
+                .is_some_and(|max| progress.bytes_written >= max)
+    }
+}
+
+/// Starts the ticker for a recording written to `files`. It runs until the
+/// returned handle is aborted or a limit stops the recording.
+pub fn spawn(
+    app: AppHandle,
+    frames_written: Arc<AtomicU64>,
+    started_at: Instant,
+    format: OutputFormat,
+    layout: Layout,
+    files: &[PathBuf],
+    limits: Limits,
+) -> JoinHandle<()> {
+    let file_count = files.len() as u64;
+    let dir = files[0].parent().map(Path::to_path_buf).unwrap_or_default();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        let mut warned = false;
+        loop {
+            ticker.tick().await;
+            let frames = frames_written.load(Ordering::Relaxed);
+            let progress = Progress {
+                elapsed_ms: started_at.elapsed().as_millis() as u64,
+                bytes_written: file_count * encoder::wav_size(frames, layout.bit_depth),
+                estimated_size: file_count * format.estimated_size(frames, layout),
+            };
+            let _ = app.emit("recording-progress", &progress);
+            if let (false, Some(threshold)) = (warned, limits.low_disk_bytes) {
+                if let Some(free) = free_space(&dir).filter(|&free| free < threshold) {
+                    notifications::low_disk(&app, free, &dir);
+                    warned = true;
+                }
+            }
+            if limits.reached(&progress) {
+                crate::stop_at_limit(&app);
+                return;
+            }
+        }
+    })
+}
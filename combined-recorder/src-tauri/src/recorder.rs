@@ -0,0 +1,140 @@
+//! The recorder lives on a thread of its own that owns the streams and
+//! writers. Commands send it requests and await the reply, so probing
+//! devices, looking up shareable content and creating files never happens
+//! under a lock other commands, or the async runtime, are waiting on.
+
+use crate::{notifications, profiles, progress, SharedRecorder};
+use recorder_core::RecorderError;
+use std::sync::mpsc;
+use tauri::AppHandle;
+use tokio::sync::oneshot;
+
+type Reply = oneshot::Sender<Result<Vec<String>, RecorderError>>;
+
+enum Request {
+    Start {
+        profile: profiles::RecordingProfile,
+        limits: progress::Limits,
+        reply: Reply,
+    },
+    Stop {
+        /// As sent with `recording-stopped`
+        reason: &'static str,
+        reply: Option<Reply>,
+    },
+    InstallUpdate {
+        reply: oneshot::Sender<bool>,
+    },
+}
+
+pub struct Recorder {
+    requests: mpsc::Sender<Request>,
+}
+
+fn gone() -> RecorderError {
+    RecorderError::Other("The recorder isn't running".to_string())
+}
+
+impl Recorder {
+    pub fn spawn(app: AppHandle) -> Self {
+        let (requests, inbox) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("recorder".to_string())
+            .spawn(move || run(app, inbox))
+            .expect("failed to start the recorder thread");
+        Self { requests }
+    }
+
+    async fn request(
+        &self,
+        request: impl FnOnce(Reply) -> Request,
+    ) -> Result<Vec<String>, RecorderError> {
+        let (reply, response) = oneshot::channel();
+        self.requests.send(request(reply)).map_err(|_| gone())?;
+        response.await.map_err(|_| gone())?
+    }
+
+    /// Starts recording with `profile` and returns the files being written.
+    pub async fn start(
+        &self,
+        profile: profiles::RecordingProfile,
+        limits: progress::Limits,
+    ) -> Result<Vec<String>, RecorderError> {
+        self.request(|reply| Request::Start {
+            profile,
+            limits,
+            reply,
+        })
+        .await
+    }
+
+    pub async fn stop(&self) -> Result<Vec<String>, RecorderError> {
+        self.request(|reply| Request::Stop {
+            reason: "user",
+            reply: Some(reply),
+        })
+        .await
+    }
+
+    /// Installs the pending update now, or once the current recording stops.
+    /// Returns whether the install started right away.
+    pub async fn install_update(&self) -> Result<bool, RecorderError> {
+        let (reply, response) = oneshot::channel();
+        self.requests
+            .send(Request::InstallUpdate { reply })
+            .map_err(|_| gone())?;
+        response.await.map_err(|_| gone())
+    }
+
+    /// Stops without waiting for it, for the progress ticker.
+    pub fn stop_at_limit(&self) {
+        let _ = self.requests.send(Request::Stop {
+            reason: "limit-reached",
+            reply: None,
+        });
+    }
+}
+
+fn run(app: AppHandle, inbox: mpsc::Receiver<Request>) {
+    let mut recorder = SharedRecorder::new();
+    for request in inbox {
+        match request {
+            Request::Start {
+                profile,
+                limits,
+                reply,
+            } => {
+                let result = crate::start(&app, &mut recorder, profile, limits);
+                match &result {
+                    Ok(files) => notifications::started(&app, files),
+                    Err(e) => notifications::failed(&app, e),
+                }
+                let _ = reply.send(result);
+            }
+            Request::Stop { reason, reply } => {
+                let result = crate::stop(&app, &mut recorder, reason);
+                match &result {
+                    Ok(files) => notifications::stopped(&app, reason, files),
+                    Err(RecorderError::NotRecording) => {}
+                    Err(e) => notifications::failed(&app, e),
+                }
+                match reply {
+                    Some(reply) => {
+                        let _ = reply.send(result);
+                    }
+                    // Stopped by hand before the limit's request came in
+                    None if matches!(result, Err(RecorderError::NotRecording)) => {}
+                    None => {
+                        if let Err(e) = result {
+                            eprintln!("Failed to stop the recording: {}", e);
+                        }
+                    }
+                }
+            }
+            Request::InstallUpdate { reply } => {
+                let recording = recorder.core.is_recording();
+                let _ = reply.send(recorder_updater::install_when_idle(&app, recording));
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Recordings set up ahead of time, kept in `schedules.json` in the app data
+//! dir so they survive a restart. A watcher starts each one at its time with
+//! its profile and stops it after its duration, whether or not the window is
+//! open, and emits `scheduled-recording-started`.
+
+use crate::profiles::{ProfileStore, RecordingProfile};
+use crate::{progress, recorder, sessions};
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    /// Seconds since the Unix epoch
+    pub start_time: u64,
+    pub duration_secs: u64,
+    /// Saved profile to record with, the defaults when unset
+    pub profile: Option<String>,
+}
+
+impl Schedule {
+    fn end_time(&self) -> u64 {
+        self.start_time + self.duration_secs
+    }
+
+    fn overlaps(&self, other: &Schedule) -> bool {
+        self.start_time < other.end_time() && other.start_time < self.end_time()
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct ScheduledRecordingStarted {
+    schedule: Schedule,
+    files: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct ScheduledRecordingFailed {
+    schedule: Schedule,
+    error: RecorderError,
+}
+
+pub struct Scheduler {
+    store: PathBuf,
+    schedules: Mutex<Vec<Schedule>>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+impl Scheduler {
+    pub fn load(app: &AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("schedules.json");
+        let schedules = std::fs::read_to_string(&store)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            schedules: Mutex::new(schedules),
+        }
+    }
+
+    fn save(&self, schedules: &[Schedule]) -> Result<(), RecorderError> {
+        if let Some(dir) = self.store.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(schedules).map_err(|e| e.to_string())?;
+        Ok(std::fs::write(&self.store, json)?)
+    }
+
+    /// Removes and returns the schedules whose start time has come. One
+    /// whose whole slot passed while the app wasn't running is dropped.
+    fn take_due(&self, now: u64) -> Vec<Schedule> {
+        let mut schedules = self.schedules.lock();
+        if !schedules.iter().any(|schedule| schedule.start_time <= now) {
+            return Vec::new();
+        }
+        let (due, pending) = schedules
+            .drain(..)
+            .partition::<Vec<_>, _>(|schedule| schedule.start_time <= now);
+        *schedules = pending;
+        if let Err(e) = self.save(&schedules) {
+            eprintln!("Failed to save schedules: {}", e);
+        }
+        due.into_iter()
+            .filter(|schedule| schedule.end_time() > now)
+            .collect()
+    }
+}
+
+/// Starts `schedule` for what's left of its slot, late if the app wasn't
+/// running at its start time.
+async fn start(app: &AppHandle, schedule: &Schedule) -> Result<Vec<String>, RecorderError> {
+    let profile = match &schedule.profile {
+        Some(name) => app
+            .state::<ProfileStore>()
+            .get(name)
+            .ok_or_else(|| RecorderError::NotFound(format!("Profile {} was deleted", name)))?,
+        None => RecordingProfile::default(),
+    };
+    let remaining = schedule.end_time().saturating_sub(now_secs());
+    let limits = progress::Limits {
+        max_duration: Some(Duration::from_secs(remaining)),
+        ..Default::default()
+    };
+    app.state::<recorder::Recorder>()
+        .start(profile, limits)
+        .await
+}
+
+async fn begin(app: &AppHandle, schedule: Schedule) {
+    let _ = match start(app, &schedule).await {
+        Ok(files) => app.emit(
+            "scheduled-recording-started",
+            ScheduledRecordingStarted { schedule, files },
+        ),
+        Err(error) => app.emit(
+            "scheduled-recording-failed",
+            ScheduledRecordingFailed { schedule, error },
+        ),
+    };
+}
+
+/// Starts scheduled recordings while the app runs.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            for schedule in app.state::<Scheduler>().take_due(now_secs()) {
+                begin(&app, schedule).await;
+            }
+        }
+    });
+}
+
+/// Records for `duration_secs` from `start_time`, seconds since the Unix
+/// epoch, with the saved `profile` or the defaults.
+#[tauri::command]
+pub fn schedule_recording(
+    scheduler: State<'_, Scheduler>,
+    profiles: State<'_, ProfileStore>,
+    start_time: u64,
+    duration_secs: u64,
+    profile: Option<String>,
+) -> Result<Schedule, RecorderError> {
+    let invalid = |message: &str| Err(RecorderError::InvalidArgument(message.to_string()));
+    if start_time <= now_secs() {
+        return invalid("The start time has already passed");
+    }
+    if duration_secs == 0 {
+        return invalid("Scheduled recordings need a duration");
+    }
+    if let Some(name) = &profile {
+        if profiles.get(name).is_none() {
+            return Err(RecorderError::NotFound("Profile not found".to_string()));
+        }
+    }
+
+    let schedule = Schedule {
+        id: sessions::new_id(),
+        start_time,
+        duration_secs,
+        profile,
+    };
+    let mut schedules = scheduler.schedules.lock();
+    // Only one recording runs at a time
+    if schedules.iter().any(|other| other.overlaps(&schedule)) {
+        return invalid("Overlaps another scheduled recording");
+    }
+    schedules.push(schedule.clone());
+    scheduler.save(&schedules)?;
+    Ok(schedule)
+}
+
+/// Upcoming scheduled recordings, soonest first.
+#[tauri::command]
+pub fn list_schedules(scheduler: State<'_, Scheduler>) -> Vec<Schedule> {
+    let mut schedules = scheduler.schedules.lock().clone();
+    schedules.sort_by_key(|schedule| schedule.start_time);
+    schedules
+}
+
+#[tauri::command]
+pub fn cancel_schedule(scheduler: State<'_, Scheduler>, id: String) -> Result<(), RecorderError> {
+    let mut schedules = scheduler.schedules.lock();
+    let before = schedules.len();
+    schedules.retain(|schedule| schedule.id != id);
+    if schedules.len() == before {
+        return Err(RecorderError::NotFound("Schedule not found".to_string()));
+    }
+    scheduler.save(&schedules)
+}
@@ -0,0 +1,233 @@
+//! Library of finished recordings, kept in `sessions.json` in the app data
+//! dir so the frontend can list, rename and delete past takes.
+
+use crate::tray;
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub name: String,
+    /// One file when mixed, system then mic in multitrack mode
+    pub files: Vec<PathBuf>,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub duration_ms: u64,
+    pub size_bytes: u64,
+    /// `mic` and/or `system`
+    pub sources: Vec<String>,
+}
+
+pub struct SessionManager {
+    store: PathBuf,
+    sessions: Mutex<Vec<Session>>,
+}
+
+/// A fresh session id, milliseconds since the Unix epoch.
+pub fn new_id() -> String {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis())
+        .to_string()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+impl SessionManager {
+    pub fn load(app: &AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("sessions.json");
+        let sessions = std::fs::read_to_string(&store)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            sessions: Mutex::new(sessions),
+        }
+    }
+
+    fn save(&self, sessions: &[Session]) -> Result<(), RecorderError> {
+        let json = serde_json::to_string_pretty(sessions).map_err(|e| e.to_string())?;
+        Ok(std::fs::write(&self.store, json)?)
+    }
+
+    /// Records a finished take. `id` also prefixes its file names.
+    pub fn add(
+        &self,
+        id: String,
+        files: Vec<PathBuf>,
+        duration_ms: u64,
+        sources: Vec<String>,
+    ) -> Result<(), RecorderError> {
+        let size_bytes = files
+            .iter()
+            .filter_map(|file| std::fs::metadata(file).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        let session = Session {
+            name: id.clone(),
+            id,
+            files,
+            created_at: now_secs(),
+            duration_ms,
+            size_bytes,
+            sources,
+        };
+
+        let mut sessions = self.sessions.lock();
+        sessions.push(session);
+        self.save(&sessions)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Session> {
+        self.sessions
+            .lock()
+            .iter()
+            .find(|session| session.id == id)
+            .cloned()
+    }
+
+    /// Up to `count` of the newest recordings, newest first.
+    pub fn recent(&self, count: usize) -> Vec<Session> {
+        let mut sessions = self.newest_first();
+        sessions.truncate(count);
+        sessions
+    }
+
+    fn newest_first(&self) -> Vec<Session> {
+        let mut sessions = self.sessions.lock().clone();
+        sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        sessions
+    }
+}
+
+fn not_found() -> RecorderError {
+    RecorderError::NotFound("Recording not found".to_string())
+}
+
+/// Past recordings, newest first.
+#[tauri::command]
+pub fn list_recordings(manager: State<'_, SessionManager>) -> Vec<Session> {
+    manager.newest_first()
+}
+
+/// Removes the recording's files and forgets it.
+#[tauri::command]
+pub fn delete_recording(
+    app: AppHandle,
+    manager: State<'_, SessionManager>,
+    id: String,
+) -> Result<(), RecorderError> {
+    let mut sessions = manager.sessions.lock();
+    let index = sessions
+        .iter()
+        .position(|session| session.id == id)
+        .ok_or_else(not_found)?;
+    for file in &sessions[index].files {
+        match std::fs::remove_file(file) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    sessions.remove(index);
+    let saved = manager.save(&sessions);
+    drop(sessions);
+    tray::refresh_recent(&app);
+    saved
+}
+
+fn renamed(file: &Path, name: &str) -> PathBuf {
+    // Multitrack files keep their `-system`/`-mic` suffix
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let suffix = ["-system", "-mic"]
+        .into_iter()
+        .find(|suffix| stem.ends_with(suffix))
+        .unwrap_or_default();
+    // Built in one go, `set_extension` would take anything after a dot in
+    // `name` for an extension and cut it off
+    match file.extension() {
+        Some(extension) => file.with_file_name(format!(
+            "{}{}.{}",
+            name,
+            suffix,
+            extension.to_string_lossy()
+        )),
+        None => file.with_file_name(format!("{}{}", name, suffix)),
+    }
+}
+
+/// Gives the recording a new name, renaming its files to match.
+#[tauri::command]
+pub fn rename_recording(
+    app: AppHandle,
+    manager: State<'_, SessionManager>,
+    id: String,
+    name: String,
+) -> Result<Session, RecorderError> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(['/', '\\', ':']) || name.starts_with('.') {
+        return Err(RecorderError::InvalidArgument(
+            "Not a usable file name".to_string(),
+        ));
+    }
+
+    let mut sessions = manager.sessions.lock();
+    let session = sessions
+        .iter_mut()
+        .find(|session| session.id == id)
+        .ok_or_else(not_found)?;
+    let targets: Vec<_> = session
+        .files
+        .iter()
+        .map(|file| renamed(file, name))
+        .collect();
+    let distinct: HashSet<_> = targets.iter().collect();
+    if distinct.len() != targets.len() {
+        return Err(RecorderError::InvalidArgument(
+            "That name would give the recording's files the same name".to_string(),
+        ));
+    }
+    if targets
+        .iter()
+        .zip(&session.files)
+        .any(|(target, file)| target != file && target.exists())
+    {
+        return Err(RecorderError::InvalidArgument(
+            "A recording with that name already exists".to_string(),
+        ));
+    }
+
+    // Whatever got renamed before a failure is still saved, so the store
+    // keeps pointing at the files on disk
+    let mut result = Ok(());
+    for (file, target) in session.files.iter_mut().zip(targets) {
+        if let Err(e) = std::fs::rename(&*file, &target) {
+            result = Err(e.into());
+            break;
+        }
+        *file = target;
+    }
+    if result.is_ok() {
+        session.name = name.to_string();
+    }
+    let session = session.clone();
+    let saved = manager.save(&sessions);
+    drop(sessions);
+    tray::refresh_recent(&app);
+    saved?;
+    result.map(|_| session)
+}
@@ -0,0 +1,108 @@
+//! Menu bar icon that brings back the closed window and lists the latest
+//! takes from the session store, each of which can be opened or revealed in
+//! Finder.
+
+use crate::sessions::SessionManager;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu, SubmenuBuilder};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_opener::OpenerExt;
+
+const TRAY_ID: &str = "main";
+const SHOW_ID: &str = "show-window";
+const RECENT_COUNT: usize = 5;
+// Per-recording item ids are `<action><session id>`
+const OPEN_PREFIX: &str = "recent-open:";
+const REVEAL_PREFIX: &str = "recent-reveal:";
+
+pub struct TrayMenu {
+    recent_menu: Submenu<Wry>,
+}
+
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let show_item = MenuItem::with_id(app, SHOW_ID, "Show Window", true, None::<&str>)?;
+    let recent_menu = Submenu::with_id(app, "recent", "Recent Recordings", true)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit_item = PredefinedMenuItem::quit(app, None)?;
+    let menu = Menu::with_items(app, &[&show_item, &recent_menu, &separator, &quit_item])?;
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(app.default_window_icon().unwrap().clone())
+        .icon_as_template(true)
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id.as_ref()))
+        .build(app)?;
+
+    app.manage(TrayMenu { recent_menu });
+    refresh_recent(app);
+    Ok(())
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    if id == SHOW_ID {
+        // Closing only hides it
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+        return;
+    }
+    let (session_id, reveal) = if let Some(session_id) = id.strip_prefix(OPEN_PREFIX) {
+        (session_id, false)
+    } else if let Some(session_id) = id.strip_prefix(REVEAL_PREFIX) {
+        (session_id, true)
+    } else {
+        return;
+    };
+    // The mix, or the system track of a multitrack take
+    let Some(file) = app
+        .state::<SessionManager>()
+        .get(session_id)
+        .and_then(|session| session.files.first().cloned())
+    else {
+        return;
+    };
+
+    let result = if reveal {
+        app.opener().reveal_item_in_dir(&file)
+    } else {
+        app.opener().open_path(file.to_string_lossy(), None::<&str>)
+    };
+    if let Err(e) = result {
+        eprintln!("Tray action {} failed: {}", id, e);
+    }
+}
+
+/// Rebuilds the recent recordings submenu from the session store.
+pub fn refresh_recent(app: &AppHandle) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    if let Err(e) = rebuild_recent(app, &menu.recent_menu) {
+        eprintln!("Failed to rebuild recent recordings menu: {}", e);
+    }
+}
+
+fn rebuild_recent(app: &AppHandle, submenu: &Submenu<Wry>) -> tauri::Result<()> {
+    for item in submenu.items()? {
+        submenu.remove(&item)?;
+    }
+
+    let sessions = app.state::<SessionManager>().recent(RECENT_COUNT);
+    if sessions.is_empty() {
+        let empty = MenuItem::new(app, "No Recordings", false, None::<&str>)?;
+        submenu.append(&empty)?;
+    }
+    for session in sessions {
+        let item = SubmenuBuilder::new(app, &session.name)
+            .text(format!("{}{}", OPEN_PREFIX, session.id), "Open")
+            .text(
+                format!("{}{}", REVEAL_PREFIX, session.id),
+                "Reveal in Finder",
+            )
+            .build()?;
+        submenu.append(&item)?;
+    }
+    Ok(())
+}
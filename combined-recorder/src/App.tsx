@@ -1,23 +1,174 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import "./App.css";
 
+interface Session {
+  id: string;
+  name: string;
+  files: string[];
+  created_at: number;
+  duration_ms: number;
+  size_bytes: number;
+  sources: string[];
+}
+
+interface Progress {
+  elapsed_ms: number;
+  bytes_written: number;
+  estimated_size: number;
+}
+
+interface RecordingProfile {
+  name: string;
+  format: string;
+  multitrack: boolean;
+}
+
+type PermissionStatus = "granted" | "denied" | "restricted" | "not-determined" | "unsupported";
+
+interface Permissions {
+  ready: boolean;
+  microphone: { status: PermissionStatus; settings_url: string | null };
+  screen_recording: { status: PermissionStatus; settings_url: string | null };
+}
+
+interface RecorderError {
+  kind:
+    | "AlreadyRecording"
+    | "NotRecording"
+    | "Busy"
+    | "DeviceNotFound"
+    | "PermissionDenied"
+    | "InvalidArgument"
+    | "NotFound"
+    | "IoError"
+    | "CaptureFailed"
+    | "Other";
+  code: number;
+  message: string;
+  resource?: "microphone" | "screen-recording";
+  settings_url?: string | null;
+}
+
+function errorMessage(e: unknown) {
+  return (e as RecorderError)?.message ?? String(e);
+}
+
+interface RecordingStopped {
+  reason: "user" | "limit-reached";
+  files: string[];
+}
+
+function formatDuration(ms: number) {
+  const seconds = Math.floor(ms / 1000);
+  return `${Math.floor(seconds / 60)}:${String(seconds % 60).padStart(2, "0")}`;
+}
+
 function App() {
   const [isRecording, setIsRecording] = useState(false);
-  const [filePath, setFilePath] = useState("");
+  const [filePaths, setFilePaths] = useState<string[]>([]);
+  const [multitrack, setMultitrack] = useState(false);
+  const [format, setFormat] = useState("wav");
+  const [bitDepth, setBitDepth] = useState("float32");
   const [error, setError] = useState("");
   const [status, setStatus] = useState("Ready");
+  const [library, setLibrary] = useState<Session[]>([]);
+  const [progress, setProgress] = useState<Progress | null>(null);
+  const [profiles, setProfiles] = useState<RecordingProfile[]>([]);
+  const [profile, setProfile] = useState("");
+  const [permissions, setPermissions] = useState<Permissions | null>(null);
+  const [deniedResource, setDeniedResource] = useState<string | null>(null);
+
+  async function refreshLibrary() {
+    try {
+      setLibrary(await invoke<Session[]>("list_recordings"));
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  }
+
+  useEffect(() => {
+    refreshLibrary();
+    invoke<Permissions>("check_permissions")
+      .then(setPermissions)
+      .catch((e) => setError(errorMessage(e)));
+    invoke<RecordingProfile[]>("list_profiles")
+      .then(setProfiles)
+      .catch((e) => setError(errorMessage(e)));
+    const unlisten = listen<Progress>("recording-progress", (event) => {
+      setProgress(event.payload);
+    });
+    const unlistenStopped = listen<RecordingStopped>("recording-stopped", (event) => {
+      if (event.payload.reason !== "limit-reached") return;
+      setIsRecording(false);
+      setFilePaths(event.payload.files);
+      setStatus("Saved (limit reached)");
+      refreshLibrary();
+    });
+    return () => {
+      unlisten.then((f) => f());
+      unlistenStopped.then((f) => f());
+    };
+  }, []);
+
+  async function renameRecording(session: Session) {
+    const name = window.prompt("Rename recording", session.name);
+    if (!name || name === session.name) return;
+    try {
+      setError("");
+      await invoke("rename_recording", { id: session.id, name });
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+    refreshLibrary();
+  }
+
+  async function deleteRecording(session: Session) {
+    try {
+      setError("");
+      await invoke("delete_recording", { id: session.id });
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+    refreshLibrary();
+  }
+
+  async function requestPermissions() {
+    try {
+      setPermissions(await invoke<Permissions>("request_permissions"));
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  }
+
+  // Off macOS nothing can be checked, so don't nag
+  const missingPermissions =
+    permissions && !permissions.ready
+      ? [
+          ["Microphone", permissions.microphone.status],
+          ["Screen Recording", permissions.screen_recording.status],
+        ].filter(([, status]) => status !== "granted" && status !== "unsupported")
+      : [];
 
   async function startRecording() {
     try {
       setError("");
+      setDeniedResource(null);
       setStatus("Starting...");
-      const path = await invoke<string>("start_recording");
+      setProgress(null);
+      const paths = profile
+        ? await invoke<string[]>("start_recording_with_profile", { name: profile })
+        : await invoke<string[]>("start_recording", { multitrack, format, bitDepth });
       setIsRecording(true);
-      setFilePath(path);
-      setStatus("Recording Mic + System Audio");
+      setFilePaths(paths);
+      setStatus(profile ? `Recording (${profile})` : "Recording Mic + System Audio");
     } catch (e) {
-      setError(String(e));
+      const startError = e as RecorderError;
+      setError(errorMessage(e));
+      if (startError.kind === "PermissionDenied") {
+        setDeniedResource(startError.resource ?? null);
+      }
       setStatus("Error");
     }
   }
@@ -25,14 +176,15 @@ function App() {
   async function stopRecording() {
     try {
       setStatus("Stopping...");
-      const path = await invoke<string>("stop_recording");
+      const paths = await invoke<string[]>("stop_recording");
       setIsRecording(false);
-      setFilePath(path);
+      setFilePaths(paths);
       setStatus("Saved");
     } catch (e) {
-      setError(String(e));
+      setError(errorMessage(e));
       setStatus("Error");
     }
+    refreshLibrary();
   }
 
   return (
@@ -40,11 +192,29 @@ function App() {
       <h1>Combined Recorder</h1>
       <p className="description">Captures Microphone and System Audio into one file.</p>
 
+      {missingPermissions.length > 0 && (
+        <div className="permissions-box">
+          <p>
+            Allow {missingPermissions.map(([name]) => name).join(" and ")} access
+            before recording. If nothing pops up, turn it on under System Settings ›
+            Privacy & Security, then restart the app.
+          </p>
+          <button onClick={requestPermissions}>Grant Access</button>
+        </div>
+      )}
+
       <div className="card">
         <div className="status-badge">
           <span className={`dot ${isRecording ? "active" : ""}`}></span>
           {status}
         </div>
+
+        {isRecording && progress && (
+          <div className="progress">
+            {formatDuration(progress.elapsed_ms)} ·{" "}
+            {(progress.estimated_size / 1_000_000).toFixed(1)} MB
+          </div>
+        )}
         
         {isRecording ? (
           <button onClick={stopRecording} className="stop-btn">
@@ -55,16 +225,100 @@ function App() {
             Start Recording
           </button>
         )}
+
+        {profiles.length > 0 && (
+          <select
+            className="profile-select"
+            value={profile}
+            disabled={isRecording}
+            onChange={(e) => setProfile(e.currentTarget.value)}
+          >
+            <option value="">No profile</option>
+            {profiles.map((p) => (
+              <option value={p.name} key={p.name}>{p.name}</option>
+            ))}
+          </select>
+        )}
+
+        <label className="multitrack-toggle">
+          <input
+            type="checkbox"
+            checked={multitrack}
+            disabled={isRecording || profile !== ""}
+            onChange={(e) => setMultitrack(e.currentTarget.checked)}
+          />
+          Separate mic and system tracks
+        </label>
+
+        <select
+          className="format-select"
+          value={format}
+          disabled={isRecording || profile !== ""}
+          onChange={(e) => setFormat(e.currentTarget.value)}
+        >
+          <option value="wav">WAV</option>
+          <option value="flac">FLAC</option>
+          <option value="mp3">MP3</option>
+          <option value="aac">AAC</option>
+        </select>
+
+        <select
+          className="format-select"
+          value={bitDepth}
+          disabled={isRecording || profile !== "" || format !== "wav"}
+          onChange={(e) => setBitDepth(e.currentTarget.value)}
+        >
+          <option value="float32">32-bit float</option>
+          <option value="pcm24">24-bit</option>
+          <option value="pcm16">16-bit</option>
+        </select>
       </div>
 
-      {filePath && (
+      {filePaths.length > 0 && (
         <div className="file-info fade-in">
           <p className="label">Latest Recording:</p>
-          <code className="path">{filePath}</code>
+          {filePaths.map((path) => (
+            <code className="path" key={path}>{path}</code>
+          ))}
         </div>
       )}
 
-      {error && <div className="error-box">{error}</div>}
+      {library.length > 0 && (
+        <div className="file-info library">
+          <p className="label">Recordings</p>
+          {library.map((session) => (
+            <div className="library-item" key={session.id}>
+              <div>
+                <span className="library-name">{session.name}</span>
+                <span className="library-meta">
+                  {new Date(session.created_at * 1000).toLocaleString()} ·{" "}
+                  {formatDuration(session.duration_ms)} ·{" "}
+                  {(session.size_bytes / 1_000_000).toFixed(1)} MB
+                </span>
+              </div>
+              <div className="library-actions">
+                <button onClick={() => renameRecording(session)}>Rename</button>
+                <button onClick={() => deleteRecording(session)}>Delete</button>
+              </div>
+            </div>
+          ))}
+        </div>
+      )}
+
+      {error && (
+        <div className="error-box">
+          {error}
+          {deniedResource && (
+            <button
+              onClick={() =>
+                invoke("open_permission_settings", { resource: deniedResource })
+              }
+            >
+              Open System Settings
+            </button>
+          )}
+        </div>
+      )}
       
       <div className="info-footer">
         <p>Uses ScreenCaptureKit & CPAL</p>
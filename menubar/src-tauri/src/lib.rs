@@ -1,38 +1,205 @@
+mod recorder;
+
+use recorder::{Recorder, Status};
+use recorder_core::tray_icon::{self, ICON_SIZE};
+use std::time::Duration;
 use tauri::{
-    menu::{Menu, MenuItem},
+    image::Image,
+    menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::TrayIconBuilder,
+    AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent, Wry,
 };
 use tauri_plugin_dialog::DialogExt;
 
+const MAIN_WINDOW: &str = "main";
+const TRAY_ID: &str = "main";
+
+struct TrayItems {
+    start: MenuItem<Wry>,
+    pause: MenuItem<Wry>,
+    stop: MenuItem<Wry>,
+    idle_icon: Image<'static>,
+    recording_icon: Image<'static>,
+}
+
+/// The red dot shown while recording.
+fn render_recording_icon() -> Image<'static> {
+    Image::new_owned(tray_icon::recording_dot(1.0), ICON_SIZE, ICON_SIZE)
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Shows the elapsed time next to the icon while there's a recording.
+fn update_tray_title(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let recorder = app.state::<Recorder>();
+    let title = match recorder.status() {
+        Status::Idle => None,
+        Status::Recording => Some(format_elapsed(recorder.elapsed())),
+        Status::Paused => Some(format!("{} paused", format_elapsed(recorder.elapsed()))),
+    };
+    let _ = tray.set_title(title);
+}
+
+/// Brings the icon, title and menu in line with the recorder.
+fn refresh_tray(app: &AppHandle) {
+    let (Some(items), Some(tray)) = (app.try_state::<TrayItems>(), app.tray_by_id(TRAY_ID)) else {
+        return;
+    };
+    let status = app.state::<Recorder>().status();
+    let recording = status != Status::Idle;
+
+    let _ = items.start.set_enabled(!recording);
+    let _ = items.pause.set_enabled(recording);
+    let _ = items.pause.set_text(if status == Status::Paused {
+        "Resume"
+    } else {
+        "Pause"
+    });
+    let _ = items.stop.set_enabled(recording);
+
+    let icon = if recording {
+        &items.recording_icon
+    } else {
+        &items.idle_icon
+    };
+    let _ = tray.set_icon(Some(icon.clone()));
+    let _ = tray.set_icon_as_template(!recording);
+    update_tray_title(app);
+}
+
+fn handle_recording_action(app: &AppHandle, id: &str) {
+    let result = match id {
+        "start" => recorder::start_recording(app.clone(), app.state(), None),
+        "pause" if app.state::<Recorder>().status() == Status::Paused => {
+            recorder::resume_recording(app.clone(), app.state())
+        }
+        "pause" => recorder::pause_recording(app.clone(), app.state()),
+        "stop" => recorder::stop_recording(app.clone(), app.state()).map(|_| ()),
+        _ => return,
+    };
+    if let Err(e) = result {
+        eprintln!("Tray action {} failed: {}", id, e);
+    }
+}
+
+// The app runs as an Accessory with no windows in the config, so the main
+// window is only created the first time it's asked for.
+fn toggle_main_window(app: &AppHandle) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        if window.is_visible()? {
+            window.hide()?;
+        } else {
+            window.show()?;
+            window.set_focus()?;
+        }
+        return Ok(());
+    }
+
+    let window = WebviewWindowBuilder::new(app, MAIN_WINDOW, WebviewUrl::default())
+        .title("Menubar")
+        .inner_size(400.0, 300.0)
+        .center()
+        .build()?;
+    window.set_focus()?;
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
+        .plugin(recorder_updater::plugin())
+        .manage(Recorder::default())
+        .manage(recorder_updater::PendingUpdate::default())
         .setup(|app| {
+            recorder_updater::check_in_background(app.handle(), "menubar");
+
             #[cfg(target_os = "macos")]
             app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
+            let toggle_window =
+                MenuItem::with_id(app, "toggle-window", "Show/Hide Window", true, None::<&str>)?;
+            let start = MenuItem::with_id(app, "start", "Start Recording", true, None::<&str>)?;
+            let pause = MenuItem::with_id(app, "pause", "Pause", false, None::<&str>)?;
+            let stop = MenuItem::with_id(app, "stop", "Stop Recording", false, None::<&str>)?;
             let foo = MenuItem::with_id(app, "foo", "Foo", true, None::<&str>)?;
             let bar = MenuItem::with_id(app, "bar", "Bar", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&foo, &bar])?;
+            let menu = Menu::with_items(
+                app,
+                &[
+                    &toggle_window,
+                    &PredefinedMenuItem::separator(app)?,
+                    &start,
+                    &pause,
+                    &stop,
+                    &PredefinedMenuItem::separator(app)?,
+                    &foo,
+                    &bar,
+                ],
+            )?;
 
-            TrayIconBuilder::new()
-                .icon(app.default_window_icon().unwrap().clone())
+            let idle_icon = app.default_window_icon().unwrap().clone();
+            TrayIconBuilder::with_id(TRAY_ID)
+                .icon(idle_icon.clone())
                 .icon_as_template(true)
                 .menu(&menu)
                 .show_menu_on_left_click(true)
                 .on_menu_event(|app, event| match event.id.as_ref() {
+                    "toggle-window" => {
+                        if let Err(e) = toggle_main_window(app) {
+                            eprintln!("Failed to toggle window: {}", e);
+                        }
+                    }
                     "foo" | "bar" => {
                         app.dialog()
                             .message("Foo, bar!")
                             .blocking_show();
                     }
-                    _ => {}
+                    id => handle_recording_action(app, id),
                 })
                 .build(app)?;
 
+            app.manage(TrayItems {
+                start,
+                pause,
+                stop,
+                idle_icon,
+                recording_icon: render_recording_icon(),
+            });
+
+            // Keeps the elapsed time in the menu bar ticking
+            let handle = app.handle().clone();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(Duration::from_secs(1));
+                if handle.state::<Recorder>().status() == Status::Recording {
+                    update_tray_title(&handle);
+                }
+            });
+
             Ok(())
         })
+        .on_window_event(|window, event| {
+            // Closing the window only hides it, the app keeps living in the tray
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == MAIN_WINDOW {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            recorder::start_recording,
+            recorder::pause_recording,
+            recorder::resume_recording,
+            recorder::stop_recording,
+            recorder::install_update
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
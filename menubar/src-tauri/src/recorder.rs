@@ -0,0 +1,132 @@
+//! Stand-in for a real recorder that only keeps time, so the tray has a
+//! recording to show. The tray menu calls the same commands a window would.
+
+use recorder_core::{RecorderError, RecordingOptions};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Idle,
+    Recording,
+    Paused,
+}
+
+#[derive(Default)]
+struct Clock {
+    started_at: Option<Instant>,
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+}
+
+#[derive(Default)]
+pub struct Recorder(Mutex<Clock>);
+
+impl Recorder {
+    pub fn status(&self) -> Status {
+        let clock = self.0.lock().unwrap();
+        match (clock.started_at, clock.paused_at) {
+            (None, _) => Status::Idle,
+            (Some(_), None) => Status::Recording,
+            (Some(_), Some(_)) => Status::Paused,
+        }
+    }
+
+    /// Time recorded so far, pauses excluded.
+    pub fn elapsed(&self) -> Duration {
+        let clock = self.0.lock().unwrap();
+        let Some(started_at) = clock.started_at else {
+            return Duration::ZERO;
+        };
+        let paused = clock.paused_total + clock.paused_at.map_or(Duration::ZERO, |at| at.elapsed());
+        started_at.elapsed().saturating_sub(paused)
+    }
+}
+
+/// Takes the same `options` as the real recorders so callers can share one
+/// call, but with no file to write only checks them.
+#[tauri::command]
+pub fn start_recording(
+    app: AppHandle,
+    recorder: State<'_, Recorder>,
+    options: Option<RecordingOptions>,
+) -> Result<(), RecorderError> {
+    if let Some(options) = options {
+        options.validate().map_err(RecorderError::InvalidArgument)?;
+    }
+    {
+        let mut clock = recorder.0.lock().unwrap();
+        if clock.started_at.is_some() {
+            return Err(RecorderError::AlreadyRecording);
+        }
+        *clock = Clock {
+            started_at: Some(Instant::now()),
+            ..Clock::default()
+        };
+    }
+    crate::refresh_tray(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pause_recording(app: AppHandle, recorder: State<'_, Recorder>) -> Result<(), RecorderError> {
+    {
+        let mut clock = recorder.0.lock().unwrap();
+        if clock.started_at.is_none() {
+            return Err(RecorderError::NotRecording);
+        }
+        if clock.paused_at.is_some() {
+            return Err(RecorderError::Other("Already paused".to_string()));
+        }
+        clock.paused_at = Some(Instant::now());
+    }
+    crate::refresh_tray(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_recording(
+    app: AppHandle,
+    recorder: State<'_, Recorder>,
+) -> Result<(), RecorderError> {
+    {
+        let mut clock = recorder.0.lock().unwrap();
+        let Some(paused_at) = clock.paused_at.take() else {
+            return Err(RecorderError::Other("Not paused".to_string()));
+        };
+        clock.paused_total += paused_at.elapsed();
+    }
+    crate::refresh_tray(&app);
+    Ok(())
+}
+
+/// Stops and returns how long was recorded, in milliseconds.
+#[tauri::command]
+pub fn stop_recording(app: AppHandle, recorder: State<'_, Recorder>) -> Result<u64, RecorderError> {
+    let elapsed = recorder.elapsed();
+    {
+        let mut clock = recorder.0.lock().unwrap();
+        if clock.started_at.is_none() {
+            return Err(RecorderError::NotRecording);
+        }
+        *clock = Clock::default();
+    }
+    recorder_updater::resume_deferred_install(&app);
+    crate::refresh_tray(&app);
+    Ok(elapsed.as_millis() as u64)
+}
+
+/// Installs the update announced by `update-available`, once the recording
+/// stops if one is running. Returns whether the install started right away.
+#[tauri::command]
+pub fn install_update(
+    app: AppHandle,
+    recorder: State<'_, Recorder>,
+) -> Result<bool, RecorderError> {
+    if recorder_updater::pending_version(&app).is_none() {
+        return Err(RecorderError::NotFound("No update is pending".to_string()));
+    }
+    let recording = recorder.status() != Status::Idle;
+    Ok(recorder_updater::install_when_idle(&app, recording))
+}
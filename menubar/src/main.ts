@@ -1 +1 @@
-// No-op: this app runs entirely from the system tray
+// No-op: the window is static, everything else runs from the system tray
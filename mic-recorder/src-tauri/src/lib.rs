@@ -1,44 +1,53 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use recorder_core::{recording_lock, RecorderError, RecordingOptions, SampleEncoder};
 use std::sync::{Arc, Mutex};
-use tauri::State;
+use tauri::{AppHandle, State};
 
 pub struct AppState {
     pub stream: Arc<Mutex<Option<cpal::Stream>>>,
     pub recording_path: Arc<Mutex<Option<String>>>,
+    recording_lock: Mutex<Option<recording_lock::RecordingLock>>,
 }
 
+/// Records the default mic as it delivers, so of `options` only the bit
+/// depth applies.
 #[tauri::command]
-fn start_recording(state: State<'_, AppState>) -> Result<String, String> {
+fn start_recording(
+    state: State<'_, AppState>,
+    options: Option<RecordingOptions>,
+) -> Result<String, RecorderError> {
+    if state.stream.lock().unwrap().is_some() {
+        return Err(RecorderError::AlreadyRecording);
+    }
     let host = cpal::default_host();
     let device = host
         .default_input_device()
-        .ok_or("No input device available")?;
+        .ok_or_else(|| RecorderError::DeviceNotFound("No input device available".to_string()))?;
     
     let config = device
         .default_input_config()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| RecorderError::CaptureFailed(e.to_string()))?;
 
-    let spec = hound::WavSpec {
-        channels: config.channels() as u16,
-        sample_rate: config.sample_rate(),
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
-    };
+    let bit_depth = options.unwrap_or_default().file_bit_depth();
+    let spec = bit_depth.spec(config.sample_rate(), config.channels());
 
     let temp_dir = std::env::temp_dir();
     let path = temp_dir.join("recorded_audio.wav");
     let path_str = path.to_string_lossy().to_string();
+    let lock = recording_lock::acquire("mic-recorder", &path).map_err(RecorderError::Busy)?;
     
-    let writer = hound::WavWriter::create(&path, spec).map_err(|e| e.to_string())?;
+    let writer = hound::WavWriter::create(&path, spec)
+        .map_err(|e| RecorderError::IoError(e.to_string()))?;
     let writer = Arc::new(Mutex::new(Some(writer)));
 
     let writer_clone = writer.clone();
+    let mut encoder = SampleEncoder::new(bit_depth);
     let stream = device.build_input_stream(
         &config.into(),
         move |data: &[f32], _: &cpal::InputCallbackInfo| {
             if let Some(ref mut w) = *writer_clone.lock().unwrap() {
                 for &sample in data {
-                    w.write_sample(sample).ok();
+                    encoder.write(w, sample).ok();
                 }
             }
         },
@@ -46,29 +55,45 @@ fn start_recording(state: State<'_, AppState>) -> Result<String, String> {
             eprintln!("An error occurred on stream: {}", err);
         },
         None,
-    ).map_err(|e| e.to_string())?;
+    ).map_err(|e| RecorderError::CaptureFailed(e.to_string()))?;
 
-    stream.play().map_err(|e| e.to_string())?;
+    stream
+        .play()
+        .map_err(|e| RecorderError::CaptureFailed(e.to_string()))?;
 
     let mut state_stream = state.stream.lock().unwrap();
     *state_stream = Some(stream);
 
     let mut state_path = state.recording_path.lock().unwrap();
     *state_path = Some(path_str.clone());
+    *state.recording_lock.lock().unwrap() = Some(lock);
     
     Ok(path_str)
 }
 
 #[tauri::command]
-fn stop_recording(state: State<'_, AppState>) -> Result<String, String> {
+fn stop_recording(app: AppHandle, state: State<'_, AppState>) -> Result<String, RecorderError> {
     let mut state_stream = state.stream.lock().unwrap();
     if let Some(stream) = state_stream.take() {
         drop(stream);
+        state.recording_lock.lock().unwrap().take();
+        recorder_updater::resume_deferred_install(&app);
         let path = state.recording_path.lock().unwrap().clone().unwrap_or_default();
         Ok(path)
     } else {
-        Err("Not recording".to_string())
+        Err(RecorderError::NotRecording)
+    }
+}
+
+/// Installs the update announced by `update-available`, once the recording
+/// stops if one is running. Returns whether the install started right away.
+#[tauri::command]
+fn install_update(app: AppHandle, state: State<'_, AppState>) -> Result<bool, RecorderError> {
+    if recorder_updater::pending_version(&app).is_none() {
+        return Err(RecorderError::NotFound("No update is pending".to_string()));
     }
+    let recording = state.stream.lock().unwrap().is_some();
+    Ok(recorder_updater::install_when_idle(&app, recording))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -77,9 +102,21 @@ pub fn run() {
         .manage(AppState {
             stream: Arc::new(Mutex::new(None)),
             recording_path: Arc::new(Mutex::new(None)),
+            recording_lock: Mutex::new(None),
         })
+        .manage(recorder_updater::PendingUpdate::default())
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![start_recording, stop_recording])
+        .plugin(tauri_plugin_window_state::Builder::default().build())
+        .plugin(recorder_updater::plugin())
+        .setup(|app| {
+            recorder_updater::check_in_background(app.handle(), "mic-recorder");
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            start_recording,
+            stop_recording,
+            install_update
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -2,6 +2,12 @@ import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import "./App.css";
 
+interface RecorderError {
+  kind: string;
+  code: number;
+  message: string;
+}
+
 function App() {
   const [isRecording, setIsRecording] = useState(false);
   const [status, setStatus] = useState("Ready");
@@ -22,7 +28,7 @@ function App() {
       }
     } catch (error) {
       console.error(error);
-      setStatus(`Error: ${error}`);
+      setStatus(`Error: ${(error as RecorderError)?.message ?? error}`);
     }
   }
 
@@ -1,19 +1,23 @@
 use anyhow::Result;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use hound::{WavSpec, WavWriter};
 use parking_lot::Mutex;
+use recorder_core::{
+    loudness, AppState, CpalSource, FinalizeOptions, MixFn, Mixer, RecordingOptions,
+    ScreenCaptureSource, WavFile,
+};
 use screencapturekit::prelude::*;
-use std::collections::VecDeque;
-use std::fs::File;
-use std::io::BufWriter;
 use std::path::PathBuf;
-use std::sync::Arc;
 use tauri::{AppHandle, Emitter, Manager, State, WebviewUrl};
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 use chrono::Local;
 
+const DEFAULT_SHORTCUT: &str = if cfg!(target_os = "macos") {
+    "Command+Shift+R"
+} else {
+    "Ctrl+Shift+R"
+};
+
 #[cfg(target_os = "macos")]
-use tauri_nspanel::{tauri_panel, PanelBuilder, PanelLevel};
+use tauri_nspanel::{tauri_panel, CollectionBehavior, PanelBuilder, PanelLevel};
 
 #[cfg(target_os = "macos")]
 tauri_panel!(panel!(RecordingOverlayPanel {
@@ -25,119 +29,6 @@ tauri_panel!(panel!(RecordingOverlayPanel {
     }
 }));
 
-struct SharedRecorder {
-    system_stream: Option<SCStream>,
-    mic_stream: Option<cpal::Stream>,
-    file_path: Option<PathBuf>,
-    writer: Option<Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>>,
-    
-    // Buffers for mixing
-    system_buffer: Arc<Mutex<VecDeque<f32>>>,
-    mic_buffer: Arc<Mutex<VecDeque<f32>>>,
-}
-
-pub struct AppState(Mutex<SharedRecorder>);
-
-impl AppState {
-    pub fn new() -> Self {
-        Self(Mutex::new(SharedRecorder {
-            system_stream: None,
-            mic_stream: None,
-            file_path: None,
-            writer: None,
-            system_buffer: Arc::new(Mutex::new(VecDeque::new())),
-            mic_buffer: Arc::new(Mutex::new(VecDeque::new())),
-        }))
-    }
-
-    pub fn is_recording(&self) -> bool {
-        let recorder = self.0.lock();
-        recorder.system_stream.is_some() || recorder.mic_stream.is_some()
-    }
-}
-
-struct Mixer {
-    system_buffer: Arc<Mutex<VecDeque<f32>>>,
-    mic_buffer: Arc<Mutex<VecDeque<f32>>>,
-    writer: Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>,
-}
-
-impl Mixer {
-    fn mix_available(&self) {
-        let mut sys = self.system_buffer.lock();
-        let mut mic = self.mic_buffer.lock();
-        let mut writer_lock = self.writer.lock();
-        
-        if let Some(writer) = writer_lock.as_mut() {
-            // We assume stereo (2 channels) for output
-            while sys.len() >= 2 && mic.len() >= 2 {
-                let s1 = sys.pop_front().unwrap();
-                let s2 = sys.pop_front().unwrap();
-                let m1 = mic.pop_front().unwrap();
-                let m2 = mic.pop_front().unwrap();
-                
-                // Simple mixing: average the samples
-                let mixed_1 = (s1 + m1) / 2.0;
-                let mixed_2 = (s2 + m2) / 2.0;
-                
-                let _ = writer.write_sample(mixed_1);
-                let _ = writer.write_sample(mixed_2);
-            }
-        }
-    }
-}
-
-// Handler for ScreenCaptureKit (System Audio)
-struct SystemAudioOutputHandler {
-    buffer: Arc<Mutex<VecDeque<f32>>>,
-    mixer_trigger: Arc<Mixer>,
-}
-
-impl SCStreamOutputTrait for SystemAudioOutputHandler {
-    fn did_output_sample_buffer(&self, sample: CMSampleBuffer, of_type: SCStreamOutputType) {
-        if let SCStreamOutputType::Audio = of_type {
-            if let Some(buffer_list) = sample.audio_buffer_list() {
-                let mut samples = Vec::new();
-                let num_buffers = buffer_list.num_buffers();
-                
-                if num_buffers == 1 {
-                    let buffer = buffer_list.get(0).unwrap();
-                    let data = buffer.data();
-                    let f32_samples: &[f32] = unsafe {
-                        std::slice::from_raw_parts(data.as_ptr() as *const f32, data.len() / 4)
-                    };
-                    samples.extend_from_slice(f32_samples);
-                } else {
-                    let mut channel_data = Vec::new();
-                    for i in 0..num_buffers {
-                        let buffer = buffer_list.get(i).unwrap();
-                        let data = buffer.data();
-                        let f32_samples: &[f32] = unsafe {
-                            std::slice::from_raw_parts(data.as_ptr() as *const f32, data.len() / 4)
-                        };
-                        channel_data.push(f32_samples);
-                    }
-                    if !channel_data.is_empty() {
-                        let len = channel_data[0].len();
-                        for i in 0..len {
-                            for channel in &channel_data {
-                                if i < channel.len() {
-                                    samples.push(channel[i]);
-                                }
-                            }
-                        }
-                    }
-                }
-
-                if !samples.is_empty() {
-                    self.buffer.lock().extend(samples);
-                    self.mixer_trigger.mix_available();
-                }
-            }
-        }
-    }
-}
-
 fn update_overlay(app: &AppHandle, is_recording: bool) {
     let _ = app.emit("recording-status", is_recording);
     if let Some(window) = app.get_webview_window("overlay") {
@@ -149,10 +40,16 @@ fn update_overlay(app: &AppHandle, is_recording: bool) {
     }
 }
 
+/// `options` picks the file's bit depth, sample rate and channels, 32-bit
+/// float 48 kHz stereo by default and 16-bit for mono voice memos.
 #[tauri::command]
-async fn start_recording(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+async fn start_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    options: Option<RecordingOptions>,
+) -> Result<String, String> {
     let mut recorder = state.0.lock();
-    if recorder.system_stream.is_some() || recorder.mic_stream.is_some() {
+    if recorder.is_recording() {
         return Err("Already recording".to_string());
     }
 
@@ -164,156 +61,176 @@ async fn start_recording(app: AppHandle, state: State<'_, AppState>) -> Result<S
     let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
     let file_path = audio_dir.join(format!("recording_{}.wav", timestamp));
 
-    let spec = WavSpec {
-        channels: 2,
-        sample_rate: 48000,
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
-    };
-
-    let writer = WavWriter::create(&file_path, spec).map_err(|e| e.to_string())?;
-    let writer_arc = Arc::new(Mutex::new(Some(writer)));
-    
-    let mixer = Arc::new(Mixer {
-        system_buffer: recorder.system_buffer.clone(),
-        mic_buffer: recorder.mic_buffer.clone(),
-        writer: writer_arc.clone(),
-    });
-
     // --- SETUP SYSTEM AUDIO (ScreenCaptureKit) ---
     let content = SCShareableContent::get().map_err(|e| e.to_string())?;
     let display = content.displays().first().cloned().ok_or_else(|| "No display found".to_string())?;
     let filter = SCContentFilter::create().with_display(&display).with_excluding_windows(&[]).build();
-    let config = SCStreamConfiguration::new()
-        .with_captures_audio(true)
-        .with_sample_rate(48000)
-        .with_channel_count(2);
+    let options = options.unwrap_or_default();
+    let config = ScreenCaptureSource::configure(SCStreamConfiguration::new(), options.sample_rate);
 
-    let system_handler = SystemAudioOutputHandler {
-        buffer: recorder.system_buffer.clone(),
-        mixer_trigger: mixer.clone(),
-    };
-
-    let mut system_stream = SCStream::new(&filter, &config);
-    system_stream.add_output_handler(system_handler, SCStreamOutputType::Audio);
-    system_stream.start_capture().map_err(|e| e.to_string())?;
+    let system_source = ScreenCaptureSource::new(&filter, &config, options.sample_rate);
 
     // --- SETUP MIC AUDIO (cpal) ---
-    let host = cpal::default_host();
-    let device = host.default_input_device().ok_or("No input device available")?;
-    
-    let supported_configs = device.supported_input_configs()
-        .map_err(|e| e.to_string())?;
-    
-    // --- MIC CONFIGURATION ---
-    let mic_config_support = supported_configs
-        .filter(|c| c.sample_format() == cpal::SampleFormat::F32)
-        .find(|c| c.min_sample_rate() <= 48000 && c.max_sample_rate() >= 48000)
-        .or_else(|| device.supported_input_configs().ok()?.next())
-        .ok_or("Could not find any suitable input config")?;
-    
-    let mic_channels = mic_config_support.channels();
-    let mic_source_sr = if mic_config_support.min_sample_rate() <= 48000 && mic_config_support.max_sample_rate() >= 48000 {
-        48000
-    } else {
-        mic_config_support.max_sample_rate()
-    };
-    
-    let mic_config = mic_config_support.with_sample_rate(mic_source_sr);
-    eprintln!("Selected Mic: {} channels, {} Hz", mic_channels, mic_source_sr);
-
-    let mic_buffer_clone = recorder.mic_buffer.clone();
-    let mixer_clone = mixer.clone();
-    
-    // Resampling state for nearest-neighbor interpolation
-    let mut total_in = 0u64;
-    let mut total_out = 0u64;
-    let target_sr_val = 48000.0f64;
-    let source_sr_val = mic_source_sr as f64;
-
-    let mic_stream = device.build_input_stream(
-        &mic_config.into(),
-        move |data: &[f32], _: &cpal::InputCallbackInfo| {
-            let mut mic_buf = mic_buffer_clone.lock();
-            for frame in data.chunks(mic_channels as usize) {
-                total_in += 1;
-                // Resample to 48000 Hz by repeating or skipping samples
-                while (total_out as f64 * source_sr_val) < (total_in as f64 * target_sr_val) {
-                    if mic_channels == 1 {
-                        mic_buf.push_back(frame[0]);
-                        mic_buf.push_back(frame[0]);
-                    } else if mic_channels >= 2 {
-                        mic_buf.push_back(frame[0]);
-                        mic_buf.push_back(frame[1]);
-                    }
-                    total_out += 1;
-                }
-            }
-            drop(mic_buf);
-            mixer_clone.mix_available();
-        },
-        move |err| {
-            eprintln!("Mic stream error: {}", err);
-        },
-        None,
-    ).map_err(|e| e.to_string())?;
-
-    mic_stream.play().map_err(|e| e.to_string())?;
+    let mic_source = CpalSource::default_input(options.sample_rate)?;
 
-    recorder.system_stream = Some(system_stream);
-    recorder.mic_stream = Some(mic_stream);
-    recorder.file_path = Some(file_path.clone());
-    recorder.writer = Some(writer_arc);
+    let mix: MixFn = Box::new(|mixer: &Mixer, writer: &mut WavFile| {
+        mixer.mix_into(writer, |_, _, _| {})
+    });
+    recorder.start(
+        &file_path,
+        &options,
+        Box::new(system_source),
+        Box::new(mic_source),
+        mix,
+    )?;
 
     update_overlay(&app, true);
 
     Ok(file_path.to_string_lossy().to_string())
 }
 
+/// With `finalize_options.normalize` the file is brought to `target_lufs`,
+/// reporting `normalize-progress` from 0 to 1 as it goes. A failed
+/// normalization is reported as `normalize-failed` and the file kept as is.
 #[tauri::command]
-async fn stop_recording(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
-    let mut recorder = state.0.lock();
-    
-    if let Some(stream) = recorder.system_stream.take() {
-        let _ = stream.stop_capture();
-    }
-    
-    if let Some(stream) = recorder.mic_stream.take() {
-        let _ = stream.pause(); 
-    }
-
-    if let Some(writer_arc) = recorder.writer.take() {
-        let mut writer_lock = writer_arc.lock();
-        if let Some(writer) = writer_lock.take() {
-            writer.finalize().map_err(|e| e.to_string())?;
-        }
-    }
-
-    // Clear buffers
-    recorder.system_buffer.lock().clear();
-    recorder.mic_buffer.lock().clear();
+async fn stop_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    finalize_options: Option<FinalizeOptions>,
+) -> Result<String, String> {
+    let path = state.0.lock().stop()?;
 
     update_overlay(&app, false);
 
-    if let Some(path) = &recorder.file_path {
-        return Ok(path.to_string_lossy().to_string());
+    let finalize_options = finalize_options.unwrap_or_default();
+    if finalize_options.normalize {
+        // Rewrites the whole file, so not on the command thread
+        let normalize_app = app.clone();
+        let normalize_path = path.clone();
+        let normalized = tauri::async_runtime::spawn_blocking(move || {
+            loudness::normalize(&normalize_path, finalize_options.target_lufs, |progress| {
+                let _ = normalize_app.emit("normalize-progress", progress);
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+        if let Err(e) = normalized {
+            eprintln!("Failed to normalize {}: {}", path.display(), e);
+            let _ = app.emit("normalize-failed", e);
+        }
     }
-    
-    Err("Not recording".to_string())
+
+    Ok(path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
 async fn toggle_recording(app: AppHandle, state: State<'_, AppState>) -> Result<bool, String> {
     let is_recording = state.is_recording();
     if is_recording {
-        stop_recording(app, state).await?;
+        stop_recording(app, state, None).await?;
         Ok(false)
     } else {
-        start_recording(app, state).await?;
+        start_recording(app, state, None).await?;
         Ok(true)
     }
 }
 
+/// The accelerator that toggles recording, kept in `shortcut.json` in the
+/// app config dir.
+pub struct RecordingShortcut {
+    store: PathBuf,
+    accelerator: Mutex<String>,
+}
+
+impl RecordingShortcut {
+    fn load(app: &AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_config_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("shortcut.json");
+        // A saved one that no longer parses falls back to the default
+        let accelerator = std::fs::read_to_string(&store)
+            .ok()
+            .and_then(|json| serde_json::from_str::<String>(&json).ok())
+            .filter(|accelerator| accelerator.parse::<Shortcut>().is_ok())
+            .unwrap_or_else(|| DEFAULT_SHORTCUT.to_string());
+        Self {
+            store,
+            accelerator: Mutex::new(accelerator),
+        }
+    }
+
+    fn save(&self, accelerator: &str) -> Result<(), String> {
+        if let Some(dir) = self.store.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string(accelerator).map_err(|e| e.to_string())?;
+        std::fs::write(&self.store, json).map_err(|e| e.to_string())
+    }
+}
+
+/// Registers `accelerator` to toggle recording. Fails when another app or the
+/// system already holds it.
+fn register_shortcut(app: &AppHandle, accelerator: &str) -> Result<(), String> {
+    let shortcut = accelerator
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Not a valid shortcut: {}", e))?;
+    app.global_shortcut()
+        .on_shortcut(shortcut, |app_handle, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                let app_handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let app_handle_inner = app_handle.clone();
+                    let state = app_handle.state::<AppState>();
+                    let _ = toggle_recording(app_handle_inner, state).await;
+                });
+            }
+        })
+        .map_err(|e| format!("Couldn't register {}: {}", accelerator, e))
+}
+
+#[tauri::command]
+fn get_recording_shortcut(shortcut: State<'_, RecordingShortcut>) -> String {
+    shortcut.accelerator.lock().clone()
+}
+
+/// Switches the recording shortcut to `accelerator`, e.g. `Alt+Shift+R`. If
+/// it can't be registered or saved the previous one stays in place.
+#[tauri::command]
+fn set_recording_shortcut(
+    app: AppHandle,
+    shortcut: State<'_, RecordingShortcut>,
+    accelerator: String,
+) -> Result<(), String> {
+    let parsed = accelerator
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Not a valid shortcut: {}", e))?;
+    let mut current = shortcut.accelerator.lock();
+    if current.parse::<Shortcut>().ok() == Some(parsed) {
+        return Ok(());
+    }
+
+    let restore = |current: &str| {
+        if let Err(e) = register_shortcut(&app, current) {
+            eprintln!("Failed to restore the recording shortcut: {}", e);
+        }
+    };
+    let _ = app.global_shortcut().unregister(current.as_str());
+    if let Err(e) = register_shortcut(&app, &accelerator) {
+        restore(&current);
+        return Err(e);
+    }
+    if let Err(e) = shortcut.save(&accelerator) {
+        let _ = app.global_shortcut().unregister(accelerator.as_str());
+        restore(&current);
+        return Err(e);
+    }
+    *current = accelerator;
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let mut builder = tauri::Builder::default();
@@ -328,24 +245,12 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .setup(|app| {
-            let ctrl_shift_r = if cfg!(target_os = "macos") {
-                "Command+Shift+R"
-            } else {
-                "Ctrl+Shift+R"
-            };
-
-            let shortcut = ctrl_shift_r.parse::<Shortcut>().unwrap();
-
-            app.global_shortcut().on_shortcut(shortcut, move |app_handle, scut, event| {
-                if event.state == ShortcutState::Pressed && scut == &shortcut {
-                    let app_handle = app_handle.clone();
-                    tauri::async_runtime::spawn(async move {
-                        let app_handle_inner = app_handle.clone();
-                        let state = app_handle.state::<AppState>();
-                        let _ = toggle_recording(app_handle_inner, state).await;
-                    });
-                }
-            })?;
+            let shortcut = RecordingShortcut::load(app.handle());
+            // E.g. taken by another app, pick a different one with `set_recording_shortcut`
+            if let Err(e) = register_shortcut(app.handle(), &shortcut.accelerator.lock()) {
+                eprintln!("Recording shortcut not registered: {}", e);
+            }
+            app.manage(shortcut);
 
             // Create Overlay Window / Panel
             #[cfg(target_os = "macos")]
@@ -353,6 +258,12 @@ pub fn run() {
                 let panel = PanelBuilder::<_, RecordingOverlayPanel>::new(app.handle(), "overlay")
                     .url(WebviewUrl::App("/?overlay=true".into()))
                     .level(PanelLevel::Status)
+                    // Stay visible when switching Spaces and over full-screen apps
+                    .collection_behavior(
+                        CollectionBehavior::new()
+                            .can_join_all_spaces()
+                            .full_screen_auxiliary(),
+                    )
                     .size(tauri::Size::Logical(tauri::LogicalSize {
                         width: 540.0,
                         height: 260.0,
@@ -387,6 +298,7 @@ pub fn run() {
                 .transparent(true)
                 .shadow(false)
                 .always_on_top(true)
+                .visible_on_all_workspaces(true)
                 .inner_size(540.0, 260.0)
                 .visible(false)
                 .resizable(false)
@@ -400,7 +312,13 @@ pub fn run() {
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![start_recording, stop_recording, toggle_recording])
+        .invoke_handler(tauri::generate_handler![
+            start_recording,
+            stop_recording,
+            toggle_recording,
+            get_recording_shortcut,
+            set_recording_shortcut
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
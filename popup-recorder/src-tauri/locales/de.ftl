@@ -0,0 +1,104 @@
+## Tray
+
+tray-start-recording = Aufnahme starten
+tray-stop-recording = Aufnahme beenden
+tray-recordings = Aufnahmen
+tray-no-recordings = Keine Aufnahmen
+tray-presets = Aufnahmequelle
+tray-preset-whole-display = Ganzer Bildschirm
+tray-open = Öffnen
+tray-reveal = Im Finder zeigen
+tray-copy-path = Pfad kopieren
+tray-delete = Löschen
+tray-check-updates = Nach Updates suchen…
+tray-no-update = Kein Update verfügbar
+tray-install-update = Update { $version } installieren
+tray-update-deferred = Update wird nach der Aufnahme installiert
+tray-quit = popup-recorder beenden
+tray-tooltip = popup-recorder
+tray-tooltip-recording = popup-recorder — Aufnahme läuft
+
+## Notifications
+
+idle-title = Läuft die Aufnahme noch?
+idle-body = Seit { $minutes } Minuten ist nichts zu hören. Aufnahme fortsetzen oder im Overlay beenden.
+thermal-title = Der Mac ist sehr warm
+thermal-body = Die Pegelanzeige ist pausiert, damit die Aufnahme nicht stockt.
+low-disk-title = Festplatte fast voll
+low-disk-body = Nur noch { $free } MB frei. Die Aufnahme endet von selbst, bevor der Platz ausgeht.
+low-disk-stopped-title = Aufnahme beendet
+low-disk-stopped-body = Es waren nur noch { $free } MB frei, deshalb wurde die Aufnahme beendet und gespeichert.
+start-failed-title = Aufnahme kann nicht starten
+encryption-failed-title = Aufnahme nicht verschlüsselt
+encryption-failed-body = { $file } konnte nicht verschlüsselt werden und wurde unverschlüsselt behalten.
+auto-stop-title = Aufnahme beendet
+auto-stop-body = Die Aufnahme wurde wie geplant um { $time } beendet und gespeichert.
+calendar-title = Besprechung beginnt
+calendar-body = { $summary } beginnt. Starte die Aufnahme über die Abfrage im Overlay.
+meeting-detected-title = Anruf erkannt
+meeting-detected-body = { $app } ist in einem Anruf. Starte die Aufnahme über das Overlay.
+recovery-title = Unvollständige Aufnahme gefunden
+recovery-body = { $count ->
+    [one] Eine Aufnahme wurde abgebrochen, weil popup-recorder unerwartet beendet wurde. Sie kann wiederhergestellt werden.
+   *[other] { $count } Aufnahmen wurden abgebrochen, weil popup-recorder unerwartet beendet wurde. Sie können wiederhergestellt werden.
+}
+
+## Windows
+
+visualizer-title = Visualisierung
+
+## Screen reader
+
+announce-started = Aufnahme gestartet
+announce-stopped-seconds = Aufnahme beendet, { $count ->
+    [one] 1 Sekunde
+   *[other] { $count } Sekunden
+}
+announce-stopped-minutes = Aufnahme beendet, { $count ->
+    [one] 1 Minute
+   *[other] { $count } Minuten
+}
+
+## Errors
+
+error-no-display = Kein Bildschirm gefunden
+error-no-input-device = Kein Eingabegerät verfügbar
+error-no-input-config = Keine passende Eingabekonfiguration gefunden
+error-no-output-device = Kein Ausgabegerät verfügbar
+error-nothing-playing = Es wird nichts abgespielt
+error-no-update = Kein Update verfügbar
+error-recording-not-found = Aufnahme nicht gefunden: { $path }
+error-pasteboard = Schreiben in die Zwischenablage fehlgeschlagen
+error-clipboard-unsupported = Dateien in die Zwischenablage kopieren geht nur unter macOS
+error-output-dir-denied = Zugriff auf den Ausgabeordner verweigert
+error-permission-not-granted = Erteile zuerst die Berechtigung in den Systemeinstellungen
+error-no-test-recording = Mach zuerst eine Testaufnahme
+error-conversion-running = Es läuft bereits eine Konvertierung
+error-invalid-time = Keine gültige Uhrzeit: { $time }
+error-past-stop-time = Aufnahmen nach { $time } sind nicht erlaubt
+error-meeting-not-found = Diese Besprechung ist nicht mehr im Kalender
+error-preset-not-found = Keine Voreinstellung mit diesem Namen
+error-app-not-running = { $app } läuft nicht
+error-window-not-found = Kein geöffnetes Fenster passt zu { $pattern }
+error-not-wav = Keine lesbare WAV-Datei
+error-bad-key = Der Aufnahmeschlüssel im Schlüsselbund ist beschädigt
+error-bad-container = Die verschlüsselte Aufnahme ist beschädigt oder wurde mit einem anderen Schlüssel erstellt
+error-not-encrypted = Diese Aufnahme ist nicht verschlüsselt
+error-password-too-short = Das Passwort muss mindestens 8 Zeichen haben
+error-ffmpeg-not-found = ffmpeg wurde nicht gefunden. Installiere es oder gib den Pfad in den Einstellungen an
+error-ffmpeg-failed = ffmpeg ist fehlgeschlagen: { $detail }
+error-export-same-file = Der Export würde die Aufnahme selbst überschreiben
+error-invalid-gain = Die Verstärkung muss zwischen 0 und { $max } liegen
+error-invalid-monitor-volume = Die Mithörlautstärke muss zwischen 0 und { $max } liegen
+error-invalid-monitor-latency = Die Mithörlatenz muss zwischen { $min } und { $max } ms liegen
+error-invalid-dsp-options = Unterdrückungsstärke und Gate-Schwelle müssen zwischen 0 und 1 liegen
+error-already-transcribing = Transkription läuft bereits
+error-no-transcription-model = Kein Transkriptionsmodell eingestellt
+error-not-unfinished = Das ist keine unvollständige Aufnahme
+error-low-disk-space = Zu wenig Speicherplatz für eine Aufnahme, nur { $free } MB frei
+error-output-dir-missing = Der Ausgabeordner existiert nicht
+error-invalid-filename-template = Keine gültige Dateinamen-Vorlage: { $template }
+error-output-not-f32 = Das Ausgabegerät unterstützt kein 32-Bit-Float-Audio
+error-not-a-file = Keine Datei
+error-no-config-dir = Der Einstellungsordner wurde nicht gefunden
+error-sleep-unsupported = Den Ruhezustand zu verhindern wird auf diesem System nicht unterstützt
@@ -0,0 +1,104 @@
+## Tray
+
+tray-start-recording = Start Recording
+tray-stop-recording = Stop Recording
+tray-recordings = Recordings
+tray-no-recordings = No Recordings
+tray-presets = Capture
+tray-preset-whole-display = Whole Display
+tray-open = Open
+tray-reveal = Reveal in Finder
+tray-copy-path = Copy Path
+tray-delete = Delete
+tray-check-updates = Check for Updates…
+tray-no-update = No Update Available
+tray-install-update = Install Update { $version }
+tray-update-deferred = Update Will Install After Recording
+tray-quit = Quit popup-recorder
+tray-tooltip = popup-recorder
+tray-tooltip-recording = popup-recorder — Recording
+
+## Notifications
+
+idle-title = Still recording?
+idle-body = Nothing has been heard for { $minutes } minutes. Keep recording or stop it from the overlay.
+thermal-title = Mac is running hot
+thermal-body = Live levels are paused to keep the recording from glitching.
+low-disk-title = Disk almost full
+low-disk-body = Only { $free } MB left. The recording stops on its own before the disk runs out.
+low-disk-stopped-title = Recording stopped
+low-disk-stopped-body = Only { $free } MB were left, so the recording was stopped and saved.
+start-failed-title = Can't start recording
+encryption-failed-title = Recording not encrypted
+encryption-failed-body = { $file } couldn't be encrypted and was kept unencrypted.
+auto-stop-title = Recording stopped
+auto-stop-body = The recording was stopped and saved at { $time } as scheduled.
+calendar-title = Meeting starting
+calendar-body = { $summary } is starting. Record it from the overlay prompt.
+meeting-detected-title = Call detected
+meeting-detected-body = { $app } is in a call. Start recording from the overlay.
+recovery-title = Unfinished recording found
+recovery-body = { $count ->
+    [one] A recording was cut short when popup-recorder quit unexpectedly. It can be recovered.
+   *[other] { $count } recordings were cut short when popup-recorder quit unexpectedly. They can be recovered.
+}
+
+## Windows
+
+visualizer-title = Visualizer
+
+## Screen reader
+
+announce-started = Recording started
+announce-stopped-seconds = Recording stopped, { $count ->
+    [one] 1 second
+   *[other] { $count } seconds
+}
+announce-stopped-minutes = Recording stopped, { $count ->
+    [one] 1 minute
+   *[other] { $count } minutes
+}
+
+## Errors
+
+error-no-display = No display found
+error-no-input-device = No input device available
+error-no-input-config = Could not find any suitable input config
+error-no-output-device = No output device available
+error-nothing-playing = Nothing is playing
+error-no-update = No update available
+error-recording-not-found = Recording not found: { $path }
+error-pasteboard = Failed to write to the pasteboard
+error-clipboard-unsupported = Copying files to the clipboard is only supported on macOS
+error-output-dir-denied = Access to the output folder was denied
+error-permission-not-granted = Grant the permission in System Settings first
+error-no-test-recording = Make a test recording first
+error-conversion-running = A conversion is already running
+error-invalid-time = Not a valid time: { $time }
+error-past-stop-time = Recording is not allowed after { $time }
+error-meeting-not-found = That meeting is no longer in the calendar
+error-preset-not-found = No preset with that name
+error-app-not-running = { $app } isn't running
+error-window-not-found = No open window matches { $pattern }
+error-not-wav = Not a readable WAV file
+error-bad-key = The recording key in the keychain is damaged
+error-bad-container = The encrypted recording is damaged or was made with another key
+error-not-encrypted = This recording isn't encrypted
+error-password-too-short = Use a password of at least 8 characters
+error-ffmpeg-not-found = ffmpeg wasn't found. Install it or set its path in the settings
+error-ffmpeg-failed = ffmpeg failed: { $detail }
+error-export-same-file = The export would overwrite the recording itself
+error-invalid-gain = Gain must be between 0 and { $max }
+error-invalid-monitor-volume = Monitoring volume must be between 0 and { $max }
+error-invalid-monitor-latency = Monitoring latency must be between { $min } and { $max } ms
+error-invalid-dsp-options = Suppression strength and gate threshold must be between 0 and 1
+error-already-transcribing = Already transcribing
+error-no-transcription-model = No transcription model configured
+error-not-unfinished = This isn't an unfinished recording
+error-low-disk-space = Not enough disk space to record, only { $free } MB free
+error-output-dir-missing = The output folder doesn't exist
+error-invalid-filename-template = Not a usable file name template: { $template }
+error-output-not-f32 = The output device doesn't support 32-bit float audio
+error-not-a-file = Not a file
+error-no-config-dir = Could not find the settings folder
+error-sleep-unsupported = Keeping the computer awake isn't supported on this platform
@@ -0,0 +1,72 @@
+//! Screen reader announcements, so shortcut-driven recording can be followed
+//! without seeing the overlay or tray.
+
+use crate::i18n::{t, tr_count};
+use parking_lot::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+#[derive(Default)]
+pub struct RecordingClock(Mutex<Option<Instant>>);
+
+/// Asks VoiceOver to speak `message` right away.
+#[cfg(target_os = "macos")]
+pub fn announce(app: &AppHandle, message: String) {
+    let _ = app.run_on_main_thread(move || {
+        use objc2::runtime::AnyObject;
+        use objc2::MainThreadMarker;
+        use objc2_app_kit::{
+            NSAccessibilityAnnouncementKey, NSAccessibilityAnnouncementRequestedNotification,
+            NSAccessibilityPostNotificationWithUserInfo, NSAccessibilityPriorityKey,
+            NSAccessibilityPriorityLevel, NSApplication,
+        };
+        use objc2_foundation::{NSDictionary, NSNumber, NSString};
+
+        let Some(mtm) = MainThreadMarker::new() else {
+            return;
+        };
+        let ns_app = NSApplication::sharedApplication(mtm);
+        let message = NSString::from_str(&message);
+        let priority = NSNumber::numberWithInteger(NSAccessibilityPriorityLevel::High.0);
+        let values: [&AnyObject; 2] = [&message, &priority];
+
+        // SAFETY: the keys and notification are AppKit constants, the values
+        // are the NSString and NSNumber the announcement API expects
+        unsafe {
+            let user_info = NSDictionary::from_slices(
+                &[NSAccessibilityAnnouncementKey, NSAccessibilityPriorityKey],
+                &values,
+            );
+            NSAccessibilityPostNotificationWithUserInfo(
+                &ns_app,
+                NSAccessibilityAnnouncementRequestedNotification,
+                Some(&*user_info),
+            );
+        }
+    });
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn announce(_app: &AppHandle, _message: String) {}
+
+/// Announces a start, or a stop together with how long the recording ran.
+pub fn announce_recording(app: &AppHandle, recording: bool) {
+    let clock = app.state::<RecordingClock>();
+    let mut started = clock.0.lock();
+
+    let message = if recording {
+        *started = Some(Instant::now());
+        t("announce-started")
+    } else {
+        let Some(started) = started.take() else {
+            return;
+        };
+        let secs = started.elapsed().as_secs();
+        if secs < 60 {
+            tr_count("announce-stopped-seconds", secs)
+        } else {
+            tr_count("announce-stopped-minutes", secs / 60)
+        }
+    };
+    announce(app, message);
+}
@@ -0,0 +1,228 @@
+//! Mirrors every finished recording into `settings.backup_dir` (an external
+//! drive or network share). Copies that can't happen right away stay queued
+//! in `backup-queue.json` and are retried until the destination is back.
+
+use crate::i18n::t;
+use crate::{events, metadata, settings};
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupState {
+    Pending,
+    Done,
+    Failed,
+}
+
+/// Backup state of one recording, kept in its metadata sidecar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupStatus {
+    pub state: BackupState,
+    pub destination: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: Vec<PathBuf>,
+    // Taken off `pending` while copying, but still saved so a copy cut short
+    // by quitting is retried
+    copying: Vec<PathBuf>,
+}
+
+impl Queue {
+    fn contains(&self, source: &PathBuf) -> bool {
+        self.pending.contains(source) || self.copying.contains(source)
+    }
+
+    fn save(&self, app: &AppHandle) {
+        let all: Vec<&PathBuf> = self.copying.iter().chain(&self.pending).collect();
+        save_queue(app, &all);
+    }
+}
+
+#[derive(Default)]
+pub struct BackupQueue(Mutex<Queue>);
+
+fn queue_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join("backup-queue.json"))
+}
+
+impl BackupQueue {
+    pub fn load(app: &AppHandle) -> Self {
+        let pending = queue_path(app)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self(Mutex::new(Queue {
+            pending,
+            copying: Vec::new(),
+        }))
+    }
+}
+
+fn save_queue(app: &AppHandle, pending: &[&PathBuf]) {
+    let Some(path) = queue_path(app) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(pending) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+fn set_status(app: &AppHandle, source: &Path, status: BackupStatus) {
+    events::emit(
+        app,
+        "backup-status",
+        json!({ "path": source, "state": status.state, "error": status.error }),
+    );
+    if let Err(e) = metadata::update(source, |metadata| metadata.backup = Some(status)) {
+        eprintln!(
+            "Failed to record backup status for {}: {}",
+            source.display(),
+            e
+        );
+    }
+}
+
+// Copied under a temporary name first so the mirror never holds half a file
+fn copy(source: &Path, destination_dir: &Path) -> Result<PathBuf, String> {
+    let name = source.file_name().ok_or_else(|| t("error-not-a-file"))?;
+    let target = destination_dir.join(name);
+    let partial = target.with_extension("partial");
+    std::fs::copy(source, &partial).map_err(|e| e.to_string())?;
+    std::fs::rename(&partial, &target).map_err(|e| e.to_string())?;
+    Ok(target)
+}
+
+/// Tries every queued copy once. Anything that can't be copied stays queued.
+/// Copies run with the queue unlocked, so a slow share doesn't hold up
+/// `enqueue`, and a job is only ever taken by one pass.
+fn process(app: &AppHandle) {
+    let Some(destination_dir) = settings::current(app).backup_dir.map(PathBuf::from) else {
+        return;
+    };
+    // Unmounted drive or share, wait for it to come back
+    if !destination_dir.is_dir() {
+        return;
+    }
+
+    let queue = app.state::<BackupQueue>();
+    let mut failed = Vec::new();
+    loop {
+        let source = {
+            let mut queue = queue.0.lock();
+            if queue.pending.is_empty() {
+                break;
+            }
+            let source = queue.pending.remove(0);
+            queue.copying.push(source.clone());
+            source
+        };
+
+        // Deleted before it could be mirrored otherwise
+        if source.is_file() {
+            match copy(&source, &destination_dir) {
+                Ok(target) => set_status(
+                    app,
+                    &source,
+                    BackupStatus {
+                        state: BackupState::Done,
+                        destination: Some(target),
+                        error: None,
+                    },
+                ),
+                Err(e) => {
+                    set_status(
+                        app,
+                        &source,
+                        BackupStatus {
+                            state: BackupState::Failed,
+                            destination: None,
+                            error: Some(e),
+                        },
+                    );
+                    // Stays under `copying` until the pass is over, so
+                    // it isn't picked up again right away
+                    failed.push(source);
+                    continue;
+                }
+            }
+        }
+
+        let mut queue = queue.0.lock();
+        queue.copying.retain(|copying| *copying != source);
+        queue.save(app);
+    }
+
+    if !failed.is_empty() {
+        let mut queue = queue.0.lock();
+        queue.copying.retain(|copying| !failed.contains(copying));
+        queue.pending.extend(failed);
+        queue.save(app);
+    }
+}
+
+/// Queues a finalized recording for mirroring, if a backup dir is configured.
+/// Only once it's where it stays, see `crate::settle`.
+pub fn enqueue(app: &AppHandle, source: PathBuf) {
+    if settings::current(app).backup_dir.is_none() {
+        return;
+    }
+    set_status(
+        app,
+        &source,
+        BackupStatus {
+            state: BackupState::Pending,
+            destination: None,
+            error: None,
+        },
+    );
+    {
+        let queue = app.state::<BackupQueue>();
+        let mut queue = queue.0.lock();
+        if !queue.contains(&source) {
+            queue.pending.push(source);
+        }
+        queue.save(app);
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || process(&app));
+}
+
+/// Retries queued copies periodically.
+pub fn spawn_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(RETRY_INTERVAL);
+        loop {
+            interval.tick().await;
+            let app = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || process(&app)).await;
+        }
+    });
+}
+
+/// Sets or clears (`None`) the mirror folder.
+#[tauri::command]
+pub fn set_backup_dir(app: AppHandle, dir: Option<String>) -> Result<(), RecorderError> {
+    settings::modify(&app, |settings| settings.backup_dir = dir)?;
+    // Flush anything queued while no usable destination was set
+    std::thread::spawn(move || process(&app));
+    Ok(())
+}
@@ -0,0 +1,349 @@
+//! Calendar-driven recording. Polls the ICS feed in `settings.calendar_url`
+//! and, when a meeting matching `settings.calendar_keywords` begins, asks
+//! whether to record it. Accepted meetings stop recording when they end.
+//!
+//! Only single events are considered, recurrence rules are not expanded.
+
+use crate::i18n::{t, tr};
+use crate::{events, schedule, settings, show_and_start, AppState};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+const FETCH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+// Meetings are still offered if the app notices them this late
+const START_GRACE: chrono::Duration = chrono::Duration::minutes(5);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Meeting {
+    pub uid: String,
+    pub summary: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+}
+
+// VEVENT being parsed, kept only if it has all of these
+#[derive(Default)]
+struct PartialEvent {
+    uid: Option<String>,
+    summary: String,
+    start: Option<DateTime<Local>>,
+    end: Option<DateTime<Local>>,
+}
+
+#[derive(Default)]
+struct Calendar {
+    meetings: Vec<Meeting>,
+    fetched: Option<Instant>,
+    // Offered once per event, whatever the answer was
+    offered: HashSet<String>,
+}
+
+#[derive(Default)]
+pub struct CalendarState(Mutex<Calendar>);
+
+/// Joins folded lines (continuations start with a space or tab).
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// `DTSTART`/`DTEND` values. All-day dates return `None`, those aren't meetings.
+fn parse_time(params: &str, value: &str) -> Option<DateTime<Local>> {
+    if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") {
+        return None;
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&naive).with_timezone(&Local));
+    }
+    // Floating or TZID times are taken as local, which holds for most feeds
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+fn parse(ics: &str) -> Vec<Meeting> {
+    let mut meetings = Vec::new();
+    let mut current: Option<PartialEvent> = None;
+
+    for line in unfold(ics) {
+        if line == "BEGIN:VEVENT" {
+            current = Some(PartialEvent::default());
+            continue;
+        }
+        if line == "END:VEVENT" {
+            if let Some(PartialEvent {
+                uid: Some(uid),
+                summary,
+                start: Some(start),
+                end: Some(end),
+            }) = current.take()
+            {
+                meetings.push(Meeting {
+                    uid,
+                    summary,
+                    start,
+                    end,
+                });
+            }
+            continue;
+        }
+        let Some(event) = current.as_mut() else {
+            continue;
+        };
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        match name {
+            "UID" => event.uid = Some(value.to_string()),
+            "SUMMARY" => event.summary = unescape(value),
+            "DTSTART" => event.start = parse_time(params, value),
+            "DTEND" => event.end = parse_time(params, value),
+            _ => {}
+        }
+    }
+    meetings
+}
+
+fn matches(meeting: &Meeting, keywords: &[String]) -> bool {
+    let summary = meeting.summary.to_lowercase();
+    keywords.is_empty()
+        || keywords
+            .iter()
+            .any(|keyword| summary.contains(&keyword.to_lowercase()))
+}
+
+async fn fetch(url: &str) -> Result<Vec<Meeting>, String> {
+    // Calendar apps hand out subscriptions as webcal://
+    let url = match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    };
+    let ics = reqwest::get(&url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let today = Local::now().date_naive();
+    Ok(parse(&ics)
+        .into_iter()
+        .filter(|meeting| meeting.end.date_naive() >= today)
+        .collect())
+}
+
+fn offer(app: &AppHandle, meeting: &Meeting) {
+    events::emit(app, "meeting-starting", meeting);
+    let _ = app
+        .notification()
+        .builder()
+        .title(t("calendar-title"))
+        .body(tr("calendar-body", &[("summary", meeting.summary.clone())]))
+        .show();
+}
+
+fn check(app: &AppHandle) {
+    let settings = settings::current(app);
+    if settings.calendar_url.is_none() || app.state::<AppState>().is_recording() {
+        return;
+    }
+
+    let now = Local::now();
+    let state = app.state::<CalendarState>();
+    let mut calendar = state.0.lock();
+    let starting: Vec<Meeting> = calendar
+        .meetings
+        .iter()
+        .filter(|meeting| meeting.start <= now && now < meeting.start + START_GRACE)
+        .filter(|meeting| now < meeting.end)
+        .filter(|meeting| !calendar.offered.contains(&meeting.uid))
+        .filter(|meeting| matches(meeting, &settings.calendar_keywords))
+        .cloned()
+        .collect();
+
+    for meeting in starting {
+        calendar.offered.insert(meeting.uid.clone());
+        offer(app, &meeting);
+    }
+}
+
+async fn refresh(app: &AppHandle) {
+    let Some(url) = settings::current(app).calendar_url else {
+        return;
+    };
+    let state = app.state::<CalendarState>();
+    let stale = state
+        .0
+        .lock()
+        .fetched
+        .is_none_or(|fetched| fetched.elapsed() >= FETCH_INTERVAL);
+    if !stale {
+        return;
+    }
+
+    match fetch(&url).await {
+        Ok(meetings) => {
+            let mut calendar = state.0.lock();
+            calendar.meetings = meetings;
+            calendar.fetched = Some(Instant::now());
+        }
+        Err(e) => {
+            eprintln!("Calendar fetch failed: {}", e);
+            // Back off for a full interval rather than retrying every check
+            state.0.lock().fetched = Some(Instant::now());
+        }
+    }
+}
+
+/// Polls the feed and offers meetings as they begin.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            refresh(&app).await;
+            check(&app);
+        }
+    });
+}
+
+/// Answer to a `meeting-starting` prompt. Recording stops when the meeting ends.
+#[tauri::command]
+pub fn respond_meeting_prompt(
+    app: AppHandle,
+    state: State<'_, CalendarState>,
+    uid: String,
+    record: bool,
+) -> Result<(), RecorderError> {
+    if !record {
+        return Ok(());
+    }
+    let end = state
+        .0
+        .lock()
+        .meetings
+        .iter()
+        .find(|meeting| meeting.uid == uid)
+        .map(|meeting| meeting.end)
+        .ok_or_else(|| RecorderError::NotFound(t("error-meeting-not-found")))?;
+
+    schedule::stop_session_at(&app, Some(end));
+    show_and_start(&app);
+    Ok(())
+}
+
+/// Sets the ICS feed (`None` turns the integration off) and the keywords a
+/// meeting title has to contain. No keywords offers every meeting.
+#[tauri::command]
+pub fn set_calendar(
+    app: AppHandle,
+    state: State<'_, CalendarState>,
+    url: Option<String>,
+    keywords: Vec<String>,
+) -> Result<(), RecorderError> {
+    settings::modify(&app, |settings| {
+        settings.calendar_url = url;
+        settings.calendar_keywords = keywords;
+    })?;
+    // Refetch on the next check
+    *state.0.lock() = Calendar::default();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:standup@example.com\r\n\
+        SUMMARY:Team standup\\, daily \r\n \
+        sync\r\n\
+        DTSTART:20261019T080000Z\r\n\
+        DTEND:20261019T081500Z\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:holiday@example.com\r\n\
+        SUMMARY:Holiday\r\n\
+        DTSTART;VALUE=DATE:20261020\r\n\
+        DTEND;VALUE=DATE:20261021\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:No uid\r\n\
+        DTSTART:20261019T090000Z\r\n\
+        DTEND:20261019T100000Z\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:review@example.com\r\n\
+        SUMMARY:Review\r\n\
+        DTSTART;TZID=Europe/Berlin:20261019T140000\r\n\
+        DTEND;TZID=Europe/Berlin:20261019T150000\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    fn utc(text: &str) -> DateTime<Local> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Local)
+    }
+
+    #[test]
+    fn keeps_timed_events_with_a_uid() {
+        let meetings = parse(FEED);
+        let uids: Vec<&str> = meetings.iter().map(|m| m.uid.as_str()).collect();
+        assert_eq!(uids, ["standup@example.com", "review@example.com"]);
+    }
+
+    #[test]
+    fn unfolds_and_unescapes_summaries() {
+        assert_eq!(parse(FEED)[0].summary, "Team standup, daily sync");
+        assert_eq!(unescape(r"a\nb\;c\\d"), "a b;c\\d");
+    }
+
+    #[test]
+    fn reads_utc_and_floating_times() {
+        let meetings = parse(FEED);
+        assert_eq!(meetings[0].start, utc("2026-10-19T08:00:00Z"));
+        assert_eq!(meetings[0].end, utc("2026-10-19T08:15:00Z"));
+        let local = NaiveDateTime::parse_from_str("20261019T140000", "%Y%m%dT%H%M%S").unwrap();
+        assert_eq!(meetings[1].start.naive_local(), local);
+    }
+
+    #[test]
+    fn all_day_dates_are_not_meetings() {
+        assert_eq!(parse_time("VALUE=DATE", "20261020"), None);
+        assert!(parse_time("VALUE=DATE-TIME", "20261020T100000Z").is_some());
+        assert_eq!(parse_time("", "not a time"), None);
+    }
+
+    #[test]
+    fn matches_keywords_ignoring_case() {
+        let meeting = &parse(FEED)[0];
+        assert!(matches(meeting, &[]));
+        assert!(matches(meeting, &["STANDUP".to_string()]));
+        assert!(!matches(meeting, &["retro".to_string()]));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::i18n::{t, tr};
+use recorder_core::RecorderError;
+use std::path::{Path, PathBuf};
+
+#[cfg(target_os = "macos")]
+fn write_to_pasteboard(path: &Path, include_path_text: bool) -> Result<(), String> {
+    use objc2::rc::Retained;
+    use objc2::runtime::ProtocolObject;
+    use objc2_app_kit::{NSPasteboard, NSPasteboardWriting};
+    use objc2_foundation::{NSArray, NSString, NSURL};
+
+    let path_string = NSString::from_str(&path.to_string_lossy());
+    let url = NSURL::fileURLWithPath(&path_string);
+
+    // The file URL is what Finder and chat apps paste as an attachment
+    let mut objects: Vec<Retained<ProtocolObject<dyn NSPasteboardWriting>>> =
+        vec![ProtocolObject::from_retained(url)];
+    if include_path_text {
+        objects.push(ProtocolObject::from_retained(path_string));
+    }
+
+    let pasteboard = NSPasteboard::generalPasteboard();
+    pasteboard.clearContents();
+    if pasteboard.writeObjects(&NSArray::from_retained_slice(&objects)) {
+        Ok(())
+    } else {
+        Err(t("error-pasteboard"))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn write_to_pasteboard(_path: &Path, _include_path_text: bool) -> Result<(), String> {
+    Err(t("error-clipboard-unsupported"))
+}
+
+#[tauri::command]
+pub fn copy_recording_to_clipboard(
+    path: String,
+    include_path_text: Option<bool>,
+) -> Result<(), RecorderError> {
+    let path = PathBuf::from(path);
+    if !path.is_file() {
+        return Err(RecorderError::NotFound(tr(
+            "error-recording-not-found",
+            &[("path", path.display().to_string())],
+        )));
+    }
+    let path = path.canonicalize()?;
+    write_to_pasteboard(&path, include_path_text.unwrap_or(false)).map_err(RecorderError::Other)
+}
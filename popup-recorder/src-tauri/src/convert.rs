@@ -0,0 +1,237 @@
+//! Bulk conversion of the recordings library, mostly to get disk space back
+//! from 32-bit float WAVs. One job runs at a time and can be cancelled between
+//! files.
+
+use crate::i18n::t;
+use crate::{crypto, events, indicators, recordings};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use recorder_core::{read_samples, RecorderError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConvertFormat {
+    /// 16-bit integer WAV, half the size of float
+    Pcm16,
+    /// 24-bit integer WAV
+    Pcm24,
+}
+
+impl ConvertFormat {
+    pub(crate) fn bits(self) -> u16 {
+        match self {
+            ConvertFormat::Pcm16 => 16,
+            ConvertFormat::Pcm24 => 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ConvertOptions {
+    /// Recordings to convert, `None` converts the whole library
+    pub paths: Option<Vec<String>>,
+    /// Overwrite the originals instead of writing into `output_dir`
+    pub replace_originals: bool,
+    /// Where converted copies go, defaults to `converted/` in the recordings dir
+    pub output_dir: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ConversionJob {
+    running: AtomicBool,
+    cancel: AtomicBool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum FileStatus {
+    Converted,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ConversionProgress {
+    path: String,
+    index: usize,
+    total: usize,
+    status: FileStatus,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionSummary {
+    converted: usize,
+    skipped: usize,
+    failed: usize,
+    cancelled: bool,
+}
+
+fn already_in_format(path: &Path, format: ConvertFormat) -> bool {
+    crypto::read_wav(path).is_ok_and(|reader| {
+        let spec = reader.spec();
+        spec.sample_format == SampleFormat::Int && spec.bits_per_sample == format.bits()
+    })
+}
+
+/// Writes `source` as a plain WAV in `format`, decrypting it if needed.
+pub(crate) fn transcode(source: &Path, target: &Path, format: ConvertFormat) -> Result<(), String> {
+    let reader = crypto::read_wav(source)?;
+    let spec = reader.spec();
+    let bits = format.bits();
+    let mut writer = WavWriter::create(
+        target,
+        WavSpec {
+            bits_per_sample: bits,
+            sample_format: SampleFormat::Int,
+            ..spec
+        },
+    )
+    .map_err(|e| e.to_string())?;
+
+    let max = ((1i64 << (bits - 1)) - 1) as f32;
+    let mut write = |sample: f32| {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * max).round() as i32)
+            .map_err(|e| e.to_string())
+    };
+    for sample in read_samples(reader) {
+        write(sample.map_err(|e| e.to_string())?)?;
+    }
+    writer.finalize().map_err(|e| e.to_string())
+}
+
+// Encrypted recordings stay encrypted, the plaintext only exists while converting
+fn convert_file(source: &Path, target: &Path, format: ConvertFormat) -> Result<(), String> {
+    if !crypto::is_encrypted(source) {
+        return transcode(source, target, format);
+    }
+    let plain = target.with_extension("plain");
+    let result =
+        transcode(source, &plain, format).and_then(|_| crypto::encrypt_file(&plain, target));
+    let _ = std::fs::remove_file(&plain);
+    result
+}
+
+fn convert_one(
+    source: &Path,
+    format: ConvertFormat,
+    options: &ConvertOptions,
+    output_dir: &Path,
+) -> Result<FileStatus, String> {
+    if options.replace_originals {
+        if already_in_format(source, format) {
+            return Ok(FileStatus::Skipped);
+        }
+        // Written next to the original and swapped in, so a failure halfway
+        // never costs the recording
+        let partial = source.with_extension("converting");
+        if let Err(e) = convert_file(source, &partial, format) {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+        std::fs::rename(&partial, source).map_err(|e| e.to_string())?;
+        return Ok(FileStatus::Converted);
+    }
+
+    let target = output_dir.join(source.file_name().ok_or_else(|| t("error-not-a-file"))?);
+    if target.exists() {
+        return Ok(FileStatus::Skipped);
+    }
+    std::fs::create_dir_all(output_dir).map_err(|e| e.to_string())?;
+    convert_file(source, &target, format).inspect_err(|_| {
+        let _ = std::fs::remove_file(&target);
+    })?;
+    Ok(FileStatus::Converted)
+}
+
+fn run(
+    app: &AppHandle,
+    format: ConvertFormat,
+    options: &ConvertOptions,
+    sources: Vec<PathBuf>,
+) -> ConversionSummary {
+    let job = app.state::<ConversionJob>();
+    let output_dir = options
+        .output_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| recordings::recordings_dir(app).join("converted"));
+    let mut summary = ConversionSummary {
+        converted: 0,
+        skipped: 0,
+        failed: 0,
+        cancelled: false,
+    };
+
+    let total = sources.len();
+    for (index, source) in sources.iter().enumerate() {
+        if job.cancel.load(Ordering::Relaxed) {
+            summary.cancelled = true;
+            break;
+        }
+        indicators::set_progress(app, Some((index * 100 / total.max(1)) as u64));
+
+        let (status, error) = match convert_one(source, format, options, &output_dir) {
+            Ok(status) => (status, None),
+            Err(e) => (FileStatus::Failed, Some(e)),
+        };
+        match status {
+            FileStatus::Converted => summary.converted += 1,
+            FileStatus::Skipped => summary.skipped += 1,
+            FileStatus::Failed => summary.failed += 1,
+        }
+        events::emit(
+            app,
+            "conversion-progress",
+            &ConversionProgress {
+                path: source.to_string_lossy().to_string(),
+                index,
+                total,
+                status,
+                error,
+            },
+        );
+    }
+
+    indicators::set_progress(app, None);
+    summary
+}
+
+/// Converts the library (or `options.paths`) to `format`, emitting a
+/// `conversion-progress` event per file.
+#[tauri::command]
+pub async fn convert_library(
+    app: AppHandle,
+    job: State<'_, ConversionJob>,
+    format: ConvertFormat,
+    options: Option<ConvertOptions>,
+) -> Result<ConversionSummary, RecorderError> {
+    if job.running.swap(true, Ordering::SeqCst) {
+        return Err(RecorderError::Busy(t("error-conversion-running")));
+    }
+    job.cancel.store(false, Ordering::SeqCst);
+
+    let options = options.unwrap_or_default();
+    let sources = match &options.paths {
+        Some(paths) => paths.iter().map(PathBuf::from).collect(),
+        None => recordings::list(&app),
+    };
+
+    let job_app = app.clone();
+    let summary =
+        tauri::async_runtime::spawn_blocking(move || run(&job_app, format, &options, sources))
+            .await;
+    job.running.store(false, Ordering::SeqCst);
+    summary.map_err(|e| RecorderError::Other(e.to_string()))
+}
+
+/// Stops a running conversion after the file in progress.
+#[tauri::command]
+pub fn cancel_conversion(job: State<'_, ConversionJob>) {
+    job.cancel.store(true, Ordering::SeqCst);
+}
@@ -0,0 +1,375 @@
+//! Optional at-rest encryption. With `settings.encrypt_recordings` each
+//! finished WAV is sealed into `<name>.enc` and the plaintext removed; the
+//! plaintext only exists on disk while the recording is running, since the
+//! WAV header has to be patched at the end.
+//!
+//! The container is a 16-byte header (magic plus an 8-byte random nonce
+//! prefix) followed by AES-256-GCM sealed 64 KiB chunks. Each chunk's nonce
+//! is the prefix plus a big-endian counter and its associated data is the
+//! header plus a last-chunk flag, so reordering and truncation both fail to
+//! open. The key lives in the OS keychain.
+
+use crate::i18n::{t, tr};
+use crate::{events, recordings, settings};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use hound::WavReader;
+use recorder_core::RecorderError;
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+pub const EXTENSION: &str = "enc";
+const MAGIC: &[u8; 8] = b"PRENC\0\0\x01";
+const HEADER_LEN: usize = 16;
+const CHUNK: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+const KEYCHAIN_SERVICE: &str = "popup-recorder";
+const KEYCHAIN_USER: &str = "recording-key";
+
+pub fn is_encrypted(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == EXTENSION)
+}
+
+/// The recording key, created on first use.
+fn key() -> Result<Key<Aes256Gcm>, String> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER).map_err(|e| e.to_string())?;
+    match entry.get_secret() {
+        Ok(secret) if secret.len() == 32 => Ok(Key::<Aes256Gcm>::clone_from_slice(&secret)),
+        Ok(_) => Err(t("error-bad-key")),
+        Err(keyring::Error::NoEntry) => {
+            let key = Aes256Gcm::generate_key(OsRng);
+            entry.set_secret(&key).map_err(|e| e.to_string())?;
+            Ok(key)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn read_full(reader: &mut impl Read, len: usize) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(len);
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+fn nonce(header: &[u8; HEADER_LEN], counter: u32) -> Nonce<<Aes256Gcm as AeadCore>::NonceSize> {
+    let mut nonce = [0u8; 12];
+    nonce[..8].copy_from_slice(&header[8..]);
+    nonce[8..].copy_from_slice(&counter.to_be_bytes());
+    Nonce::clone_from_slice(&nonce)
+}
+
+fn aad(header: &[u8; HEADER_LEN], last: bool) -> Vec<u8> {
+    [&header[..], &[last as u8]].concat()
+}
+
+/// Seals `source` into `target`.
+pub fn encrypt_file(source: &Path, target: &Path) -> Result<(), String> {
+    let reader = BufReader::new(File::open(source).map_err(|e| e.to_string())?);
+    let mut writer = BufWriter::new(File::create(target).map_err(|e| e.to_string())?);
+    seal(&key()?, reader, &mut writer)?;
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// Writes everything in `reader` to `writer` as a container sealed with
+/// `key`.
+fn seal(
+    key: &Key<Aes256Gcm>,
+    mut reader: impl Read,
+    writer: &mut impl Write,
+) -> Result<(), String> {
+    let cipher = Aes256Gcm::new(key);
+    let mut header = [0u8; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..].copy_from_slice(&Aes256Gcm::generate_nonce(&mut OsRng)[..8]);
+    writer.write_all(&header).map_err(|e| e.to_string())?;
+
+    let mut current = read_full(&mut reader, CHUNK).map_err(|e| e.to_string())?;
+    let mut counter = 0u32;
+    loop {
+        // One chunk of lookahead tells whether this one is the last
+        let next = read_full(&mut reader, CHUNK).map_err(|e| e.to_string())?;
+        let last = next.is_empty();
+        let sealed = cipher
+            .encrypt(
+                &nonce(&header, counter),
+                Payload {
+                    msg: &current,
+                    aad: &aad(&header, last),
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        writer.write_all(&sealed).map_err(|e| e.to_string())?;
+        if last {
+            break;
+        }
+        current = next;
+        counter = counter
+            .checked_add(1)
+            .ok_or_else(|| t("error-bad-container"))?;
+    }
+    Ok(())
+}
+
+/// Plaintext stream over an encrypted container, one chunk in memory at a time.
+pub struct DecryptReader<R> {
+    inner: R,
+    cipher: Aes256Gcm,
+    header: [u8; HEADER_LEN],
+    counter: u32,
+    // Next sealed chunk, read ahead to know when the current one is the last
+    pending: Vec<u8>,
+    plain: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    pub fn new(inner: R) -> Result<Self, String> {
+        Self::with_key(inner, &key()?)
+    }
+
+    fn with_key(mut inner: R, key: &Key<Aes256Gcm>) -> Result<Self, String> {
+        let mut header = [0u8; HEADER_LEN];
+        inner
+            .read_exact(&mut header)
+            .map_err(|_| t("error-bad-container"))?;
+        if &header[..8] != MAGIC {
+            return Err(t("error-bad-container"));
+        }
+        let pending = read_full(&mut inner, CHUNK + TAG_LEN).map_err(|e| e.to_string())?;
+        Ok(Self {
+            inner,
+            cipher: Aes256Gcm::new(key),
+            header,
+            counter: 0,
+            pending,
+            plain: Vec::new(),
+            position: 0,
+            finished: false,
+        })
+    }
+
+    fn advance(&mut self) -> std::io::Result<()> {
+        let invalid =
+            || std::io::Error::new(std::io::ErrorKind::InvalidData, t("error-bad-container"));
+        let current = std::mem::take(&mut self.pending);
+        if current.is_empty() {
+            return Err(invalid());
+        }
+        self.pending = read_full(&mut self.inner, CHUNK + TAG_LEN)?;
+        let last = self.pending.is_empty();
+        self.plain = self
+            .cipher
+            .decrypt(
+                &nonce(&self.header, self.counter),
+                Payload {
+                    msg: &current,
+                    aad: &aad(&self.header, last),
+                },
+            )
+            .map_err(|_| invalid())?;
+        self.position = 0;
+        self.counter = self.counter.checked_add(1).ok_or_else(invalid)?;
+        self.finished = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        while self.position == self.plain.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.advance()?;
+        }
+        let count = out.len().min(self.plain.len() - self.position);
+        out[..count].copy_from_slice(&self.plain[self.position..][..count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+/// Opens a recording's plaintext, decrypting on the fly if needed.
+pub fn open(path: &Path) -> Result<Box<dyn Read + Send>, String> {
+    let file = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+    if is_encrypted(path) {
+        Ok(Box::new(DecryptReader::new(file)?))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// `WavReader::open` that also reads encrypted recordings.
+pub fn read_wav(path: &Path) -> Result<WavReader<Box<dyn Read + Send>>, String> {
+    WavReader::new(open(path)?).map_err(|e| e.to_string())
+}
+
+/// Swaps `path` for its sealed copy. Failures keep the plaintext rather
+/// than lose the take, and tell the user it's lying around unencrypted.
+fn seal_in_place(app: &AppHandle, path: PathBuf) -> PathBuf {
+    let target = path.with_extension(EXTENSION);
+    match encrypt_file(&path, &target) {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&path);
+            target
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&target);
+            events::emit(
+                app,
+                "encryption-failed",
+                json!({ "path": path, "error": e }),
+            );
+            let file = path.file_name().unwrap_or_default().to_string_lossy();
+            let _ = app
+                .notification()
+                .builder()
+                .title(t("encryption-failed-title"))
+                .body(tr("encryption-failed-body", &[("file", file.to_string())]))
+                .show();
+            path
+        }
+    }
+}
+
+/// Encrypts a finished recording, its companion tracks and the `converted`
+/// copies written alongside it if the setting is on, returning the path the
+/// recording ends up at.
+pub fn seal_recording(app: &AppHandle, path: PathBuf, converted: Vec<PathBuf>) -> PathBuf {
+    if !settings::current(app).encrypt_recordings {
+        return path;
+    }
+    // Stems and converted copies hold the same conversation as the mix
+    let companions = recordings::companions(&path)
+        .into_iter()
+        .map(|(_, path)| path);
+    for copy in companions.chain(converted) {
+        if copy.is_file() && !is_encrypted(&copy) {
+            seal_in_place(app, copy);
+        }
+    }
+    seal_in_place(app, path)
+}
+
+/// Writes a decrypted copy next to the recording (or to `output`) and returns its path.
+/// Only takes sealed recordings, and never writes over the recording itself.
+#[tauri::command]
+pub async fn decrypt_recording(
+    path: String,
+    output: Option<String>,
+) -> Result<String, RecorderError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let source = PathBuf::from(&path);
+        if !is_encrypted(&source) {
+            return Err(RecorderError::InvalidArgument(t("error-not-encrypted")));
+        }
+        let target = output
+            .map(PathBuf::from)
+            .unwrap_or_else(|| source.with_extension("wav"));
+        if recordings::same_file(&source, &target) {
+            return Err(RecorderError::InvalidArgument(t("error-export-same-file")));
+        }
+        // Checks the header too, whatever the extension says
+        let mut reader = DecryptReader::new(BufReader::new(File::open(&source)?))?;
+        let mut writer = BufWriter::new(File::create(&target)?);
+        std::io::copy(&mut reader, &mut writer)
+            .and_then(|_| writer.flush())
+            .inspect_err(|_| {
+                let _ = std::fs::remove_file(&target);
+            })?;
+        Ok(target.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sealed(key: &Key<Aes256Gcm>, plain: &[u8]) -> Vec<u8> {
+        let mut container = Vec::new();
+        seal(key, plain, &mut container).unwrap();
+        container
+    }
+
+    fn unseal(key: &Key<Aes256Gcm>, container: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut plain = Vec::new();
+        DecryptReader::with_key(container, key)
+            .map_err(std::io::Error::other)?
+            .read_to_end(&mut plain)?;
+        Ok(plain)
+    }
+
+    /// Bytes that differ from chunk to chunk, so a swap would show.
+    fn plaintext(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / CHUNK) as u8).collect()
+    }
+
+    #[test]
+    fn round_trips_any_length() {
+        let key = Aes256Gcm::generate_key(OsRng);
+        for len in [0, 1, CHUNK - 1, CHUNK, CHUNK + 1, 3 * CHUNK + 100] {
+            let plain = plaintext(len);
+            let container = sealed(&key, &plain);
+            let chunks = len.div_ceil(CHUNK).max(1);
+            assert_eq!(container.len(), HEADER_LEN + len + chunks * TAG_LEN);
+            assert_eq!(unseal(&key, &container).unwrap(), plain, "{} bytes", len);
+        }
+    }
+
+    #[test]
+    fn needs_the_same_key() {
+        let container = sealed(&Aes256Gcm::generate_key(OsRng), b"take one");
+        assert!(unseal(&Aes256Gcm::generate_key(OsRng), &container).is_err());
+    }
+
+    #[test]
+    fn rejects_a_tampered_chunk() {
+        let key = Aes256Gcm::generate_key(OsRng);
+        let mut container = sealed(&key, &plaintext(2 * CHUNK));
+        container[HEADER_LEN + CHUNK + 10] ^= 1;
+        assert!(unseal(&key, &container).is_err());
+    }
+
+    #[test]
+    fn rejects_a_truncated_container() {
+        let key = Aes256Gcm::generate_key(OsRng);
+        let container = sealed(&key, &plaintext(2 * CHUNK + 5));
+        // Cut right after a whole chunk, which is only caught by the last flag
+        let cut = HEADER_LEN + 2 * (CHUNK + TAG_LEN);
+        assert!(unseal(&key, &container[..cut]).is_err());
+        assert!(unseal(&key, &container[..HEADER_LEN]).is_err());
+    }
+
+    #[test]
+    fn rejects_reordered_chunks() {
+        let key = Aes256Gcm::generate_key(OsRng);
+        let container = sealed(&key, &plaintext(3 * CHUNK));
+        let sealed_chunk = CHUNK + TAG_LEN;
+        let first = HEADER_LEN..HEADER_LEN + sealed_chunk;
+        let second = first.end..first.end + sealed_chunk;
+        let swapped = [
+            &container[..HEADER_LEN],
+            &container[second],
+            &container[first.clone()],
+            &container[first.end + sealed_chunk..],
+        ]
+        .concat();
+        assert!(unseal(&key, &swapped).is_err());
+    }
+
+    #[test]
+    fn rejects_something_else() {
+        let key = Aes256Gcm::generate_key(OsRng);
+        let mut container = sealed(&key, b"take one");
+        container[0] = b'X';
+        assert!(unseal(&key, &container).is_err());
+        assert!(unseal(&key, b"RIFF").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::i18n::t;
+use crate::output;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use std::time::Duration;
+use tauri::AppHandle;
+
+const TICK_HZ: f32 = 1000.0;
+const TICK_MS: u32 = 30;
+const TICK_GAP_MS: u32 = 90;
+// Give the device a moment to drain before the stream is dropped
+const TAIL_MS: u64 = 50;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Cue {
+    Start,
+    Stop,
+}
+
+impl Cue {
+    fn ticks(self) -> u32 {
+        match self {
+            Cue::Start => 1,
+            Cue::Stop => 2,
+        }
+    }
+}
+
+fn render(cue: Cue, sample_rate: u32) -> Vec<f32> {
+    let tick_len = (sample_rate * TICK_MS / 1000) as usize;
+    let gap_len = (sample_rate * TICK_GAP_MS / 1000) as usize;
+
+    let mut out = Vec::new();
+    for i in 0..cue.ticks() {
+        if i > 0 {
+            out.resize(out.len() + gap_len, 0.0);
+        }
+        for n in 0..tick_len {
+            let t = n as f32 / sample_rate as f32;
+            let envelope = (-t * 150.0).exp();
+            out.push((2.0 * std::f32::consts::PI * TICK_HZ * t).sin() * envelope * 0.4);
+        }
+    }
+    out
+}
+
+/// Plays the cue on the configured output device and blocks until it has finished,
+/// so callers can sequence it strictly before or after the capture is running.
+pub fn play(app: &AppHandle, cue: Cue) -> Result<(), String> {
+    let device = output::output_device(app)?;
+    let config = device.default_output_config().map_err(|e| e.to_string())?;
+    if config.sample_format() != cpal::SampleFormat::F32 {
+        return Err(t("error-output-not-f32"));
+    }
+
+    let channels = config.channels() as usize;
+    let sample_rate = config.sample_rate();
+    let samples = render(cue, sample_rate);
+    let duration = Duration::from_millis(samples.len() as u64 * 1000 / sample_rate as u64);
+
+    let mut position = 0usize;
+    let stream = device
+        .build_output_stream(
+            &config.into(),
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                for frame in data.chunks_mut(channels) {
+                    let s = samples.get(position).copied().unwrap_or(0.0);
+                    position += 1;
+                    frame.fill(s);
+                }
+            },
+            move |err| {
+                eprintln!("Cue stream error: {}", err);
+            },
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+
+    stream.play().map_err(|e| e.to_string())?;
+    std::thread::sleep(duration + Duration::from_millis(TAIL_MS));
+    Ok(())
+}
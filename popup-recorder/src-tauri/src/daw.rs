@@ -0,0 +1,117 @@
+//! Reaper session export. The tracks are copied (decrypted where needed)
+//! into a session folder next to a `.RPP` that points at them by relative
+//! path, with the recording's markers as project markers.
+
+use crate::i18n::t;
+use crate::{crypto, metadata, recordings, SAMPLE_RATE};
+use hound::WavReader;
+use recorder_core::RecorderError;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+struct SessionTrack {
+    name: &'static str,
+    file: String,
+    length_secs: f64,
+    muted: bool,
+}
+
+fn track_name(kind: &str) -> &'static str {
+    match kind {
+        "mic" => "Mic",
+        "system" => "System",
+        "timecode" => "Timecode",
+        _ => "Mix",
+    }
+}
+
+// RPP strings have no escapes, so quotes inside labels become apostrophes
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "'"))
+}
+
+fn copy_track(source: &Path, target: &Path) -> Result<f64, String> {
+    let mut writer = BufWriter::new(File::create(target).map_err(|e| e.to_string())?);
+    std::io::copy(&mut crypto::open(source)?, &mut writer).map_err(|e| e.to_string())?;
+    drop(writer);
+    let reader = WavReader::open(target).map_err(|e| e.to_string())?;
+    Ok(reader.duration() as f64 / reader.spec().sample_rate as f64)
+}
+
+fn render(tracks: &[SessionTrack], markers: &[metadata::Marker]) -> String {
+    let mut rpp = String::new();
+    let _ = writeln!(rpp, "<REAPER_PROJECT 0.1 \"6.0\" 0");
+    let _ = writeln!(rpp, "  SAMPLERATE {} 0 0", SAMPLE_RATE);
+    for (index, marker) in markers.iter().enumerate() {
+        let label = marker.label.as_deref().unwrap_or_default();
+        let _ = writeln!(
+            rpp,
+            "  MARKER {} {:.3} {} 0",
+            index + 1,
+            marker.offset_ms as f64 / 1000.0,
+            quote(label)
+        );
+    }
+    for track in tracks {
+        let _ = writeln!(rpp, "  <TRACK");
+        let _ = writeln!(rpp, "    NAME {}", quote(track.name));
+        let _ = writeln!(rpp, "    MUTESOLO {} 0 0", track.muted as u8);
+        let _ = writeln!(rpp, "    <ITEM");
+        let _ = writeln!(rpp, "      POSITION 0");
+        let _ = writeln!(rpp, "      LENGTH {:.6}", track.length_secs);
+        let _ = writeln!(rpp, "      NAME {}", quote(&track.file));
+        let _ = writeln!(rpp, "      <SOURCE WAVE");
+        let _ = writeln!(rpp, "        FILE {}", quote(&track.file));
+        let _ = writeln!(rpp, "      >");
+        let _ = writeln!(rpp, "    >");
+        let _ = writeln!(rpp, "  >");
+    }
+    rpp.push_str(">\n");
+    rpp
+}
+
+fn write_session(recording: &Path, folder: &Path) -> Result<PathBuf, String> {
+    let name = recording
+        .file_stem()
+        .ok_or_else(|| t("error-not-a-file"))?
+        .to_string_lossy()
+        .to_string();
+    std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+
+    let companions = recordings::companions(recording);
+    let has_stems = companions
+        .iter()
+        .any(|(kind, _)| *kind == "mic" || *kind == "system");
+    let mut tracks = Vec::new();
+    for (kind, source) in std::iter::once(("mix", recording.to_path_buf())).chain(companions) {
+        let file = format!("{}-{}.wav", name, kind);
+        let length_secs = copy_track(&source, &folder.join(&file))?;
+        tracks.push(SessionTrack {
+            name: track_name(kind),
+            file,
+            length_secs,
+            // The mix would double the stems, and timecode is only for syncing
+            muted: kind == "timecode" || (kind == "mix" && has_stems),
+        });
+    }
+
+    let project = folder.join(format!("{}.RPP", name));
+    let rpp = render(&tracks, &metadata::load(recording).markers);
+    std::fs::write(&project, rpp).map_err(|e| e.to_string())?;
+    Ok(project)
+}
+
+/// Writes a Reaper session for the recording into `folder` and returns the
+/// path of the `.RPP`.
+#[tauri::command]
+pub async fn export_daw_session(path: String, folder: String) -> Result<String, RecorderError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        write_session(Path::new(&path), Path::new(&folder))
+            .map(|project| project.to_string_lossy().to_string())
+            .map_err(RecorderError::IoError)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
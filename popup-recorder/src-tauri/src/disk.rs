@@ -0,0 +1,104 @@
+//! Keeps a full disk from cutting a recording off mid-write. Starting needs
+//! `settings.min_free_space_mb` free on the recording's volume; while
+//! recording a warning goes out at twice that, and at the threshold itself
+//! the recording is stopped and finalized while there's still room for it.
+
+use crate::i18n::{t, tr};
+use crate::{events, settings, stop_and_hide, AppState};
+use parking_lot::Mutex;
+use recorder_core::disk::free_space;
+use serde_json::json;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+const MB: u64 = 1_000_000;
+
+#[derive(Default)]
+pub struct DiskState {
+    // Warned about the current recording already
+    warned: Mutex<bool>,
+}
+
+fn threshold(app: &AppHandle) -> Option<u64> {
+    let mb = settings::current(app).min_free_space_mb;
+    (mb > 0).then_some(mb * MB)
+}
+
+/// Refuses to start a recording in `dir` with less than the threshold free.
+pub fn check_start(app: &AppHandle, dir: &Path) -> Result<(), String> {
+    let (Some(threshold), Some(free)) = (threshold(app), free_space(dir)) else {
+        return Ok(());
+    };
+    if free < threshold {
+        return Err(tr(
+            "error-low-disk-space",
+            &[("free", (free / MB).to_string())],
+        ));
+    }
+    *app.state::<DiskState>().warned.lock() = false;
+    Ok(())
+}
+
+fn check(app: &AppHandle) {
+    let Some(threshold) = threshold(app) else {
+        return;
+    };
+    let Some(dir) = app
+        .state::<AppState>()
+        .recording_path()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+    else {
+        return;
+    };
+    let Some(free) = free_space(&dir) else {
+        return;
+    };
+
+    let stopping = free < threshold;
+    let mut warned = app.state::<DiskState>().warned.lock();
+    if !stopping && (free >= threshold * 2 || *warned) {
+        return;
+    }
+    *warned = true;
+    drop(warned);
+
+    events::emit(
+        app,
+        "low-disk-space",
+        json!({
+            "path": dir,
+            "free_bytes": free,
+            "threshold_bytes": threshold,
+            "stopping": stopping,
+        }),
+    );
+    let free_mb = (free / MB).to_string();
+    let (title, body) = match stopping {
+        true => ("low-disk-stopped-title", "low-disk-stopped-body"),
+        false => ("low-disk-title", "low-disk-body"),
+    };
+    let _ = app
+        .notification()
+        .builder()
+        .title(t(title))
+        .body(tr(body, &[("free", free_mb)]))
+        .show();
+    if stopping {
+        stop_and_hide(app);
+    }
+}
+
+/// Watches the free space left while recording.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            check(&app);
+        }
+    });
+}
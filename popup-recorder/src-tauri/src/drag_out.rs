@@ -0,0 +1,41 @@
+use crate::i18n::tr;
+use drag::{DragItem, Image, Options};
+use recorder_core::RecorderError;
+use std::path::PathBuf;
+use tauri::WebviewWindow;
+
+const DRAG_ICON: &[u8] = include_bytes!("../icons/128x128.png");
+
+/// Starts a native file drag for a finished recording so it can be dropped
+/// into other apps. Must be called while the mouse button is still down.
+#[tauri::command]
+pub async fn start_drag_recording(
+    window: WebviewWindow,
+    path: String,
+) -> Result<(), RecorderError> {
+    let path = PathBuf::from(path);
+    if !path.is_file() {
+        return Err(RecorderError::NotFound(tr(
+            "error-recording-not-found",
+            &[("path", path.display().to_string())],
+        )));
+    }
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let drag_window = window.clone();
+    // The OS drag session has to be started from the main thread
+    window
+        .run_on_main_thread(move || {
+            let result = drag::start_drag(
+                &drag_window,
+                DragItem::Files(vec![path]),
+                Image::Raw(DRAG_ICON.to_vec()),
+                |_, _| {},
+                Options::default(),
+            );
+            let _ = tx.send(result.map_err(|e| RecorderError::Other(e.to_string())));
+        })
+        .map_err(|e| e.to_string())?;
+
+    rx.await.map_err(|e| e.to_string())?
+}
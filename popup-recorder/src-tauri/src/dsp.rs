@@ -0,0 +1,330 @@
+//! Processing applied before samples are written: `VoiceChain` on the mix
+//! for voice presets, and the configurable `Pipeline` on the mic input.
+
+use crate::i18n::t;
+use crate::settings;
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+
+const SAMPLE_RATE: f32 = 48000.0;
+// Cuts rumble and desk thumps below the voice range
+const HIGH_PASS_HZ: f32 = 90.0;
+// Roughly -55 dBFS, below that the gate closes
+const GATE_THRESHOLD: f32 = 0.0018;
+const GATE_FLOOR: f32 = 0.1;
+const GATE_ATTACK: f32 = 0.01;
+const GATE_RELEASE: f32 = 0.0005;
+const ENVELOPE_SMOOTHING: f32 = 0.001;
+
+// ~10.7ms analysis frames at 48 kHz, half overlapped
+const FFT_SIZE: usize = 512;
+const HOP: usize = FFT_SIZE / 2;
+// How fast the per-bin noise floor follows the signal down and up. Rising
+// slowly keeps speech from being learned as noise
+const NOISE_FALL: f32 = 0.1;
+const NOISE_RISE: f32 = 0.002;
+// The floor estimate sits below the average noise level, so it's taken out
+// this many times over
+const OVERSUBTRACTION: f32 = 2.0;
+// Never take a bin below this, full removal sounds watery
+const SUPPRESSION_FLOOR: f32 = 0.15;
+
+/// First order high-pass for one channel.
+#[derive(Default)]
+struct HighPass {
+    previous_in: f32,
+    previous_out: f32,
+}
+
+impl HighPass {
+    fn process(&mut self, sample: f32, coefficient: f32) -> f32 {
+        let out = coefficient * (self.previous_out + sample - self.previous_in);
+        self.previous_in = sample;
+        self.previous_out = out;
+        out
+    }
+}
+
+/// Soft gate over both channels, following the louder one.
+struct NoiseGate {
+    threshold: f32,
+    envelope: f32,
+    gain: f32,
+}
+
+impl NoiseGate {
+    fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            envelope: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let peak = left.abs().max(right.abs());
+        self.envelope += (peak - self.envelope) * ENVELOPE_SMOOTHING;
+        // Attenuate rather than mute so room tone doesn't drop out abruptly
+        let (target, rate) = if self.envelope >= self.threshold {
+            (1.0, GATE_ATTACK)
+        } else {
+            (GATE_FLOOR, GATE_RELEASE)
+        };
+        self.gain += (target - self.gain) * rate;
+
+        (left * self.gain, right * self.gain)
+    }
+}
+
+/// High-pass plus a soft noise gate, tuned for speech in calls.
+pub struct VoiceChain {
+    coefficient: f32,
+    high_pass: [HighPass; 2],
+    gate: NoiseGate,
+}
+
+impl VoiceChain {
+    pub fn new() -> Self {
+        let rc = 1.0 / (std::f32::consts::TAU * HIGH_PASS_HZ);
+        let dt = 1.0 / SAMPLE_RATE;
+        Self {
+            coefficient: rc / (rc + dt),
+            high_pass: Default::default(),
+            gate: NoiseGate::new(GATE_THRESHOLD),
+        }
+    }
+
+    /// Processes one stereo frame.
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let left = self.high_pass[0].process(left, self.coefficient);
+        let right = self.high_pass[1].process(right, self.coefficient);
+        self.gate.process(left, right)
+    }
+}
+
+/// Spectral subtraction for one channel. Anything steady, like fan noise or
+/// hum, is learned as noise. Works on half-overlapped frames, so its output
+/// trails the input by `FFT_SIZE - 1` samples.
+struct SpectralChannel {
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    window: Arc<[f32]>,
+    strength: f32,
+    /// Previous hop, then the current one filling up to `pending`
+    input: Vec<f32>,
+    pending: usize,
+    /// Overlap-add accumulator for the frames in flight
+    overlap: Vec<f32>,
+    ready: VecDeque<f32>,
+    /// Per-bin noise magnitude estimate, seeded from the first frame
+    noise: Option<Vec<f32>>,
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl SpectralChannel {
+    fn process(&mut self, sample: f32) -> f32 {
+        self.input[HOP + self.pending] = sample;
+        self.pending += 1;
+        if self.pending == HOP {
+            self.pending = 0;
+            self.analyse();
+            self.input.copy_within(HOP.., 0);
+        }
+        // Silence until the first frame is through
+        self.ready.pop_front().unwrap_or(0.0)
+    }
+
+    fn analyse(&mut self) {
+        for ((bin, sample), window) in self.spectrum.iter_mut().zip(&self.input).zip(&*self.window)
+        {
+            *bin = Complex::new(sample * window, 0.0);
+        }
+        self.forward.process(&mut self.spectrum);
+
+        let spectrum = &self.spectrum;
+        let noise = self.noise.get_or_insert_with(|| {
+            spectrum[..=FFT_SIZE / 2]
+                .iter()
+                .map(|bin| bin.norm())
+                .collect()
+        });
+        for (k, noise) in noise.iter_mut().enumerate() {
+            let magnitude = self.spectrum[k].norm();
+            let rate = if magnitude < *noise {
+                NOISE_FALL
+            } else {
+                NOISE_RISE
+            };
+            *noise += (magnitude - *noise) * rate;
+
+            let gain = if magnitude > 0.0 {
+                (1.0 - self.strength * OVERSUBTRACTION * *noise / magnitude).max(SUPPRESSION_FLOOR)
+            } else {
+                SUPPRESSION_FLOOR
+            };
+            self.spectrum[k] *= gain;
+            // Keep the spectrum conjugate-symmetric so the output stays real
+            if k != 0 && k != FFT_SIZE / 2 {
+                self.spectrum[FFT_SIZE - k] *= gain;
+            }
+        }
+
+        self.inverse.process(&mut self.spectrum);
+        let scale = 1.0 / FFT_SIZE as f32;
+        for ((out, bin), window) in self
+            .overlap
+            .iter_mut()
+            .zip(&self.spectrum)
+            .zip(&*self.window)
+        {
+            *out += bin.re * scale * window;
+        }
+        self.ready.extend(self.overlap.drain(..HOP));
+        self.overlap.resize(FFT_SIZE, 0.0);
+    }
+}
+
+/// Mic noise suppression, one spectral channel per side.
+struct NoiseSuppressor([SpectralChannel; 2]);
+
+impl NoiseSuppressor {
+    fn new(strength: f32) -> Self {
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(FFT_SIZE);
+        let inverse = planner.plan_fft_inverse(FFT_SIZE);
+        // Square-root Hann on both ends, which sums to one at half overlap
+        let window: Arc<[f32]> = (0..FFT_SIZE)
+            .map(|i| (std::f32::consts::PI * i as f32 / FFT_SIZE as f32).sin())
+            .collect();
+        let channel = || SpectralChannel {
+            forward: forward.clone(),
+            inverse: inverse.clone(),
+            window: window.clone(),
+            strength,
+            input: vec![0.0; FFT_SIZE],
+            pending: 0,
+            overlap: vec![0.0; FFT_SIZE],
+            ready: VecDeque::with_capacity(HOP),
+            noise: None,
+            spectrum: vec![Complex::default(); FFT_SIZE],
+        };
+        Self([channel(), channel()])
+    }
+}
+
+/// One step of the mic pipeline.
+pub trait Stage: Send {
+    fn process(&mut self, frame: [f32; 2]) -> [f32; 2];
+}
+
+impl Stage for NoiseGate {
+    fn process(&mut self, [left, right]: [f32; 2]) -> [f32; 2] {
+        let (left, right) = NoiseGate::process(self, left, right);
+        [left, right]
+    }
+}
+
+impl Stage for NoiseSuppressor {
+    fn process(&mut self, [left, right]: [f32; 2]) -> [f32; 2] {
+        [self.0[0].process(left), self.0[1].process(right)]
+    }
+}
+
+/// What the mic pipeline runs, persisted in the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DspOptions {
+    pub noise_suppression: bool,
+    /// 0 to 1, how much of the learned noise floor is taken out
+    pub suppression_strength: f32,
+    pub noise_gate: bool,
+    /// Level the gate opens at, the default is roughly -55 dBFS
+    pub gate_threshold: f32,
+}
+
+impl Default for DspOptions {
+    fn default() -> Self {
+        Self {
+            noise_suppression: false,
+            suppression_strength: 0.8,
+            noise_gate: false,
+            gate_threshold: GATE_THRESHOLD,
+        }
+    }
+}
+
+/// Stages run in order on every mic frame before it's mixed. Suppression
+/// goes first so the gate judges the cleaned signal.
+pub struct Pipeline(Vec<Box<dyn Stage>>);
+
+impl Pipeline {
+    fn new(options: &DspOptions) -> Self {
+        let mut stages: Vec<Box<dyn Stage>> = Vec::new();
+        if options.noise_suppression {
+            stages.push(Box::new(NoiseSuppressor::new(options.suppression_strength)));
+        }
+        if options.noise_gate {
+            stages.push(Box::new(NoiseGate::new(options.gate_threshold)));
+        }
+        Self(stages)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn process(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        self.0
+            .iter_mut()
+            .fold(frame, |frame, stage| stage.process(frame))
+    }
+}
+
+pub struct DspState(Arc<Mutex<Pipeline>>);
+
+impl DspState {
+    pub fn load(app: &AppHandle) -> Self {
+        Self(Arc::new(Mutex::new(Pipeline::new(
+            &settings::current(app).dsp,
+        ))))
+    }
+
+    // Shared with the mixer, so new options apply mid-recording
+    pub fn shared(&self) -> Arc<Mutex<Pipeline>> {
+        self.0.clone()
+    }
+
+    /// Replaces the pipeline with one for `validate`d `options`.
+    pub fn set(&self, options: &DspOptions) {
+        *self.0.lock() = Pipeline::new(options);
+    }
+}
+
+pub fn validate(options: &DspOptions) -> Result<(), RecorderError> {
+    let in_range = |value: f32| value.is_finite() && (0.0..=1.0).contains(&value);
+    if !in_range(options.suppression_strength) || !in_range(options.gate_threshold) {
+        return Err(RecorderError::InvalidArgument(t(
+            "error-invalid-dsp-options",
+        )));
+    }
+    Ok(())
+}
+
+/// Replaces the mic pipeline, taking effect on the next mixed block.
+#[tauri::command]
+pub fn set_dsp_options(
+    app: AppHandle,
+    state: State<'_, DspState>,
+    options: DspOptions,
+) -> Result<(), RecorderError> {
+    validate(&options)?;
+    settings::modify(&app, |settings| settings.dsp = options)?;
+    state.set(&options);
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! Secondary encoders run off the capture path. The mixer hands each mixed
+//! block to a bounded queue per encoder; a full queue drops the block for
+//! that encoder instead of stalling the capture callbacks.
+
+use crate::convert::ConvertFormat;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+// Mix blocks are ~10 ms, so this is a few seconds of slack
+const QUEUE_CAPACITY: usize = 256;
+
+pub trait Encoder: Send + 'static {
+    fn name(&self) -> String;
+    fn encode(&mut self, samples: &[f32]) -> Result<(), String>;
+    fn finish(self: Box<Self>) -> Result<(), String>;
+}
+
+/// Integer PCM copy of the mix, for the dual-format output.
+pub struct PcmEncoder {
+    writer: WavWriter<BufWriter<File>>,
+    format: ConvertFormat,
+    max: f32,
+}
+
+impl PcmEncoder {
+    pub fn create(path: &Path, format: ConvertFormat) -> Result<Self, String> {
+        let bits = format.bits();
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: bits,
+            sample_format: SampleFormat::Int,
+        };
+        Ok(Self {
+            writer: WavWriter::create(path, spec).map_err(|e| e.to_string())?,
+            format,
+            max: ((1i64 << (bits - 1)) - 1) as f32,
+        })
+    }
+}
+
+impl Encoder for PcmEncoder {
+    fn name(&self) -> String {
+        format!("pcm{}", self.format.bits())
+    }
+
+    fn encode(&mut self, samples: &[f32]) -> Result<(), String> {
+        for sample in samples {
+            self.writer
+                .write_sample((sample.clamp(-1.0, 1.0) * self.max).round() as i32)
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        self.writer.finalize().map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStats {
+    name: String,
+    depth: usize,
+    max_depth: usize,
+    dropped_blocks: u64,
+}
+
+struct Worker {
+    name: String,
+    sender: Mutex<Option<SyncSender<Arc<[f32]>>>>,
+    handle: Mutex<Option<JoinHandle<Result<(), String>>>>,
+    depth: Arc<AtomicUsize>,
+    max_depth: AtomicUsize,
+    dropped_blocks: AtomicU64,
+}
+
+/// One thread per encoder, so each output still sees blocks in order.
+pub struct EncoderPool {
+    workers: Vec<Worker>,
+}
+
+impl EncoderPool {
+    pub fn start(encoders: Vec<Box<dyn Encoder>>) -> Self {
+        let workers = encoders
+            .into_iter()
+            .map(|mut encoder| {
+                let name = encoder.name();
+                let (sender, receiver) = sync_channel::<Arc<[f32]>>(QUEUE_CAPACITY);
+                let depth = Arc::new(AtomicUsize::new(0));
+                let worker_depth = depth.clone();
+                let handle = std::thread::spawn(move || {
+                    let mut result = Ok(());
+                    for block in receiver {
+                        worker_depth.fetch_sub(1, Ordering::Relaxed);
+                        // Keep draining after an error so the mixer never blocks
+                        if result.is_ok() {
+                            result = encoder.encode(&block);
+                        }
+                    }
+                    result.and(encoder.finish())
+                });
+                Worker {
+                    name,
+                    sender: Mutex::new(Some(sender)),
+                    handle: Mutex::new(Some(handle)),
+                    depth,
+                    max_depth: AtomicUsize::new(0),
+                    dropped_blocks: AtomicU64::new(0),
+                }
+            })
+            .collect();
+        Self { workers }
+    }
+
+    /// Queues a block of interleaved samples for every encoder. Never blocks.
+    pub fn submit(&self, samples: &[f32]) {
+        let block: Arc<[f32]> = Arc::from(samples);
+        for worker in &self.workers {
+            let sender = worker.sender.lock();
+            let Some(sender) = sender.as_ref() else {
+                continue;
+            };
+            // Counted before sending so the worker can't decrement first
+            let depth = worker.depth.fetch_add(1, Ordering::Relaxed) + 1;
+            match sender.try_send(block.clone()) {
+                Ok(()) => {
+                    worker.max_depth.fetch_max(depth, Ordering::Relaxed);
+                }
+                Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => {
+                    worker.depth.fetch_sub(1, Ordering::Relaxed);
+                    worker.dropped_blocks.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Vec<QueueStats> {
+        self.workers
+            .iter()
+            .map(|worker| QueueStats {
+                name: worker.name.clone(),
+                depth: worker.depth.load(Ordering::Relaxed),
+                max_depth: worker.max_depth.load(Ordering::Relaxed),
+                dropped_blocks: worker.dropped_blocks.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    /// Closes the queues, waits for the encoders to drain and finalize, and
+    /// returns any errors they hit.
+    pub fn finish(&self) -> Vec<String> {
+        for worker in &self.workers {
+            worker.sender.lock().take();
+        }
+        self.workers
+            .iter()
+            .filter_map(|worker| {
+                let handle = worker.handle.lock().take()?;
+                match handle.join() {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(format!("{}: {}", worker.name, e)),
+                    Err(_) => Some(format!("{}: encoder thread panicked", worker.name)),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,105 @@
+//! Versioned recorder event stream.
+//!
+//! Every recorder event is wrapped in an envelope `{ v, type, payload }` and
+//! delivered both as the `recorder-event` tauri event and to any `ipc::Channel`
+//! a window registered through `subscribe_events`.
+//!
+//! Event types (v1):
+//! - `recording-state`: `{ recording: bool }`
+//! - `audio-levels`: `{ mic_level, system_level, mixed_level }`
+//! - `quality-report-ready`: `{ path, report }`
+//! - `update-available`: `{ version, channel, notes }`
+//! - `playback-position`: `{ path, position_ms, duration_ms, paused, finished }`
+//! - `overlay-auto-hide`: `{ collapsed: bool }`
+//! - `idle-reminder`: `{ quiet_secs }`
+//! - `power-mode-changed`: `{ on_battery, reduced }`
+//! - `thermal-state-changed`: `{ level, degraded }`
+//! - `conversion-progress`: `{ path, index, total, status, error }`
+//! - `backup-status`: `{ path, state, error }`
+//! - `encryption-failed`: `{ path, error }`
+//! - `auto-stopped`: `{ at }`
+//! - `low-disk-space`: `{ path, free_bytes, threshold_bytes, stopping }`
+//! - `meeting-starting`: `{ uid, summary, start, end }`
+//! - `meeting-detected`: `{ app, activity: { microphone, system_audio } }`
+//! - `output-spilled`: `{ target, spill, reason }`
+//! - `output-share-status`: `{ target, reachable }`
+//! - `output-moved`: `{ from, to }`
+//! - `output-move-pending`: `{ path, target }`
+//! - `handoff`: `{ path, target, action, error }`
+//! - `hook-finished`: `{ program, path, success, exit_code, timed_out, error }`
+//! - `ffmpeg-progress`: `{ path, output, percent }`
+//! - `timecode`: `{ sample_position, sample_rate, wall_clock_ms, host_time_ns }`
+//! - `capture-window-lost`: `{ pattern, window_id }`
+//! - `capture-window-changed`: `{ pattern, window_id, title }`
+//! - `silence-detected`: `{ silent, start_ms, duration_ms }`
+//! - `speech-started`: `{ at_ms }`
+//! - `speech-stopped`: `{ at_ms, duration_ms }`
+//! - `transcript-partial`: `{ start_ms, end_ms, text }`
+//! - `transcript-final`: `{ start_ms, end_ms, text }`
+//! - `marker-added`: `{ offset_ms, label }`
+//! - `launch-recording-blocked`: `{ source, preflight }`
+//! - `recording-start-failed`: `{ error }`
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+
+pub const EVENT_API_VERSION: u32 = 1;
+pub const RECORDER_EVENT: &str = "recorder-event";
+
+#[derive(Debug, Clone, Serialize)]
+struct Envelope<'a, T: Serialize> {
+    v: u32,
+    #[serde(rename = "type")]
+    kind: &'a str,
+    payload: T,
+}
+
+/// Channels registered per window label.
+#[derive(Default)]
+pub struct EventChannels(Mutex<HashMap<String, Channel<serde_json::Value>>>);
+
+pub fn emit<T: Serialize>(app: &AppHandle, kind: &str, payload: T) {
+    let envelope = Envelope {
+        v: EVENT_API_VERSION,
+        kind,
+        payload,
+    };
+    let value = match serde_json::to_value(&envelope) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Failed to serialize {} event: {}", kind, e);
+            return;
+        }
+    };
+
+    let _ = app.emit(RECORDER_EVENT, &value);
+
+    if let Some(channels) = app.try_state::<EventChannels>() {
+        // Drop channels whose window went away
+        channels
+            .0
+            .lock()
+            .retain(|_, channel| channel.send(value.clone()).is_ok());
+    }
+}
+
+#[tauri::command]
+pub fn subscribe_events(
+    window: WebviewWindow,
+    channels: State<'_, EventChannels>,
+    channel: Channel<serde_json::Value>,
+) -> u32 {
+    channels
+        .0
+        .lock()
+        .insert(window.label().to_string(), channel);
+    EVENT_API_VERSION
+}
+
+#[tauri::command]
+pub fn unsubscribe_events(window: WebviewWindow, channels: State<'_, EventChannels>) {
+    channels.0.lock().remove(window.label());
+}
@@ -0,0 +1,183 @@
+//! Exports through a user-provided or bundled ffmpeg. Progress comes from
+//! ffmpeg's own stderr: the input `Duration:` line gives the total and
+//! `-progress pipe:2` reports how far along it is.
+
+use crate::i18n::{t, tr};
+use crate::{crypto, events, recordings, settings};
+use recorder_core::RecorderError;
+use serde::Deserialize;
+use serde_json::json;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FfmpegPreset {
+    /// Mono AAC at 96 kbit/s, small files for speech
+    M4aVoice,
+    /// MP3 at 320 kbit/s
+    #[serde(rename = "mp3-320k")]
+    Mp3320k,
+    /// Stream copy into MP4, for video captures
+    VideoRemux,
+}
+
+impl FfmpegPreset {
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            FfmpegPreset::M4aVoice => &["-vn", "-ac", "1", "-c:a", "aac", "-b:a", "96k"],
+            FfmpegPreset::Mp3320k => &["-vn", "-c:a", "libmp3lame", "-b:a", "320k"],
+            FfmpegPreset::VideoRemux => &["-c", "copy", "-movflags", "+faststart"],
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            FfmpegPreset::M4aVoice => "m4a",
+            FfmpegPreset::Mp3320k => "mp3",
+            FfmpegPreset::VideoRemux => "mp4",
+        }
+    }
+}
+
+/// The configured path, then wherever `recorder_core::ffmpeg` finds it.
+fn locate(app: &AppHandle) -> Option<PathBuf> {
+    settings::current(app)
+        .ffmpeg_path
+        .map(PathBuf::from)
+        .or_else(recorder_core::ffmpeg::locate)
+}
+
+/// `Duration: 00:01:02.34, ...` to microseconds.
+fn parse_duration(line: &str) -> Option<u64> {
+    let rest = line.trim_start().strip_prefix("Duration: ")?;
+    let timestamp = rest.split(',').next()?;
+    let mut parts = timestamp.split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(((hours * 3600.0 + minutes * 60.0 + seconds) * 1_000_000.0) as u64)
+}
+
+/// Runs ffmpeg into `partial`, reporting progress as the export to `output`.
+fn export(
+    app: &AppHandle,
+    source: &Path,
+    preset: FfmpegPreset,
+    output: &Path,
+    partial: &Path,
+) -> Result<(), String> {
+    let ffmpeg = locate(app).ok_or_else(|| t("error-ffmpeg-not-found"))?;
+    // ffmpeg can't read our container, encrypted recordings are streamed in decrypted
+    let encrypted = crypto::is_encrypted(source);
+    let mut command = Command::new(ffmpeg);
+    command.args([
+        "-hide_banner",
+        "-nostats",
+        "-progress",
+        "pipe:2",
+        "-y",
+        "-i",
+    ]);
+    if encrypted {
+        command.arg("pipe:0").stdin(Stdio::piped());
+    } else {
+        command.arg(source).stdin(Stdio::null());
+    }
+    let mut child = command
+        .args(preset.args())
+        .arg(partial)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let feeder = child.stdin.take().map(|mut stdin| {
+        let source = source.to_path_buf();
+        std::thread::spawn(move || -> Result<(), String> {
+            let mut plaintext = crypto::open(&source)?;
+            std::io::copy(&mut plaintext, &mut stdin).map_err(|e| e.to_string())?;
+            stdin.flush().map_err(|e| e.to_string())
+        })
+    });
+
+    let mut total_us = None;
+    let mut last_percent = None;
+    let mut last_line = String::new();
+    if let Some(stderr) = child.stderr.take() {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            if total_us.is_none() {
+                total_us = parse_duration(&line);
+            }
+            // `out_time_ms` is microseconds too, despite the name
+            let position = line
+                .strip_prefix("out_time_us=")
+                .or_else(|| line.strip_prefix("out_time_ms="))
+                .and_then(|value| value.parse::<u64>().ok());
+            if let (Some(position), Some(total)) = (position, total_us.filter(|&t| t > 0)) {
+                let percent = (position.min(total) * 100 / total) as u8;
+                if last_percent != Some(percent) {
+                    last_percent = Some(percent);
+                    events::emit(
+                        app,
+                        "ffmpeg-progress",
+                        json!({ "path": source, "output": output, "percent": percent }),
+                    );
+                }
+            }
+            if !line.contains('=') && !line.trim().is_empty() {
+                last_line = line;
+            }
+        }
+    }
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if let Some(Err(e)) = feeder.map(|feeder| feeder.join().unwrap_or(Ok(()))) {
+        eprintln!("Feeding ffmpeg failed: {}", e);
+    }
+    if !status.success() {
+        return Err(tr("error-ffmpeg-failed", &[("detail", last_line)]));
+    }
+    Ok(())
+}
+
+/// `<name>.partial.<ext>`, ffmpeg picks the container from the last extension.
+fn partial_path(output: &Path, preset: FfmpegPreset) -> PathBuf {
+    let extension = output
+        .extension()
+        .map_or(preset.extension().into(), |ext| ext.to_string_lossy());
+    output.with_extension(format!("partial.{}", extension))
+}
+
+/// Exports a recording with one of the built-in presets. `output` defaults to
+/// the recording's path with the preset's extension and must not be the
+/// recording itself; an existing file there is only replaced once the export
+/// succeeded. Returns the output path.
+#[tauri::command]
+pub async fn export_with_ffmpeg(
+    app: AppHandle,
+    path: String,
+    preset: FfmpegPreset,
+    output: Option<String>,
+) -> Result<String, RecorderError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let source = PathBuf::from(&path);
+        let output = output
+            .map(PathBuf::from)
+            .unwrap_or_else(|| source.with_extension(preset.extension()));
+        if recordings::same_file(&source, &output) {
+            return Err(RecorderError::InvalidArgument(t("error-export-same-file")));
+        }
+        // Written aside so a failure only removes what this export created
+        let partial = partial_path(&output, preset);
+        export(&app, &source, preset, &output, &partial).inspect_err(|_| {
+            let _ = std::fs::remove_file(&partial);
+        })?;
+        std::fs::rename(&partial, &output)?;
+        Ok(output.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
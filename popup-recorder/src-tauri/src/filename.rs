@@ -0,0 +1,42 @@
+//! Names for new recordings, rendered from the `filename_template` setting.
+//! Tokens are `{date}`, `{time}`, `{profile}` (the active preset) and
+//! `{counter}`, the lowest number not yet taken in the folder.
+
+use crate::i18n::tr;
+use crate::{crypto, presets};
+use recorder_core::filename;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+pub const DEFAULT_TEMPLATE: &str = "recording-{date}-{time}";
+
+pub fn validate(template: &str) -> Result<(), String> {
+    match filename::is_valid(template) {
+        true => Ok(()),
+        false => Err(tr(
+            "error-invalid-filename-template",
+            &[("template", template.to_string())],
+        )),
+    }
+}
+
+// A plain or sealed recording already has the name
+fn taken(path: &Path) -> bool {
+    path.is_file() || path.with_extension(crypto::EXTENSION).is_file()
+}
+
+/// Path for a new recording in `dir`. Without `{counter}` in the template a
+/// name that's already taken gets `-2`, `-3`, ... appended instead.
+pub fn next_path(app: &AppHandle, dir: &Path, template: &str) -> PathBuf {
+    let template = match validate(template) {
+        Ok(()) => template,
+        Err(e) => {
+            eprintln!("Using the default file name: {}", e);
+            DEFAULT_TEMPLATE
+        }
+    };
+    let profile = presets::active(app)
+        .map(|preset| preset.name)
+        .unwrap_or_default();
+    filename::next_path(dir, template, &profile, taken)
+}
@@ -0,0 +1,88 @@
+//! Per-source gain applied by the mixer. Gains are plain multipliers (1.0
+//! leaves a source as captured), persisted in the settings and kept as f32
+//! bits so the capture callbacks can read them without locking.
+
+use crate::i18n::tr;
+use crate::settings;
+use recorder_core::RecorderError;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+
+const MAX_GAIN: f32 = 4.0;
+
+pub struct SourceGains {
+    mic: AtomicU32,
+    system: AtomicU32,
+}
+
+impl SourceGains {
+    pub fn mic(&self) -> f32 {
+        f32::from_bits(self.mic.load(Ordering::Relaxed))
+    }
+
+    pub fn system(&self) -> f32 {
+        f32::from_bits(self.system.load(Ordering::Relaxed))
+    }
+}
+
+pub struct GainState(Arc<SourceGains>);
+
+impl GainState {
+    pub fn load(app: &AppHandle) -> Self {
+        let settings = settings::current(app);
+        Self(Arc::new(SourceGains {
+            mic: AtomicU32::new(settings.mic_gain.to_bits()),
+            system: AtomicU32::new(settings.system_gain.to_bits()),
+        }))
+    }
+
+    // Shared with the mixer, which reads it for every block
+    pub fn shared(&self) -> Arc<SourceGains> {
+        self.0.clone()
+    }
+
+    /// Takes effect on the next mixed block. `gain` has to be `validate`d.
+    pub fn set_mic(&self, gain: f32) {
+        self.0.mic.store(gain.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Takes effect on the next mixed block. `gain` has to be `validate`d.
+    pub fn set_system(&self, gain: f32) {
+        self.0.system.store(gain.to_bits(), Ordering::Relaxed);
+    }
+}
+
+pub fn validate(gain: f32) -> Result<f32, String> {
+    if gain.is_finite() && (0.0..=MAX_GAIN).contains(&gain) {
+        Ok(gain)
+    } else {
+        Err(tr("error-invalid-gain", &[("max", MAX_GAIN.to_string())]))
+    }
+}
+
+/// Sets the mic multiplier, taking effect on the next mixed block.
+#[tauri::command]
+pub fn set_mic_gain(
+    app: AppHandle,
+    state: State<'_, GainState>,
+    gain: f32,
+) -> Result<(), RecorderError> {
+    let gain = validate(gain).map_err(RecorderError::InvalidArgument)?;
+    settings::modify(&app, |settings| settings.mic_gain = gain)?;
+    state.set_mic(gain);
+    Ok(())
+}
+
+/// Sets the system audio multiplier, taking effect on the next mixed block.
+#[tauri::command]
+pub fn set_system_gain(
+    app: AppHandle,
+    state: State<'_, GainState>,
+    gain: f32,
+) -> Result<(), RecorderError> {
+    let gain = validate(gain).map_err(RecorderError::InvalidArgument)?;
+    settings::modify(&app, |settings| settings.system_gain = gain)?;
+    state.set_system(gain);
+    Ok(())
+}
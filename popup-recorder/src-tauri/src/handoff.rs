@@ -0,0 +1,116 @@
+//! Hands finished recordings to a watch folder so external tools (Hazel
+//! rules, transcription watchers) see them without reading the app's data dir.
+
+use crate::convert::{self, ConvertFormat};
+use crate::i18n::t;
+use crate::{events, metadata, settings};
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HandoffAction {
+    Copy,
+    /// The recording and its sidecar leave the library
+    Move,
+    Symlink,
+}
+
+/// With `convert` set the folder always gets a converted file; `Move` then
+/// removes the original and `Symlink` behaves like `Copy`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffRule {
+    pub dir: String,
+    pub action: HandoffAction,
+    #[serde(default)]
+    pub convert: Option<ConvertFormat>,
+}
+
+#[cfg(unix)]
+fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink(original: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(original, link)
+}
+
+// Files show up under their final name only once complete, watchers fire on creation
+fn place(source: &Path, target: &Path, rule: &HandoffRule) -> Result<(), String> {
+    let partial = target.with_extension("partial");
+    let result = match (rule.convert, rule.action) {
+        (Some(format), _) => convert::transcode(source, &partial, format),
+        (None, HandoffAction::Symlink) => {
+            return symlink(source, target).map_err(|e| e.to_string());
+        }
+        (None, HandoffAction::Move) => {
+            if std::fs::rename(source, target).is_ok() {
+                return Ok(());
+            }
+            std::fs::copy(source, &partial)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        (None, HandoffAction::Copy) => std::fs::copy(source, &partial)
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+    };
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, target).map_err(|e| e.to_string())
+}
+
+fn hand_off(source: &Path, rule: &HandoffRule) -> Result<PathBuf, String> {
+    let dir = PathBuf::from(&rule.dir);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let target = dir.join(source.file_name().ok_or_else(|| t("error-not-a-file"))?);
+    place(source, &target, rule)?;
+
+    if rule.action == HandoffAction::Move {
+        let sidecar = metadata::metadata_path(source);
+        if sidecar.is_file() {
+            let _ = std::fs::rename(&sidecar, metadata::metadata_path(&target))
+                .or_else(|_| std::fs::remove_file(&sidecar));
+        }
+        if source.exists() {
+            std::fs::remove_file(source).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(target)
+}
+
+/// Applies the configured handoff rule to a finished recording and returns
+/// where the recording is afterwards, the watch folder for a `Move`. Blocking.
+pub fn run(app: &AppHandle, path: &Path) -> PathBuf {
+    let Some(rule) = settings::current(app).handoff else {
+        return path.to_path_buf();
+    };
+    let (target, error) = match hand_off(path, &rule) {
+        Ok(target) => (Some(target), None),
+        Err(e) => {
+            eprintln!("Handoff of {} failed: {}", path.display(), e);
+            (None, Some(e))
+        }
+    };
+    events::emit(
+        app,
+        "handoff",
+        json!({ "path": path, "target": target, "action": rule.action, "error": error }),
+    );
+    match (rule.action, target) {
+        (HandoffAction::Move, Some(target)) => target,
+        _ => path.to_path_buf(),
+    }
+}
+
+#[tauri::command]
+pub fn set_handoff(app: AppHandle, rule: Option<HandoffRule>) -> Result<(), RecorderError> {
+    settings::modify(&app, |settings| settings.handoff = rule)?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! User hook scripts run after a recording is finalized. Each hook gets its
+//! configured arguments followed by the recording path and the sidecar
+//! metadata as a JSON string. Output goes to the log; a `hook-finished`
+//! event reports how it went.
+
+use crate::{events, metadata, settings};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+fn default_timeout_secs() -> u64 {
+    300
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hook {
+    /// Executable path, or a name looked up on `PATH`
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Killed after this long
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Default)]
+struct Outcome {
+    exit_code: Option<i32>,
+    timed_out: bool,
+    error: Option<String>,
+}
+
+// Reads a pipe on its own thread so a chatty script can't fill it and stall
+fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut output);
+        }
+        output
+    })
+}
+
+fn run_one(hook: &Hook, path: &Path, metadata_json: &str) -> Outcome {
+    let mut child = match Command::new(&hook.program)
+        .args(&hook.args)
+        .arg(path)
+        .arg(metadata_json)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            return Outcome {
+                error: Some(e.to_string()),
+                ..Default::default()
+            }
+        }
+    };
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + Duration::from_secs(hook.timeout_secs);
+    let mut outcome = Outcome::default();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                outcome.exit_code = status.code();
+                break;
+            }
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                outcome.timed_out = true;
+                break;
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                outcome.error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+
+    for (stream, output) in [("stdout", stdout), ("stderr", stderr)] {
+        let output = output.join().unwrap_or_default();
+        for line in output.lines() {
+            eprintln!("[hook {} {}] {}", hook.program, stream, line);
+        }
+    }
+    outcome
+}
+
+/// Runs every configured hook for a finished recording, one after another. Blocking.
+pub fn run(app: &AppHandle, path: &Path) {
+    let hooks = settings::current(app).hooks;
+    if hooks.is_empty() {
+        return;
+    }
+    let metadata_json = serde_json::to_string(&metadata::load(path)).unwrap_or_default();
+
+    for hook in &hooks {
+        let outcome = run_one(hook, path, &metadata_json);
+        let success = outcome.exit_code == Some(0);
+        if !success {
+            eprintln!("Hook {} failed: {:?}", hook.program, outcome);
+        }
+        events::emit(
+            app,
+            "hook-finished",
+            json!({
+                "program": hook.program,
+                "path": path,
+                "success": success,
+                "exit_code": outcome.exit_code,
+                "timed_out": outcome.timed_out,
+                "error": outcome.error,
+            }),
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Translations for backend-generated text (tray, notifications, errors).
+//! Messages are Fluent files in `locales/`, compiled into the binary. Lookups
+//! fall back to English for missing messages.
+
+use crate::{settings, tray};
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use recorder_core::RecorderError;
+use tauri::AppHandle;
+use unic_langid::LanguageIdentifier;
+
+const FALLBACK: &str = "en";
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+type Bundle = FluentBundle<FluentResource>;
+
+struct Catalog {
+    locale: &'static str,
+    bundle: Bundle,
+    fallback: Bundle,
+}
+
+static CATALOG: Lazy<RwLock<Catalog>> = Lazy::new(|| RwLock::new(Catalog::new(FALLBACK)));
+
+fn bundle(locale: &'static str) -> Bundle {
+    let source = LOCALES
+        .iter()
+        .find(|(id, _)| *id == locale)
+        .map(|(_, source)| *source)
+        .unwrap_or(LOCALES[0].1);
+    let langid: LanguageIdentifier = locale.parse().unwrap_or_default();
+
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Isolation marks show up as stray glyphs in native menus
+    bundle.set_use_isolating(false);
+    let resource =
+        FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+            eprintln!("Errors in {} translations: {:?}", locale, errors);
+            resource
+        });
+    if let Err(errors) = bundle.add_resource(resource) {
+        eprintln!("Errors in {} translations: {:?}", locale, errors);
+    }
+    bundle
+}
+
+impl Catalog {
+    fn new(locale: &'static str) -> Self {
+        Self {
+            locale,
+            bundle: bundle(locale),
+            fallback: bundle(FALLBACK),
+        }
+    }
+}
+
+/// Best bundled match for `requested` (e.g. `de-AT`), or the system locale.
+fn resolve(requested: Option<&str>) -> &'static str {
+    let requested = requested
+        .map(str::to_string)
+        .or_else(sys_locale::get_locale)
+        .unwrap_or_default();
+    let language = requested
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    LOCALES
+        .iter()
+        .map(|(id, _)| *id)
+        .find(|id| *id == language)
+        .unwrap_or(FALLBACK)
+}
+
+fn format(bundle: &Bundle, id: &str, args: Option<&FluentArgs>) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    Some(
+        bundle
+            .format_pattern(pattern, args, &mut errors)
+            .into_owned(),
+    )
+}
+
+/// Translated message with `{ $name }` placeholders filled from `args`.
+pub fn tr(id: &str, args: &[(&str, String)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+    let args = (!args.is_empty()).then_some(&fluent_args);
+
+    let catalog = CATALOG.read();
+    format(&catalog.bundle, id, args)
+        .or_else(|| format(&catalog.fallback, id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Translated message with a numeric `$count`, for plural selection.
+pub fn tr_count(id: &str, count: u64) -> String {
+    let mut args = FluentArgs::new();
+    args.set("count", count);
+
+    let catalog = CATALOG.read();
+    format(&catalog.bundle, id, Some(&args))
+        .or_else(|| format(&catalog.fallback, id, Some(&args)))
+        .unwrap_or_else(|| id.to_string())
+}
+
+pub fn t(id: &str) -> String {
+    tr(id, &[])
+}
+
+fn apply(locale: &'static str) {
+    let mut catalog = CATALOG.write();
+    if catalog.locale != locale {
+        *catalog = Catalog::new(locale);
+    }
+}
+
+/// Picks the locale from settings, or the system one, at startup.
+pub fn init(app: &AppHandle) {
+    apply(resolve(settings::current(app).locale.as_deref()));
+}
+
+/// Switches the backend language, `None` follows the system. Returns the
+/// locale actually used.
+#[tauri::command]
+pub fn set_locale(app: AppHandle, locale: Option<String>) -> Result<String, RecorderError> {
+    let resolved = resolve(locale.as_deref());
+    settings::modify(&app, |settings| settings.locale = locale)?;
+    apply(resolved);
+    tray::relabel(&app);
+    Ok(resolved.to_string())
+}
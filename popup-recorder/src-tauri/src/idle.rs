@@ -0,0 +1,73 @@
+use crate::i18n::{t, tr};
+use crate::{events, settings, stop_and_hide, AppState};
+use parking_lot::Mutex;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+// Roughly -50 dBFS on both sources counts as nothing happening
+const QUIET_LEVEL: f32 = 0.003;
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Quiet {
+    since: Option<Instant>,
+    prompted: bool,
+}
+
+#[derive(Default)]
+pub struct IdleState(Mutex<Quiet>);
+
+fn prompt(app: &AppHandle, quiet_for: Duration) {
+    let minutes = quiet_for.as_secs() / 60;
+    events::emit(
+        app,
+        "idle-reminder",
+        json!({ "quiet_secs": quiet_for.as_secs() }),
+    );
+    let _ = app
+        .notification()
+        .builder()
+        .title(t("idle-title"))
+        .body(tr("idle-body", &[("minutes", minutes.to_string())]))
+        .show();
+}
+
+fn check(app: &AppHandle) {
+    let minutes = settings::current(app).idle_reminder_mins;
+    let recorder = app.state::<AppState>();
+    let state = app.state::<IdleState>();
+    let mut quiet = state.0.lock();
+
+    if minutes == 0 || !recorder.is_recording() || recorder.input_level() >= QUIET_LEVEL {
+        *quiet = Quiet::default();
+        return;
+    }
+
+    let since = *quiet.since.get_or_insert_with(Instant::now);
+    if !quiet.prompted && since.elapsed() >= Duration::from_secs(minutes * 60) {
+        quiet.prompted = true;
+        prompt(app, since.elapsed());
+    }
+}
+
+/// Watches for recordings that have been near-silent for too long.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            check(&app);
+        }
+    });
+}
+
+/// Answer to the idle prompt. Keeping re-arms the reminder for another period.
+#[tauri::command]
+pub fn respond_idle_reminder(app: AppHandle, state: State<'_, IdleState>, keep: bool) {
+    *state.0.lock() = Quiet::default();
+    if !keep {
+        stop_and_hide(&app);
+    }
+}
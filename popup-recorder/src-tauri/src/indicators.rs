@@ -0,0 +1,50 @@
+//! Single place that reflects recording state on every surface (event stream,
+//! Dock tile, tray, screen reader) so they can't drift apart.
+
+use crate::{accessibility, events, settings, tray};
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager};
+
+#[cfg(target_os = "macos")]
+const RECORDING_BADGE: &str = "●";
+
+pub fn set_recording(app: &AppHandle, recording: bool) {
+    events::emit(
+        app,
+        "recording-state",
+        serde_json::json!({ "recording": recording }),
+    );
+
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.set_badge_label(recording.then(|| RECORDING_BADGE.to_string()));
+        }
+    }
+
+    tray::set_recording(app, recording);
+    accessibility::announce_recording(app, recording);
+}
+
+/// Shows background job progress (0-100) on the Dock tile, `None` clears it.
+/// Does nothing unless enabled in settings.
+pub fn set_progress(app: &AppHandle, progress: Option<u64>) {
+    if progress.is_some() && !settings::current(app).dock_progress {
+        return;
+    }
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+
+    let state = match progress {
+        Some(progress) => ProgressBarState {
+            status: Some(ProgressBarStatus::Normal),
+            progress: Some(progress.min(100)),
+        },
+        None => ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: None,
+        },
+    };
+    let _ = window.set_progress_bar(state);
+}
@@ -0,0 +1,86 @@
+//! SHA-256 over a recording's audio data. The digest is built while the
+//! mixer writes samples and kept in the sidecar; `verify_recording` hashes
+//! the file's `data` chunk again. The header isn't covered since it's only
+//! finalized after the last sample.
+
+use crate::i18n::t;
+use crate::{crypto, metadata};
+use recorder_core::RecorderError;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+
+/// Streaming digest of the samples as they go into the file.
+#[derive(Default)]
+pub struct DataHasher(Sha256);
+
+impl DataHasher {
+    /// Must see exactly the bytes hound writes, 32-bit float little endian.
+    pub fn update(&mut self, sample: f32) {
+        self.0.update(sample.to_le_bytes());
+    }
+
+    pub fn hex(&self) -> String {
+        format!("{:x}", self.0.clone().finalize())
+    }
+}
+
+/// Hashes the `data` chunk of a WAV file, decrypting it first if needed.
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let mut reader = crypto::open(path)?;
+    let mut riff = [0u8; 12];
+    reader.read_exact(&mut riff).map_err(|e| e.to_string())?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err(t("error-not-wav"));
+    }
+
+    loop {
+        let mut header = [0u8; 8];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| t("error-not-wav"))?;
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+        if &header[0..4] == b"data" {
+            let mut hasher = Sha256::new();
+            let copied = std::io::copy(&mut (&mut reader).take(size), &mut hasher)
+                .map_err(|e| e.to_string())?;
+            if copied != size {
+                return Err(t("error-not-wav"));
+            }
+            return Ok(format!("{:x}", hasher.finalize()));
+        }
+        // Chunks are padded to an even length. Skipped by reading since
+        // encrypted recordings can't seek
+        std::io::copy(
+            &mut (&mut reader).take(size + size % 2),
+            &mut std::io::sink(),
+        )
+        .map_err(|e| e.to_string())?;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Verification {
+    /// Digest recorded at capture time, `None` for recordings made before checksums
+    expected: Option<String>,
+    actual: String,
+    matches: bool,
+}
+
+/// Re-hashes a recording and compares it to the digest in its sidecar.
+#[tauri::command]
+pub async fn verify_recording(path: String) -> Result<Verification, RecorderError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&path);
+        let actual = hash_file(path)?;
+        let expected = metadata::load(path).sha256;
+        Ok(Verification {
+            matches: expected.as_deref() == Some(actual.as_str()),
+            expected,
+            actual,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,67 @@
+//! Recording straight from launch, for scripted or scheduled captures:
+//! always with `settings.record_on_launch`, or when asked by the `--record`
+//! argument or a `popup-recorder://record` link. Nobody is watching such a
+//! start, so it only goes ahead once every permission a session needs is
+//! granted.
+
+use crate::i18n::t;
+use crate::permissions::{self, SessionSpec};
+use crate::{events, settings, show_and_start, AppState};
+use serde_json::json;
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_notification::NotificationExt;
+
+const RECORD_ARG: &str = "--record";
+const LINK_SCHEME: &str = "popup-recorder";
+const LINK_RECORD: &str = "record";
+
+fn is_record_link(url: &Url) -> bool {
+    url.scheme() == LINK_SCHEME && url.host_str() == Some(LINK_RECORD)
+}
+
+// Windows and Linux hand links to a fresh launch as an argument
+fn is_record_arg(arg: &str) -> bool {
+    arg == RECORD_ARG || Url::parse(arg).is_ok_and(|url| is_record_link(&url))
+}
+
+/// Starts recording unless one is running or a permission is missing.
+/// `source` is how it was asked for, as sent with `launch-recording-blocked`.
+fn record(app: &AppHandle, source: &str) {
+    if app.state::<AppState>().is_recording() {
+        return;
+    }
+    let preflight = permissions::preflight(&SessionSpec::default());
+    if !preflight.ready {
+        events::emit(
+            app,
+            "launch-recording-blocked",
+            json!({ "source": source, "preflight": preflight }),
+        );
+        let _ = app
+            .notification()
+            .builder()
+            .title(t("start-failed-title"))
+            .body(t("error-permission-not-granted"))
+            .show();
+        return;
+    }
+    show_and_start(app);
+}
+
+/// Starts recording if launched for it, and on every record link after.
+pub fn init(app: &AppHandle) {
+    if std::env::args().skip(1).any(|arg| is_record_arg(&arg)) {
+        record(app, "argument");
+    } else if settings::current(app).record_on_launch {
+        record(app, "settings");
+    }
+
+    // macOS delivers links here, including the one that launched the app
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        if event.urls().iter().any(is_record_link) {
+            record(&handle, "link");
+        }
+    });
+}
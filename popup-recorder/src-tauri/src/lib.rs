@@ -1,39 +1,120 @@
+mod accessibility;
+mod backup;
+mod calendar;
+mod clipboard;
+mod convert;
+mod crypto;
+mod cues;
+mod daw;
+mod disk;
+mod drag_out;
+mod dsp;
+mod encoder;
+mod events;
+mod ffmpeg;
+mod filename;
+mod gain;
+mod handoff;
+mod hooks;
+mod i18n;
+mod idle;
+mod indicators;
+mod integrity;
+mod launch;
+mod library;
+mod ltc;
+mod markers;
+mod meetings;
+mod metadata;
+mod monitor;
+mod onboarding;
+mod output;
+mod output_dir;
+mod overlay;
+mod permissions;
+mod playback;
+mod power;
+mod presets;
+mod quality;
+mod recordings;
+mod recovery;
+mod schedule;
+mod secure;
+mod settings;
+mod shortcuts;
+mod silence;
+mod sleep;
+mod spill;
+mod stems;
+mod thermal;
+mod timecode;
+mod transcribe;
+mod tray;
+mod updater;
+mod vad;
+mod visualizer;
+mod waveform;
+mod webhooks;
+mod window_capture;
+
 use anyhow::Result;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use hound::{WavSpec, WavWriter};
+use hound::WavWriter;
+use i18n::{t, tr};
 use parking_lot::Mutex;
+use recorder_core::{
+    capture, recording_lock, AudioLevels, BitDepth, CaptureSource, CpalSource, Mixer,
+    RecorderError, SampleEncoder, ScreenCaptureSource,
+};
 use screencapturekit::prelude::*;
 use serde::Serialize;
-use std::collections::VecDeque;
+use serde_json::json;
 use std::fs::File;
 use std::io::BufWriter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter, Manager, State};
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+use std::time::Instant;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_window_state::StateFlags;
 
-#[derive(Debug, Clone, Serialize)]
-struct AudioLevels {
-    mic_level: f32,
-    system_level: f32,
-    mixed_level: f32,
-}
+pub(crate) use recorder_core::SAMPLE_RATE;
 
 struct SharedRecorder {
-    system_stream: Option<SCStream>,
-    mic_stream: Option<cpal::Stream>,
+    // Sources, mixing thread and levels
+    core: recorder_core::Recorder,
     file_path: Option<PathBuf>,
-    writer: Option<Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>>,
 
-    // Buffers for mixing
-    system_buffer: Arc<Mutex<VecDeque<f32>>>,
-    mic_buffer: Arc<Mutex<VecDeque<f32>>>,
+    // Also held by `core`, kept to follow a window that moves or reopens
+    system_source: Option<Arc<Mutex<ScreenCaptureSource>>>,
+
+    // Frames that never made it into the file, reported in the quality report
+    dropped_frames: Arc<AtomicU64>,
+
+    // Frames in the file so far, the position timecode ticks refer to
+    frames_written: Arc<AtomicU64>,
+
+    // Held for the whole recording so other playground apps keep off the mic
+    recording_lock: Option<recording_lock::RecordingLock>,
+
+    // Secondary outputs (dual-format) and the files they write
+    encoders: Option<Arc<encoder::EncoderPool>>,
+    encoder_outputs: Vec<PathBuf>,
+
+    // Digest of everything written so far, stored in the sidecar on stop
+    hasher: Option<Arc<Mutex<integrity::DataHasher>>>,
+
+    // Linear timecode track written alongside the mix
+    ltc: Option<Arc<Mutex<Option<ltc::LtcTrack>>>>,
+
+    // Unmixed mic and system tracks
+    stems: Option<Arc<Mutex<Option<stems::StemWriters>>>>,
 
-    // Level tracking for visualization
-    system_level: Arc<Mutex<f32>>,
-    mic_level: Arc<Mutex<f32>>,
-    last_levels_update: Arc<Mutex<Instant>>,
+    // Knows where the sound starts and ends, for trimming on stop
+    silence: Option<Arc<Mutex<silence::SilenceDetector>>>,
+
+    // Speech detection, closed out on stop
+    vad: Option<Arc<Mutex<vad::VoiceActivity>>>,
 }
 
 pub struct AppState(pub(crate) Arc<Mutex<SharedRecorder>>);
@@ -41,374 +122,647 @@ pub struct AppState(pub(crate) Arc<Mutex<SharedRecorder>>);
 impl AppState {
     pub fn new() -> Self {
         Self(Arc::new(Mutex::new(SharedRecorder {
-            system_stream: None,
-            mic_stream: None,
+            core: recorder_core::Recorder::default(),
             file_path: None,
-            writer: None,
-            system_buffer: Arc::new(Mutex::new(VecDeque::new())),
-            mic_buffer: Arc::new(Mutex::new(VecDeque::new())),
-            system_level: Arc::new(Mutex::new(0.0)),
-            mic_level: Arc::new(Mutex::new(0.0)),
-            last_levels_update: Arc::new(Mutex::new(Instant::now())),
+            system_source: None,
+            dropped_frames: Arc::new(AtomicU64::new(0)),
+            frames_written: Arc::new(AtomicU64::new(0)),
+            recording_lock: None,
+            encoders: None,
+            encoder_outputs: Vec::new(),
+            hasher: None,
+            ltc: None,
+            stems: None,
+            silence: None,
+            vad: None,
         })))
     }
-}
-
-struct Mixer {
-    system_buffer: Arc<Mutex<VecDeque<f32>>>,
-    mic_buffer: Arc<Mutex<VecDeque<f32>>>,
-    writer: Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>,
-    app_handle: AppHandle,
-    system_level: Arc<Mutex<f32>>,
-    mic_level: Arc<Mutex<f32>>,
-    last_levels_update: Arc<Mutex<Instant>>,
-}
 
-impl Mixer {
-    fn mix_available(&self) {
-        let mut sys = self.system_buffer.lock();
-        let mut mic = self.mic_buffer.lock();
-        let mut writer_lock = self.writer.lock();
+    pub fn is_recording(&self) -> bool {
+        self.0.lock().core.is_recording()
+    }
 
-        if let Some(writer) = writer_lock.as_mut() {
-            let mut mixed_sum = 0.0f32;
-            let mut mixed_count = 0u32;
+    /// Louder of the latest mic and system RMS levels.
+    pub fn input_level(&self) -> f32 {
+        let (system, mic) = self.0.lock().core.mixer.levels().sources();
+        mic.max(system)
+    }
 
-            while sys.len() >= 2 && mic.len() >= 2 {
-                let s1 = sys.pop_front().unwrap();
-                let s2 = sys.pop_front().unwrap();
-                let m1 = mic.pop_front().unwrap();
-                let m2 = mic.pop_front().unwrap();
+    /// Frames written to the active recording, `None` when not recording.
+    pub fn frames_written(&self) -> Option<u64> {
+        let recorder = self.0.lock();
+        let recording = recorder.core.is_recording();
+        recording.then(|| recorder.frames_written.load(Ordering::Relaxed))
+    }
 
-                let mixed_1 = (s1 + m1) / 2.0;
-                let mixed_2 = (s2 + m2) / 2.0;
+    /// File the active recording is written to, `None` when not recording.
+    pub fn recording_path(&self) -> Option<PathBuf> {
+        let recorder = self.0.lock();
+        let recording = recorder.core.is_recording();
+        recorder.file_path.clone().filter(|_| recording)
+    }
+}
 
-                mixed_sum += (mixed_1 * mixed_1 + mixed_2 * mixed_2) / 2.0;
-                mixed_count += 1;
+/// What the core mixer hands each delivery to, on its mixing thread: the
+/// mic processing, the file and everything written alongside it.
+struct Capture {
+    writer: WavWriter<BufWriter<File>>,
+    encoder: SampleEncoder,
+    app_handle: AppHandle,
+    last_levels_update: Instant,
+    reduced_power: Arc<AtomicBool>,
+    thermal_degraded: Arc<AtomicBool>,
+    // Set when the capture preset asks for voice processing
+    voice: Option<dsp::VoiceChain>,
+    encoders: Option<Arc<encoder::EncoderPool>>,
+    // Only for float files, integer ones are hashed once finished
+    hasher: Option<Arc<Mutex<integrity::DataHasher>>>,
+    dropped_frames: Arc<AtomicU64>,
+    frames_written: Arc<AtomicU64>,
+    ltc: Arc<Mutex<Option<ltc::LtcTrack>>>,
+    stems: Arc<Mutex<Option<stems::StemWriters>>>,
+    gains: Arc<gain::SourceGains>,
+    // Noise gate and suppression on the mic, before it's mixed
+    mic_dsp: Arc<Mutex<dsp::Pipeline>>,
+    silence: Arc<Mutex<silence::SilenceDetector>>,
+    vad: Arc<Mutex<vad::VoiceActivity>>,
+    // Set while `start_transcription` is running
+    transcriber: Arc<Mutex<Option<transcribe::Transcriber>>>,
+    last_flush: Instant,
+}
 
-                let _ = writer.write_sample(mixed_1);
-                let _ = writer.write_sample(mixed_2);
-            }
+impl recorder_core::Output for Capture {
+    fn mix(&mut self, mixer: &Mixer) {
+        let mut hasher = self.hasher.as_ref().map(|hasher| hasher.lock());
+        let mut stems = self.stems.lock();
+        let mut mic_dsp = self.mic_dsp.lock();
+        let mut vad = self.vad.lock();
+        let mut transcriber = self.transcriber.lock();
+
+        for overrun in mixer.overruns() {
+            self.dropped_frames
+                .fetch_add(overrun.dropped_frames, Ordering::Relaxed);
+        }
 
-            // Emit audio levels every 50ms
-            if mixed_count > 0 {
-                let mut last_update = self.last_levels_update.lock();
-                if last_update.elapsed() >= Duration::from_millis(50) {
-                    let mixed_rms = (mixed_sum / mixed_count as f32).sqrt();
-                    let mic_rms = *self.mic_level.lock();
-                    let sys_rms = *self.system_level.lock();
-
-                    let levels = AudioLevels {
-                        mic_level: mic_rms,
-                        system_level: sys_rms,
-                        mixed_level: mixed_rms,
-                    };
-
-                    let _ = self.app_handle.emit("audio-levels", &levels);
-                    *last_update = Instant::now();
+        let mut mixed_sum = 0.0f32;
+        let mut mixed_count = 0u32;
+        let mut frames = Vec::new();
+        let mut block = Vec::new();
+        let (mic_gain, system_gain) = (self.gains.mic(), self.gains.system());
+
+        mixer.drain(|[s1, s2], [m1, m2]| {
+            let [p1, p2] = if mic_dsp.is_empty() {
+                [m1, m2]
+            } else {
+                mic_dsp.process([m1, m2])
+            };
+            let utterance_ended = vad.push(&self.app_handle, [p1, p2]);
+            if let Some(transcriber) = transcriber.as_mut() {
+                transcriber.push([p1, p2]);
+                if utterance_ended {
+                    transcriber.end_utterance();
                 }
             }
-        }
-    }
-}
 
-struct SystemAudioOutputHandler {
-    buffer: Arc<Mutex<VecDeque<f32>>>,
-    mixer_trigger: Arc<Mixer>,
-    system_level: Arc<Mutex<f32>>,
-}
+            // Halved for headroom, so unity gains give the plain average
+            let mut mixed_1 = (s1 * system_gain + p1 * mic_gain) / 2.0;
+            let mut mixed_2 = (s2 * system_gain + p2 * mic_gain) / 2.0;
+            if let Some(voice) = self.voice.as_mut() {
+                (mixed_1, mixed_2) = voice.process(mixed_1, mixed_2);
+            }
 
-impl SCStreamOutputTrait for SystemAudioOutputHandler {
-    fn did_output_sample_buffer(&self, sample: CMSampleBuffer, of_type: SCStreamOutputType) {
-        if let SCStreamOutputType::Audio = of_type {
-            if let Some(buffer_list) = sample.audio_buffer_list() {
-                let mut samples = Vec::new();
-                let num_buffers = buffer_list.num_buffers();
-
-                if num_buffers == 1 {
-                    let buffer = buffer_list.get(0).unwrap();
-                    let data = buffer.data();
-                    let f32_samples: &[f32] = unsafe {
-                        std::slice::from_raw_parts(data.as_ptr() as *const f32, data.len() / 4)
-                    };
-                    samples.extend_from_slice(f32_samples);
-                } else {
-                    let mut channel_data = Vec::new();
-                    for i in 0..num_buffers {
-                        let buffer = buffer_list.get(i).unwrap();
-                        let data = buffer.data();
-                        let f32_samples: &[f32] = unsafe {
-                            std::slice::from_raw_parts(data.as_ptr() as *const f32, data.len() / 4)
-                        };
-                        channel_data.push(f32_samples);
-                    }
-                    if !channel_data.is_empty() {
-                        let len = channel_data[0].len();
-                        for i in 0..len {
-                            for channel in &channel_data {
-                                if i < channel.len() {
-                                    samples.push(channel[i]);
-                                }
-                            }
-                        }
-                    }
+            mixed_sum += (mixed_1 * mixed_1 + mixed_2 * mixed_2) / 2.0;
+            mixed_count += 1;
+            frames.push(([s1, s2], [m1, m2], [mixed_1, mixed_2]));
+        });
+
+        // Long silences may be left out of the file entirely
+        let write = mixed_count == 0
+            || self.silence.lock().observe(
+                &self.app_handle,
+                mixed_count as u64,
+                (mixed_sum / mixed_count as f32).sqrt(),
+            );
+        if write {
+            for (system, mic, [mixed_1, mixed_2]) in frames {
+                let _ = self.encoder.write(&mut self.writer, mixed_1);
+                let _ = self.encoder.write(&mut self.writer, mixed_2);
+                if let Some(stems) = stems.as_mut() {
+                    let _ = stems.write(system, mic);
                 }
+                if let Some(hasher) = hasher.as_mut() {
+                    hasher.update(mixed_1);
+                    hasher.update(mixed_2);
+                }
+                if self.encoders.is_some() {
+                    block.extend_from_slice(&[mixed_1, mixed_2]);
+                }
+            }
 
-                if !samples.is_empty() {
-                    // Track system audio RMS level
-                    let sum: f32 = samples.iter().map(|s| s * s).sum();
-                    let rms = (sum / samples.len() as f32).sqrt();
-                    *self.system_level.lock() = rms;
+            self.frames_written
+                .fetch_add(mixed_count as u64, Ordering::Relaxed);
+            if let Some(track) = self.ltc.lock().as_mut() {
+                let _ = track.advance(mixed_count);
+            }
 
-                    self.buffer.lock().extend(samples);
-                    self.mixer_trigger.mix_available();
+            // Keep the headers current so a crash leaves playable files
+            if self.last_flush.elapsed() >= recovery::FLUSH_INTERVAL {
+                let _ = self.writer.flush();
+                if let Some(stems) = stems.as_mut() {
+                    let _ = stems.flush();
+                }
+                if let Some(track) = self.ltc.lock().as_mut() {
+                    let _ = track.flush();
                 }
+                self.last_flush = Instant::now();
+            }
+        }
+        if let Some(encoders) = self.encoders.as_ref().filter(|_| !block.is_empty()) {
+            encoders.submit(&block);
+        }
+
+        // Emit audio levels every 50ms, less often on battery and not at
+        // all under thermal pressure so the mixing keeps up
+        if mixed_count > 0 && !self.thermal_degraded.load(Ordering::Relaxed) {
+            if self.last_levels_update.elapsed() >= power::levels_interval(&self.reduced_power) {
+                let (system_level, mic_level) = mixer.levels().sources();
+                let levels = AudioLevels {
+                    mic_level,
+                    system_level,
+                    mixed_level: (mixed_sum / mixed_count as f32).sqrt(),
+                };
+
+                events::emit(&self.app_handle, "audio-levels", &levels);
+                self.last_levels_update = Instant::now();
             }
         }
     }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        self.writer.finalize().map_err(|e| e.to_string())
+    }
 }
 
+/// `bit_depth` overrides the one in the settings.
 async fn start_recording_inner(
     app: AppHandle,
     recorder_arc: Arc<Mutex<SharedRecorder>>,
-) -> Result<String, String> {
-    let (system_buffer, mic_buffer, system_level, mic_level, last_levels_update) = {
+    bit_depth: Option<BitDepth>,
+) -> Result<String, RecorderError> {
+    let bit_depth = bit_depth.unwrap_or(settings::current(&app).bit_depth);
+    let result = start_capture(app.clone(), recorder_arc, bit_depth).await;
+    match &result {
+        Ok(path) => webhooks::dispatch(&app, "recording-started", json!({ "path": path })),
+        Err(e) => webhooks::dispatch(
+            &app,
+            "recording-error",
+            json!({ "stage": "start", "error": e.to_string() }),
+        ),
+    }
+    result
+}
+
+async fn start_capture(
+    app: AppHandle,
+    recorder_arc: Arc<Mutex<SharedRecorder>>,
+    bit_depth: BitDepth,
+) -> Result<String, RecorderError> {
+    let (dropped_frames, frames_written) = {
         let recorder = recorder_arc.lock();
-        if recorder.system_stream.is_some() || recorder.mic_stream.is_some() {
-            return Err("Already recording".to_string());
+        if recorder.core.is_recording() {
+            return Err(RecorderError::AlreadyRecording);
         }
+        recorder.dropped_frames.store(0, Ordering::Relaxed);
+        recorder.frames_written.store(0, Ordering::Relaxed);
         (
-            recorder.system_buffer.clone(),
-            recorder.mic_buffer.clone(),
-            recorder.system_level.clone(),
-            recorder.mic_level.clone(),
-            recorder.last_levels_update.clone(),
+            recorder.dropped_frames.clone(),
+            recorder.frames_written.clone(),
         )
     };
 
-    // --- SETUP WAV WRITER ---
-    let audio_dir = app
-        .path()
-        .app_data_dir()
-        .unwrap_or_else(|_| PathBuf::from("."));
-    std::fs::create_dir_all(&audio_dir).map_err(|e| e.to_string())?;
-    let file_path = audio_dir.join("combined_audio.wav");
-
-    let spec = WavSpec {
-        channels: 2,
-        sample_rate: 48000,
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
-    };
+    schedule::check_start(&app)?;
+
+    let audio_dir = spill::recording_dir(&app)?;
+    disk::check_start(&app, &audio_dir)?;
+    let template = settings::current(&app).filename_template;
+    let file_path = filename::next_path(&app, &audio_dir, &template);
+
+    let lock = recording_lock::acquire("popup-recorder", &file_path)
+        .inspect_err(|e| {
+            let _ = app
+                .notification()
+                .builder()
+                .title(t("start-failed-title"))
+                .body(e)
+                .show();
+        })
+        .map_err(RecorderError::Busy)?;
 
-    let writer = WavWriter::create(&file_path, spec).map_err(|e| e.to_string())?;
-    let writer_arc = Arc::new(Mutex::new(Some(writer)));
+    if settings::current(&app).sound_cues {
+        // Play the cue before anything is capturing so it stays out of the file
+        let cue_app = app.clone();
+        let _ = tauri::async_runtime::spawn_blocking(move || cues::play(&cue_app, cues::Cue::Start))
+            .await;
+    }
 
-    let mixer = Arc::new(Mixer {
-        system_buffer: system_buffer.clone(),
-        mic_buffer: mic_buffer.clone(),
-        writer: writer_arc.clone(),
-        app_handle: app.clone(),
-        system_level: system_level.clone(),
-        mic_level: mic_level.clone(),
-        last_levels_update: last_levels_update.clone(),
+    // --- SETUP WAV WRITER ---
+    let spec = bit_depth.spec(SAMPLE_RATE, 2);
+
+    let (buffer_size, reduced_power) = {
+        let power = app.state::<power::PowerState>();
+        (power.write_buffer_size(), power.reduced_flag())
+    };
+    let file = File::create(&file_path)?;
+    let buffered = BufWriter::with_capacity(buffer_size, file);
+    let writer =
+        WavWriter::new(buffered, spec).map_err(|e| RecorderError::IoError(e.to_string()))?;
+    recovery::begin(&file_path);
+
+    let preset = presets::active(&app);
+    let hasher = (bit_depth == BitDepth::Float32)
+        .then(|| Arc::new(Mutex::new(integrity::DataHasher::default())));
+    let encoder_output = audio_dir
+        .join("converted")
+        .join(file_path.file_name().unwrap_or_default());
+    let encoders = settings::current(&app).dual_format.and_then(|format| {
+        let pcm = encoder_output
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(|e| e.to_string())
+            .and_then(|_| encoder::PcmEncoder::create(&encoder_output, format));
+        match pcm {
+            Ok(pcm) => {
+                let pcm: Box<dyn encoder::Encoder> = Box::new(pcm);
+                Some(Arc::new(encoder::EncoderPool::start(vec![pcm])))
+            }
+            Err(e) => {
+                eprintln!("Dual-format output disabled for this recording: {}", e);
+                None
+            }
+        }
+    });
+    let ltc = settings::current(&app).ltc_rate.and_then(|rate| {
+        ltc::LtcTrack::create(&recordings::companion_path(&file_path, "timecode"), rate)
+            .inspect_err(|e| eprintln!("Timecode track disabled for this recording: {}", e))
+            .ok()
     });
+    let ltc = Arc::new(Mutex::new(ltc));
+    let stems = if settings::current(&app).record_stems {
+        stems::StemWriters::create(&file_path)
+            .inspect_err(|e| eprintln!("Stems disabled for this recording: {}", e))
+            .ok()
+    } else {
+        None
+    };
+    let stems = Arc::new(Mutex::new(stems));
+    let silence = Arc::new(Mutex::new(silence::SilenceDetector::new(&app)));
+    let vad = Arc::new(Mutex::new(vad::VoiceActivity::new()));
+    let capture = Capture {
+        writer,
+        encoder: SampleEncoder::new(bit_depth),
+        app_handle: app.clone(),
+        last_levels_update: Instant::now(),
+        reduced_power,
+        thermal_degraded: app.state::<thermal::ThermalState>().degraded_flag(),
+        voice: preset
+            .as_ref()
+            .filter(|preset| preset.voice_processing)
+            .map(|_| dsp::VoiceChain::new()),
+        encoders: encoders.clone(),
+        hasher: hasher.clone(),
+        dropped_frames,
+        frames_written,
+        ltc: ltc.clone(),
+        stems: stems.clone(),
+        gains: app.state::<gain::GainState>().shared(),
+        mic_dsp: app.state::<dsp::DspState>().shared(),
+        silence: silence.clone(),
+        vad: vad.clone(),
+        transcriber: app.state::<transcribe::TranscriptionState>().shared(),
+        last_flush: Instant::now(),
+    };
 
     // --- SETUP SYSTEM AUDIO (ScreenCaptureKit) ---
-    let content = SCShareableContent::get().map_err(|e| e.to_string())?;
+    let content =
+        SCShareableContent::get().map_err(|e| RecorderError::CaptureFailed(e.to_string()))?;
     let display = content
         .displays()
         .first()
         .cloned()
-        .ok_or_else(|| "No display found".to_string())?;
-    let filter = SCContentFilter::create()
-        .with_display(&display)
-        .with_excluding_windows(&[])
-        .build();
-    let config = SCStreamConfiguration::new()
-        .with_captures_audio(true)
-        .with_sample_rate(48000)
-        .with_channel_count(2);
-
-    let system_handler = SystemAudioOutputHandler {
-        buffer: system_buffer.clone(),
-        mixer_trigger: mixer.clone(),
-        system_level: system_level.clone(),
-    };
-
-    let mut system_stream = SCStream::new(&filter, &config);
-    system_stream.add_output_handler(system_handler, SCStreamOutputType::Audio);
-    system_stream.start_capture().map_err(|e| e.to_string())?;
-
-    // --- SETUP MIC AUDIO (cpal) ---
-    let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .ok_or("No input device available")?;
-
-    let supported_configs = device
-        .supported_input_configs()
-        .map_err(|e| e.to_string())?;
-
-    let mic_config_support = supported_configs
-        .filter(|c| c.sample_format() == cpal::SampleFormat::F32)
-        .find(|c| c.min_sample_rate() <= 48000 && c.max_sample_rate() >= 48000)
-        .or_else(|| device.supported_input_configs().ok()?.next())
-        .ok_or("Could not find any suitable input config")?;
-
-    let mic_channels = mic_config_support.channels();
-    let mic_source_sr = if mic_config_support.min_sample_rate() <= 48000
-        && mic_config_support.max_sample_rate() >= 48000
-    {
-        48000
-    } else {
-        mic_config_support.max_sample_rate()
+        .ok_or_else(|| RecorderError::DeviceNotFound(t("error-no-display")))?;
+    let app_bundle_id = preset
+        .as_ref()
+        .and_then(|preset| preset.app_bundle_id.clone());
+    let window_title = preset
+        .as_ref()
+        .and_then(|preset| preset.window_title.clone());
+    window_capture::track(&app, None);
+    let filter = match (window_title, app_bundle_id) {
+        // Window-scoped presets follow the first window whose title matches
+        (Some(pattern), _) => {
+            let window = window_capture::resolve(&content, &pattern).ok_or_else(|| {
+                RecorderError::NotFound(tr(
+                    "error-window-not-found",
+                    &[("pattern", pattern.clone())],
+                ))
+            })?;
+            let filter = window_capture::filter(&window);
+            window_capture::track(&app, Some((pattern, &window)));
+            filter
+        }
+        // App-scoped presets only hear that app
+        (None, Some(bundle_id)) => {
+            let target = content
+                .applications()
+                .into_iter()
+                .find(|running| running.bundle_identifier() == bundle_id)
+                .ok_or_else(|| {
+                    let name = preset.map(|preset| preset.name).unwrap_or(bundle_id);
+                    RecorderError::NotFound(tr("error-app-not-running", &[("app", name)]))
+                })?;
+            SCContentFilter::create()
+                .with_display(&display)
+                .with_including_applications(&[&target], &[])
+                .build()
+        }
+        (None, None) => SCContentFilter::create()
+            .with_display(&display)
+            .with_excluding_windows(&[])
+            .build(),
     };
-
-    let mic_config = mic_config_support.with_sample_rate(mic_source_sr);
-    eprintln!(
-        "Selected Mic: {} channels, {} Hz",
-        mic_channels, mic_source_sr
+    let config = ScreenCaptureSource::configure(
+        SCStreamConfiguration::new()
+            .with_excludes_current_process_audio(settings::current(&app).exclude_own_audio),
+        SAMPLE_RATE,
     );
+    let system_source = Arc::new(Mutex::new(ScreenCaptureSource::new(
+        &filter,
+        &config,
+        SAMPLE_RATE,
+    )));
 
-    let mic_buffer_clone = mic_buffer.clone();
-    let mic_level_clone = mic_level.clone();
-    let mixer_clone = mixer.clone();
-
-    // Resampling state for nearest-neighbor interpolation
-    let mut total_in = 0u64;
-    let mut total_out = 0u64;
-    let target_sr_val = 48000.0f64;
-    let source_sr_val = mic_source_sr as f64;
-
-    let mic_stream = device
-        .build_input_stream(
-            &mic_config.into(),
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                let mut mic_buf = mic_buffer_clone.lock();
-                let mut level_sum = 0.0f32;
-                let mut level_count = 0u32;
-
-                for frame in data.chunks(mic_channels as usize) {
-                    total_in += 1;
-                    while (total_out as f64 * source_sr_val)
-                        < (total_in as f64 * target_sr_val)
-                    {
-                        if mic_channels == 1 {
-                            let s = frame[0];
-                            level_sum += s * s;
-                            level_count += 1;
-                            mic_buf.push_back(s);
-                            mic_buf.push_back(s);
-                        } else {
-                            let l = frame[0];
-                            let r = frame[1];
-                            level_sum += (l * l + r * r) / 2.0;
-                            level_count += 1;
-                            mic_buf.push_back(l);
-                            mic_buf.push_back(r);
-                        }
-                        total_out += 1;
-                    }
-                }
-
-                if level_count > 0 {
-                    let rms = (level_sum / level_count as f32).sqrt();
-                    *mic_level_clone.lock() = rms;
-                }
+    // --- SETUP MIC AUDIO (cpal) ---
+    let mic = CpalSource::default_input(SAMPLE_RATE).map_err(|e| match e.as_str() {
+        capture::NO_INPUT_DEVICE => RecorderError::DeviceNotFound(t("error-no-input-device")),
+        _ => RecorderError::CaptureFailed(t("error-no-input-config")),
+    })?;
+    let mic = app.state::<monitor::Monitoring>().tapped(mic);
 
-                drop(mic_buf);
-                mixer_clone.mix_available();
-            },
-            move |err| {
-                eprintln!("Mic stream error: {}", err);
-            },
-            None,
+    let system: Box<dyn CaptureSource> = Box::new(system_source.clone());
+    let mut recorder = recorder_arc.lock();
+    recorder
+        .core
+        .start_output(
+            &file_path,
+            SAMPLE_RATE,
+            Some(system),
+            Some(Box::new(mic)),
+            Box::new(capture),
         )
-        .map_err(|e| e.to_string())?;
-
-    mic_stream.play().map_err(|e| e.to_string())?;
-
-    {
-        let mut recorder = recorder_arc.lock();
-        recorder.system_stream = Some(system_stream);
-        recorder.mic_stream = Some(mic_stream);
-        recorder.file_path = Some(file_path.clone());
-        recorder.writer = Some(writer_arc);
-    }
+        .map_err(RecorderError::CaptureFailed)?;
+    recorder.system_source = Some(system_source);
+    recorder.file_path = Some(file_path.clone());
+    recorder.recording_lock = Some(lock);
+    recorder.encoder_outputs = encoders.iter().map(|_| encoder_output.clone()).collect();
+    recorder.encoders = encoders;
+    recorder.hasher = hasher;
+    recorder.ltc = Some(ltc);
+    recorder.stems = Some(stems);
+    recorder.silence = Some(silence);
+    recorder.vad = Some(vad);
+    drop(recorder);
+    sleep::set_prevented(&app, true);
 
     Ok(file_path.to_string_lossy().to_string())
 }
 
 async fn stop_recording_inner(
+    app: AppHandle,
     recorder_arc: Arc<Mutex<SharedRecorder>>,
-) -> Result<String, String> {
+) -> Result<String, RecorderError> {
     let mut recorder = recorder_arc.lock();
+    let mut finalized = false;
+    let mut stop_error = None;
+    let mut frames_written = 0u64;
+
+    if recorder.core.is_recording() {
+        recorder.system_source = None;
+        // Stops the sources, mixes what they delivered and finalizes the file
+        match recorder.core.stop() {
+            Ok(_) => {
+                frames_written = recorder.frames_written.load(Ordering::Relaxed);
+                finalized = true;
+            }
+            Err(e) => {
+                webhooks::dispatch(
+                    &app,
+                    "recording-error",
+                    json!({ "stage": "finalize", "error": e }),
+                );
+                stop_error = Some(e);
+            }
+        }
+    }
 
-    if let Some(stream) = recorder.system_stream.take() {
-        let _ = stream.stop_capture();
+    if let Some(encoders) = recorder.encoders.take() {
+        for e in encoders.finish() {
+            eprintln!("Encoder failed: {}", e);
+        }
     }
 
-    if let Some(stream) = recorder.mic_stream.take() {
-        let _ = stream.pause();
+    if let Some(track) = recorder.ltc.take().and_then(|ltc| ltc.lock().take()) {
+        if let Err(e) = track.finish() {
+            eprintln!("Failed to finalize the timecode track: {}", e);
+        }
+    }
+    if let Some(stems) = recorder.stems.take().and_then(|stems| stems.lock().take()) {
+        if let Err(e) = stems.finish() {
+            eprintln!("Failed to finalize the stems: {}", e);
+        }
     }
 
-    if let Some(writer_arc) = recorder.writer.take() {
-        let mut writer_lock = writer_arc.lock();
-        if let Some(writer) = writer_lock.take() {
-            writer.finalize().map_err(|e| e.to_string())?;
+    if let Some(vad) = recorder.vad.take() {
+        vad.lock().finish(&app);
+    }
+    if let Some(path) = recorder.file_path.as_ref().filter(|_| finalized) {
+        recovery::end(path);
+    }
+
+    let trim_range = recorder
+        .silence
+        .take()
+        .and_then(|silence| silence.lock().trim_range())
+        .filter(|_| finalized);
+    if let (Some(range), Some(path)) = (trim_range, recorder.file_path.as_ref()) {
+        let companions = recordings::companions(path)
+            .into_iter()
+            .map(|(_, path)| path);
+        let trimmed = std::iter::once(path.clone())
+            .chain(companions)
+            .chain(recorder.encoder_outputs.iter().cloned());
+        for file in trimmed {
+            if let Err(e) = silence::trim_wav(&file, range) {
+                eprintln!("Failed to trim silence from {}: {}", file.display(), e);
+            }
+        }
+        frames_written = range.1 - range.0;
+    }
+    let converted = std::mem::take(&mut recorder.encoder_outputs);
+
+    let hasher = recorder.hasher.take();
+    if let (true, Some(path)) = (finalized, recorder.file_path.as_ref()) {
+        // Trimming changed the data, so the running digest no longer applies,
+        // and integer files never had one
+        let digest = match (trim_range, hasher) {
+            (None, Some(hasher)) => Ok(hasher.lock().hex()),
+            _ => integrity::hash_file(path),
+        };
+        let trimmed_ms = trim_range.map_or(0, |(start, _)| start * 1000 / SAMPLE_RATE as u64);
+        let stored = digest.and_then(|digest| {
+            metadata::update(path, |metadata| {
+                metadata.sha256 = Some(digest);
+                for marker in &mut metadata.markers {
+                    marker.offset_ms = marker.offset_ms.saturating_sub(trimmed_ms);
+                }
+            })
+        });
+        if let Err(e) = stored {
+            eprintln!("Failed to store the recording checksum: {}", e);
+        }
+    }
+    if let (true, Some(path)) = (finalized, recorder.file_path.as_ref()) {
+        if settings::current(&app).marker_cue_chunks {
+            if let Err(e) = markers::write_cue_chunks(path, &metadata::load(path).markers) {
+                eprintln!("Failed to write the markers into the recording: {}", e);
+            }
         }
     }
 
-    // Clear buffers and reset levels
-    recorder.system_buffer.lock().clear();
-    recorder.mic_buffer.lock().clear();
-    *recorder.system_level.lock() = 0.0;
-    *recorder.mic_level.lock() = 0.0;
+    if finalized && settings::current(&app).sound_cues {
+        // The writer is closed at this point, so the cue can't end up in the file
+        let cue_app = app.clone();
+        std::thread::spawn(move || {
+            let _ = cues::play(&cue_app, cues::Cue::Stop);
+        });
+    }
 
-    if let Some(path) = &recorder.file_path {
+    let dropped_frames = recorder.dropped_frames.load(Ordering::Relaxed);
+    recorder.recording_lock.take();
+    sleep::set_prevented(&app, false);
+    // Only now, a failed finalize mustn't keep the lock and every later
+    // start out
+    if let Some(e) = stop_error {
+        transcribe::finish(&app, None);
+        return Err(RecorderError::IoError(e));
+    }
+
+    if finalized {
+        updater::resume_deferred_install(&app);
+    }
+
+    let file_path = recorder.file_path.clone();
+    if let Some(path) = file_path {
+        if !finalized {
+            transcribe::finish(&app, None);
+            return Ok(path.to_string_lossy().to_string());
+        }
+        // Moving to a share can take a while, don't hold the recorder meanwhile
+        drop(recorder);
+        let path = tauri::async_runtime::spawn_blocking({
+            let app = app.clone();
+            move || spill::deliver(&app, crypto::seal_recording(&app, path, converted))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        transcribe::finish(&app, Some(path.clone()));
+        webhooks::dispatch(
+            &app,
+            "recording-stopped",
+            json!({
+                "path": path,
+                "duration_ms": frames_written * 1000 / SAMPLE_RATE as u64,
+            }),
+        );
+        // A queued move settles the recording once it's on the share
+        let queued = spill::is_queued(&app, &path);
+        let report_path = path.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            quality::report(&app, &report_path, dropped_frames);
+            hooks::run(&app, &report_path);
+            if !queued {
+                settle(&app, &report_path);
+            }
+        });
         return Ok(path.to_string_lossy().to_string());
     }
 
-    Err("Not recording".to_string())
+    Err(RecorderError::NotRecording)
+}
+
+/// Last steps for a recording that's in its final folder. The handoff goes
+/// first, so a moved recording takes a complete sidecar along and hooks
+/// still found it in place, and the backup is queued only after it, from
+/// wherever the handoff left the file. Blocking.
+pub(crate) fn settle(app: &AppHandle, path: &Path) {
+    let path = handoff::run(app, path);
+    backup::enqueue(app, path);
 }
 
+/// Starts recording at `bit_depth`, or the one in the settings when unset.
 #[tauri::command]
-async fn start_recording(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
-    start_recording_inner(app, state.0.clone()).await
+async fn start_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    bit_depth: Option<BitDepth>,
+) -> Result<String, RecorderError> {
+    let path = start_recording_inner(app.clone(), state.0.clone(), bit_depth).await?;
+    indicators::set_recording(&app, true);
+    Ok(path)
 }
 
 #[tauri::command]
-async fn stop_recording(state: State<'_, AppState>) -> Result<String, String> {
-    stop_recording_inner(state.0.clone()).await
+async fn stop_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, RecorderError> {
+    let path = stop_recording_inner(app.clone(), state.0.clone()).await?;
+    indicators::set_recording(&app, false);
+    Ok(path)
 }
 
-async fn cancel_recording_inner(recorder_arc: Arc<Mutex<SharedRecorder>>) -> Result<(), String> {
-    let file_path = {
+async fn cancel_recording_inner(
+    recorder_arc: Arc<Mutex<SharedRecorder>>,
+) -> Result<(), RecorderError> {
+    let (file_path, encoder_outputs) = {
         let mut recorder = recorder_arc.lock();
 
-        if let Some(stream) = recorder.system_stream.take() {
-            let _ = stream.stop_capture();
+        recorder.system_source = None;
+        let _ = recorder.core.stop();
+        recorder.recording_lock.take();
+        if let Some(encoders) = recorder.encoders.take() {
+            encoders.finish();
         }
-        if let Some(stream) = recorder.mic_stream.take() {
-            let _ = stream.pause();
+        recorder.hasher.take();
+        recorder.silence.take();
+        recorder.vad.take();
+        if let Some(track) = recorder.ltc.take().and_then(|ltc| ltc.lock().take()) {
+            let _ = track.finish();
         }
-        if let Some(writer_arc) = recorder.writer.take() {
-            let mut writer_lock = writer_arc.lock();
-            if let Some(writer) = writer_lock.take() {
-                let _ = writer.finalize();
-            }
+        if let Some(stems) = recorder.stems.take().and_then(|stems| stems.lock().take()) {
+            let _ = stems.finish();
         }
 
-        recorder.system_buffer.lock().clear();
-        recorder.mic_buffer.lock().clear();
-        *recorder.system_level.lock() = 0.0;
-        *recorder.mic_level.lock() = 0.0;
-
-        recorder.file_path.take()
+        (
+            recorder.file_path.take(),
+            std::mem::take(&mut recorder.encoder_outputs),
+        )
     };
 
     if let Some(path) = file_path {
+        let _ = recordings::delete(&path);
+        recovery::end(&path);
+    }
+    for path in encoder_outputs {
         let _ = std::fs::remove_file(path);
     }
 
@@ -416,62 +770,237 @@ async fn cancel_recording_inner(recorder_arc: Arc<Mutex<SharedRecorder>>) -> Res
 }
 
 #[tauri::command]
-async fn cancel_recording(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+async fn cancel_recording(app: AppHandle, state: State<'_, AppState>) -> Result<(), RecorderError> {
     cancel_recording_inner(state.0.clone()).await?;
-    let _ = app.emit("recording-state", serde_json::json!({"recording": false}));
+    transcribe::finish(&app, None);
+    sleep::set_prevented(&app, false);
+    indicators::set_recording(&app, false);
     if let Some(window) = app.get_webview_window("main") {
         let _ = window.hide();
     }
     Ok(())
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct RecorderStatus {
+    recording: bool,
+    sleep_prevented: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RecordingStats {
+    recording: bool,
+    dropped_frames: u64,
+    encoder_queues: Vec<encoder::QueueStats>,
+}
+
+/// Live counters for the current recording, including encoder queue depths.
+#[tauri::command]
+fn get_recording_stats(state: State<'_, AppState>) -> RecordingStats {
+    let recording = state.is_recording();
+    let recorder = state.0.lock();
+    RecordingStats {
+        recording,
+        dropped_frames: recorder.dropped_frames.load(Ordering::Relaxed),
+        encoder_queues: recorder
+            .encoders
+            .as_ref()
+            .map(|encoders| encoders.stats())
+            .unwrap_or_default(),
+    }
+}
+
+#[tauri::command]
+fn get_state(state: State<'_, AppState>, sleep: State<'_, sleep::SleepGuard>) -> RecorderStatus {
+    RecorderStatus {
+        recording: state.is_recording(),
+        sleep_prevented: sleep.is_active(),
+    }
+}
+
+/// Stops the recording in the background, then hides the popup.
+pub(crate) fn stop_and_hide(app_handle: &AppHandle) {
+    let recorder_arc = app_handle.state::<AppState>().0.clone();
+    let app_clone = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let _ = stop_recording_inner(app_clone.clone(), recorder_arc).await;
+        indicators::set_recording(&app_clone, false);
+        let _ = app_clone.get_webview_window("main").unwrap().hide();
+    });
+}
+
+/// Shows the popup and starts recording, or stops and hides it.
+pub(crate) fn toggle_from_shortcut(app_handle: &AppHandle) {
+    let window = app_handle.get_webview_window("main").unwrap();
+    let is_visible = window.is_visible().unwrap_or(false);
+
+    if is_visible {
+        stop_and_hide(app_handle);
+    } else {
+        show_and_start(app_handle);
+    }
+}
+
+/// Shows the popup, then starts recording in the background, emitting
+/// `recording-start-failed` if that doesn't work out.
+pub(crate) fn show_and_start(app_handle: &AppHandle) {
+    let window = app_handle.get_webview_window("main").unwrap();
+    let recorder_arc = app_handle.state::<AppState>().0.clone();
+    let app_clone = app_handle.clone();
+    let _ = window.show();
+    let _ = window.set_focus();
+    tauri::async_runtime::spawn(async move {
+        match start_recording_inner(app_clone.clone(), recorder_arc, None).await {
+            Ok(_) => indicators::set_recording(&app_clone, true),
+            // Nobody awaits this, so the window hears about it instead
+            Err(e) => events::emit(
+                &app_clone,
+                "recording-start-failed",
+                json!({ "error": e.to_string() }),
+            ),
+        }
+    });
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .manage(AppState::new())
+        .manage(events::EventChannels::default())
+        .manage(updater::PendingUpdate::default())
+        .manage(playback::Playback::default())
+        .manage(overlay::OverlayState::new())
+        .manage(idle::IdleState::default())
+        .manage(power::PowerState::default())
+        .manage(thermal::ThermalState::default())
+        .manage(sleep::SleepGuard::default())
+        .manage(accessibility::RecordingClock::default())
+        .manage(convert::ConversionJob::default())
+        .manage(schedule::StopAt::default())
+        .manage(disk::DiskState::default())
+        .manage(calendar::CalendarState::default())
+        .manage(meetings::MeetingState::default())
+        .manage(timecode::TimecodeChannels::default())
+        .manage(window_capture::WindowTarget::default())
+        .manage(transcribe::TranscriptionState::default())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(updater::plugin())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(
+            // Visibility is driven by the shortcut, only remember where the popup was left
+            tauri_plugin_window_state::Builder::default()
+                .with_state_flags(StateFlags::all() - StateFlags::VISIBLE)
+                .build(),
+        )
         .setup(|app| {
-            let app_handle = app.handle().clone();
-            app.global_shortcut().on_shortcut(
-                Shortcut::new(Some(Modifiers::META | Modifiers::SHIFT), Code::KeyR),
-                move |_app_handle, _shortcut, event| {
-                    if event.state() == ShortcutState::Pressed {
-                        let window = app_handle.get_webview_window("main").unwrap();
-                        let is_visible = window.is_visible().unwrap_or(false);
-                        let recorder_arc = app_handle.state::<AppState>().0.clone();
-                        let app_clone = app_handle.clone();
-
-                        if is_visible {
-                            // Stop recording, then hide
-                            tauri::async_runtime::spawn(async move {
-                                let _ = stop_recording_inner(recorder_arc).await;
-                                let _ = app_clone.emit(
-                                    "recording-state",
-                                    serde_json::json!({"recording": false}),
-                                );
-                                let _ =
-                                    app_clone.get_webview_window("main").unwrap().hide();
-                            });
-                        } else {
-                            // Show, then start recording
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            tauri::async_runtime::spawn(async move {
-                                let _ = start_recording_inner(app_clone.clone(), recorder_arc)
-                                    .await;
-                                let _ = app_clone.emit(
-                                    "recording-state",
-                                    serde_json::json!({"recording": true}),
-                                );
-                            });
-                        }
-                    }
-                },
-            )?;
+            app.manage(settings::SettingsState::load(app.handle()));
+            app.manage(backup::BackupQueue::load(app.handle()));
+            app.manage(spill::SpillState::load(app.handle()));
+            app.manage(gain::GainState::load(app.handle()));
+            app.manage(monitor::Monitoring::load(app.handle()));
+            app.manage(dsp::DspState::load(app.handle()));
+            output_dir::restore(app.handle());
+            i18n::init(app.handle());
+            // After i18n, it may notify
+            app.manage(recovery::RecoveryState::scan(app.handle()));
+            tray::init(app.handle())?;
+
+            if let Some(window) = app.get_webview_window("main") {
+                overlay::show_on_all_spaces(&window)?;
+            }
+            overlay::spawn_auto_hide(app.handle().clone());
+            idle::spawn_watcher(app.handle().clone());
+            power::spawn_watcher(app.handle().clone());
+            backup::spawn_worker(app.handle().clone());
+            schedule::spawn_watcher(app.handle().clone());
+            disk::spawn_watcher(app.handle().clone());
+            calendar::spawn_watcher(app.handle().clone());
+            meetings::spawn_watcher(app.handle().clone());
+            spill::spawn_watcher(app.handle().clone());
+            timecode::spawn_watcher(app.handle().clone());
+            window_capture::spawn_watcher(app.handle().clone());
+            thermal::init(app.handle());
+
+            let update_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = updater::check(&update_handle).await {
+                    eprintln!("Update check failed: {}", e);
+                }
+            });
+
+            shortcuts::register(app.handle())?;
+            launch::init(app.handle());
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![start_recording, stop_recording, cancel_recording])
+        .invoke_handler(tauri::generate_handler![
+            start_recording,
+            stop_recording,
+            cancel_recording,
+            get_state,
+            get_recording_stats,
+            settings::get_settings,
+            settings::update_settings,
+            events::subscribe_events,
+            events::unsubscribe_events,
+            updater::check_for_update,
+            updater::install_update,
+            visualizer::open_visualizer_window,
+            waveform::get_waveform,
+            playback::play_recording,
+            playback::pause_playback,
+            playback::resume_playback,
+            playback::seek,
+            playback::stop_playback,
+            output::list_output_devices,
+            drag_out::start_drag_recording,
+            clipboard::copy_recording_to_clipboard,
+            overlay::overlay_interaction,
+            overlay::set_overlay_auto_hide,
+            idle::respond_idle_reminder,
+            power::get_power_mode,
+            output_dir::choose_output_dir,
+            output_dir::reset_output_dir,
+            output_dir::set_output_settings,
+            permissions::preflight_permissions,
+            i18n::set_locale,
+            onboarding::get_onboarding_state,
+            onboarding::complete_onboarding_step,
+            metadata::set_recording_tags,
+            markers::add_marker,
+            library::export_library,
+            convert::convert_library,
+            convert::cancel_conversion,
+            backup::set_backup_dir,
+            schedule::set_stop_at,
+            schedule::set_stop_rule,
+            calendar::respond_meeting_prompt,
+            calendar::set_calendar,
+            meetings::record_meeting,
+            presets::set_capture_preset,
+            presets::save_capture_presets,
+            presets::get_capture_presets,
+            handoff::set_handoff,
+            integrity::verify_recording,
+            crypto::decrypt_recording,
+            secure::secure_delete_recording,
+            secure::export_protected,
+            ffmpeg::export_with_ffmpeg,
+            timecode::subscribe_timecode,
+            stems::export_stems,
+            daw::export_daw_session,
+            gain::set_mic_gain,
+            gain::set_system_gain,
+            monitor::set_monitoring,
+            dsp::set_dsp_options,
+            transcribe::start_transcription,
+            transcribe::get_transcript,
+            recovery::list_unfinished_recordings,
+            recovery::recover_recording
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,130 @@
+//! Recordings library export, for backups or spreadsheet analysis.
+
+use crate::backup::BackupStatus;
+use crate::metadata::{self, Marker};
+use crate::{crypto, recordings};
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryEntry {
+    path: String,
+    name: String,
+    /// Last modified, seconds since the Unix epoch
+    modified: u64,
+    size_bytes: u64,
+    duration_ms: Option<u64>,
+    tags: Vec<String>,
+    markers: Vec<Marker>,
+    has_transcript: bool,
+    backup: Option<BackupStatus>,
+}
+
+fn duration_ms(path: &Path) -> Option<u64> {
+    let reader = crypto::read_wav(path).ok()?;
+    let sample_rate = reader.spec().sample_rate.max(1) as u64;
+    Some(reader.duration() as u64 * 1000 / sample_rate)
+}
+
+fn entry(path: &Path) -> LibraryEntry {
+    let file = std::fs::metadata(path).ok();
+    let metadata = metadata::load(path);
+    LibraryEntry {
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        modified: file
+            .as_ref()
+            .and_then(|file| file.modified().ok())
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_secs())
+            .unwrap_or_default(),
+        size_bytes: file.map(|file| file.len()).unwrap_or_default(),
+        duration_ms: duration_ms(path),
+        tags: metadata.tags,
+        markers: metadata.markers,
+        has_transcript: metadata
+            .transcript
+            .is_some_and(|transcript| transcript.is_file()),
+        backup: metadata.backup,
+    }
+}
+
+pub fn entries(app: &AppHandle) -> Vec<LibraryEntry> {
+    recordings::list(app)
+        .iter()
+        .map(|path| entry(path))
+        .collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(entries: &[LibraryEntry]) -> String {
+    let mut csv = String::from(
+        "path,name,modified,size_bytes,duration_ms,tags,markers,has_transcript,backup\n",
+    );
+    for entry in entries {
+        let row = [
+            csv_field(&entry.path),
+            csv_field(&entry.name),
+            entry.modified.to_string(),
+            entry.size_bytes.to_string(),
+            entry
+                .duration_ms
+                .map(|ms| ms.to_string())
+                .unwrap_or_default(),
+            // Tags joined with `;` so the column stays one cell
+            csv_field(&entry.tags.join(";")),
+            entry.markers.len().to_string(),
+            entry.has_transcript.to_string(),
+            entry
+                .backup
+                .as_ref()
+                .map(|backup| format!("{:?}", backup.state).to_lowercase())
+                .unwrap_or_default(),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Writes metadata for every recording to `path`. Returns how many were exported.
+#[tauri::command]
+pub async fn export_library(
+    app: AppHandle,
+    format: ExportFormat,
+    path: String,
+) -> Result<usize, RecorderError> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<usize, RecorderError> {
+        let entries = entries(&app);
+        let contents = match format {
+            ExportFormat::Csv => to_csv(&entries),
+            ExportFormat::Json => {
+                serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?
+            }
+        };
+        std::fs::write(&path, contents)?;
+        Ok(entries.len())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
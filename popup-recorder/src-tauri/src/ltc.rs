@@ -0,0 +1,159 @@
+//! SMPTE linear timecode rendered next to the recording as its `timecode`
+//! companion track.
+//!
+//! The mixer advances the track by exactly the frames it writes to the main
+//! file, so the two stay sample-aligned however the capture callbacks bunch
+//! up. Timecode starts at the local time of day, the usual choice for
+//! jam-syncing other devices.
+
+use crate::SAMPLE_RATE;
+use chrono::{Local, Timelike};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+const BITS_PER_FRAME: u32 = 80;
+// -6 dBFS, loud enough for any LTC reader without clipping a line input
+const LEVEL: f32 = 0.5;
+// Bits 64..80, read forwards: 0011 1111 1111 1101
+const SYNC_WORD: [bool; 16] = [
+    false, false, true, true, true, true, true, true, true, true, true, true, true, true, false,
+    true,
+];
+
+/// Non-drop frame rates, all of which divide 48 kHz into whole samples per bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LtcRate {
+    #[serde(rename = "24")]
+    Fps24,
+    #[serde(rename = "25")]
+    Fps25,
+    #[serde(rename = "30")]
+    Fps30,
+}
+
+impl LtcRate {
+    fn fps(self) -> u32 {
+        match self {
+            LtcRate::Fps24 => 24,
+            LtcRate::Fps25 => 25,
+            LtcRate::Fps30 => 30,
+        }
+    }
+
+    // 25 fps keeps the polarity bit where 30 fps has a binary group flag
+    fn polarity_bit(self) -> usize {
+        match self {
+            LtcRate::Fps25 => 59,
+            LtcRate::Fps24 | LtcRate::Fps30 => 27,
+        }
+    }
+}
+
+pub struct LtcTrack {
+    writer: WavWriter<BufWriter<File>>,
+    path: PathBuf,
+    rate: LtcRate,
+    samples_per_bit: u32,
+    /// Frames since midnight of the timecode being written
+    frame: u64,
+    bits: [bool; BITS_PER_FRAME as usize],
+    /// Sample offset inside the current timecode frame
+    position: u32,
+    level: f32,
+}
+
+impl LtcTrack {
+    pub fn create(path: &Path, rate: LtcRate) -> Result<Self, String> {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let now = Local::now();
+        let fps = rate.fps() as u64;
+        let frame = now.num_seconds_from_midnight() as u64 * fps
+            + now.nanosecond().min(999_999_999) as u64 * fps / 1_000_000_000;
+        let mut track = Self {
+            writer: WavWriter::create(path, spec).map_err(|e| e.to_string())?,
+            path: path.to_path_buf(),
+            rate,
+            samples_per_bit: SAMPLE_RATE / (rate.fps() * BITS_PER_FRAME),
+            frame,
+            bits: [false; BITS_PER_FRAME as usize],
+            position: 0,
+            level: LEVEL,
+        };
+        track.encode_frame();
+        Ok(track)
+    }
+
+    fn encode_frame(&mut self) {
+        let fps = self.rate.fps() as u64;
+        let frame = self.frame % (fps * 86_400);
+        let digits = [
+            (0, frame % fps),
+            (16, frame / fps % 60),
+            (32, frame / fps / 60 % 60),
+            (48, frame / fps / 3600),
+        ];
+
+        // User bits, drop frame and binary group flags all stay clear
+        self.bits = [false; BITS_PER_FRAME as usize];
+        for (start, value) in digits {
+            let (units, tens) = (value % 10, value / 10);
+            for bit in 0..4 {
+                self.bits[start + bit] = units >> bit & 1 == 1;
+            }
+            // Tens fields are 2 or 3 bits wide, frame and hour tens never
+            // reach the third so the flag after them stays clear
+            for bit in 0..3 {
+                self.bits[start + 8 + bit] = tens >> bit & 1 == 1;
+            }
+        }
+        self.bits[64..].copy_from_slice(&SYNC_WORD);
+
+        // Every frame carries an even number of zeros so each one starts on
+        // the same polarity
+        let zeros = self.bits.iter().filter(|bit| !**bit).count();
+        self.bits[self.rate.polarity_bit()] = zeros % 2 == 1;
+    }
+
+    /// Writes the next `frames` samples of biphase-mark timecode.
+    pub fn advance(&mut self, frames: u32) -> Result<(), String> {
+        let half = self.samples_per_bit / 2;
+        for _ in 0..frames {
+            let bit = (self.position / self.samples_per_bit) as usize;
+            let offset = self.position % self.samples_per_bit;
+            // A transition at every bit boundary, plus one mid-bit for a 1
+            if offset == 0 || (offset == half && self.bits[bit]) {
+                self.level = -self.level;
+            }
+            self.writer
+                .write_sample((self.level * i16::MAX as f32) as i16)
+                .map_err(|e| e.to_string())?;
+
+            self.position += 1;
+            if self.position == self.samples_per_bit * BITS_PER_FRAME {
+                self.position = 0;
+                self.frame += 1;
+                self.encode_frame();
+            }
+        }
+        Ok(())
+    }
+
+    /// Brings the header up to date, see `recovery`.
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|e| e.to_string())
+    }
+
+    /// Finalizes the file and returns where it was written.
+    pub fn finish(self) -> Result<PathBuf, String> {
+        self.writer.finalize().map_err(|e| e.to_string())?;
+        Ok(self.path)
+    }
+}
@@ -0,0 +1,94 @@
+//! Markers flagged while recording, from the overlay or the marker shortcut.
+//! Each one goes straight into the recording's sidecar so a crash doesn't
+//! lose it. With `settings.marker_cue_chunks` they're also written into the
+//! finished WAV as `cue ` and `LIST`/`adtl` chunks, which most editors and
+//! DAWs show as markers.
+
+use crate::metadata::{self, Marker};
+use crate::{events, AppState, SAMPLE_RATE};
+use recorder_core::RecorderError;
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+/// Marks the current position of the running recording.
+pub fn add(app: &AppHandle, label: Option<String>) -> Result<Marker, RecorderError> {
+    let state = app.state::<AppState>();
+    let (Some(path), Some(frames)) = (state.recording_path(), state.frames_written()) else {
+        return Err(RecorderError::NotRecording);
+    };
+    let marker = Marker {
+        // Position in the file, so time spent auto-paused doesn't count
+        offset_ms: frames * 1000 / SAMPLE_RATE as u64,
+        label: label
+            .map(|label| label.trim().to_string())
+            .filter(|label| !label.is_empty()),
+    };
+    metadata::update(&path, |metadata| metadata.markers.push(marker.clone()))?;
+    events::emit(app, "marker-added", &marker);
+    Ok(marker)
+}
+
+fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(body.len() + 9);
+    chunk.extend_from_slice(id);
+    chunk.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(body);
+    // Chunks are padded to an even length
+    if body.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+/// Appends the markers to a finalized WAV as cue points, labeled ones with a
+/// `labl` note. Leaves the `data` chunk alone, so the checksum still holds.
+pub fn write_cue_chunks(path: &Path, markers: &[Marker]) -> Result<(), String> {
+    if markers.is_empty() {
+        return Ok(());
+    }
+
+    let mut cues = (markers.len() as u32).to_le_bytes().to_vec();
+    let mut labels = b"adtl".to_vec();
+    for (index, marker) in markers.iter().enumerate() {
+        let id = (index as u32 + 1).to_le_bytes();
+        let position = ((marker.offset_ms * SAMPLE_RATE as u64 / 1000) as u32).to_le_bytes();
+        cues.extend_from_slice(&id);
+        cues.extend_from_slice(&position);
+        cues.extend_from_slice(b"data");
+        // Chunk and block start, 0 for uncompressed audio
+        cues.extend_from_slice(&[0; 8]);
+        cues.extend_from_slice(&position);
+
+        if let Some(label) = &marker.label {
+            let mut note = id.to_vec();
+            note.extend_from_slice(label.as_bytes());
+            note.push(0);
+            labels.extend(chunk(b"labl", &note));
+        }
+    }
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+    file.write_all(&chunk(b"cue ", &cues))
+        .map_err(|e| e.to_string())?;
+    if labels.len() > 4 {
+        file.write_all(&chunk(b"LIST", &labels))
+            .map_err(|e| e.to_string())?;
+    }
+    let riff_size = file.stream_position().map_err(|e| e.to_string())? - 8;
+    file.seek(SeekFrom::Start(4)).map_err(|e| e.to_string())?;
+    file.write_all(&(riff_size as u32).to_le_bytes())
+        .map_err(|e| e.to_string())
+}
+
+/// Marks the running recording, with an optional `label`.
+#[tauri::command]
+pub fn add_marker(app: AppHandle, label: Option<String>) -> Result<Marker, RecorderError> {
+    add(&app, label)
+}
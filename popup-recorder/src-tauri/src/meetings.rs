@@ -0,0 +1,239 @@
+//! Notices meeting apps going live (the app is running and its own
+//! processes are capturing or playing audio) and offers to record. Off
+//! unless `settings.meeting_detection` is turned on.
+
+use crate::i18n::{t, tr};
+use crate::{events, settings, show_and_start, AppState};
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// Bundle id, the bundle id whose processes (or their `.`-suffixed helpers) do
+// its audio, and display name. Meet only counts as its installed Chrome app,
+// a browser tab can't be told apart from any other page, and its audio runs
+// in Chrome's helpers.
+const MEETING_APPS: &[(&str, &str, &str)] = &[
+    ("us.zoom.xos", "us.zoom.xos", "Zoom"),
+    (
+        "com.microsoft.teams2",
+        "com.microsoft.teams2",
+        "Microsoft Teams",
+    ),
+    (
+        "com.microsoft.teams",
+        "com.microsoft.teams",
+        "Microsoft Teams",
+    ),
+    (
+        "com.google.Chrome.app.kjgfgldnnfoeklkmfkjfagphfepbbdan",
+        "com.google.Chrome",
+        "Google Meet",
+    ),
+];
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+struct AudioActivity {
+    microphone: bool,
+    system_audio: bool,
+}
+
+// Apps already announced, cleared once their call ends so the next one is offered
+#[derive(Default)]
+pub struct MeetingState(Mutex<HashSet<&'static str>>);
+
+#[cfg(target_os = "macos")]
+fn is_running(bundle_id: &str) -> bool {
+    use objc2_app_kit::NSRunningApplication;
+    use objc2_foundation::NSString;
+
+    NSRunningApplication::runningApplicationsWithBundleIdentifier(&NSString::from_str(bundle_id))
+        .count()
+        > 0
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_running(_bundle_id: &str) -> bool {
+    false
+}
+
+#[cfg(target_os = "macos")]
+fn property<T>(object: u32, selector: u32) -> Option<T> {
+    use objc2_core_audio::{
+        kAudioObjectPropertyElementMain, kAudioObjectPropertyScopeGlobal,
+        AudioObjectGetPropertyData, AudioObjectPropertyAddress,
+    };
+    use std::ptr::NonNull;
+
+    let address = AudioObjectPropertyAddress {
+        mSelector: selector,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMain,
+    };
+    let mut value = std::mem::MaybeUninit::<T>::uninit();
+    let mut size = std::mem::size_of::<T>() as u32;
+    // SAFETY: callers pick a `T` of the property's size, and the buffer matches `size`
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            object,
+            NonNull::from(&address),
+            0,
+            std::ptr::null(),
+            NonNull::from(&mut size),
+            NonNull::from(&mut value).cast(),
+        )
+    };
+    // SAFETY: a successful call filled `value`
+    (status == 0).then(|| unsafe { value.assume_init() })
+}
+
+/// The HAL's audio clients, each with its bundle id and what it's doing.
+/// The process objects need macOS 14.4, before that nothing is found.
+#[cfg(target_os = "macos")]
+fn process_activity() -> Vec<(String, AudioActivity)> {
+    use objc2_core_audio::{
+        kAudioHardwarePropertyProcessObjectList, kAudioObjectPropertyElementMain,
+        kAudioObjectPropertyScopeGlobal, kAudioObjectSystemObject, kAudioProcessPropertyBundleID,
+        kAudioProcessPropertyIsRunningInput, kAudioProcessPropertyIsRunningOutput,
+        AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize, AudioObjectID,
+        AudioObjectPropertyAddress,
+    };
+    use objc2_core_foundation::{CFRetained, CFString};
+    use std::ptr::NonNull;
+
+    let system = kAudioObjectSystemObject as AudioObjectID;
+    let address = AudioObjectPropertyAddress {
+        mSelector: kAudioHardwarePropertyProcessObjectList,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMain,
+    };
+    let mut size = 0u32;
+    // SAFETY: `address` is valid and `size` is a valid out pointer
+    let status = unsafe {
+        AudioObjectGetPropertyDataSize(
+            system,
+            NonNull::from(&address),
+            0,
+            std::ptr::null(),
+            NonNull::from(&mut size),
+        )
+    };
+    if status != 0 || size == 0 {
+        return Vec::new();
+    }
+    let mut processes =
+        vec![0 as AudioObjectID; size as usize / std::mem::size_of::<AudioObjectID>()];
+    // SAFETY: `processes` holds `size` bytes of object ids
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            system,
+            NonNull::from(&address),
+            0,
+            std::ptr::null(),
+            NonNull::from(&mut size),
+            NonNull::from(&mut *processes).cast(),
+        )
+    };
+    if status != 0 {
+        return Vec::new();
+    }
+    processes.truncate(size as usize / std::mem::size_of::<AudioObjectID>());
+
+    processes
+        .into_iter()
+        .filter_map(|process| {
+            let bundle_id = property::<*mut CFString>(process, kAudioProcessPropertyBundleID)
+                .and_then(NonNull::new)
+                // SAFETY: the HAL hands out a +1 CFString for this property
+                .map(|bundle_id| unsafe { CFRetained::from_raw(bundle_id) })?;
+            let running =
+                |selector| property::<u32>(process, selector).is_some_and(|running| running != 0);
+            let activity = AudioActivity {
+                microphone: running(kAudioProcessPropertyIsRunningInput),
+                system_audio: running(kAudioProcessPropertyIsRunningOutput),
+            };
+            Some((bundle_id.to_string(), activity))
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn process_activity() -> Vec<(String, AudioActivity)> {
+    Vec::new()
+}
+
+/// What `audio_id` and its helper processes are doing, combined.
+fn app_activity(processes: &[(String, AudioActivity)], audio_id: &str) -> AudioActivity {
+    let helper_prefix = format!("{}.", audio_id);
+    processes
+        .iter()
+        .filter(|(bundle_id, _)| bundle_id == audio_id || bundle_id.starts_with(&helper_prefix))
+        .fold(AudioActivity::default(), |all, (_, activity)| {
+            AudioActivity {
+                microphone: all.microphone || activity.microphone,
+                system_audio: all.system_audio || activity.system_audio,
+            }
+        })
+}
+
+fn announce(app: &AppHandle, name: &str, activity: AudioActivity, notify: bool) {
+    events::emit(
+        app,
+        "meeting-detected",
+        json!({ "app": name, "activity": activity }),
+    );
+    if notify {
+        let _ = app
+            .notification()
+            .builder()
+            .title(t("meeting-detected-title"))
+            .body(tr("meeting-detected-body", &[("app", name.to_string())]))
+            .show();
+    }
+}
+
+fn check(app: &AppHandle) {
+    let settings = settings::current(app);
+    // Nothing to offer while a recording is running already
+    if !settings.meeting_detection || app.state::<AppState>().is_recording() {
+        return;
+    }
+
+    let processes = process_activity();
+    let state = app.state::<MeetingState>();
+    let mut announced = state.0.lock();
+
+    for &(bundle_id, audio_id, name) in MEETING_APPS {
+        let activity = app_activity(&processes, audio_id);
+        let active = activity.microphone || activity.system_audio;
+        if active && is_running(bundle_id) {
+            if announced.insert(bundle_id) {
+                announce(app, name, activity, settings.meeting_notifications);
+            }
+        } else {
+            announced.remove(bundle_id);
+        }
+    }
+}
+
+/// Polls for meeting apps using audio.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            check(&app);
+        }
+    });
+}
+
+/// One-click start from a `meeting-detected` prompt.
+#[tauri::command]
+pub fn record_meeting(app: AppHandle) {
+    show_and_start(&app);
+}
@@ -0,0 +1,61 @@
+use crate::backup::BackupStatus;
+use crate::quality::QualityReport;
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A point of interest in a recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Marker {
+    pub offset_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Sidecar metadata stored next to a recording as `<name>.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordingMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<QualityReport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<Marker>,
+    /// Path of the transcript, once one was made
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backup: Option<BackupStatus>,
+    /// Hex SHA-256 of the audio data, see `integrity`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+pub fn metadata_path(recording: &Path) -> PathBuf {
+    recording.with_extension("json")
+}
+
+pub fn load(recording: &Path) -> RecordingMetadata {
+    std::fs::read_to_string(metadata_path(recording))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+// Background jobs (quality report, backup) update sidecars concurrently
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Loads the sidecar, applies `f` and writes it back.
+pub fn update(recording: &Path, f: impl FnOnce(&mut RecordingMetadata)) -> Result<(), String> {
+    let _guard = UPDATE_LOCK.lock();
+    let mut metadata = load(recording);
+    f(&mut metadata);
+    let json = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+    std::fs::write(metadata_path(recording), json).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_recording_tags(path: String, tags: Vec<String>) -> Result<(), RecorderError> {
+    Ok(update(Path::new(&path), |metadata| metadata.tags = tags)?)
+}
@@ -0,0 +1,235 @@
+//! Mic passthrough, so users can hear themselves while recording. The mic
+//! source is `Tapped`, its callback feeds a bounded queue that an output stream on the monitoring
+//! device drains. The bound is the latency: frames older than that are
+//! dropped rather than letting the delay creep up.
+
+use crate::i18n::tr;
+use crate::{output, settings, SAMPLE_RATE};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use parking_lot::Mutex;
+use recorder_core::{CaptureSource, RecorderError, SampleCallback};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+
+const MAX_VOLUME: f32 = 2.0;
+const MIN_LATENCY_MS: u32 = 5;
+const MAX_LATENCY_MS: u32 = 500;
+
+fn frames_for(latency_ms: u32) -> usize {
+    (SAMPLE_RATE * latency_ms / 1000) as usize
+}
+
+/// Where the mic callback drops its frames, a no-op while monitoring is off.
+pub struct MonitorTap {
+    enabled: AtomicBool,
+    volume: AtomicU32,
+    /// Most stereo frames held before the oldest are dropped
+    capacity: AtomicUsize,
+    queue: Mutex<VecDeque<[f32; 2]>>,
+}
+
+impl MonitorTap {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Queues interleaved stereo at `SAMPLE_RATE`, as the mic delivers it.
+    pub fn feed(&self, samples: &[f32]) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut queue = self.queue.lock();
+        queue.extend(samples.chunks_exact(2).map(|frame| [frame[0], frame[1]]));
+        let excess = queue
+            .len()
+            .saturating_sub(self.capacity.load(Ordering::Relaxed));
+        queue.drain(..excess);
+    }
+
+    fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+}
+
+pub struct Monitoring {
+    tap: Arc<MonitorTap>,
+    // Dropping the stream stops the device callback
+    stream: Mutex<Option<cpal::Stream>>,
+}
+
+impl Monitoring {
+    pub fn load(app: &AppHandle) -> Self {
+        let settings = settings::current(app);
+        Self {
+            tap: Arc::new(MonitorTap {
+                enabled: AtomicBool::new(false),
+                volume: AtomicU32::new(settings.monitor_volume.to_bits()),
+                capacity: AtomicUsize::new(frames_for(settings.monitor_latency_ms)),
+                queue: Mutex::new(VecDeque::new()),
+            }),
+            stream: Mutex::new(None),
+        }
+    }
+
+    /// Applies `validate`d `volume` and `latency_ms`, rebuilding a running
+    /// passthrough when the latency changed.
+    pub fn update(&self, app: &AppHandle, volume: f32, latency_ms: u32) -> Result<(), String> {
+        self.tap.volume.store(volume.to_bits(), Ordering::Relaxed);
+        let capacity = frames_for(latency_ms);
+        if self.tap.capacity.swap(capacity, Ordering::Relaxed) == capacity {
+            return Ok(());
+        }
+        let mut stream = self.stream.lock();
+        if stream.take().is_some() {
+            self.tap.enabled.store(false, Ordering::Relaxed);
+            self.tap.queue.lock().clear();
+            *stream = Some(build_stream(app, self.tap.clone(), latency_ms)?);
+            self.tap.enabled.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// `mic` with what it delivers also going to the monitor.
+    pub fn tapped(&self, mic: impl CaptureSource + 'static) -> Tapped {
+        Tapped {
+            mic: Box::new(mic),
+            tap: self.tap.clone(),
+        }
+    }
+}
+
+/// A mic source whose samples reach the monitor before the recording.
+pub struct Tapped {
+    mic: Box<dyn CaptureSource>,
+    tap: Arc<MonitorTap>,
+}
+
+impl CaptureSource for Tapped {
+    fn on_samples(&mut self, mut callback: SampleCallback) {
+        let tap = self.tap.clone();
+        self.mic.on_samples(Box::new(move |samples| {
+            tap.feed(samples);
+            callback(samples);
+        }));
+    }
+
+    fn start(&mut self) -> Result<(), String> {
+        self.mic.start()
+    }
+
+    fn stop(&mut self) {
+        self.mic.stop();
+    }
+}
+
+fn build_stream(
+    app: &AppHandle,
+    tap: Arc<MonitorTap>,
+    latency_ms: u32,
+) -> Result<cpal::Stream, String> {
+    let device = output::output_device(app)?;
+    let config = device.default_output_config().map_err(|e| e.to_string())?;
+    if config.sample_format() != cpal::SampleFormat::F32 {
+        return Err(t("error-output-not-f32"));
+    }
+
+    let out_channels = config.channels() as usize;
+    let out_rate = config.sample_rate();
+    let step = SAMPLE_RATE as f64 / out_rate as f64;
+    // Half the latency goes to the device buffer, the rest is queue headroom
+    let wanted = out_rate * latency_ms / 2000;
+    let buffer_size = match *config.buffer_size() {
+        cpal::SupportedBufferSize::Range { min, max } => {
+            cpal::BufferSize::Fixed(wanted.clamp(min, max))
+        }
+        cpal::SupportedBufferSize::Unknown => cpal::BufferSize::Default,
+    };
+    let mut stream_config: cpal::StreamConfig = config.into();
+    stream_config.buffer_size = buffer_size;
+
+    // Fraction of the front frame already played, for rate conversion
+    let mut position = 0.0f64;
+    let stream = device
+        .build_output_stream(
+            &stream_config,
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                let volume = tap.volume();
+                let mut queue = tap.queue.lock();
+                for frame in data.chunks_mut(out_channels) {
+                    // Running dry plays silence until the mic catches up
+                    let source = queue.front().copied().unwrap_or([0.0; 2]);
+                    for (channel, out) in frame.iter_mut().enumerate() {
+                        *out = source[channel.min(1)] * volume;
+                    }
+                    position += step;
+                    while position >= 1.0 {
+                        queue.pop_front();
+                        position -= 1.0;
+                    }
+                }
+            },
+            move |err| {
+                eprintln!("Monitor stream error: {}", err);
+            },
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+
+    stream.play().map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+pub fn validate(volume: f32, latency_ms: u32) -> Result<(), RecorderError> {
+    if !volume.is_finite() || !(0.0..=MAX_VOLUME).contains(&volume) {
+        return Err(RecorderError::InvalidArgument(tr(
+            "error-invalid-monitor-volume",
+            &[("max", MAX_VOLUME.to_string())],
+        )));
+    }
+    if !(MIN_LATENCY_MS..=MAX_LATENCY_MS).contains(&latency_ms) {
+        return Err(RecorderError::InvalidArgument(tr(
+            "error-invalid-monitor-latency",
+            &[
+                ("min", MIN_LATENCY_MS.to_string()),
+                ("max", MAX_LATENCY_MS.to_string()),
+            ],
+        )));
+    }
+    Ok(())
+}
+
+/// Turns mic monitoring on or off. `volume` and `latency_ms` are kept for
+/// next time; monitoring itself always starts off.
+#[tauri::command]
+pub fn set_monitoring(
+    app: AppHandle,
+    state: State<'_, Monitoring>,
+    enabled: bool,
+    volume: f32,
+    latency_ms: Option<u32>,
+) -> Result<(), RecorderError> {
+    let latency_ms = latency_ms.unwrap_or(settings::current(&app).monitor_latency_ms);
+    validate(volume, latency_ms)?;
+    settings::modify(&app, |settings| {
+        settings.monitor_volume = volume;
+        settings.monitor_latency_ms = latency_ms;
+    })?;
+
+    let tap = &state.tap;
+    tap.volume.store(volume.to_bits(), Ordering::Relaxed);
+    tap.capacity
+        .store(frames_for(latency_ms), Ordering::Relaxed);
+
+    let mut stream = state.stream.lock();
+    // Rebuilt on every call so a new latency or output device applies
+    stream.take();
+    tap.enabled.store(false, Ordering::Relaxed);
+    tap.queue.lock().clear();
+    if enabled {
+        *stream = Some(build_stream(&app, tap.clone(), latency_ms)?);
+        tap.enabled.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
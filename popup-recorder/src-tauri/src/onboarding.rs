@@ -0,0 +1,145 @@
+//! First-run wizard state. Progress is persisted as `onboarding.json` in the
+//! app config dir so the flow resumes where it was left, and permission steps
+//! only count as done once the permission is actually granted.
+
+use crate::i18n::t;
+use crate::permissions::{self, PermissionStatus, Resource};
+use crate::recordings;
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnboardingStep {
+    MicrophonePermission,
+    ScreenRecordingPermission,
+    TestRecording,
+    ShortcutSetup,
+}
+
+const STEPS: [OnboardingStep; 4] = [
+    OnboardingStep::MicrophonePermission,
+    OnboardingStep::ScreenRecordingPermission,
+    OnboardingStep::TestRecording,
+    OnboardingStep::ShortcutSetup,
+];
+
+impl OnboardingStep {
+    fn permission(self) -> Option<Resource> {
+        match self {
+            OnboardingStep::MicrophonePermission => Some(Resource::Microphone),
+            OnboardingStep::ScreenRecordingPermission => Some(Resource::ScreenRecording),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Progress {
+    completed: Vec<OnboardingStep>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepState {
+    step: OnboardingStep,
+    done: bool,
+    /// Where to grant the permission, for permission steps that aren't done
+    settings_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingState {
+    /// First step that isn't done, `None` once the wizard is finished
+    current: Option<OnboardingStep>,
+    steps: Vec<StepState>,
+}
+
+fn progress_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join("onboarding.json"))
+}
+
+fn load(app: &AppHandle) -> Progress {
+    progress_path(app)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save(app: &AppHandle, progress: &Progress) -> Result<(), String> {
+    let path = progress_path(app).ok_or_else(|| t("error-no-config-dir"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(progress).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+// Permission steps follow the live permission, so revoking one later puts the
+// wizard back on that step
+fn is_done(progress: &Progress, step: OnboardingStep) -> bool {
+    match step.permission() {
+        Some(resource) => permissions::status(resource) == PermissionStatus::Granted,
+        None => progress.completed.contains(&step),
+    }
+}
+
+fn state(progress: &Progress) -> OnboardingState {
+    let steps: Vec<_> = STEPS
+        .iter()
+        .map(|&step| {
+            let done = is_done(progress, step);
+            StepState {
+                step,
+                done,
+                settings_url: step
+                    .permission()
+                    .filter(|_| !done)
+                    .map(|resource| resource.settings_url()),
+            }
+        })
+        .collect();
+
+    OnboardingState {
+        current: steps.iter().find(|step| !step.done).map(|step| step.step),
+        steps,
+    }
+}
+
+#[tauri::command]
+pub fn get_onboarding_state(app: AppHandle) -> OnboardingState {
+    state(&load(&app))
+}
+
+/// Marks `step` as done after checking that it really is.
+#[tauri::command]
+pub fn complete_onboarding_step(
+    app: AppHandle,
+    step: OnboardingStep,
+) -> Result<OnboardingState, RecorderError> {
+    let mut progress = load(&app);
+
+    if let Some(resource) = step.permission() {
+        if permissions::status(resource) != PermissionStatus::Granted {
+            return Err(RecorderError::PermissionDenied {
+                resource: resource.name().to_string(),
+                message: t("error-permission-not-granted"),
+                settings_url: Some(resource.settings_url()),
+            });
+        }
+    }
+    if step == OnboardingStep::TestRecording && recordings::list(&app).is_empty() {
+        return Err(t("error-no-test-recording"));
+    }
+
+    if !progress.completed.contains(&step) {
+        progress.completed.push(step);
+        save(&app, &progress)?;
+    }
+    Ok(state(&progress))
+}
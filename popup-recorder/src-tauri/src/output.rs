@@ -0,0 +1,52 @@
+use crate::i18n::t;
+use crate::settings;
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::DeviceId;
+use recorder_core::RecorderError;
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputDevice {
+    id: String,
+    name: String,
+    is_default: bool,
+}
+
+/// Device used for monitoring, playback and cues. Falls back to the system
+/// default when nothing is configured or the chosen device has gone away.
+pub fn output_device(app: &AppHandle) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    if let Some(id) = settings::current(app).output_device {
+        if let Some(device) = id
+            .parse::<DeviceId>()
+            .ok()
+            .and_then(|id| host.device_by_id(&id))
+        {
+            return Ok(device);
+        }
+    }
+    host.default_output_device()
+        .ok_or_else(|| t("error-no-output-device"))
+}
+
+#[tauri::command]
+pub fn list_output_devices() -> Result<Vec<OutputDevice>, RecorderError> {
+    let host = cpal::default_host();
+    let default_id = host.default_output_device().and_then(|d| d.id().ok());
+
+    let devices = host
+        .output_devices()
+        .map_err(|e| RecorderError::DeviceNotFound(e.to_string()))?;
+    Ok(devices
+        .filter_map(|device| {
+            let id = device.id().ok()?;
+            let name = device.description().ok()?.name().to_string();
+            Some(OutputDevice {
+                is_default: default_id.as_ref() == Some(&id),
+                id: id.to_string(),
+                name,
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,172 @@
+//! Custom folder for new recordings. Sandboxed builds lose write access to a
+//! folder picked in the dialog once the app restarts, so a security-scoped
+//! bookmark is kept next to the settings and resolved again on launch. The
+//! bundle needs the `files.user-selected.read-write` and
+//! `files.bookmarks.app-scope` entitlements for this to work.
+
+use crate::i18n::t;
+use crate::{filename, settings};
+use recorder_core::RecorderError;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+fn bookmark_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join("output-dir.bookmark"))
+}
+
+#[cfg(target_os = "macos")]
+fn create_bookmark(path: &Path) -> Result<Vec<u8>, String> {
+    use objc2_foundation::{NSString, NSURLBookmarkCreationOptions, NSURL};
+
+    let url =
+        NSURL::fileURLWithPath_isDirectory(&NSString::from_str(&path.to_string_lossy()), true);
+    let data = url
+        .bookmarkDataWithOptions_includingResourceValuesForKeys_relativeToURL_error(
+            NSURLBookmarkCreationOptions::WithSecurityScope,
+            None,
+            None,
+        )
+        .map_err(|e| e.localizedDescription().to_string())?;
+    Ok(data.to_vec())
+}
+
+/// Resolves the bookmark and starts accessing it for the rest of the session.
+/// Returns the folder's current path and whether the bookmark went stale.
+#[cfg(target_os = "macos")]
+fn resolve_bookmark(bookmark: &[u8]) -> Result<(PathBuf, bool), String> {
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSData, NSURLBookmarkResolutionOptions, NSURL};
+
+    let data = NSData::with_bytes(bookmark);
+    let mut stale = Bool::NO;
+    // SAFETY: stale is a valid out pointer for the duration of the call
+    let url = unsafe {
+        NSURL::URLByResolvingBookmarkData_options_relativeToURL_bookmarkDataIsStale_error(
+            &data,
+            NSURLBookmarkResolutionOptions::WithSecurityScope,
+            None,
+            &mut stale,
+        )
+    }
+    .map_err(|e| e.localizedDescription().to_string())?;
+
+    // Never stopped, the folder stays in use until the app quits
+    // SAFETY: url is a resolved security-scoped file URL
+    if !unsafe { url.startAccessingSecurityScopedResource() } {
+        return Err(t("error-output-dir-denied"));
+    }
+    let path = url.path().ok_or("Bookmark doesn't point to a file path")?;
+    Ok((PathBuf::from(path.to_string()), stale.as_bool()))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn create_bookmark(_path: &Path) -> Result<Vec<u8>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn resolve_bookmark(_bookmark: &[u8]) -> Result<(PathBuf, bool), String> {
+    Err("Security-scoped bookmarks are only used on macOS".to_string())
+}
+
+fn save_bookmark(app: &AppHandle, folder: &Path) -> Result<(), String> {
+    let path = bookmark_path(app).ok_or_else(|| t("error-no-config-dir"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, create_bookmark(folder)?).map_err(|e| e.to_string())
+}
+
+/// Regains access to the configured output folder after a restart, following
+/// it if it was moved or renamed in the meantime.
+pub fn restore(app: &AppHandle) {
+    let Some(bookmark) = bookmark_path(app).and_then(|path| std::fs::read(path).ok()) else {
+        return;
+    };
+    if bookmark.is_empty() {
+        return;
+    }
+
+    let (folder, stale) = match resolve_bookmark(&bookmark) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            eprintln!("Failed to resolve output folder bookmark: {}", e);
+            return;
+        }
+    };
+
+    if stale {
+        if let Err(e) = save_bookmark(app, &folder) {
+            eprintln!("Failed to refresh output folder bookmark: {}", e);
+        }
+    }
+    let folder = folder.to_string_lossy().to_string();
+    if settings::current(app).output_dir.as_deref() != Some(folder.as_str()) {
+        let _ = settings::modify(app, |settings| settings.output_dir = Some(folder));
+    }
+}
+
+/// Lets the user pick the output folder and keeps access to it.
+/// Returns `None` when the dialog was cancelled.
+#[tauri::command]
+pub async fn choose_output_dir(app: AppHandle) -> Result<Option<String>, RecorderError> {
+    let dialog_app = app.clone();
+    let picked = tauri::async_runtime::spawn_blocking(move || {
+        dialog_app.dialog().file().blocking_pick_folder()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+
+    let folder = picked.into_path().map_err(|e| e.to_string())?;
+    save_bookmark(&app, &folder)?;
+    let folder = folder.to_string_lossy().to_string();
+    settings::modify(&app, |settings| settings.output_dir = Some(folder.clone()))?;
+    Ok(Some(folder))
+}
+
+/// Goes back to recording into the app data dir.
+#[tauri::command]
+pub fn reset_output_dir(app: AppHandle) -> Result<(), RecorderError> {
+    if let Some(path) = bookmark_path(&app) {
+        let _ = std::fs::remove_file(path);
+    }
+    settings::modify(&app, |settings| settings.output_dir = None)?;
+    Ok(())
+}
+
+/// Sets the output folder and the file name template together. A folder not
+/// picked in the dialog only works outside the sandbox, `None` goes back to
+/// the app data dir.
+#[tauri::command]
+pub fn set_output_settings(
+    app: AppHandle,
+    output_dir: Option<String>,
+    filename_template: String,
+) -> Result<(), RecorderError> {
+    filename::validate(&filename_template).map_err(RecorderError::InvalidArgument)?;
+    match &output_dir {
+        Some(folder) => {
+            if !Path::new(folder).is_dir() {
+                return Err(RecorderError::NotFound(t("error-output-dir-missing")));
+            }
+            save_bookmark(&app, Path::new(folder))?;
+        }
+        None => {
+            if let Some(path) = bookmark_path(&app) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+    settings::modify(&app, |settings| {
+        settings.output_dir = output_dir;
+        settings.filename_template = filename_template;
+    })?;
+    Ok(())
+}
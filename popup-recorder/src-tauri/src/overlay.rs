@@ -0,0 +1,166 @@
+use crate::{events, settings};
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, State, WebviewWindow};
+
+/// Keeps the popup on every Space and on top of full-screen apps, so the
+/// recording indicator doesn't disappear when presenting.
+#[cfg(target_os = "macos")]
+pub fn show_on_all_spaces(window: &WebviewWindow) -> tauri::Result<()> {
+    use objc2_app_kit::{NSWindow, NSWindowCollectionBehavior};
+
+    let ns_window = window.ns_window()? as *mut NSWindow;
+    // SAFETY: tauri keeps the NSWindow alive as long as the webview window and
+    // this is only called from the main thread during setup
+    let ns_window = unsafe { &*ns_window };
+    ns_window.setCollectionBehavior(
+        ns_window.collectionBehavior()
+            | NSWindowCollectionBehavior::CanJoinAllSpaces
+            | NSWindowCollectionBehavior::FullScreenAuxiliary,
+    );
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn show_on_all_spaces(window: &WebviewWindow) -> tauri::Result<()> {
+    window.set_visible_on_all_workspaces(true)
+}
+
+const TAB_WIDTH: f64 = 120.0;
+const TAB_HEIGHT: f64 = 10.0;
+// How close (logical px) the cursor has to get to the tab to reveal the overlay
+const REVEAL_DISTANCE: f64 = 24.0;
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct OverlayState {
+    last_interaction: Mutex<Instant>,
+    // Bounds to restore once the tab expands again
+    collapsed: Mutex<Option<(PhysicalPosition<i32>, PhysicalSize<u32>)>>,
+}
+
+impl OverlayState {
+    pub fn new() -> Self {
+        Self {
+            last_interaction: Mutex::new(Instant::now()),
+            collapsed: Mutex::new(None),
+        }
+    }
+}
+
+fn collapse(app: &AppHandle, window: &WebviewWindow, state: &OverlayState) -> tauri::Result<()> {
+    let Some(monitor) = window.current_monitor()? else {
+        return Ok(());
+    };
+    let position = window.outer_position()?;
+    let size = window.outer_size()?;
+    let scale = monitor.scale_factor();
+    let tab_width = (TAB_WIDTH * scale) as u32;
+    let tab_height = (TAB_HEIGHT * scale) as u32;
+
+    // Tuck the tab against the top edge, centered on where the overlay was
+    let x = position.x + (size.width as i32 - tab_width as i32) / 2;
+    let y = monitor.position().y;
+    window.set_size(PhysicalSize::new(tab_width, tab_height))?;
+    window.set_position(PhysicalPosition::new(x, y))?;
+
+    *state.collapsed.lock() = Some((position, size));
+    events::emit(app, "overlay-auto-hide", json!({ "collapsed": true }));
+    Ok(())
+}
+
+fn expand(app: &AppHandle, window: &WebviewWindow, state: &OverlayState) -> tauri::Result<()> {
+    let Some((position, size)) = state.collapsed.lock().take() else {
+        return Ok(());
+    };
+    window.set_size(size)?;
+    window.set_position(position)?;
+
+    *state.last_interaction.lock() = Instant::now();
+    events::emit(app, "overlay-auto-hide", json!({ "collapsed": false }));
+    Ok(())
+}
+
+fn cursor_near_tab(app: &AppHandle, window: &WebviewWindow) -> tauri::Result<bool> {
+    let cursor = app.cursor_position()?;
+    let position = window.outer_position()?;
+    let size = window.outer_size()?;
+    let reach = REVEAL_DISTANCE * window.scale_factor()?;
+
+    let left = position.x as f64 - reach;
+    let right = (position.x + size.width as i32) as f64 + reach;
+    let bottom = (position.y + size.height as i32) as f64 + reach;
+    Ok(cursor.x >= left && cursor.x <= right && cursor.y <= bottom)
+}
+
+fn watch_tick(app: &AppHandle) -> tauri::Result<()> {
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    let state = app.state::<OverlayState>();
+    let settings = settings::current(app);
+    let collapsed = state.collapsed.lock().is_some();
+
+    // Hidden or disabled: make sure the next show starts fully expanded
+    if !settings.overlay_auto_hide || !window.is_visible()? {
+        if collapsed {
+            expand(app, &window, &state)?;
+        }
+        *state.last_interaction.lock() = Instant::now();
+        return Ok(());
+    }
+
+    if collapsed {
+        if cursor_near_tab(app, &window)? {
+            expand(app, &window, &state)?;
+        }
+    } else {
+        let idle = state.last_interaction.lock().elapsed();
+        if idle >= Duration::from_secs(settings.overlay_auto_hide_secs) {
+            collapse(app, &window, &state)?;
+        }
+    }
+    Ok(())
+}
+
+/// Background watcher driving the auto-hide mode.
+pub fn spawn_auto_hide(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = watch_tick(&app) {
+                eprintln!("Overlay auto-hide failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Called by the overlay on pointer activity, keeps it from collapsing.
+#[tauri::command]
+pub fn overlay_interaction(
+    app: AppHandle,
+    state: State<'_, OverlayState>,
+) -> Result<(), RecorderError> {
+    *state.last_interaction.lock() = Instant::now();
+    if let Some(window) = app.get_webview_window("main") {
+        expand(&app, &window, &state).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_overlay_auto_hide(
+    app: AppHandle,
+    enabled: bool,
+    delay_secs: Option<u64>,
+) -> Result<(), RecorderError> {
+    settings::modify(&app, |settings| {
+        settings.overlay_auto_hide = enabled;
+        if let Some(delay_secs) = delay_secs {
+            settings.overlay_auto_hide_secs = delay_secs.max(1);
+        }
+    })?;
+    Ok(())
+}
@@ -0,0 +1,159 @@
+//! Permission preflight, so the UI can gate the record button before a
+//! session fails halfway through opening its streams.
+
+use serde::{Deserialize, Serialize};
+
+const PRIVACY_PANE: &str = "x-apple.systempreferences:com.apple.preference.security";
+
+/// Resources a recording session wants to capture.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SessionSpec {
+    pub microphone: bool,
+    pub system_audio: bool,
+    pub camera: bool,
+}
+
+impl Default for SessionSpec {
+    // What a popup-recorder session captures
+    fn default() -> Self {
+        Self {
+            microphone: true,
+            system_audio: true,
+            camera: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Resource {
+    Microphone,
+    ScreenRecording,
+    Camera,
+}
+
+impl Resource {
+    /// As serialized.
+    pub fn name(self) -> &'static str {
+        match self {
+            Resource::Microphone => "microphone",
+            Resource::ScreenRecording => "screen-recording",
+            Resource::Camera => "camera",
+        }
+    }
+
+    pub fn settings_url(self) -> String {
+        let anchor = match self {
+            Resource::Microphone => "Privacy_Microphone",
+            Resource::ScreenRecording => "Privacy_ScreenCapture",
+            Resource::Camera => "Privacy_Camera",
+        };
+        format!("{}?{}", PRIVACY_PANE, anchor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+    Restricted,
+    NotDetermined,
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceStatus {
+    resource: Resource,
+    status: PermissionStatus,
+    /// Where the user can fix it, only set when not granted
+    settings_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Preflight {
+    /// Every requested resource is granted
+    pub ready: bool,
+    resources: Vec<ResourceStatus>,
+}
+
+#[cfg(target_os = "macos")]
+fn capture_device_status(resource: Resource) -> PermissionStatus {
+    use objc2_av_foundation::{
+        AVAuthorizationStatus, AVCaptureDevice, AVMediaTypeAudio, AVMediaTypeVideo,
+    };
+
+    // SAFETY: reading framework constants
+    let media_type = unsafe {
+        match resource {
+            Resource::Camera => AVMediaTypeVideo,
+            _ => AVMediaTypeAudio,
+        }
+    };
+    let Some(media_type) = media_type else {
+        return PermissionStatus::Unsupported;
+    };
+
+    // SAFETY: only audio and video media types are passed, anything else throws
+    match unsafe { AVCaptureDevice::authorizationStatusForMediaType(media_type) } {
+        AVAuthorizationStatus::Authorized => PermissionStatus::Granted,
+        AVAuthorizationStatus::Denied => PermissionStatus::Denied,
+        AVAuthorizationStatus::Restricted => PermissionStatus::Restricted,
+        _ => PermissionStatus::NotDetermined,
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn status(resource: Resource) -> PermissionStatus {
+    match resource {
+        Resource::ScreenRecording => {
+            // CoreGraphics can't tell "never asked" from "denied"
+            if objc2_core_graphics::CGPreflightScreenCaptureAccess() {
+                PermissionStatus::Granted
+            } else {
+                PermissionStatus::Denied
+            }
+        }
+        _ => capture_device_status(resource),
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn status(_resource: Resource) -> PermissionStatus {
+    PermissionStatus::Unsupported
+}
+
+pub fn preflight(spec: &SessionSpec) -> Preflight {
+    let requested = [
+        (spec.microphone, Resource::Microphone),
+        (spec.system_audio, Resource::ScreenRecording),
+        (spec.camera, Resource::Camera),
+    ];
+    let resources: Vec<_> = requested
+        .into_iter()
+        .filter(|(wanted, _)| *wanted)
+        .map(|(_, resource)| {
+            let status = status(resource);
+            ResourceStatus {
+                resource,
+                status,
+                settings_url: (status != PermissionStatus::Granted)
+                    .then(|| resource.settings_url()),
+            }
+        })
+        .collect();
+
+    Preflight {
+        ready: resources
+            .iter()
+            .all(|resource| resource.status == PermissionStatus::Granted),
+        resources,
+    }
+}
+
+/// Checks every permission `spec` needs, defaulting to a popup-recorder session.
+#[tauri::command]
+pub fn preflight_permissions(spec: Option<SessionSpec>) -> Preflight {
+    preflight(&spec.unwrap_or_default())
+}
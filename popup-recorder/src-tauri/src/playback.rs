@@ -0,0 +1,227 @@
+use crate::i18n::t;
+use crate::{crypto, events, output};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use parking_lot::Mutex;
+use recorder_core::{read_samples, RecorderError};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+const POSITION_INTERVAL: Duration = Duration::from_millis(100);
+
+struct Decoded {
+    samples: Vec<f32>,
+    channels: usize,
+    sample_rate: u32,
+}
+
+fn decode(path: &Path) -> Result<Decoded, String> {
+    let reader = crypto::read_wav(path)?;
+    let spec = reader.spec();
+    let samples = read_samples(reader)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(Decoded {
+        samples,
+        channels: spec.channels.max(1) as usize,
+        sample_rate: spec.sample_rate,
+    })
+}
+
+struct PlaybackShared {
+    decoded: Decoded,
+    // Position in source frames, fractional because of rate conversion
+    position: Mutex<f64>,
+    paused: AtomicBool,
+    finished: AtomicBool,
+}
+
+impl PlaybackShared {
+    fn total_frames(&self) -> usize {
+        self.decoded.samples.len() / self.decoded.channels
+    }
+
+    fn frames_to_ms(&self, frames: f64) -> u64 {
+        (frames * 1000.0 / self.decoded.sample_rate as f64) as u64
+    }
+}
+
+struct ActivePlayback {
+    // Dropping the stream stops the device callback
+    _stream: cpal::Stream,
+    shared: Arc<PlaybackShared>,
+}
+
+#[derive(Default)]
+pub struct Playback(Mutex<Option<ActivePlayback>>);
+
+#[derive(Debug, Clone, Serialize)]
+struct PlaybackPosition {
+    path: String,
+    position_ms: u64,
+    duration_ms: u64,
+    paused: bool,
+    finished: bool,
+}
+
+fn build_stream(app: &AppHandle, shared: Arc<PlaybackShared>) -> Result<cpal::Stream, String> {
+    let device = output::output_device(app)?;
+    let config = device.default_output_config().map_err(|e| e.to_string())?;
+    if config.sample_format() != cpal::SampleFormat::F32 {
+        return Err(t("error-output-not-f32"));
+    }
+
+    let out_channels = config.channels() as usize;
+    let step = shared.decoded.sample_rate as f64 / config.sample_rate() as f64;
+    let total_frames = shared.total_frames();
+
+    let stream = device
+        .build_output_stream(
+            &config.into(),
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                if shared.paused.load(Ordering::Relaxed) || shared.finished.load(Ordering::Relaxed)
+                {
+                    data.fill(0.0);
+                    return;
+                }
+
+                let decoded = &shared.decoded;
+                let mut position = shared.position.lock();
+                for frame in data.chunks_mut(out_channels) {
+                    let index = *position as usize;
+                    if index >= total_frames {
+                        frame.fill(0.0);
+                        shared.finished.store(true, Ordering::Relaxed);
+                        continue;
+                    }
+                    let source = &decoded.samples[index * decoded.channels..][..decoded.channels];
+                    for (channel, out) in frame.iter_mut().enumerate() {
+                        // Mono sources go to every output channel, extra outputs repeat the last one
+                        *out = source[channel.min(decoded.channels - 1)];
+                    }
+                    *position += step;
+                }
+            },
+            move |err| {
+                eprintln!("Playback stream error: {}", err);
+            },
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+
+    stream.play().map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+fn spawn_position_events(app: AppHandle, path: String, shared: Weak<PlaybackShared>) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POSITION_INTERVAL);
+        loop {
+            interval.tick().await;
+            // Gone once the playback was stopped or replaced
+            let Some(shared) = shared.upgrade() else {
+                break;
+            };
+
+            let finished = shared.finished.load(Ordering::Relaxed);
+            let position = *shared.position.lock();
+            events::emit(
+                &app,
+                "playback-position",
+                &PlaybackPosition {
+                    path: path.clone(),
+                    position_ms: shared.frames_to_ms(position.min(shared.total_frames() as f64)),
+                    duration_ms: shared.frames_to_ms(shared.total_frames() as f64),
+                    paused: shared.paused.load(Ordering::Relaxed),
+                    finished,
+                },
+            );
+
+            if finished {
+                let state = app.state::<Playback>();
+                let mut active = state.0.lock();
+                if active
+                    .as_ref()
+                    .is_some_and(|active| Arc::ptr_eq(&active.shared, &shared))
+                {
+                    *active = None;
+                }
+                break;
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn play_recording(
+    app: AppHandle,
+    state: State<'_, Playback>,
+    path: String,
+) -> Result<(), RecorderError> {
+    let decode_path = path.clone();
+    let decoded = tauri::async_runtime::spawn_blocking(move || decode(Path::new(&decode_path)))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let shared = Arc::new(PlaybackShared {
+        decoded,
+        position: Mutex::new(0.0),
+        paused: AtomicBool::new(false),
+        finished: AtomicBool::new(false),
+    });
+    let stream = build_stream(&app, shared.clone())?;
+
+    spawn_position_events(app, path, Arc::downgrade(&shared));
+    *state.0.lock() = Some(ActivePlayback {
+        _stream: stream,
+        shared,
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pause_playback(state: State<'_, Playback>) -> Result<(), RecorderError> {
+    let active = state.0.lock();
+    let active = active
+        .as_ref()
+        .ok_or_else(|| RecorderError::NotFound(t("error-nothing-playing")))?;
+    active.shared.paused.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_playback(state: State<'_, Playback>) -> Result<(), RecorderError> {
+    let active = state.0.lock();
+    let active = active
+        .as_ref()
+        .ok_or_else(|| RecorderError::NotFound(t("error-nothing-playing")))?;
+    active.shared.paused.store(false, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn seek(state: State<'_, Playback>, ms: u64) -> Result<(), RecorderError> {
+    let active = state.0.lock();
+    let active = active
+        .as_ref()
+        .ok_or_else(|| RecorderError::NotFound(t("error-nothing-playing")))?;
+    let shared = &active.shared;
+    let total_frames = shared.total_frames() as f64;
+    let frame = (ms as f64 * shared.decoded.sample_rate as f64 / 1000.0).min(total_frames);
+    // Under the position lock, so the stream can't mark it finished in between
+    let mut position = shared.position.lock();
+    *position = frame;
+    // Seeking back from the end plays on instead of ending
+    shared
+        .finished
+        .store(frame >= total_frames, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_playback(state: State<'_, Playback>) {
+    state.0.lock().take();
+}
@@ -0,0 +1,119 @@
+use crate::{events, settings};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+const LEVELS_INTERVAL: Duration = Duration::from_millis(50);
+const REDUCED_LEVELS_INTERVAL: Duration = Duration::from_millis(200);
+
+// Bigger buffers on battery mean fewer disk wakeups per second of audio
+const WRITE_BUFFER: usize = 8 * 1024;
+const REDUCED_WRITE_BUFFER: usize = 256 * 1024;
+
+#[derive(Default)]
+pub struct PowerState {
+    on_battery: AtomicBool,
+    // Shared with the mixer, which checks it on every level update
+    reduced: Arc<AtomicBool>,
+}
+
+impl PowerState {
+    pub fn reduced_flag(&self) -> Arc<AtomicBool> {
+        self.reduced.clone()
+    }
+
+    pub fn write_buffer_size(&self) -> usize {
+        if self.reduced.load(Ordering::Relaxed) {
+            REDUCED_WRITE_BUFFER
+        } else {
+            WRITE_BUFFER
+        }
+    }
+}
+
+pub fn levels_interval(reduced: &AtomicBool) -> Duration {
+    if reduced.load(Ordering::Relaxed) {
+        REDUCED_LEVELS_INTERVAL
+    } else {
+        LEVELS_INTERVAL
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PowerMode {
+    on_battery: bool,
+    reduced: bool,
+}
+
+#[cfg(target_os = "macos")]
+fn on_battery() -> bool {
+    use objc2_io_kit::{
+        kIOPMBatteryPowerKey, IOPSCopyPowerSourcesInfo, IOPSGetProvidingPowerSourceType,
+    };
+
+    let Some(snapshot) = IOPSCopyPowerSourcesInfo() else {
+        return false;
+    };
+    // SAFETY: the snapshot comes straight from IOPSCopyPowerSourcesInfo
+    let Some(source) = (unsafe { IOPSGetProvidingPowerSourceType(Some(&*snapshot)) }) else {
+        return false;
+    };
+    source.to_string() == kIOPMBatteryPowerKey.to_string_lossy()
+}
+
+#[cfg(target_os = "windows")]
+fn on_battery() -> bool {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    // SAFETY: status is a valid out pointer for the duration of the call
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return false;
+    }
+    // 0 is offline, 1 online, 255 unknown
+    status.ACLineStatus == 0
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn on_battery() -> bool {
+    false
+}
+
+fn current_mode(state: &PowerState) -> PowerMode {
+    PowerMode {
+        on_battery: state.on_battery.load(Ordering::Relaxed),
+        reduced: state.reduced.load(Ordering::Relaxed),
+    }
+}
+
+fn refresh(app: &AppHandle) {
+    let state = app.state::<PowerState>();
+    let battery = on_battery();
+    let reduced = battery && settings::current(app).battery_saver;
+
+    let battery_changed = state.on_battery.swap(battery, Ordering::Relaxed) != battery;
+    let reduced_changed = state.reduced.swap(reduced, Ordering::Relaxed) != reduced;
+    if battery_changed || reduced_changed {
+        events::emit(app, "power-mode-changed", &current_mode(&state));
+    }
+}
+
+/// Polls the power source and switches to the reduced mode while on battery.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            refresh(&app);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_power_mode(state: State<'_, PowerState>) -> PowerMode {
+    current_mode(&state)
+}
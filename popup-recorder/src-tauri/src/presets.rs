@@ -0,0 +1,102 @@
+//! Capture presets pair what gets captured (the whole display, a single app
+//! or a window picked by title) with the processing applied to it, so one tray click sets both.
+
+use crate::i18n::t;
+use crate::{settings, tray};
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturePreset {
+    pub name: String,
+    /// Only this app's audio is captured, `None` captures the whole display
+    pub app_bundle_id: Option<String>,
+    /// Only the window whose title matches this glob, e.g. `*YouTube*`. Wins
+    /// over `app_bundle_id`, see `window_capture`
+    #[serde(default)]
+    pub window_title: Option<String>,
+    /// Run the mix through `dsp::VoiceChain`
+    pub voice_processing: bool,
+}
+
+pub fn defaults() -> Vec<CapturePreset> {
+    vec![
+        CapturePreset {
+            name: "Record Zoom only".to_string(),
+            app_bundle_id: Some("us.zoom.xos".to_string()),
+            window_title: None,
+            voice_processing: true,
+        },
+        CapturePreset {
+            name: "Record Teams only".to_string(),
+            app_bundle_id: Some("com.microsoft.teams2".to_string()),
+            window_title: None,
+            voice_processing: true,
+        },
+    ]
+}
+
+/// The preset the next recording uses, `None` for the plain whole-display capture.
+pub fn active(app: &AppHandle) -> Option<CapturePreset> {
+    let settings = settings::current(app);
+    let name = settings.active_preset?;
+    settings
+        .presets
+        .into_iter()
+        .find(|preset| preset.name == name)
+}
+
+/// Selects a preset by name, `None` goes back to whole-display capture.
+pub fn select(app: &AppHandle, name: Option<String>) -> Result<(), RecorderError> {
+    if let Some(name) = &name {
+        if !settings::current(app)
+            .presets
+            .iter()
+            .any(|preset| &preset.name == name)
+        {
+            return Err(RecorderError::NotFound(t("error-preset-not-found")));
+        }
+    }
+    settings::modify(app, |settings| settings.active_preset = name)?;
+    tray::refresh_presets(app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_capture_preset(app: AppHandle, name: Option<String>) -> Result<(), RecorderError> {
+    select(&app, name)
+}
+
+#[tauri::command]
+pub fn save_capture_presets(
+    app: AppHandle,
+    presets: Vec<CapturePreset>,
+) -> Result<(), RecorderError> {
+    settings::modify(&app, |settings| {
+        // Drop the selection if its preset went away
+        if let Some(active) = &settings.active_preset {
+            if !presets.iter().any(|preset| &preset.name == active) {
+                settings.active_preset = None;
+            }
+        }
+        settings.presets = presets;
+    })?;
+    tray::refresh_presets(&app);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetList {
+    presets: Vec<CapturePreset>,
+    active: Option<String>,
+}
+
+#[tauri::command]
+pub fn get_capture_presets(state: State<'_, settings::SettingsState>) -> PresetList {
+    let settings = state.get();
+    PresetList {
+        presets: settings.presets,
+        active: settings.active_preset,
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{crypto, events, metadata};
+use recorder_core::read_samples;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+
+// Anything at or above this is treated as a clipped sample
+const CLIP_THRESHOLD: f32 = 0.999;
+// Roughly -60 dBFS
+const SILENCE_THRESHOLD: f32 = 0.001;
+const LONG_SILENCE_MS: u64 = 2000;
+// Runs of exact digital zero this long inside the audio are reported as dropouts
+const DROPOUT_MIN_MS: u64 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Span {
+    pub start_ms: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub duration_ms: u64,
+    pub peak_dbfs: f32,
+    pub average_dbfs: f32,
+    pub clipped_samples: u64,
+    pub clipping_instances: u64,
+    pub long_silences: Vec<Span>,
+    pub dropout_gaps: Vec<Span>,
+    pub dropped_frames: u64,
+    pub dc_offset: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct QualityReportReady {
+    path: String,
+    report: QualityReport,
+}
+
+fn to_dbfs(amplitude: f32) -> f32 {
+    if amplitude > 0.0 {
+        (20.0 * amplitude.log10()).max(-120.0)
+    } else {
+        -120.0
+    }
+}
+
+/// Runs a single pass over a finished recording. `dropped_frames` comes from the
+/// recorder's buffer-drop counter since the file itself can't tell us about those.
+pub fn analyze(path: &Path, dropped_frames: u64) -> Result<QualityReport, String> {
+    let reader = crypto::read_wav(path)?;
+    let spec = reader.spec();
+    let channels = spec.channels.max(1) as usize;
+    let frames_per_ms = spec.sample_rate as u64 / 1000;
+    let to_ms = |frames: u64| frames / frames_per_ms.max(1);
+
+    let mut frame = Vec::with_capacity(channels);
+    let mut frame_index = 0u64;
+
+    let mut peak = 0.0f32;
+    let mut sum_squares = 0.0f64;
+    let mut channel_sums = vec![0.0f64; channels];
+    let mut clipped_samples = 0u64;
+    let mut clipping_instances = 0u64;
+    let mut was_clipping = false;
+
+    let mut long_silences = Vec::new();
+    let mut silence_start: Option<u64> = None;
+    let mut dropout_gaps = Vec::new();
+    let mut zero_start: Option<u64> = None;
+    let mut heard_audio = false;
+
+    for sample in read_samples(reader) {
+        frame.push(sample.map_err(|e| e.to_string())?);
+        if frame.len() < channels {
+            continue;
+        }
+
+        let mut frame_peak = 0.0f32;
+        let mut clipping = false;
+        for (channel, &s) in frame.iter().enumerate() {
+            let abs = s.abs();
+            frame_peak = frame_peak.max(abs);
+            sum_squares += (s * s) as f64;
+            channel_sums[channel] += s as f64;
+            if abs >= CLIP_THRESHOLD {
+                clipped_samples += 1;
+                clipping = true;
+            }
+        }
+        if clipping && !was_clipping {
+            clipping_instances += 1;
+        }
+        was_clipping = clipping;
+        peak = peak.max(frame_peak);
+
+        if frame_peak < SILENCE_THRESHOLD {
+            silence_start.get_or_insert(frame_index);
+        } else if let Some(start) = silence_start.take() {
+            if to_ms(frame_index - start) >= LONG_SILENCE_MS {
+                long_silences.push(Span {
+                    start_ms: to_ms(start),
+                    duration_ms: to_ms(frame_index - start),
+                });
+            }
+        }
+
+        // Only count zero runs that sit between real audio, leading and
+        // trailing digital silence is just the stream starting up or winding down.
+        if frame_peak == 0.0 {
+            if heard_audio {
+                zero_start.get_or_insert(frame_index);
+            }
+        } else {
+            if let Some(start) = zero_start.take() {
+                if to_ms(frame_index - start) >= DROPOUT_MIN_MS {
+                    dropout_gaps.push(Span {
+                        start_ms: to_ms(start),
+                        duration_ms: to_ms(frame_index - start),
+                    });
+                }
+            }
+            heard_audio = true;
+        }
+
+        frame.clear();
+        frame_index += 1;
+    }
+
+    if let Some(start) = silence_start {
+        if to_ms(frame_index - start) >= LONG_SILENCE_MS {
+            long_silences.push(Span {
+                start_ms: to_ms(start),
+                duration_ms: to_ms(frame_index - start),
+            });
+        }
+    }
+
+    let total_samples = frame_index * channels as u64;
+    let average = if total_samples > 0 {
+        (sum_squares / total_samples as f64).sqrt() as f32
+    } else {
+        0.0
+    };
+    let dc_offset = channel_sums
+        .iter()
+        .map(|sum| {
+            if frame_index > 0 {
+                (*sum / frame_index as f64) as f32
+            } else {
+                0.0
+            }
+        })
+        .collect();
+
+    Ok(QualityReport {
+        duration_ms: to_ms(frame_index),
+        peak_dbfs: to_dbfs(peak),
+        average_dbfs: to_dbfs(average),
+        clipped_samples,
+        clipping_instances,
+        long_silences,
+        dropout_gaps,
+        dropped_frames,
+        dc_offset,
+    })
+}
+
+/// Analyzes the recording, stores the report in the recording's metadata
+/// and emits `quality-report-ready`. Blocks for the whole file.
+pub fn report(app: &AppHandle, path: &Path, dropped_frames: u64) {
+    let report = match analyze(path, dropped_frames) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Quality analysis failed: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = metadata::update(path, |m| m.quality = Some(report.clone())) {
+        eprintln!("Failed to write recording metadata: {}", e);
+    }
+
+    events::emit(
+        app,
+        "quality-report-ready",
+        &QualityReportReady {
+            path: path.to_string_lossy().to_string(),
+            report,
+        },
+    );
+}
@@ -0,0 +1,98 @@
+use crate::{crypto, metadata, settings};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+// Extra tracks written next to a recording as `<name>.<kind>.wav`
+const COMPANION_KINDS: &[&str] = &["mic", "system", "timecode"];
+
+pub fn recordings_dir(app: &AppHandle) -> PathBuf {
+    if let Some(dir) = settings::current(app).output_dir {
+        return PathBuf::from(dir);
+    }
+    app.path()
+        .app_data_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+}
+
+/// Where the `kind` track belonging to `recording` lives.
+pub fn companion_path(recording: &Path, kind: &str) -> PathBuf {
+    let stem = recording.file_stem().unwrap_or_default().to_string_lossy();
+    recording.with_file_name(format!("{}.{}.wav", stem, kind))
+}
+
+/// Companion tracks that exist for `recording`, sealed or not.
+pub fn companions(recording: &Path) -> Vec<(&'static str, PathBuf)> {
+    COMPANION_KINDS
+        .iter()
+        .filter_map(|kind| {
+            let plain = companion_path(recording, kind);
+            [plain.with_extension(crypto::EXTENSION), plain]
+                .into_iter()
+                .find(|path| path.is_file())
+                .map(|path| (*kind, path))
+        })
+        .collect()
+}
+
+/// Copies of `recording` in the `converted` folder beside it, from dual
+/// format recording or a library conversion, sealed or not.
+pub fn converted_copies(recording: &Path) -> Vec<PathBuf> {
+    let stem = recording.file_stem().unwrap_or_default().to_string_lossy();
+    let dir = recording.with_file_name("converted");
+    ["wav", crypto::EXTENSION]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Whether `a` and `b` are the same existing file, however they're spelled.
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn is_companion(path: &Path) -> bool {
+    Path::new(path.file_stem().unwrap_or_default())
+        .extension()
+        .is_some_and(|kind| COMPANION_KINDS.iter().any(|known| kind == *known))
+}
+
+/// Recordings in the recordings dir, newest first.
+pub fn list(app: &AppHandle) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(recordings_dir(app)) else {
+        return Vec::new();
+    };
+
+    let mut recordings: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "wav" || ext == crypto::EXTENSION)
+                && !is_companion(path)
+        })
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
+            Some((modified, path))
+        })
+        .collect();
+    recordings.sort_by(|a, b| b.0.cmp(&a.0));
+    recordings.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Removes the recording together with its metadata sidecar and companion tracks.
+pub fn delete(path: &Path) -> Result<(), String> {
+    let transcript = metadata::load(path).transcript;
+    std::fs::remove_file(path).map_err(|e| e.to_string())?;
+    if let Some(transcript) = transcript {
+        let _ = std::fs::remove_file(transcript);
+    }
+    let _ = std::fs::remove_file(metadata::metadata_path(path));
+    for (_, companion) in companions(path) {
+        let _ = std::fs::remove_file(companion);
+    }
+    Ok(())
+}
@@ -0,0 +1,200 @@
+//! Keeps a crash from costing the whole recording. While one runs, its
+//! headers are flushed every few seconds and a `<name>.recovery.json` sidecar
+//! marks it unfinished. A sidecar still around at startup means the app died
+//! mid-recording: the files are moved aside so the next recording can't
+//! overwrite them, and `recover_recording` fixes up their headers.
+
+use crate::i18n::{t, tr_count};
+use crate::{recordings, spill};
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, State};
+use tauri_plugin_notification::NotificationExt;
+
+/// How much audio a crash can lose at most.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize)]
+struct Sidecar {
+    started_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnfinishedRecording {
+    pub path: PathBuf,
+    /// Unix time in ms
+    pub started_at: u64,
+}
+
+fn sidecar_path(recording: &Path) -> PathBuf {
+    recording.with_extension("recovery.json")
+}
+
+/// Marks `recording` as in progress.
+pub fn begin(recording: &Path) {
+    let sidecar = Sidecar {
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default(),
+    };
+    let written = serde_json::to_string(&sidecar)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(sidecar_path(recording), json).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        eprintln!("Crash recovery disabled for this recording: {}", e);
+    }
+}
+
+/// Called once `recording` is finalized or thrown away.
+pub fn end(recording: &Path) {
+    let _ = std::fs::remove_file(sidecar_path(recording));
+}
+
+/// Rewrites the RIFF and `data` sizes from the file's actual length,
+/// dropping a partially written frame at the end.
+pub fn repair_wav(path: &Path) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff).map_err(|_| t("error-not-wav"))?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err(t("error-not-wav"));
+    }
+
+    let mut block_align = 0u64;
+    let mut offset = 12u64;
+    loop {
+        let mut header = [0u8; 8];
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| e.to_string())?;
+        file.read_exact(&mut header)
+            .map_err(|_| t("error-not-wav"))?;
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+        let body = offset + 8;
+        match &header[0..4] {
+            b"fmt " => {
+                let mut format = [0u8; 14];
+                file.read_exact(&mut format)
+                    .map_err(|_| t("error-not-wav"))?;
+                block_align = u16::from_le_bytes([format[12], format[13]]) as u64;
+            }
+            b"data" if block_align > 0 => {
+                // Whatever follows the header is audio, however far hound got
+                let data_len = (len - body) / block_align * block_align;
+                file.set_len(body + data_len).map_err(|e| e.to_string())?;
+                file.seek(SeekFrom::Start(offset + 4))
+                    .map_err(|e| e.to_string())?;
+                file.write_all(&(data_len as u32).to_le_bytes())
+                    .map_err(|e| e.to_string())?;
+                file.seek(SeekFrom::Start(4)).map_err(|e| e.to_string())?;
+                file.write_all(&((body + data_len - 8) as u32).to_le_bytes())
+                    .map_err(|e| e.to_string())?;
+                return file.sync_all().map_err(|e| e.to_string());
+            }
+            b"data" => return Err(t("error-not-wav")),
+            _ => {}
+        }
+        offset = body + size + size % 2;
+    }
+}
+
+// Gives a crashed recording and its tracks a name of their own
+fn set_aside(sidecar: &Path) -> Option<UnfinishedRecording> {
+    let info: Sidecar = std::fs::read_to_string(sidecar)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())?;
+    let recording = sidecar.with_extension("").with_extension("wav");
+    if !recording.is_file() {
+        return None;
+    }
+
+    let path = recording.with_file_name(format!("unfinished-{}.wav", info.started_at));
+    for (kind, companion) in recordings::companions(&recording) {
+        let _ = std::fs::rename(companion, recordings::companion_path(&path, kind));
+    }
+    std::fs::rename(&recording, &path).ok()?;
+    std::fs::rename(sidecar, sidecar_path(&path)).ok()?;
+    Some(UnfinishedRecording {
+        path,
+        started_at: info.started_at,
+    })
+}
+
+fn find(dir: &Path) -> Vec<UnfinishedRecording> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.to_string_lossy().ends_with(".recovery.json"))
+        .filter_map(|sidecar| {
+            let found = set_aside(&sidecar);
+            if found.is_none() {
+                // Nothing left to recover
+                let _ = std::fs::remove_file(&sidecar);
+            }
+            found
+        })
+        .collect()
+}
+
+pub struct RecoveryState(Mutex<Vec<UnfinishedRecording>>);
+
+impl RecoveryState {
+    /// Looks for recordings left unfinished by a crash, in the output folder
+    /// and the spill dir.
+    pub fn scan(app: &AppHandle) -> Self {
+        let mut found = find(&recordings::recordings_dir(app));
+        found.extend(find(&spill::spill_dir(app)));
+        if !found.is_empty() {
+            let _ = app
+                .notification()
+                .builder()
+                .title(t("recovery-title"))
+                .body(tr_count("recovery-body", found.len() as u64))
+                .show();
+        }
+        Self(Mutex::new(found))
+    }
+}
+
+#[tauri::command]
+pub fn list_unfinished_recordings(state: State<'_, RecoveryState>) -> Vec<UnfinishedRecording> {
+    state.0.lock().clone()
+}
+
+/// Repairs an unfinished recording and its tracks so they play again.
+/// Returns the recording's path.
+#[tauri::command]
+pub fn recover_recording(
+    state: State<'_, RecoveryState>,
+    path: String,
+) -> Result<String, RecorderError> {
+    let path = PathBuf::from(path);
+    let mut unfinished = state.0.lock();
+    let index = unfinished
+        .iter()
+        .position(|recording| recording.path == path)
+        .ok_or_else(|| RecorderError::NotFound(t("error-not-unfinished")))?;
+
+    repair_wav(&path)?;
+    for (_, companion) in recordings::companions(&path) {
+        if let Err(e) = repair_wav(&companion) {
+            eprintln!("Failed to repair {}: {}", companion.display(), e);
+        }
+    }
+    end(&path);
+    unfinished.remove(index);
+    Ok(path.to_string_lossy().to_string())
+}
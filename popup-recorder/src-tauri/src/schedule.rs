@@ -0,0 +1,126 @@
+//! Auto-stop at a wall-clock time: either a one-off `stop_at` for the
+//! current session or the standing `settings.stop_by` rule ("never record
+//! past 18:00").
+
+use crate::i18n::{t, tr};
+use crate::{events, settings, stop_and_hide, AppState};
+use chrono::{DateTime, Local, NaiveTime};
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde_json::json;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const RULE_FORMAT: &str = "%H:%M";
+
+#[derive(Default)]
+pub struct StopAt(Mutex<Option<DateTime<Local>>>);
+
+fn parse_rule(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, RULE_FORMAT)
+        .map_err(|_| tr("error-invalid-time", &[("time", time.to_string())]))
+}
+
+/// Today's cutoff from the standing rule, if one is set.
+fn rule_deadline(app: &AppHandle) -> Option<DateTime<Local>> {
+    let time = parse_rule(&settings::current(app).stop_by?).ok()?;
+    Local::now()
+        .date_naive()
+        .and_time(time)
+        .and_local_timezone(Local)
+        .earliest()
+}
+
+/// Refuses to start once today's standing cutoff has passed.
+pub fn check_start(app: &AppHandle) -> Result<(), String> {
+    match rule_deadline(app) {
+        Some(deadline) if Local::now() >= deadline => Err(tr(
+            "error-past-stop-time",
+            &[("time", deadline.format(RULE_FORMAT).to_string())],
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn auto_stop(app: &AppHandle, deadline: DateTime<Local>) {
+    let at = deadline.format(RULE_FORMAT).to_string();
+    events::emit(app, "auto-stopped", json!({ "at": deadline.to_rfc3339() }));
+    let _ = app
+        .notification()
+        .builder()
+        .title(t("auto-stop-title"))
+        .body(tr("auto-stop-body", &[("time", at)]))
+        .show();
+    stop_and_hide(app);
+}
+
+fn check(app: &AppHandle) {
+    let now = Local::now();
+    let state = app.state::<StopAt>();
+    let session = {
+        let mut stop_at = state.0.lock();
+        // A one-off deadline is used up once it passes, recording or not
+        match *stop_at {
+            Some(deadline) if now >= deadline => stop_at.take(),
+            _ => None,
+        }
+    };
+    let deadline = session
+        .into_iter()
+        .chain(rule_deadline(app).filter(|deadline| now >= *deadline))
+        .min();
+
+    if let Some(deadline) = deadline {
+        if app.state::<AppState>().is_recording() {
+            auto_stop(app, deadline);
+        }
+    }
+}
+
+/// Enforces both deadlines while the app runs.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            check(&app);
+        }
+    });
+}
+
+/// Schedules (or with `None` cancels) the one-off stop for this session.
+pub fn stop_session_at(app: &AppHandle, deadline: Option<DateTime<Local>>) {
+    *app.state::<StopAt>().0.lock() = deadline;
+}
+
+/// Stops the current (or next) session at `at`, an RFC 3339 timestamp.
+/// `None` cancels it.
+#[tauri::command]
+pub fn set_stop_at(state: State<'_, StopAt>, at: Option<String>) -> Result<(), RecorderError> {
+    let deadline = at
+        .map(|at| {
+            DateTime::parse_from_rfc3339(&at)
+                .map(|deadline| deadline.with_timezone(&Local))
+                .map_err(|_| {
+                    RecorderError::InvalidArgument(tr(
+                        "error-invalid-time",
+                        &[("time", at.clone())],
+                    ))
+                })
+        })
+        .transpose()?;
+    *state.0.lock() = deadline;
+    Ok(())
+}
+
+/// Sets the standing daily cutoff as `HH:MM` local time, or clears it.
+#[tauri::command]
+pub fn set_stop_rule(app: AppHandle, time: Option<String>) -> Result<(), RecorderError> {
+    if let Some(time) = &time {
+        parse_rule(time).map_err(RecorderError::InvalidArgument)?;
+    }
+    settings::modify(&app, |settings| settings.stop_by = time)?;
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//! Handling for sensitive recordings: overwrite-then-delete, and export as
+//! an AES-encrypted zip that can be opened without the app.
+
+use crate::i18n::t;
+use crate::{crypto, metadata, recordings, tray};
+use recorder_core::RecorderError;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use zip::write::SimpleFileOptions;
+use zip::{AesMode, CompressionMethod, ZipWriter};
+
+const OVERWRITE_BLOCK: usize = 64 * 1024;
+const MIN_PASSWORD_LEN: usize = 8;
+
+/// Copy-on-write filesystems (APFS) write the zeros to fresh blocks, so
+/// overwriting there doesn't touch the original data.
+#[cfg(target_os = "macos")]
+fn overwrite_reaches_disk(path: &Path) -> bool {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stats = std::mem::MaybeUninit::<libc::statfs>::uninit();
+    // SAFETY: `c_path` is NUL terminated and statfs fills `stats` on success
+    if unsafe { libc::statfs(c_path.as_ptr(), stats.as_mut_ptr()) } != 0 {
+        return false;
+    }
+    let stats = unsafe { stats.assume_init() };
+    // SAFETY: the kernel NUL terminates f_fstypename
+    let fs_type = unsafe { CStr::from_ptr(stats.f_fstypename.as_ptr()) };
+    fs_type.to_bytes() != b"apfs"
+}
+
+#[cfg(not(target_os = "macos"))]
+fn overwrite_reaches_disk(_path: &Path) -> bool {
+    true
+}
+
+fn overwrite(path: &Path) -> std::io::Result<()> {
+    let mut remaining = std::fs::metadata(path)?.len();
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let zeros = vec![0u8; OVERWRITE_BLOCK];
+    while remaining > 0 {
+        let count = remaining.min(OVERWRITE_BLOCK as u64) as usize;
+        file.write_all(&zeros[..count])?;
+        remaining -= count as u64;
+    }
+    file.sync_all()
+}
+
+/// Overwrites (where that helps) and removes one file. Returns whether it was overwritten.
+fn shred(path: &Path) -> Result<bool, String> {
+    let overwritten = overwrite_reaches_disk(path) && overwrite(path).is_ok();
+    std::fs::remove_file(path).map_err(|e| e.to_string())?;
+    Ok(overwritten)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SecureDelete {
+    /// False when the filesystem made overwriting pointless and the file was only removed
+    overwritten: bool,
+}
+
+#[tauri::command]
+pub async fn secure_delete_recording(
+    app: AppHandle,
+    path: String,
+) -> Result<SecureDelete, RecorderError> {
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(path);
+        // Read before the sidecar that points to it goes
+        let transcript = metadata::load(&path).transcript;
+        let overwritten = shred(&path)?;
+        // Tags and markers can be just as sensitive as the audio
+        let sidecar = metadata::metadata_path(&path);
+        if sidecar.is_file() {
+            shred(&sidecar)?;
+        }
+        if let Some(transcript) = transcript.filter(|transcript| transcript.is_file()) {
+            shred(&transcript)?;
+        }
+        for (_, companion) in recordings::companions(&path) {
+            shred(&companion)?;
+        }
+        for copy in recordings::converted_copies(&path) {
+            shred(&copy)?;
+        }
+        Ok::<_, String>(SecureDelete { overwritten })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    tray::refresh_recordings(&app);
+    Ok(result)
+}
+
+fn write_protected_zip(source: &Path, destination: &Path, password: &str) -> Result<(), String> {
+    // Encrypted recordings go in decrypted, the zip password protects them instead
+    let name = source
+        .with_extension("wav")
+        .file_name()
+        .ok_or_else(|| t("error-not-a-file"))?
+        .to_string_lossy()
+        .to_string();
+    let file = BufWriter::new(File::create(destination).map_err(|e| e.to_string())?);
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .with_aes_encryption(AesMode::Aes256, password);
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    std::io::copy(&mut crypto::open(source)?, &mut zip).map_err(|e| e.to_string())?;
+    zip.finish()
+        .map_err(|e| e.to_string())?
+        .flush()
+        .map_err(|e| e.to_string())
+}
+
+/// Exports a recording as an AES-256 zip at `destination`, which must not be
+/// the recording itself. An existing file there is only replaced once the
+/// export succeeded.
+#[tauri::command]
+pub async fn export_protected(
+    path: String,
+    destination: String,
+    password: String,
+) -> Result<(), RecorderError> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(RecorderError::InvalidArgument(t(
+            "error-password-too-short",
+        )));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let (source, destination) = (Path::new(&path), Path::new(&destination));
+        if recordings::same_file(source, destination) {
+            return Err(RecorderError::InvalidArgument(t("error-export-same-file")));
+        }
+        // Written aside so a failure only removes what this export created
+        let partial = destination.with_extension("partial.zip");
+        write_protected_zip(source, &partial, &password)
+            .inspect_err(|_| {
+                let _ = std::fs::remove_file(&partial);
+            })
+            .map_err(RecorderError::IoError)?;
+        Ok(std::fs::rename(&partial, destination)?)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
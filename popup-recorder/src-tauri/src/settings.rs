@@ -0,0 +1,248 @@
+use crate::convert::ConvertFormat;
+use crate::dsp::{self, DspOptions};
+use crate::handoff::HandoffRule;
+use crate::hooks::Hook;
+use crate::i18n::t;
+use crate::ltc::LtcRate;
+use crate::presets::{self, CapturePreset};
+use crate::updater::UpdateChannel;
+use crate::webhooks::Webhook;
+use crate::{gain, monitor};
+use parking_lot::Mutex;
+use recorder_core::{BitDepth, RecorderError};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+/// User-facing settings, persisted as `settings.json` in the app config dir.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Play a tick on start and a double tick on stop
+    pub sound_cues: bool,
+    pub update_channel: UpdateChannel,
+    /// cpal device id for monitoring, playback and cues, `None` follows the system default
+    pub output_device: Option<String>,
+    /// Show conversion/upload progress on the Dock tile
+    pub dock_progress: bool,
+    /// Pulse the tray icon while recording instead of showing a static dot
+    pub animate_tray_icon: bool,
+    /// Collapse the overlay to an edge tab after `overlay_auto_hide_secs` without interaction
+    pub overlay_auto_hide: bool,
+    pub overlay_auto_hide_secs: u64,
+    /// Key that toggles recording when tapped twice, e.g. `F13`. It has to be
+    /// a key the system can register on its own, modifiers alone like right
+    /// Option can't be
+    pub double_tap_shortcut: Option<String>,
+    /// Adds a marker to the running recording, `None` disables it
+    pub marker_shortcut: Option<String>,
+    /// Also write markers into the finished WAV as cue points
+    pub marker_cue_chunks: bool,
+    /// Ask whether to keep going after this many minutes of near-silence, 0 disables it
+    pub idle_reminder_mins: u64,
+    /// Throttle level events and batch disk writes while on battery
+    pub battery_saver: bool,
+    /// Free space a recording needs to start and is stopped below, 0 disables it
+    pub min_free_space_mb: u64,
+    /// Folder new recordings go into, `None` uses the app data dir
+    pub output_dir: Option<String>,
+    /// Name of new recordings, see `filename`
+    pub filename_template: String,
+    /// Language for tray, notifications and errors, `None` follows the system
+    pub locale: Option<String>,
+    /// Second folder every finished recording is copied to
+    pub backup_dir: Option<String>,
+    /// Start recording as soon as the app launches, see `launch`
+    pub record_on_launch: bool,
+    /// Local `HH:MM` after which no recording may continue
+    pub stop_by: Option<String>,
+    /// ICS feed polled for meetings to offer recording
+    pub calendar_url: Option<String>,
+    /// Meeting titles must contain one of these, empty offers every meeting
+    pub calendar_keywords: Vec<String>,
+    /// Watch for Zoom/Teams/Meet calls and offer to record them, off by default
+    pub meeting_detection: bool,
+    /// Also show a system notification when a call is detected
+    pub meeting_notifications: bool,
+    pub presets: Vec<CapturePreset>,
+    /// Name of the preset the next recording uses, `None` captures the whole display
+    pub active_preset: Option<String>,
+    /// Watch folder finished recordings are handed to, `None` disables it
+    pub handoff: Option<HandoffRule>,
+    /// Of recordings, unless `start_recording` is given one
+    pub bit_depth: BitDepth,
+    /// Also write an integer PCM copy into `converted/` while recording
+    pub dual_format: Option<ConvertFormat>,
+    /// Seal finished recordings with the keychain key, see `crypto`
+    pub encrypt_recordings: bool,
+    /// Endpoints notified when recordings start, stop or fail
+    pub webhooks: Vec<Webhook>,
+    /// Scripts run after each recording is finalized
+    pub hooks: Vec<Hook>,
+    /// ffmpeg binary for exports, `None` looks for a sidecar or an installed one
+    pub ffmpeg_path: Option<String>,
+    /// How often a `timecode` sync point is sent while recording, 0 disables it
+    pub timecode_interval_ms: u64,
+    /// Render SMPTE timecode at this rate into a `.timecode.wav` next to each recording
+    pub ltc_rate: Option<LtcRate>,
+    /// Also keep the unmixed mic and system tracks, see `stems`
+    pub record_stems: bool,
+    /// Multipliers applied to each source before mixing, 1.0 leaves it as captured
+    pub mic_gain: f32,
+    pub system_gain: f32,
+    /// Keep the app's own cues and playback out of the system track
+    pub exclude_own_audio: bool,
+    /// Loudness of the mic passthrough, see `monitor`
+    pub monitor_volume: f32,
+    /// How far the passthrough may lag behind the mic
+    pub monitor_latency_ms: u32,
+    /// Mix RMS below which audio counts as silence
+    pub silence_threshold: f32,
+    /// How long it has to stay quiet before `silence-detected` fires
+    pub silence_min_ms: u64,
+    /// Stop writing during detected silences, resuming when sound returns
+    pub silence_auto_pause: bool,
+    /// Cut leading and trailing silence off when the recording stops
+    pub trim_silence: bool,
+    /// Noise gate and suppression for the mic, see `dsp::Pipeline`
+    pub dsp: DspOptions,
+    /// Path of the ggml whisper model used for transcription
+    pub transcription_model: Option<String>,
+    /// Spoken language as a whisper code like "en", detected when unset
+    pub transcription_language: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            sound_cues: false,
+            update_channel: UpdateChannel::default(),
+            output_device: None,
+            dock_progress: false,
+            animate_tray_icon: true,
+            overlay_auto_hide: false,
+            overlay_auto_hide_secs: 5,
+            double_tap_shortcut: None,
+            marker_shortcut: Some("Cmd+Shift+M".to_string()),
+            marker_cue_chunks: false,
+            idle_reminder_mins: 30,
+            battery_saver: true,
+            min_free_space_mb: 500,
+            output_dir: None,
+            filename_template: crate::filename::DEFAULT_TEMPLATE.to_string(),
+            locale: None,
+            backup_dir: None,
+            record_on_launch: false,
+            stop_by: None,
+            calendar_url: None,
+            calendar_keywords: Vec::new(),
+            meeting_detection: false,
+            meeting_notifications: true,
+            presets: presets::defaults(),
+            active_preset: None,
+            handoff: None,
+            bit_depth: BitDepth::default(),
+            dual_format: None,
+            encrypt_recordings: false,
+            webhooks: Vec::new(),
+            hooks: Vec::new(),
+            ffmpeg_path: None,
+            timecode_interval_ms: 1000,
+            ltc_rate: None,
+            record_stems: false,
+            mic_gain: 1.0,
+            system_gain: 1.0,
+            exclude_own_audio: true,
+            monitor_volume: 1.0,
+            monitor_latency_ms: 40,
+            silence_threshold: 0.003,
+            silence_min_ms: 3000,
+            silence_auto_pause: false,
+            trim_silence: false,
+            dsp: DspOptions::default(),
+            transcription_model: None,
+            transcription_language: None,
+        }
+    }
+}
+
+pub struct SettingsState(Mutex<Settings>);
+
+impl SettingsState {
+    pub fn load(app: &AppHandle) -> Self {
+        let settings = settings_path(app)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self(Mutex::new(settings))
+    }
+
+    pub fn get(&self) -> Settings {
+        self.0.lock().clone()
+    }
+}
+
+fn settings_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join("settings.json"))
+}
+
+pub fn current(app: &AppHandle) -> Settings {
+    app.state::<SettingsState>().get()
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<'_, SettingsState>) -> Settings {
+    state.get()
+}
+
+fn save(app: &AppHandle, settings: Settings) -> Result<Settings, String> {
+    let path = settings_path(app).ok_or_else(|| t("error-no-config-dir"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())?;
+
+    let previous = std::mem::replace(
+        &mut *app.state::<SettingsState>().0.lock(),
+        settings.clone(),
+    );
+    crate::shortcuts::update(app, &previous, &settings);
+    Ok(settings)
+}
+
+/// Applies `f` to the current settings and persists the result.
+pub fn modify(app: &AppHandle, f: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+    let mut settings = current(app);
+    f(&mut settings);
+    save(app, settings)
+}
+
+/// Replaces every setting, checking and applying the live ones the way
+/// their own commands do.
+#[tauri::command]
+pub fn update_settings(app: AppHandle, settings: Settings) -> Result<Settings, RecorderError> {
+    let mic_gain = gain::validate(settings.mic_gain).map_err(RecorderError::InvalidArgument)?;
+    let system_gain =
+        gain::validate(settings.system_gain).map_err(RecorderError::InvalidArgument)?;
+    monitor::validate(settings.monitor_volume, settings.monitor_latency_ms)?;
+    dsp::validate(&settings.dsp)?;
+
+    let previous = current(&app);
+    let settings = save(&app, settings)?;
+    let gains = app.state::<gain::GainState>();
+    gains.set_mic(mic_gain);
+    gains.set_system(system_gain);
+    if settings.dsp != previous.dsp {
+        app.state::<dsp::DspState>().set(&settings.dsp);
+    }
+    app.state::<monitor::Monitoring>().update(
+        &app,
+        settings.monitor_volume,
+        settings.monitor_latency_ms,
+    )?;
+    Ok(settings)
+}
@@ -0,0 +1,149 @@
+use crate::settings::{self, Settings};
+use crate::{markers, toggle_from_shortcut};
+use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+
+// A press held longer than this is a hold, not a tap
+const TAP_MAX_HOLD: Duration = Duration::from_millis(250);
+// Maximum time between the end of the first tap and the end of the second
+const DOUBLE_TAP_WINDOW: Duration = Duration::from_millis(400);
+
+#[derive(Default)]
+struct TapTimes {
+    pressed_at: Option<Instant>,
+    last_tap: Option<Instant>,
+}
+
+/// Turns the raw press/release stream of a shortcut into double-tap triggers.
+#[derive(Default)]
+struct DoubleTap(Mutex<TapTimes>);
+
+impl DoubleTap {
+    /// Returns true when this event completes a double tap.
+    fn on_event(&self, state: ShortcutState) -> bool {
+        let mut times = self.0.lock();
+        let now = Instant::now();
+
+        match state {
+            ShortcutState::Pressed => {
+                // Key repeat sends more presses, keep the first one
+                times.pressed_at.get_or_insert(now);
+                false
+            }
+            ShortcutState::Released => {
+                let Some(pressed_at) = times.pressed_at.take() else {
+                    return false;
+                };
+                if now - pressed_at > TAP_MAX_HOLD {
+                    times.last_tap = None;
+                    return false;
+                }
+                if times
+                    .last_tap
+                    .is_some_and(|last| now - last <= DOUBLE_TAP_WINDOW)
+                {
+                    times.last_tap = None;
+                    true
+                } else {
+                    times.last_tap = Some(now);
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// Double-tapping `chord` toggles recording. An unusable or taken chord is
+/// logged, it's only a setting.
+fn register_double_tap(app: &AppHandle, chord: &str) {
+    let shortcut = match chord.parse::<Shortcut>() {
+        Ok(shortcut) => shortcut,
+        Err(e) => {
+            eprintln!("Invalid double-tap shortcut {:?}: {}", chord, e);
+            return;
+        }
+    };
+    let double_tap = DoubleTap::default();
+    let registered = app
+        .global_shortcut()
+        .on_shortcut(shortcut, move |app, _shortcut, event| {
+            if double_tap.on_event(event.state()) {
+                toggle_from_shortcut(app);
+            }
+        });
+    if let Err(e) = registered {
+        eprintln!("Couldn't register double-tap shortcut {:?}: {}", chord, e);
+    }
+}
+
+/// Pressing `chord` adds a marker. On by default, so another app holding it
+/// mustn't stop the recorder from launching either.
+fn register_marker(app: &AppHandle, chord: &str) {
+    let shortcut = match chord.parse::<Shortcut>() {
+        Ok(shortcut) => shortcut,
+        Err(e) => {
+            eprintln!("Invalid marker shortcut {:?}: {}", chord, e);
+            return;
+        }
+    };
+    let registered = app
+        .global_shortcut()
+        .on_shortcut(shortcut, |app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                // Pressed while idle, nothing to mark
+                let _ = markers::add(app, None);
+            }
+        });
+    if let Err(e) = registered {
+        eprintln!("Couldn't register marker shortcut {:?}: {}", chord, e);
+    }
+}
+
+fn unregister(app: &AppHandle, chord: &str) {
+    if let Ok(shortcut) = chord.parse::<Shortcut>() {
+        let _ = app.global_shortcut().unregister(shortcut);
+    }
+}
+
+pub fn register(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    app.global_shortcut().on_shortcut(
+        Shortcut::new(Some(Modifiers::META | Modifiers::SHIFT), Code::KeyR),
+        |app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                toggle_from_shortcut(app);
+            }
+        },
+    )?;
+
+    let settings = settings::current(app);
+    if let Some(chord) = &settings.double_tap_shortcut {
+        register_double_tap(app, chord);
+    }
+    if let Some(chord) = &settings.marker_shortcut {
+        register_marker(app, chord);
+    }
+    Ok(())
+}
+
+/// Swaps the configurable shortcuts over when settings are saved, so a new
+/// chord works without a restart.
+pub fn update(app: &AppHandle, previous: &Settings, settings: &Settings) {
+    if previous.double_tap_shortcut != settings.double_tap_shortcut {
+        if let Some(chord) = &previous.double_tap_shortcut {
+            unregister(app, chord);
+        }
+        if let Some(chord) = &settings.double_tap_shortcut {
+            register_double_tap(app, chord);
+        }
+    }
+    if previous.marker_shortcut != settings.marker_shortcut {
+        if let Some(chord) = &previous.marker_shortcut {
+            unregister(app, chord);
+        }
+        if let Some(chord) = &settings.marker_shortcut {
+            register_marker(app, chord);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Silence detection on the mix. The mixer reports every block it mixed; a
+//! stretch below `silence_threshold` lasting `silence_min_ms` is announced
+//! as `silence-detected` and, with `silence_auto_pause`, stops reaching the
+//! file until sound returns. Where the sound starts and ends is tracked so
+//! `trim_silence` can cut the quiet ends off once the recording is final.
+
+use crate::{events, settings, SAMPLE_RATE};
+use hound::{SampleFormat, WavReader, WavWriter};
+use serde::Serialize;
+use std::path::Path;
+use tauri::AppHandle;
+
+// Kept around the sound when trimming so words don't start clipped
+const TRIM_PADDING_MS: u64 = 250;
+
+#[derive(Debug, Clone, Serialize)]
+struct SilenceEvent {
+    /// `true` once a silence is long enough to count, `false` when it ends
+    silent: bool,
+    /// Where the silence began, in recording time
+    start_ms: u64,
+    duration_ms: u64,
+}
+
+fn frames_to_ms(frames: u64) -> u64 {
+    frames * 1000 / SAMPLE_RATE as u64
+}
+
+pub struct SilenceDetector {
+    threshold: f32,
+    min_frames: u64,
+    auto_pause: bool,
+    trim: bool,
+    /// Frames mixed so far, written or not
+    position: u64,
+    /// Frames that went into the file so far
+    written: u64,
+    quiet_since: Option<u64>,
+    silent: bool,
+    /// Written-frame range that had sound in it
+    first_loud: Option<u64>,
+    last_loud: u64,
+}
+
+impl SilenceDetector {
+    pub fn new(app: &AppHandle) -> Self {
+        let settings = settings::current(app);
+        Self {
+            threshold: settings.silence_threshold,
+            min_frames: settings.silence_min_ms * SAMPLE_RATE as u64 / 1000,
+            auto_pause: settings.silence_auto_pause,
+            trim: settings.trim_silence,
+            position: 0,
+            written: 0,
+            quiet_since: None,
+            silent: false,
+            first_loud: None,
+            last_loud: 0,
+        }
+    }
+
+    /// Takes a mixed block of `frames` frames and its RMS level, and says
+    /// whether the block should be written.
+    pub fn observe(&mut self, app: &AppHandle, frames: u64, rms: f32) -> bool {
+        let start = self.position;
+        self.position += frames;
+
+        if rms >= self.threshold {
+            if let Some(since) = self.quiet_since.take() {
+                if self.silent {
+                    events::emit(
+                        app,
+                        "silence-detected",
+                        SilenceEvent {
+                            silent: false,
+                            start_ms: frames_to_ms(since),
+                            duration_ms: frames_to_ms(start - since),
+                        },
+                    );
+                }
+            }
+            self.silent = false;
+            self.first_loud.get_or_insert(self.written);
+            self.last_loud = self.written + frames;
+            self.written += frames;
+            return true;
+        }
+
+        let since = *self.quiet_since.get_or_insert(start);
+        if !self.silent && self.position - since >= self.min_frames {
+            self.silent = true;
+            events::emit(
+                app,
+                "silence-detected",
+                SilenceEvent {
+                    silent: true,
+                    start_ms: frames_to_ms(since),
+                    duration_ms: frames_to_ms(self.position - since),
+                },
+            );
+        }
+
+        let write = !(self.silent && self.auto_pause);
+        if write {
+            self.written += frames;
+        }
+        write
+    }
+
+    /// Frame range to keep when trimming is on and there's something to cut.
+    pub fn trim_range(&self) -> Option<(u64, u64)> {
+        if !self.trim {
+            return None;
+        }
+        let padding = TRIM_PADDING_MS * SAMPLE_RATE as u64 / 1000;
+        // A recording without any sound is left alone rather than emptied
+        let first = self.first_loud?;
+        let range = (
+            first.saturating_sub(padding),
+            (self.last_loud + padding).min(self.written),
+        );
+        (range != (0, self.written)).then_some(range)
+    }
+}
+
+/// Rewrites a WAV file keeping only frames `start..end`, in whatever sample
+/// format it was written.
+pub fn trim_wav(path: &Path, (start, end): (u64, u64)) -> Result<(), String> {
+    let mut reader = WavReader::open(path).map_err(|e| e.to_string())?;
+    let spec = reader.spec();
+    let channels = spec.channels as u64;
+    let (skip, take) = (
+        (start * channels) as usize,
+        ((end - start) * channels) as usize,
+    );
+
+    let scratch = path.with_extension("trim.wav");
+    let mut writer = WavWriter::create(&scratch, spec).map_err(|e| e.to_string())?;
+    match spec.sample_format {
+        SampleFormat::Float => {
+            for sample in reader.samples::<f32>().skip(skip).take(take) {
+                let sample = sample.map_err(|e| e.to_string())?;
+                writer.write_sample(sample).map_err(|e| e.to_string())?;
+            }
+        }
+        SampleFormat::Int => {
+            for sample in reader.samples::<i32>().skip(skip).take(take) {
+                let sample = sample.map_err(|e| e.to_string())?;
+                writer.write_sample(sample).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    writer.finalize().map_err(|e| e.to_string())?;
+    std::fs::rename(&scratch, path).map_err(|e| e.to_string())
+}
@@ -0,0 +1,93 @@
+//! Keeps the machine from idle-sleeping while a recording is running.
+
+use parking_lot::Mutex;
+use tauri::{AppHandle, Manager};
+
+#[cfg(target_os = "macos")]
+struct Assertion(objc2_io_kit::IOPMAssertionID);
+
+#[cfg(target_os = "macos")]
+impl Assertion {
+    fn take() -> Result<Self, String> {
+        use objc2_core_foundation::CFString;
+        use objc2_io_kit::{kIOPMAssertionLevelOn, IOPMAssertionCreateWithName};
+
+        let kind = CFString::from_static_str("PreventUserIdleSystemSleep");
+        let name = CFString::from_static_str("popup-recorder recording");
+        let mut id = 0;
+        // SAFETY: both strings are valid CFStrings and id is a valid out pointer
+        let result = unsafe {
+            IOPMAssertionCreateWithName(Some(&kind), kIOPMAssertionLevelOn, Some(&name), &mut id)
+        };
+        if result != 0 {
+            return Err(format!("IOPMAssertionCreateWithName failed: {:#x}", result));
+        }
+        Ok(Assertion(id))
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for Assertion {
+    fn drop(&mut self) {
+        objc2_io_kit::IOPMAssertionRelease(self.0);
+    }
+}
+
+// The execution state belongs to the calling thread, so a dedicated thread
+// holds it until the sender is dropped
+#[cfg(target_os = "windows")]
+struct Assertion(std::sync::mpsc::Sender<()>);
+
+#[cfg(target_os = "windows")]
+impl Assertion {
+    fn take() -> Result<Self, String> {
+        use windows_sys::Win32::System::Power::{
+            SetThreadExecutionState, ES_CONTINUOUS, ES_SYSTEM_REQUIRED,
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        std::thread::spawn(move || {
+            // SAFETY: plain flag arguments, no pointers involved
+            unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+            // Returns once the assertion is dropped
+            let _ = rx.recv();
+            unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+        });
+        Ok(Assertion(tx))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+struct Assertion;
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+impl Assertion {
+    fn take() -> Result<Self, String> {
+        Err(crate::i18n::t("error-sleep-unsupported"))
+    }
+}
+
+#[derive(Default)]
+pub struct SleepGuard(Mutex<Option<Assertion>>);
+
+impl SleepGuard {
+    pub fn is_active(&self) -> bool {
+        self.0.lock().is_some()
+    }
+}
+
+/// Takes the power assertion for a recording, or releases it.
+pub fn set_prevented(app: &AppHandle, prevented: bool) {
+    let guard = app.state::<SleepGuard>();
+    let mut assertion = guard.0.lock();
+    if !prevented {
+        assertion.take();
+        return;
+    }
+    if assertion.is_none() {
+        match Assertion::take() {
+            Ok(taken) => *assertion = Some(taken),
+            Err(e) => eprintln!("Failed to prevent sleep: {}", e),
+        }
+    }
+}
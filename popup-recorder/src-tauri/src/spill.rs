@@ -0,0 +1,276 @@
+//! Keeps recordings safe when the output folder lives on a network share.
+//! Recordings aimed at a share, or at a folder that isn't mounted, are
+//! written to a local spill dir and moved over once finalized. Moves that
+//! can't happen yet stay queued in `spill-queue.json` until the share is back.
+
+use crate::i18n::t;
+use crate::{events, metadata, settings};
+use parking_lot::Mutex;
+use recorder_core::filename;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingMove {
+    from: PathBuf,
+    to_dir: PathBuf,
+}
+
+#[derive(Default)]
+struct Spill {
+    // Where the current recording belongs when it's being spilled
+    target: Option<PathBuf>,
+    target_reachable: bool,
+    pending: Vec<PendingMove>,
+}
+
+#[derive(Default)]
+pub struct SpillState(Mutex<Spill>);
+
+fn queue_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_config_dir()
+        .ok()
+        .map(|dir| dir.join("spill-queue.json"))
+}
+
+impl SpillState {
+    pub fn load(app: &AppHandle) -> Self {
+        let pending = queue_path(app)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self(Mutex::new(Spill {
+            pending,
+            ..Default::default()
+        }))
+    }
+}
+
+fn save_queue(app: &AppHandle, pending: &[PendingMove]) {
+    let Some(path) = queue_path(app) else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(pending) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+pub(crate) fn spill_dir(app: &AppHandle) -> PathBuf {
+    app.path()
+        .app_data_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join("spill")
+}
+
+/// Whether `dir` sits on a volume that isn't local (SMB, AFP, NFS, ...).
+#[cfg(target_os = "macos")]
+fn is_network(dir: &Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stats = std::mem::MaybeUninit::<libc::statfs>::uninit();
+    // SAFETY: `path` is NUL terminated and statfs fills `stats` on success
+    if unsafe { libc::statfs(path.as_ptr(), stats.as_mut_ptr()) } != 0 {
+        return false;
+    }
+    let stats = unsafe { stats.assume_init() };
+    stats.f_flags & libc::MNT_LOCAL as u32 == 0
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_network(_dir: &Path) -> bool {
+    false
+}
+
+/// Folder the next recording should be written to, the spill dir if the
+/// configured output folder is a share or can't be reached.
+pub fn recording_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let target = crate::recordings::recordings_dir(app);
+    let custom = settings::current(app).output_dir.is_some();
+    // Never create a configured folder, on an unmounted share that would
+    // quietly put a local folder in its place
+    if !custom {
+        std::fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+    }
+
+    let reason = if !target.is_dir() {
+        "unreachable"
+    } else if is_network(&target) {
+        "network-share"
+    } else {
+        app.state::<SpillState>().0.lock().target = None;
+        return Ok(target);
+    };
+
+    let spill = spill_dir(app);
+    std::fs::create_dir_all(&spill).map_err(|e| e.to_string())?;
+    {
+        let state = app.state::<SpillState>();
+        let mut state = state.0.lock();
+        state.target = Some(target.clone());
+        state.target_reachable = reason != "unreachable";
+    }
+    events::emit(
+        app,
+        "output-spilled",
+        json!({ "target": target, "spill": spill, "reason": reason }),
+    );
+    Ok(spill)
+}
+
+// Renames when possible, copies across volumes otherwise
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    let partial = to.with_extension("partial");
+    std::fs::copy(from, &partial).map_err(|e| e.to_string())?;
+    std::fs::rename(&partial, to).map_err(|e| e.to_string())?;
+    std::fs::remove_file(from).map_err(|e| e.to_string())
+}
+
+fn try_move(app: &AppHandle, pending: &PendingMove) -> Result<PathBuf, String> {
+    let name = pending
+        .from
+        .file_name()
+        .ok_or_else(|| t("error-not-a-file"))?;
+    // Never over a recording that's already there
+    let to = filename::unique_path(&pending.to_dir, Path::new(name));
+    move_file(&pending.from, &to)?;
+
+    // The sidecar follows the recording, losing it isn't worth failing over
+    let sidecar = metadata::metadata_path(&pending.from);
+    if sidecar.is_file() {
+        let _ = move_file(&sidecar, &metadata::metadata_path(&to));
+    }
+    events::emit(
+        app,
+        "output-moved",
+        json!({ "from": pending.from, "to": to }),
+    );
+    Ok(to)
+}
+
+/// Moves a finalized recording to where it belongs. Returns its final path,
+/// or the spill path if the move had to be queued.
+pub fn deliver(app: &AppHandle, path: PathBuf) -> PathBuf {
+    let state = app.state::<SpillState>();
+    let Some(to_dir) = state.0.lock().target.take() else {
+        return path;
+    };
+    let pending = PendingMove {
+        from: path.clone(),
+        to_dir,
+    };
+
+    if pending.to_dir.is_dir() {
+        match try_move(app, &pending) {
+            Ok(to) => return to,
+            Err(e) => eprintln!("Moving {} to the share failed: {}", path.display(), e),
+        }
+    }
+
+    events::emit(
+        app,
+        "output-move-pending",
+        json!({ "path": path, "target": pending.to_dir }),
+    );
+    let mut state = state.0.lock();
+    state.pending.push(pending);
+    save_queue(app, &state.pending);
+    path
+}
+
+/// Whether `path` is a spilled recording still waiting to be moved.
+pub fn is_queued(app: &AppHandle, path: &Path) -> bool {
+    let state = app.state::<SpillState>();
+    let state = state.0.lock();
+    state.pending.iter().any(|pending| pending.from == path)
+}
+
+fn retry(app: &AppHandle) {
+    let state = app.state::<SpillState>();
+    let mut state = state.0.lock();
+    if state.pending.is_empty() {
+        return;
+    }
+    let mut moved = Vec::new();
+    state.pending.retain(|pending| {
+        if !pending.from.is_file() {
+            // Moved or deleted by hand in the meantime
+            return false;
+        }
+        if !pending.to_dir.is_dir() {
+            return true;
+        }
+        match try_move(app, pending) {
+            Ok(to) => {
+                moved.push(to);
+                false
+            }
+            Err(e) => {
+                eprintln!(
+                    "Moving {} to the share failed: {}",
+                    pending.from.display(),
+                    e
+                );
+                true
+            }
+        }
+    });
+    save_queue(app, &state.pending);
+    drop(state);
+
+    // Held back until now, the spill copy isn't where the recording stays
+    for path in moved {
+        crate::settle(app, &path);
+    }
+}
+
+// Reports the share dropping out or coming back while a spilled recording runs
+fn check_target(app: &AppHandle) {
+    let state = app.state::<SpillState>();
+    let mut state = state.0.lock();
+    let Some(target) = state.target.clone() else {
+        return;
+    };
+    let reachable = target.is_dir();
+    if reachable != state.target_reachable {
+        state.target_reachable = reachable;
+        events::emit(
+            app,
+            "output-share-status",
+            json!({ "target": target, "reachable": reachable }),
+        );
+    }
+}
+
+/// Watches the share during spilled recordings and retries queued moves.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        let mut ticks = 0u64;
+        loop {
+            interval.tick().await;
+            check_target(&app);
+            if ticks % (RETRY_INTERVAL.as_secs() / CHECK_INTERVAL.as_secs()) == 0 {
+                let app = app.clone();
+                let _ = tauri::async_runtime::spawn_blocking(move || retry(&app)).await;
+            }
+            ticks += 1;
+        }
+    });
+}
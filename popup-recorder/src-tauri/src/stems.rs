@@ -0,0 +1,153 @@
+//! Separate mic and system tracks, written next to the mix when
+//! `settings.record_stems` is on, and the zip bundle that hands a whole
+//! session to an editor.
+
+use crate::i18n::t;
+use crate::{crypto, metadata, recordings, SAMPLE_RATE};
+use chrono::Local;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use recorder_core::RecorderError;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// The unmixed sources, in the same format and frame timing as the mix.
+pub struct StemWriters {
+    mic: WavWriter<BufWriter<File>>,
+    system: WavWriter<BufWriter<File>>,
+}
+
+impl StemWriters {
+    pub fn create(recording: &Path) -> Result<Self, String> {
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+        let create = |kind| {
+            WavWriter::create(recordings::companion_path(recording, kind), spec)
+                .map_err(|e| e.to_string())
+        };
+        Ok(Self {
+            mic: create("mic")?,
+            system: create("system")?,
+        })
+    }
+
+    /// Writes one stereo frame of each source.
+    pub fn write(&mut self, system: [f32; 2], mic: [f32; 2]) -> Result<(), String> {
+        for (writer, frame) in [(&mut self.system, system), (&mut self.mic, mic)] {
+            for sample in frame {
+                writer.write_sample(sample).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Brings both headers up to date, see `recovery`.
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.mic.flush().map_err(|e| e.to_string())?;
+        self.system.flush().map_err(|e| e.to_string())
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        self.mic.finalize().map_err(|e| e.to_string())?;
+        self.system.finalize().map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ManifestTrack {
+    file: String,
+    kind: &'static str,
+}
+
+/// `manifest.json` at the root of the bundle.
+#[derive(Debug, Clone, Serialize)]
+struct Manifest {
+    recording: String,
+    exported_at: String,
+    sample_rate: u32,
+    tracks: Vec<ManifestTrack>,
+    markers: &'static str,
+    metadata: &'static str,
+}
+
+fn add_json<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    value: &impl Serialize,
+) -> Result<(), String> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(zip, value).map_err(|e| e.to_string())
+}
+
+fn write_bundle(recording: &Path, destination: &Path) -> Result<(), String> {
+    // Audio barely compresses and can run past 4 GiB on long sessions
+    let audio = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+
+    let tracks: Vec<_> = std::iter::once(("mix", recording.to_path_buf()))
+        .chain(recordings::companions(recording))
+        .collect();
+    let sidecar = metadata::load(recording);
+    let manifest = Manifest {
+        recording: recording
+            .with_extension("wav")
+            .file_name()
+            .ok_or_else(|| t("error-not-a-file"))?
+            .to_string_lossy()
+            .to_string(),
+        exported_at: Local::now().to_rfc3339(),
+        sample_rate: SAMPLE_RATE,
+        tracks: tracks
+            .iter()
+            .map(|(kind, _)| ManifestTrack {
+                file: format!("{}.wav", kind),
+                kind: *kind,
+            })
+            .collect(),
+        markers: "markers.json",
+        metadata: "metadata.json",
+    };
+
+    let file = BufWriter::new(File::create(destination).map_err(|e| e.to_string())?);
+    let mut zip = ZipWriter::new(file);
+    add_json(&mut zip, "manifest.json", &manifest)?;
+    add_json(&mut zip, manifest.markers, &sidecar.markers)?;
+    add_json(&mut zip, manifest.metadata, &sidecar)?;
+
+    // Sealed tracks go in decrypted, the bundle is meant for another machine
+    for (kind, path) in &tracks {
+        zip.start_file(format!("{}.wav", kind), audio)
+            .map_err(|e| e.to_string())?;
+        std::io::copy(&mut crypto::open(path)?, &mut zip).map_err(|e| e.to_string())?;
+    }
+
+    zip.finish()
+        .map_err(|e| e.to_string())?
+        .flush()
+        .map_err(|e| e.to_string())
+}
+
+/// Bundles the mix, any stems and the timecode track, the markers and the
+/// metadata sidecar into a zip at `destination`, described by `manifest.json`.
+#[tauri::command]
+pub async fn export_stems(path: String, destination: String) -> Result<(), RecorderError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let destination = Path::new(&destination);
+        write_bundle(Path::new(&path), destination)
+            .inspect_err(|_| {
+                let _ = std::fs::remove_file(destination);
+            })
+            .map_err(RecorderError::IoError)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,107 @@
+use crate::i18n::t;
+use crate::{events, AppState};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThermalLevel {
+    Nominal,
+    Fair,
+    Serious,
+    Critical,
+}
+
+impl ThermalLevel {
+    fn is_degraded(self) -> bool {
+        matches!(self, ThermalLevel::Serious | ThermalLevel::Critical)
+    }
+}
+
+#[derive(Default)]
+pub struct ThermalState {
+    // Shared with the mixer, which skips level metering while set
+    degraded: Arc<AtomicBool>,
+}
+
+impl ThermalState {
+    pub fn degraded_flag(&self) -> Arc<AtomicBool> {
+        self.degraded.clone()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ThermalChanged {
+    level: ThermalLevel,
+    degraded: bool,
+}
+
+fn apply(app: &AppHandle, level: ThermalLevel) {
+    let degraded = level.is_degraded();
+    let was_degraded = app
+        .state::<ThermalState>()
+        .degraded
+        .swap(degraded, Ordering::Relaxed);
+
+    events::emit(
+        app,
+        "thermal-state-changed",
+        &ThermalChanged { level, degraded },
+    );
+
+    if degraded && !was_degraded && app.state::<AppState>().is_recording() {
+        let _ = app
+            .notification()
+            .builder()
+            .title(t("thermal-title"))
+            .body(t("thermal-body"))
+            .show();
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn current_level() -> ThermalLevel {
+    use objc2_foundation::{NSProcessInfo, NSProcessInfoThermalState};
+
+    match NSProcessInfo::processInfo().thermalState() {
+        NSProcessInfoThermalState::Fair => ThermalLevel::Fair,
+        NSProcessInfoThermalState::Serious => ThermalLevel::Serious,
+        NSProcessInfoThermalState::Critical => ThermalLevel::Critical,
+        _ => ThermalLevel::Nominal,
+    }
+}
+
+/// Applies the current thermal state and follows changes to it.
+#[cfg(target_os = "macos")]
+pub fn init(app: &AppHandle) {
+    use block2::RcBlock;
+    use objc2_foundation::{
+        NSNotification, NSNotificationCenter, NSProcessInfoThermalStateDidChangeNotification,
+    };
+    use std::ptr::NonNull;
+
+    apply(app, current_level());
+
+    let app = app.clone();
+    let block = RcBlock::new(move |_: NonNull<NSNotification>| {
+        apply(&app, current_level());
+    });
+    // SAFETY: no object filter, and the block only touches Send + Sync state so
+    // it can run on whichever thread posts the notification
+    let observer = unsafe {
+        NSNotificationCenter::defaultCenter().addObserverForName_object_queue_usingBlock(
+            Some(NSProcessInfoThermalStateDidChangeNotification),
+            None,
+            None,
+            &block,
+        )
+    };
+    // Observes for the lifetime of the app
+    std::mem::forget(observer);
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn init(_app: &AppHandle) {}
@@ -0,0 +1,100 @@
+//! Periodic sync points for the active session so other capture tools (OBS,
+//! a second recorder) can line their media up with ours in post.
+//!
+//! Each tick pairs the number of frames written to the file so far with the
+//! wall clock and the host clock read right after it. Ticks go out as the
+//! `timecode` recorder event and, for tools that only want these, on any
+//! channel registered through `subscribe_timecode`.
+
+use crate::{events, settings, AppState, SAMPLE_RATE};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, State};
+
+// Re-read between ticks so a disabled stream notices when it's switched on
+const IDLE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Timecode {
+    /// Frames written to the recording so far
+    sample_position: u64,
+    sample_rate: u32,
+    /// Milliseconds since the Unix epoch
+    wall_clock_ms: u64,
+    /// Host clock in nanoseconds, `mach_absolute_time` scaled on macOS
+    host_time_ns: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct TimecodeChannels(Mutex<Vec<Channel<Timecode>>>);
+
+/// Uptime clock the rest of the system (CoreAudio, AVFoundation) stamps media with.
+#[cfg(target_os = "macos")]
+fn host_time_ns() -> Option<u64> {
+    read_clock(libc::CLOCK_UPTIME_RAW)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn host_time_ns() -> Option<u64> {
+    read_clock(libc::CLOCK_MONOTONIC)
+}
+
+#[cfg(not(unix))]
+fn host_time_ns() -> Option<u64> {
+    None
+}
+
+#[cfg(unix)]
+fn read_clock(clock: libc::clockid_t) -> Option<u64> {
+    let mut time = std::mem::MaybeUninit::<libc::timespec>::uninit();
+    // SAFETY: clock_gettime fills `time` on success
+    if unsafe { libc::clock_gettime(clock, time.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let time = unsafe { time.assume_init() };
+    Some(time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64)
+}
+
+fn tick(app: &AppHandle) {
+    let Some(sample_position) = app.state::<AppState>().frames_written() else {
+        return;
+    };
+    let wall_clock_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64);
+    let timecode = Timecode {
+        sample_position,
+        sample_rate: SAMPLE_RATE,
+        wall_clock_ms,
+        host_time_ns: host_time_ns(),
+    };
+
+    app.state::<TimecodeChannels>()
+        .0
+        .lock()
+        .retain(|channel| channel.send(timecode.clone()).is_ok());
+    events::emit(app, "timecode", &timecode);
+}
+
+/// Emits a `timecode` tick every `settings.timecode_interval_ms` while recording.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let interval = settings::current(&app).timecode_interval_ms;
+            if interval == 0 {
+                tokio::time::sleep(IDLE_INTERVAL).await;
+                continue;
+            }
+            tokio::time::sleep(Duration::from_millis(interval)).await;
+            tick(&app);
+        }
+    });
+}
+
+/// Delivers timecode ticks to `channel` until the receiving side goes away.
+#[tauri::command]
+pub fn subscribe_timecode(channels: State<'_, TimecodeChannels>, channel: Channel<Timecode>) {
+    channels.0.lock().push(channel);
+}
@@ -0,0 +1,317 @@
+//! Near-real-time transcription of the mic with whisper.cpp. The mixer hands
+//! the processed mic to a `Transcriber`, which downsamples it to the 16 kHz
+//! mono whisper wants and passes it to a worker thread. The worker re-runs
+//! the open utterance every couple of seconds for `transcript-partial`, and
+//! transcribes it once more for `transcript-final` when the VAD says the
+//! speaker stopped. Segment times are in recording time.
+
+use crate::i18n::t;
+use crate::{crypto, events, metadata, settings, SAMPLE_RATE};
+use parking_lot::Mutex;
+use recorder_core::RecorderError;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+const WHISPER_RATE: u32 = 16000;
+const DECIMATION: usize = (SAMPLE_RATE / WHISPER_RATE) as usize;
+// Sent to the worker in 100ms chunks rather than sample by sample
+const CHUNK_LEN: usize = WHISPER_RATE as usize / 10;
+// New audio needed before the open utterance is transcribed again
+const PARTIAL_INTERVAL: usize = 2 * WHISPER_RATE as usize;
+// Whisper's window; longer monologues are cut here
+const MAX_UTTERANCE: usize = 30 * WHISPER_RATE as usize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptSegment {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+enum Message {
+    Audio(Vec<f32>),
+    /// The speaker stopped, finalize what's open
+    UtteranceEnd,
+    /// The recording stopped. The transcript goes next to this file, if given
+    Finish(Option<PathBuf>),
+}
+
+/// Mixer side of a transcription session.
+pub struct Transcriber {
+    sender: Sender<Message>,
+    /// Messages the worker hasn't picked up yet
+    queued: Arc<AtomicUsize>,
+    // Running sum for the 48k to 16k box filter
+    sum: f32,
+    summed: usize,
+    chunk: Vec<f32>,
+}
+
+impl Transcriber {
+    /// Feeds one stereo mic frame at `SAMPLE_RATE`.
+    pub fn push(&mut self, [left, right]: [f32; 2]) {
+        self.sum += (left + right) / 2.0;
+        self.summed += 1;
+        if self.summed == DECIMATION {
+            self.chunk.push(self.sum / DECIMATION as f32);
+            self.sum = 0.0;
+            self.summed = 0;
+        }
+        if self.chunk.len() == CHUNK_LEN {
+            self.flush();
+        }
+    }
+
+    fn send(&self, message: Message) {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        let _ = self.sender.send(message);
+    }
+
+    fn flush(&mut self) {
+        if !self.chunk.is_empty() {
+            let chunk = std::mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_LEN));
+            self.send(Message::Audio(chunk));
+        }
+    }
+
+    pub fn end_utterance(&mut self) {
+        self.flush();
+        self.send(Message::UtteranceEnd);
+    }
+
+    fn finish(mut self, recording: Option<PathBuf>) {
+        self.flush();
+        self.send(Message::Finish(recording));
+    }
+}
+
+struct Worker {
+    app: AppHandle,
+    context: WhisperContext,
+    language: Option<String>,
+    segments: Arc<Mutex<Vec<TranscriptSegment>>>,
+    queued: Arc<AtomicUsize>,
+    /// Audio of the open utterance
+    utterance: Vec<f32>,
+    /// Where it starts, in 16 kHz samples since the recording started
+    utterance_start: u64,
+    /// Length of the utterance at the last partial
+    partial_at: usize,
+}
+
+impl Worker {
+    fn transcribe(&self, single_segment: bool) -> Result<Vec<TranscriptSegment>, String> {
+        let mut state = self.context.create_state().map_err(|e| e.to_string())?;
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some(self.language.as_deref().unwrap_or("auto")));
+        params.set_single_segment(single_segment);
+        params.set_no_context(true);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        state
+            .full(params, &self.utterance)
+            .map_err(|e| e.to_string())?;
+
+        let offset_ms = self.utterance_start * 1000 / WHISPER_RATE as u64;
+        let count = state.full_n_segments().map_err(|e| e.to_string())?;
+        let mut segments = Vec::new();
+        for i in 0..count {
+            let text = state.full_get_segment_text(i).map_err(|e| e.to_string())?;
+            // Whisper counts in 10ms steps
+            let t0 = state.full_get_segment_t0(i).map_err(|e| e.to_string())?;
+            let t1 = state.full_get_segment_t1(i).map_err(|e| e.to_string())?;
+            let text = text.trim();
+            if !text.is_empty() {
+                segments.push(TranscriptSegment {
+                    start_ms: offset_ms + t0.max(0) as u64 * 10,
+                    end_ms: offset_ms + t1.max(0) as u64 * 10,
+                    text: text.to_string(),
+                });
+            }
+        }
+        Ok(segments)
+    }
+
+    fn partial(&mut self) {
+        self.partial_at = self.utterance.len();
+        match self.transcribe(true) {
+            Ok(segments) => {
+                if let Some(segment) = segments.into_iter().next() {
+                    events::emit(&self.app, "transcript-partial", &segment);
+                }
+            }
+            Err(e) => eprintln!("Partial transcription failed: {}", e),
+        }
+    }
+
+    fn finalize(&mut self) {
+        if !self.utterance.is_empty() {
+            match self.transcribe(false) {
+                Ok(segments) => {
+                    for segment in &segments {
+                        events::emit(&self.app, "transcript-final", segment);
+                    }
+                    self.segments.lock().extend(segments);
+                }
+                Err(e) => eprintln!("Transcription failed: {}", e),
+            }
+        }
+        self.utterance_start += self.utterance.len() as u64;
+        self.utterance.clear();
+        self.partial_at = 0;
+    }
+
+    fn run(mut self, receiver: Receiver<Message>) {
+        while let Ok(message) = receiver.recv() {
+            self.queued.fetch_sub(1, Ordering::Relaxed);
+            match message {
+                Message::Audio(chunk) => {
+                    self.utterance.extend(chunk);
+                    if self.utterance.len() >= MAX_UTTERANCE {
+                        self.finalize();
+                    } else if self.utterance.len() - self.partial_at >= PARTIAL_INTERVAL
+                        // Skip partials while behind, the final catches up
+                        && self.queued.load(Ordering::Relaxed) == 0
+                    {
+                        self.partial();
+                    }
+                }
+                Message::UtteranceEnd => self.finalize(),
+                Message::Finish(recording) => {
+                    self.finalize();
+                    if let Some(recording) = recording {
+                        save(&recording, &self.segments.lock());
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn timestamp(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        ms % 1000
+    )
+}
+
+/// Writes `<name>.transcript.txt` next to the recording and links it in the
+/// sidecar. Skipped for encrypted recordings, which would otherwise leave
+/// their content readable next to them.
+fn save(recording: &Path, segments: &[TranscriptSegment]) {
+    if segments.is_empty() || crypto::is_encrypted(recording) {
+        return;
+    }
+    let path = recording.with_extension("transcript.txt");
+    let text: String = segments
+        .iter()
+        .map(|segment| {
+            format!(
+                "[{} --> {}] {}\n",
+                timestamp(segment.start_ms),
+                timestamp(segment.end_ms),
+                segment.text
+            )
+        })
+        .collect();
+    let saved = std::fs::write(&path, text)
+        .map_err(|e| e.to_string())
+        .and_then(|_| metadata::update(recording, |metadata| metadata.transcript = Some(path)));
+    if let Err(e) = saved {
+        eprintln!("Failed to save the transcript: {}", e);
+    }
+}
+
+#[derive(Default)]
+pub struct TranscriptionState {
+    /// Handed to the mixer; `None` while not transcribing
+    transcriber: Arc<Mutex<Option<Transcriber>>>,
+    /// Final segments of the current or last session
+    segments: Arc<Mutex<Vec<TranscriptSegment>>>,
+}
+
+impl TranscriptionState {
+    pub fn shared(&self) -> Arc<Mutex<Option<Transcriber>>> {
+        self.transcriber.clone()
+    }
+}
+
+/// Ends the session when its recording stops, saving the transcript next to
+/// `recording` once the last utterance is through.
+pub fn finish(app: &AppHandle, recording: Option<PathBuf>) {
+    let state = app.state::<TranscriptionState>();
+    if let Some(transcriber) = state.transcriber.lock().take() {
+        transcriber.finish(recording);
+    }
+}
+
+/// Starts transcribing the running recording's mic with the model set in
+/// the settings.
+#[tauri::command]
+pub async fn start_transcription(
+    app: AppHandle,
+    state: State<'_, TranscriptionState>,
+    recorder: State<'_, crate::AppState>,
+) -> Result<(), RecorderError> {
+    if !recorder.is_recording() {
+        return Err(RecorderError::NotRecording);
+    }
+    if state.transcriber.lock().is_some() {
+        return Err(RecorderError::Busy(t("error-already-transcribing")));
+    }
+    let settings = settings::current(&app);
+    let model = settings
+        .transcription_model
+        .ok_or_else(|| RecorderError::InvalidArgument(t("error-no-transcription-model")))?;
+
+    // Loading a model takes a moment, keep it off the async runtime
+    let context = tauri::async_runtime::spawn_blocking(move || {
+        WhisperContext::new_with_params(&model, WhisperContextParameters::default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+
+    let (sender, receiver) = mpsc::channel();
+    let queued = Arc::new(AtomicUsize::new(0));
+    state.segments.lock().clear();
+    let worker = Worker {
+        app: app.clone(),
+        context,
+        language: settings.transcription_language,
+        segments: state.segments.clone(),
+        queued: queued.clone(),
+        utterance: Vec::new(),
+        // Lines up with the recording, even when started part way through
+        utterance_start: recorder.frames_written().unwrap_or(0) / DECIMATION as u64,
+        partial_at: 0,
+    };
+    std::thread::spawn(move || worker.run(receiver));
+
+    *state.transcriber.lock() = Some(Transcriber {
+        sender,
+        queued,
+        sum: 0.0,
+        summed: 0,
+        chunk: Vec::with_capacity(CHUNK_LEN),
+    });
+    Ok(())
+}
+
+/// Final segments so far, of the running session or the last one.
+#[tauri::command]
+pub fn get_transcript(state: State<'_, TranscriptionState>) -> Vec<TranscriptSegment> {
+    state.segments.lock().clone()
+}
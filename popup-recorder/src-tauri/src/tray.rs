@@ -0,0 +1,386 @@
+use crate::i18n::{t, tr};
+use crate::{
+    presets, recordings, settings, show_and_start, stop_and_hide, updater, AppState, SAMPLE_RATE,
+};
+use recorder_core::tray_icon::{self, ICON_SIZE};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu, SubmenuBuilder};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_opener::OpenerExt;
+
+// Per-recording item ids are `<action><path>`
+const OPEN_PREFIX: &str = "recording-open:";
+const REVEAL_PREFIX: &str = "recording-reveal:";
+const DELETE_PREFIX: &str = "recording-delete:";
+const COPY_PATH_PREFIX: &str = "recording-copy-path:";
+// `<prefix><preset name>`, nothing after the prefix is whole-display capture
+const PRESET_PREFIX: &str = "preset:";
+
+const TRAY_ID: &str = "main";
+const PULSE_FRAMES: usize = 8;
+const PULSE_INTERVAL: Duration = Duration::from_millis(120);
+
+pub struct TrayMenu {
+    start_item: MenuItem<Wry>,
+    stop_item: MenuItem<Wry>,
+    check_item: MenuItem<Wry>,
+    update_item: MenuItem<Wry>,
+    recordings_menu: Submenu<Wry>,
+    presets_menu: Submenu<Wry>,
+    quit_item: PredefinedMenuItem<Wry>,
+    idle_icon: Image<'static>,
+    pulse_frames: Vec<Image<'static>>,
+    // Bumped on every state change so a running animation or timer knows to stop
+    animation_generation: AtomicU64,
+}
+
+fn render_pulse_frames() -> Vec<Image<'static>> {
+    (0..PULSE_FRAMES)
+        .map(|i| {
+            let phase = i as f32 / PULSE_FRAMES as f32 * std::f32::consts::TAU;
+            let scale = 0.6 + 0.4 * (0.5 - 0.5 * phase.cos());
+            Image::new_owned(tray_icon::recording_dot(scale), ICON_SIZE, ICON_SIZE)
+        })
+        .collect()
+}
+
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let start_item = MenuItem::with_id(
+        app,
+        "start-recording",
+        t("tray-start-recording"),
+        true,
+        None::<&str>,
+    )?;
+    let stop_item = MenuItem::with_id(
+        app,
+        "stop-recording",
+        t("tray-stop-recording"),
+        false,
+        None::<&str>,
+    )?;
+    let recording_separator = PredefinedMenuItem::separator(app)?;
+    let check_item = MenuItem::with_id(
+        app,
+        "check-updates",
+        t("tray-check-updates"),
+        true,
+        None::<&str>,
+    )?;
+    let update_item = MenuItem::with_id(
+        app,
+        "install-update",
+        t("tray-no-update"),
+        false,
+        None::<&str>,
+    )?;
+    let recordings_menu = Submenu::with_id(app, "recordings", t("tray-recordings"), true)?;
+    let presets_menu = Submenu::with_id(app, "presets", t("tray-presets"), true)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let updates_separator = PredefinedMenuItem::separator(app)?;
+    let quit_item = PredefinedMenuItem::quit(app, Some(t("tray-quit").as_str()))?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &start_item,
+            &stop_item,
+            &recording_separator,
+            &recordings_menu,
+            &presets_menu,
+            &updates_separator,
+            &check_item,
+            &update_item,
+            &separator,
+            &quit_item,
+        ],
+    )?;
+
+    let idle_icon = app.default_window_icon().unwrap().clone();
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(idle_icon.clone())
+        .icon_as_template(true)
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "start-recording" => show_and_start(app),
+            "stop-recording" => stop_and_hide(app),
+            "check-updates" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = updater::check(&app).await {
+                        eprintln!("Update check failed: {}", e);
+                    }
+                });
+            }
+            "install-update" => {
+                if !updater::install_when_idle(app) {
+                    if let Some(menu) = app.try_state::<TrayMenu>() {
+                        let _ = menu.update_item.set_text(t("tray-update-deferred"));
+                    }
+                }
+            }
+            id => handle_recording_action(app, id),
+        })
+        .build(app)?;
+
+    app.manage(TrayMenu {
+        start_item,
+        stop_item,
+        check_item,
+        update_item,
+        recordings_menu,
+        presets_menu,
+        quit_item,
+        idle_icon,
+        pulse_frames: render_pulse_frames(),
+        animation_generation: AtomicU64::new(0),
+    });
+    refresh_recordings(app);
+    refresh_presets(app);
+    Ok(())
+}
+
+fn handle_recording_action(app: &AppHandle, id: &str) {
+    let result = if let Some(path) = id.strip_prefix(OPEN_PREFIX) {
+        app.opener()
+            .open_path(path, None::<&str>)
+            .map_err(|e| e.to_string())
+    } else if let Some(path) = id.strip_prefix(REVEAL_PREFIX) {
+        app.opener()
+            .reveal_item_in_dir(path)
+            .map_err(|e| e.to_string())
+    } else if let Some(path) = id.strip_prefix(DELETE_PREFIX) {
+        let result = recordings::delete(Path::new(path));
+        refresh_recordings(app);
+        result
+    } else if let Some(path) = id.strip_prefix(COPY_PATH_PREFIX) {
+        app.clipboard().write_text(path).map_err(|e| e.to_string())
+    } else if let Some(name) = id.strip_prefix(PRESET_PREFIX) {
+        let name = (!name.is_empty()).then(|| name.to_string());
+        presets::select(app, name).map_err(|e| e.to_string())
+    } else {
+        return;
+    };
+
+    if let Err(e) = result {
+        eprintln!("Tray action {} failed: {}", id, e);
+    }
+}
+
+fn recording_submenu(app: &AppHandle, path: &Path) -> tauri::Result<Submenu<Wry>> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let path = path.to_string_lossy();
+
+    SubmenuBuilder::new(app, name)
+        .text(format!("{}{}", OPEN_PREFIX, path), t("tray-open"))
+        .text(format!("{}{}", REVEAL_PREFIX, path), t("tray-reveal"))
+        .text(format!("{}{}", COPY_PATH_PREFIX, path), t("tray-copy-path"))
+        .separator()
+        .text(format!("{}{}", DELETE_PREFIX, path), t("tray-delete"))
+        .build()
+}
+
+/// Rebuilds the recordings submenu from what's on disk.
+pub fn refresh_recordings(app: &AppHandle) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    if let Err(e) = rebuild_recordings(app, &menu.recordings_menu) {
+        eprintln!("Failed to rebuild recordings menu: {}", e);
+    }
+}
+
+fn rebuild_recordings(app: &AppHandle, submenu: &Submenu<Wry>) -> tauri::Result<()> {
+    for item in submenu.items()? {
+        submenu.remove(&item)?;
+    }
+
+    let recordings = recordings::list(app);
+    if recordings.is_empty() {
+        let empty = MenuItem::new(app, t("tray-no-recordings"), false, None::<&str>)?;
+        submenu.append(&empty)?;
+    }
+    for path in recordings {
+        submenu.append(&recording_submenu(app, &path)?)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the presets submenu, checking the active one.
+pub fn refresh_presets(app: &AppHandle) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    if let Err(e) = rebuild_presets(app, &menu.presets_menu) {
+        eprintln!("Failed to rebuild presets menu: {}", e);
+    }
+}
+
+fn rebuild_presets(app: &AppHandle, submenu: &Submenu<Wry>) -> tauri::Result<()> {
+    for item in submenu.items()? {
+        submenu.remove(&item)?;
+    }
+
+    let settings = settings::current(app);
+    let active = settings.active_preset.as_deref();
+    let whole_display = CheckMenuItem::with_id(
+        app,
+        PRESET_PREFIX,
+        t("tray-preset-whole-display"),
+        true,
+        active.is_none(),
+        None::<&str>,
+    )?;
+    submenu.append(&whole_display)?;
+    for preset in &settings.presets {
+        let item = CheckMenuItem::with_id(
+            app,
+            format!("{}{}", PRESET_PREFIX, preset.name),
+            &preset.name,
+            true,
+            active == Some(preset.name.as_str()),
+            None::<&str>,
+        )?;
+        submenu.append(&item)?;
+    }
+    Ok(())
+}
+
+pub fn set_update_available(app: &AppHandle, version: Option<&str>) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    match version {
+        Some(version) => {
+            let _ = menu.update_item.set_text(tr(
+                "tray-install-update",
+                &[("version", version.to_string())],
+            ));
+            let _ = menu.update_item.set_enabled(true);
+        }
+        None => {
+            let _ = menu.update_item.set_text(t("tray-no-update"));
+            let _ = menu.update_item.set_enabled(false);
+        }
+    }
+}
+
+/// Re-applies every label after the locale changed.
+pub fn relabel(app: &AppHandle) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let _ = menu.start_item.set_text(t("tray-start-recording"));
+    let _ = menu.stop_item.set_text(t("tray-stop-recording"));
+    let _ = menu.check_item.set_text(t("tray-check-updates"));
+    let _ = menu.recordings_menu.set_text(t("tray-recordings"));
+    let _ = menu.presets_menu.set_text(t("tray-presets"));
+    let _ = menu.quit_item.set_text(t("tray-quit"));
+    set_update_available(app, updater::pending_version(app).as_deref());
+    set_tooltip(app, app.state::<AppState>().is_recording());
+    refresh_recordings(app);
+    refresh_presets(app);
+}
+
+fn set_tooltip(app: &AppHandle, recording: bool) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let tooltip = if recording {
+            t("tray-tooltip-recording")
+        } else {
+            t("tray-tooltip")
+        };
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
+fn set_recording_icon(app: &AppHandle, recording: bool) {
+    let (Some(menu), Some(tray)) = (app.try_state::<TrayMenu>(), app.tray_by_id(TRAY_ID)) else {
+        return;
+    };
+    let generation = menu.animation_generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    if !recording {
+        let _ = tray.set_icon(Some(menu.idle_icon.clone()));
+        let _ = tray.set_icon_as_template(true);
+        return;
+    }
+
+    if !settings::current(app).animate_tray_icon {
+        let _ = tray.set_icon(Some(menu.pulse_frames[PULSE_FRAMES / 2].clone()));
+        let _ = tray.set_icon_as_template(false);
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(PULSE_INTERVAL);
+        let mut frame = 0;
+        loop {
+            interval.tick().await;
+            let menu = app.state::<TrayMenu>();
+            if menu.animation_generation.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            let _ = tray.set_icon(Some(menu.pulse_frames[frame].clone()));
+            let _ = tray.set_icon_as_template(false);
+            frame = (frame + 1) % PULSE_FRAMES;
+        }
+    });
+}
+
+fn format_elapsed(secs: u64) -> String {
+    if secs < 3600 {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    } else {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    }
+}
+
+/// Shows how much has been recorded next to the icon, updated every second
+/// until the recording state changes again.
+fn show_elapsed(app: &AppHandle, recording: bool) {
+    let (Some(menu), Some(tray)) = (app.try_state::<TrayMenu>(), app.tray_by_id(TRAY_ID)) else {
+        return;
+    };
+    if !recording {
+        let _ = tray.set_title(None::<&str>);
+        return;
+    }
+
+    let generation = menu.animation_generation.load(Ordering::SeqCst);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            let menu = app.state::<TrayMenu>();
+            if menu.animation_generation.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            // Time in the file, so an auto-paused silence doesn't count
+            let frames = app.state::<AppState>().frames_written().unwrap_or(0);
+            let _ = tray.set_title(Some(format_elapsed(frames / SAMPLE_RATE as u64)));
+        }
+    });
+}
+
+pub fn set_recording(app: &AppHandle, recording: bool) {
+    set_recording_icon(app, recording);
+    show_elapsed(app, recording);
+    set_tooltip(app, recording);
+    if let Some(menu) = app.try_state::<TrayMenu>() {
+        let _ = menu.start_item.set_enabled(!recording);
+        let _ = menu.stop_item.set_enabled(recording);
+    }
+    if !recording {
+        refresh_recordings(app);
+    }
+}
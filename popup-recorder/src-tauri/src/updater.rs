@@ -0,0 +1,39 @@
+use crate::i18n::t;
+use crate::{events, settings, tray, AppState};
+use recorder_core::RecorderError;
+use tauri::{AppHandle, Manager};
+
+pub use recorder_updater::{
+    pending_version, plugin, resume_deferred_install, PendingUpdate, UpdateChannel,
+};
+
+/// Checks the configured channel, remembers the update and surfaces it in the tray.
+pub async fn check(app: &AppHandle) -> Result<Option<String>, String> {
+    let channel = settings::current(app).update_channel;
+    let update = recorder_updater::check(app, "popup-recorder", channel).await?;
+    if let Some(update) = &update {
+        events::emit(app, "update-available", update);
+    }
+    let version = update.map(|update| update.version);
+    tray::set_update_available(app, version.as_deref());
+    Ok(version)
+}
+
+/// Installs the pending update now, or once the current recording stops.
+/// Returns whether the install started right away.
+pub fn install_when_idle(app: &AppHandle) -> bool {
+    recorder_updater::install_when_idle(app, app.state::<AppState>().is_recording())
+}
+
+#[tauri::command]
+pub async fn check_for_update(app: AppHandle) -> Result<Option<String>, RecorderError> {
+    Ok(check(&app).await?)
+}
+
+#[tauri::command]
+pub fn install_update(app: AppHandle) -> Result<bool, RecorderError> {
+    if pending_version(&app).is_none() {
+        return Err(RecorderError::NotFound(t("error-no-update")));
+    }
+    Ok(install_when_idle(&app))
+}
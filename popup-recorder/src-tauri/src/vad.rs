@@ -0,0 +1,146 @@
+//! Voice activity detection on the mic, after its DSP. Energy over 10ms
+//! frames is compared against an adaptive noise floor; a few loud frames in
+//! a row start an utterance and a longer quiet stretch ends it. Announced as
+//! `speech-started` and `speech-stopped`, with positions in recording time,
+//! so the overlay can show who's talking and transcription can split on them.
+
+use crate::{events, SAMPLE_RATE};
+use serde::Serialize;
+use tauri::AppHandle;
+
+const FRAME_LEN: usize = SAMPLE_RATE as usize / 100;
+// About 10 dB over the noise floor counts as speech
+const SPEECH_RATIO: f32 = 3.0;
+// Roughly -54 dBFS, quieter than this is never speech however still the room is
+const MIN_SPEECH_LEVEL: f32 = 0.002;
+const FLOOR_FALL: f32 = 0.2;
+const FLOOR_RISE: f32 = 0.005;
+// 30ms of speech to start, 300ms of quiet to stop, so single clicks and
+// the gaps between words don't toggle it
+const ONSET_FRAMES: u32 = 3;
+const HANGOVER_FRAMES: u32 = 30;
+
+#[derive(Debug, Clone, Serialize)]
+struct SpeechEvent {
+    /// Where the utterance started or ended, in recording time
+    at_ms: u64,
+    /// Length of the utterance, only set when it ends
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+}
+
+fn frames_to_ms(frames: u64) -> u64 {
+    frames * 1000 / SAMPLE_RATE as u64
+}
+
+pub struct VoiceActivity {
+    /// Sum of squares of the frame being filled
+    energy: f32,
+    filled: usize,
+    /// Samples seen so far
+    position: u64,
+    floor: Option<f32>,
+    speaking: bool,
+    /// Loud frames in a row while quiet, quiet frames in a row while speaking
+    run: u32,
+    started_at: u64,
+    last_speech: u64,
+}
+
+impl VoiceActivity {
+    pub fn new() -> Self {
+        Self {
+            energy: 0.0,
+            filled: 0,
+            position: 0,
+            floor: None,
+            speaking: false,
+            run: 0,
+            started_at: 0,
+            last_speech: 0,
+        }
+    }
+
+    /// Feeds one stereo mic frame. True when that closed an utterance.
+    pub fn push(&mut self, app: &AppHandle, [left, right]: [f32; 2]) -> bool {
+        let sample = (left + right) / 2.0;
+        self.energy += sample * sample;
+        self.filled += 1;
+        self.position += 1;
+        if self.filled == FRAME_LEN {
+            let rms = (self.energy / FRAME_LEN as f32).sqrt();
+            self.energy = 0.0;
+            self.filled = 0;
+            return self.frame(app, rms);
+        }
+        false
+    }
+
+    fn frame(&mut self, app: &AppHandle, rms: f32) -> bool {
+        let floor = self.floor.get_or_insert(rms);
+        let speech = rms >= MIN_SPEECH_LEVEL && rms >= *floor * SPEECH_RATIO;
+        // Drops quickly and rises slowly, so talking barely moves it but a
+        // fan switching on is learned within a few seconds
+        let rate = if rms < *floor { FLOOR_FALL } else { FLOOR_RISE };
+        *floor += (rms - *floor) * rate;
+
+        let frame_start = self.position - FRAME_LEN as u64;
+        if self.speaking {
+            if speech {
+                self.run = 0;
+                self.last_speech = self.position;
+            } else {
+                self.run += 1;
+                if self.run == HANGOVER_FRAMES {
+                    self.speaking = false;
+                    self.run = 0;
+                    events::emit(
+                        app,
+                        "speech-stopped",
+                        SpeechEvent {
+                            at_ms: frames_to_ms(self.last_speech),
+                            duration_ms: Some(frames_to_ms(self.last_speech - self.started_at)),
+                        },
+                    );
+                    return true;
+                }
+            }
+        } else if speech {
+            self.run += 1;
+            if self.run == 1 {
+                self.started_at = frame_start;
+            }
+            if self.run == ONSET_FRAMES {
+                self.speaking = true;
+                self.run = 0;
+                self.last_speech = self.position;
+                events::emit(
+                    app,
+                    "speech-started",
+                    SpeechEvent {
+                        at_ms: frames_to_ms(self.started_at),
+                        duration_ms: None,
+                    },
+                );
+            }
+        } else {
+            self.run = 0;
+        }
+        false
+    }
+
+    /// Closes an utterance still open when the recording stops.
+    pub fn finish(&mut self, app: &AppHandle) {
+        if self.speaking {
+            self.speaking = false;
+            events::emit(
+                app,
+                "speech-stopped",
+                SpeechEvent {
+                    at_ms: frames_to_ms(self.position),
+                    duration_ms: Some(frames_to_ms(self.position - self.started_at)),
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::i18n::t;
+use recorder_core::RecorderError;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+pub const VISUALIZER_LABEL: &str = "visualizer";
+
+/// Opens (or focuses) a standalone meter window that subscribes to the event
+/// channel on its own, so it keeps running while the popup is hidden.
+#[tauri::command]
+pub async fn open_visualizer_window(app: AppHandle) -> Result<(), RecorderError> {
+    if let Some(window) = app.get_webview_window(VISUALIZER_LABEL) {
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    WebviewWindowBuilder::new(
+        &app,
+        VISUALIZER_LABEL,
+        WebviewUrl::App("/?visualizer=true".into()),
+    )
+    .title(t("visualizer-title"))
+    .inner_size(800.0, 300.0)
+    .min_inner_size(320.0, 120.0)
+    .resizable(true)
+    .always_on_top(true)
+    .build()
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use crate::crypto;
+use recorder_core::{read_samples, RecorderError};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Min/max peak pairs, one per bucket, over all channels.
+pub fn extract(path: &Path, resolution: usize) -> Result<Vec<[f32; 2]>, String> {
+    let reader = crypto::read_wav(path)?;
+    let channels = reader.spec().channels.max(1) as u64;
+    let total_frames = reader.duration() as u64;
+    let resolution = resolution.max(1);
+    let frames_per_bucket = total_frames.div_ceil(resolution as u64).max(1);
+
+    let mut peaks = Vec::with_capacity(resolution);
+    let mut min = f32::MAX;
+    let mut max = f32::MIN;
+    let mut samples_in_bucket = 0u64;
+
+    for sample in read_samples(reader) {
+        let s = sample.map_err(|e| e.to_string())?;
+        min = min.min(s);
+        max = max.max(s);
+        samples_in_bucket += 1;
+
+        if samples_in_bucket == frames_per_bucket * channels {
+            peaks.push([min, max]);
+            min = f32::MAX;
+            max = f32::MIN;
+            samples_in_bucket = 0;
+        }
+    }
+    if samples_in_bucket > 0 {
+        peaks.push([min, max]);
+    }
+
+    Ok(peaks)
+}
+
+// Keyed on path, size and mtime so a re-recorded file doesn't hit a stale entry
+fn cache_path(app: &AppHandle, path: &Path, resolution: usize) -> Option<PathBuf> {
+    let meta = std::fs::metadata(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    meta.len().hash(&mut hasher);
+    meta.modified().ok()?.hash(&mut hasher);
+
+    let dir = app.path().app_cache_dir().ok()?.join("waveforms");
+    Some(dir.join(format!("{:016x}-{}.json", hasher.finish(), resolution)))
+}
+
+#[tauri::command]
+pub async fn get_waveform(
+    app: AppHandle,
+    path: String,
+    resolution: usize,
+) -> Result<Vec<[f32; 2]>, RecorderError> {
+    let path = PathBuf::from(path);
+    let cache = cache_path(&app, &path, resolution);
+
+    if let Some(peaks) = cache
+        .as_ref()
+        .and_then(|cache| std::fs::read_to_string(cache).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+    {
+        return Ok(peaks);
+    }
+
+    let peaks = tauri::async_runtime::spawn_blocking(move || extract(&path, resolution))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    if let Some(cache) = cache {
+        if let Some(dir) = cache.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Ok(json) = serde_json::to_string(&peaks) {
+            let _ = std::fs::write(cache, json);
+        }
+    }
+
+    Ok(peaks)
+}
@@ -0,0 +1,87 @@
+//! Lifecycle webhooks. Each configured endpoint gets a JSON POST on
+//! `recording-started`, `recording-stopped` and `recording-error`, retried
+//! with backoff. Endpoints with a secret get an
+//! `X-Popup-Recorder-Signature: sha256=<hex>` HMAC of the body.
+
+use crate::settings;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+const ATTEMPTS: u32 = 4;
+const FIRST_RETRY: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const SIGNATURE_HEADER: &str = "X-Popup-Recorder-Signature";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// HMAC-SHA256 key, `None` sends unsigned requests
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+async fn deliver(client: &reqwest::Client, webhook: &Webhook, body: &[u8]) -> Result<(), String> {
+    let mut delay = FIRST_RETRY;
+    let mut last_error = String::new();
+    for attempt in 1..=ATTEMPTS {
+        let mut request = client
+            .post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .body(body.to_vec());
+        if let Some(secret) = &webhook.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, body));
+        }
+        match request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = e.to_string(),
+        }
+        if attempt < ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+    Err(last_error)
+}
+
+/// Sends `event` to every configured webhook in the background.
+pub fn dispatch(app: &AppHandle, event: &str, data: Value) {
+    let webhooks = settings::current(app).webhooks;
+    if webhooks.is_empty() {
+        return;
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+    let body = json!({ "event": event, "timestamp": timestamp, "data": data }).to_string();
+
+    tauri::async_runtime::spawn(async move {
+        let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                eprintln!("Webhook client failed: {}", e);
+                return;
+            }
+        };
+        for webhook in &webhooks {
+            if let Err(e) = deliver(&client, webhook, body.as_bytes()).await {
+                eprintln!("Webhook {} failed: {}", webhook.url, e);
+            }
+        }
+    });
+}
@@ -0,0 +1,144 @@
+//! Capture scoped to a window picked by title pattern ("*YouTube*", "*Meet*").
+//!
+//! Browsers recreate windows when tabs are dragged around, so while recording
+//! the pattern is re-resolved and the stream's filter follows the new window.
+//! `capture-window-lost` goes out when nothing matches anymore.
+
+use crate::{events, AppState};
+use parking_lot::Mutex;
+use screencapturekit::prelude::*;
+use serde_json::json;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+struct Target {
+    pattern: String,
+    window_id: u32,
+    lost: bool,
+}
+
+/// The window the running session follows, if it was started with a pattern.
+#[derive(Default)]
+pub struct WindowTarget(Mutex<Option<Target>>);
+
+/// Case-insensitive glob match, `*` for any run of characters and `?` for one.
+fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was and how much text it has swallowed so far
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, swallowed)) => {
+                    backtrack = Some((star, swallowed + 1));
+                    p = star + 1;
+                    t = swallowed + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// First on-screen window whose title matches `pattern`.
+pub fn resolve(content: &SCShareableContent, pattern: &str) -> Option<SCWindow> {
+    content.windows().into_iter().find(|window| {
+        window.is_on_screen() && window.title().is_some_and(|title| matches(pattern, &title))
+    })
+}
+
+pub fn filter(window: &SCWindow) -> SCContentFilter {
+    SCContentFilter::create().with_window(window).build()
+}
+
+/// Follows `window` for the session about to start, or stops following
+/// anything when `None`.
+pub fn track(app: &AppHandle, target: Option<(String, &SCWindow)>) {
+    *app.state::<WindowTarget>().0.lock() = target.map(|(pattern, window)| Target {
+        pattern,
+        window_id: window.window_id(),
+        lost: false,
+    });
+}
+
+fn check(app: &AppHandle) {
+    let Some(pattern) = app
+        .state::<WindowTarget>()
+        .0
+        .lock()
+        .as_ref()
+        .map(|target| target.pattern.clone())
+    else {
+        return;
+    };
+    if !app.state::<AppState>().is_recording() {
+        return;
+    }
+    let Ok(content) = SCShareableContent::get() else {
+        return;
+    };
+    let window = resolve(&content, &pattern);
+
+    let state = app.state::<WindowTarget>();
+    let mut target = state.0.lock();
+    let Some(target) = target.as_mut() else {
+        return;
+    };
+    match window {
+        None if !target.lost => {
+            target.lost = true;
+            events::emit(
+                app,
+                "capture-window-lost",
+                json!({ "pattern": target.pattern, "window_id": target.window_id }),
+            );
+        }
+        Some(window) if target.lost || window.window_id() != target.window_id => {
+            let recorder = app.state::<AppState>();
+            let recorder = recorder.0.lock();
+            let Some(source) = recorder.system_source.as_ref() else {
+                return;
+            };
+            if let Err(e) = source.lock().update_content_filter(&filter(&window)) {
+                eprintln!("Failed to follow window {}: {}", window.window_id(), e);
+                return;
+            }
+            target.window_id = window.window_id();
+            target.lost = false;
+            events::emit(
+                app,
+                "capture-window-changed",
+                json!({
+                    "pattern": target.pattern,
+                    "window_id": target.window_id,
+                    "title": window.title(),
+                }),
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Re-resolves the followed window while recording.
+pub fn spawn_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            check(&app);
+        }
+    });
+}
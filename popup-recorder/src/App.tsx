@@ -1,45 +1,136 @@
-import { useState, useEffect } from "react";
-import { listen } from "@tauri-apps/api/event";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import "./App.css";
 import WaveformVisualization from "./WaveformVisualization";
+import { listenRecorder } from "./events";
 
 function App() {
   const [isRecording, setIsRecording] = useState(false);
+  const [collapsed, setCollapsed] = useState(false);
+  const [idlePrompt, setIdlePrompt] = useState(false);
+  const [reducedPower, setReducedPower] = useState(false);
+  const [thermalDegraded, setThermalDegraded] = useState(false);
+  const lastInteraction = useRef(0);
 
   useEffect(() => {
-    const promise = listen<{ recording: boolean }>("recording-state", (e) => {
-      setIsRecording(e.payload.recording);
+    const promise = listenRecorder<{ recording: boolean }>("recording-state", (payload) => {
+      setIsRecording(payload.recording);
     });
     return () => {
       promise.then((fn) => fn());
     };
   }, []);
 
+  useEffect(() => {
+    const promise = listenRecorder<{ collapsed: boolean }>("overlay-auto-hide", (payload) => {
+      setCollapsed(payload.collapsed);
+    });
+    return () => {
+      promise.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const promise = listenRecorder("idle-reminder", () => {
+      setIdlePrompt(true);
+      setCollapsed(false);
+    });
+    return () => {
+      promise.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    invoke<{ reduced: boolean }>("get_power_mode")
+      .then((mode) => setReducedPower(mode.reduced))
+      .catch(console.error);
+    const promise = listenRecorder<{ reduced: boolean }>("power-mode-changed", (payload) => {
+      setReducedPower(payload.reduced);
+    });
+    return () => {
+      promise.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    const promise = listenRecorder<{ degraded: boolean }>("thermal-state-changed", (payload) => {
+      setThermalDegraded(payload.degraded);
+    });
+    return () => {
+      promise.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!isRecording) setIdlePrompt(false);
+  }, [isRecording]);
+
+  const respondIdle = (keep: boolean) => {
+    setIdlePrompt(false);
+    invoke("respond_idle_reminder", { keep }).catch(console.error);
+  };
+
+  // Keep the auto-hide timer from firing while the pointer is on the overlay
+  const handlePointerMove = () => {
+    const now = Date.now();
+    if (now - lastInteraction.current < 500) return;
+    lastInteraction.current = now;
+    invoke("overlay_interaction").catch(console.error);
+  };
+
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
       if (e.key === "Escape") {
         invoke("cancel_recording").catch(console.error);
+      } else if (e.key === "m" && !e.repeat) {
+        invoke("add_marker").catch(console.error);
       }
     };
     document.addEventListener("keydown", handleKeyDown);
     return () => document.removeEventListener("keydown", handleKeyDown);
   }, []);
 
+  if (collapsed) {
+    return (
+      <div className="container" onPointerMove={handlePointerMove}>
+        <div className="edge-tab" />
+      </div>
+    );
+  }
+
   return (
-    <div className="container" data-tauri-drag-region>
+    <div className="container" data-tauri-drag-region onPointerMove={handlePointerMove}>
       <div className="pill" data-tauri-drag-region>
         <div className="waveform-area" data-tauri-drag-region>
           <WaveformVisualization isRecording={isRecording} />
         </div>
         <div className="controls">
-          <div className="controls-right">
-            <span className="control-label">Stop</span>
-            <kbd className="key">⇧⌘</kbd>
-            <kbd className="key key-accent">R</kbd>
-            <span className="control-label cancel-label">Cancel</span>
-            <kbd className="key">esc</kbd>
-          </div>
+          {thermalDegraded ? (
+            <span className="power-badge">Running hot, levels paused</span>
+          ) : (
+            reducedPower && <span className="power-badge">Battery saver</span>
+          )}
+          {idlePrompt ? (
+            <div className="controls-right idle-prompt">
+              <span className="control-label">Still recording?</span>
+              <button className="prompt-button" onClick={() => respondIdle(true)}>
+                Keep
+              </button>
+              <button className="prompt-button prompt-accent" onClick={() => respondIdle(false)}>
+                Stop
+              </button>
+            </div>
+          ) : (
+            <div className="controls-right">
+              <span className="control-label">Stop</span>
+              <kbd className="key">⇧⌘</kbd>
+              <kbd className="key key-accent">R</kbd>
+              <span className="control-label cancel-label">Mark</span>
+              <kbd className="key">M</kbd>
+              <span className="control-label cancel-label">Cancel</span>
+              <kbd className="key">esc</kbd>
+            </div>
+          )}
         </div>
       </div>
     </div>
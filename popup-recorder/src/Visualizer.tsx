@@ -0,0 +1,60 @@
+import { useEffect, useState } from "react";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import { RecorderEvent } from "./events";
+import "./Visualizer.css";
+
+interface AudioLevels {
+  mic_level: number;
+  system_level: number;
+  mixed_level: number;
+}
+
+const SILENT: AudioLevels = { mic_level: 0, system_level: 0, mixed_level: 0 };
+
+// Map RMS to a 0..1 meter position over a -60..0 dBFS range
+function meterPosition(rms: number): number {
+  if (rms <= 0) return 0;
+  const db = 20 * Math.log10(rms);
+  return Math.min(Math.max((db + 60) / 60, 0), 1);
+}
+
+function Meter({ label, level }: { label: string; level: number }) {
+  return (
+    <div className="meter">
+      <span className="meter-label">{label}</span>
+      <div className="meter-track">
+        <div className="meter-fill" style={{ width: `${meterPosition(level) * 100}%` }} />
+      </div>
+    </div>
+  );
+}
+
+function Visualizer() {
+  const [levels, setLevels] = useState<AudioLevels>(SILENT);
+
+  useEffect(() => {
+    const channel = new Channel<RecorderEvent>();
+    channel.onmessage = (event) => {
+      if (event.type === "audio-levels") {
+        setLevels(event.payload as AudioLevels);
+      } else if (event.type === "recording-state") {
+        const { recording } = event.payload as { recording: boolean };
+        if (!recording) setLevels(SILENT);
+      }
+    };
+    invoke("subscribe_events", { channel }).catch(console.error);
+    return () => {
+      invoke("unsubscribe_events").catch(console.error);
+    };
+  }, []);
+
+  return (
+    <div className="visualizer">
+      <Meter label="Mic" level={levels.mic_level} />
+      <Meter label="System" level={levels.system_level} />
+      <Meter label="Mix" level={levels.mixed_level} />
+    </div>
+  );
+}
+
+export default Visualizer;
@@ -1,5 +1,5 @@
 import React, { useEffect, useRef, useState } from 'react';
-import { listen } from '@tauri-apps/api/event';
+import { listenRecorder } from './events';
 
 interface AudioLevels {
   mic_level: number;
@@ -25,8 +25,8 @@ const WaveformVisualization: React.FC<WaveformVisualizationProps> = ({ isRecordi
 
     const setupListener = async () => {
       try {
-        unlisten = await listen<AudioLevels>('audio-levels', (event) => {
-          setAudioLevels(event.payload);
+        unlisten = await listenRecorder<AudioLevels>('audio-levels', (payload) => {
+          setAudioLevels(payload);
         });
       } catch (error) {
         console.error('Failed to setup audio levels listener:', error);
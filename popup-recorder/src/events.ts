@@ -0,0 +1,20 @@
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+
+export const EVENT_API_VERSION = 1;
+
+export interface RecorderEvent<T = unknown> {
+  v: number;
+  type: string;
+  payload: T;
+}
+
+export function listenRecorder<T>(
+  type: string,
+  handler: (payload: T) => void
+): Promise<UnlistenFn> {
+  return listen<RecorderEvent<T>>("recorder-event", (event) => {
+    if (event.payload.type === type) {
+      handler(event.payload.payload);
+    }
+  });
+}
@@ -1,9 +1,12 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import Visualizer from "./Visualizer";
+
+const isVisualizer = new URLSearchParams(window.location.search).has("visualizer");
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {isVisualizer ? <Visualizer /> : <App />}
   </React.StrictMode>,
 );
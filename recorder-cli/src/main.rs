@@ -0,0 +1,344 @@
+//! Records system and mic audio through `recorder-core` with no UI, for
+//! scripts and for trying the pipeline out:
+//!
+//!     recorder-cli record --duration 60 --out foo.wav
+//!
+//! Without `--duration` it records until Enter is pressed. Ctrl-C stops it
+//! either way, with the file finished as usual.
+
+use recorder_core::{
+    loudness, BitDepth, CaptureSource, Channels, CpalSource, MixFn, Mixer, RecordingOptions,
+    WavFile,
+};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Usage: recorder-cli record [options]
+
+Options:
+  --out <path>          Where to write the WAV, recording.wav by default
+  --duration <seconds>  Stop after this long instead of on Enter or Ctrl-C
+  --sample-rate <hz>    44100, 48000 or 96000, 48000 by default
+  --bit-depth <depth>   pcm16, pcm24 or float32
+  --mono                Downmix to one channel, 16-bit unless --bit-depth
+  --limiter             Turn loud mixes down rather than clip them
+  --normalize [lufs]    Bring the file to a loudness once stopped, -16 LUFS
+                        by default
+  -h, --help            Print this and exit";
+
+struct Args {
+    out: PathBuf,
+    duration: Option<Duration>,
+    options: RecordingOptions,
+    normalize: Option<f32>,
+}
+
+/// None when only the usage was asked for.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    match args.next().as_deref() {
+        Some("record") => {}
+        Some("--help" | "-h") => return Ok(None),
+        Some(command) => return Err(format!("Unknown command {}", command)),
+        None => return Err("Missing a command".to_string()),
+    }
+
+    let mut parsed = Args {
+        out: PathBuf::from("recording.wav"),
+        duration: None,
+        options: RecordingOptions::default(),
+        normalize: None,
+    };
+    let mut args = args.peekable();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--out" => parsed.out = PathBuf::from(value()?),
+            "--duration" => {
+                let seconds: f64 = value()?
+                    .parse()
+                    .map_err(|_| "--duration takes seconds".to_string())?;
+                parsed.duration = Some(
+                    Duration::try_from_secs_f64(seconds)
+                        .map_err(|_| "--duration takes seconds".to_string())?,
+                );
+            }
+            "--sample-rate" => {
+                parsed.options.sample_rate = value()?
+                    .parse()
+                    .map_err(|_| "--sample-rate takes Hz".to_string())?;
+            }
+            "--bit-depth" => {
+                parsed.options.bit_depth = Some(match value()?.as_str() {
+                    "pcm16" => BitDepth::Pcm16,
+                    "pcm24" => BitDepth::Pcm24,
+                    "float32" => BitDepth::Float32,
+                    other => return Err(format!("Unknown bit depth {}", other)),
+                });
+            }
+            "--mono" => parsed.options.channels = Channels::Mono,
+            "--limiter" => parsed.options.limiter = true,
+            "--normalize" => {
+                // The target is optional, the next option isn't one
+                let target = match args.next_if(|next| !next.starts_with("--")) {
+                    Some(target) => target
+                        .parse()
+                        .map_err(|_| "--normalize takes LUFS".to_string())?,
+                    None => loudness::DEFAULT_TARGET_LUFS,
+                };
+                parsed.normalize = Some(target);
+            }
+            "--help" | "-h" => return Ok(None),
+            other => return Err(format!("Unknown option {}", other)),
+        }
+    }
+    parsed.options.validate()?;
+    Ok(Some(parsed))
+}
+
+/// Everything the main display plays.
+#[cfg(target_os = "macos")]
+fn system_source(sample_rate: u32) -> Result<Box<dyn CaptureSource>, String> {
+    use recorder_core::ScreenCaptureSource;
+    use screencapturekit::prelude::*;
+
+    let content = SCShareableContent::get().map_err(|e| e.to_string())?;
+    let display = content
+        .displays()
+        .first()
+        .cloned()
+        .ok_or("No display found")?;
+    let filter = SCContentFilter::create()
+        .with_display(&display)
+        .with_excluding_windows(&[])
+        .build();
+    let config = ScreenCaptureSource::configure(SCStreamConfiguration::new(), sample_rate);
+    Ok(Box::new(ScreenCaptureSource::new(
+        &filter,
+        &config,
+        sample_rate,
+    )))
+}
+
+/// The default output's loopback.
+#[cfg(target_os = "windows")]
+fn system_source(sample_rate: u32) -> Result<Box<dyn CaptureSource>, String> {
+    let source = recorder_core::LoopbackSource::new(sample_rate).map_err(|e| e.to_string())?;
+    Ok(Box::new(source))
+}
+
+/// The default sink's monitor.
+#[cfg(target_os = "linux")]
+fn system_source(sample_rate: u32) -> Result<Box<dyn CaptureSource>, String> {
+    Ok(Box::new(recorder_core::MonitorSource::new(
+        "recorder-cli",
+        sample_rate,
+    )))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn system_source(_sample_rate: u32) -> Result<Box<dyn CaptureSource>, String> {
+    Err("Recording system audio needs macOS, Windows or Linux".to_string())
+}
+
+/// Has `interrupted` set on Ctrl-C rather than the process killed, so the
+/// file still gets finished. A second Ctrl-C quits right away.
+fn catch_interrupts() -> Result<Arc<AtomicBool>, String> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        // Registered first, so it only sees the flag the second time
+        signal_hook::flag::register_conditional_shutdown(signal, 130, interrupted.clone())
+            .map_err(|e| e.to_string())?;
+        signal_hook::flag::register(signal, interrupted.clone()).map_err(|e| e.to_string())?;
+    }
+    Ok(interrupted)
+}
+
+/// Returns once `duration` has passed, or Enter is pressed without one, or
+/// `interrupted` is set.
+fn wait(duration: Option<Duration>, interrupted: &AtomicBool) {
+    let deadline = duration.map(|duration| Instant::now() + duration);
+    let (enter, entered) = mpsc::channel();
+    if deadline.is_none() {
+        eprintln!("Recording, press Enter or Ctrl-C to stop");
+        let enter = enter.clone();
+        std::thread::spawn(move || {
+            let _ = std::io::stdin().read_line(&mut String::new());
+            let _ = enter.send(());
+        });
+    }
+    while !interrupted.load(Ordering::Relaxed)
+        && deadline.is_none_or(|deadline| Instant::now() < deadline)
+    {
+        if entered.recv_timeout(Duration::from_millis(100)).is_ok() {
+            break;
+        }
+    }
+}
+
+fn record(args: Args) -> Result<(), String> {
+    let sample_rate = args.options.sample_rate;
+    let system = system_source(sample_rate)?;
+    let mic = CpalSource::default_input(sample_rate)?;
+    let interrupted = catch_interrupts()?;
+
+    // Problems are reported from the mixing thread as they happen
+    let (report, reports) = mpsc::channel::<String>();
+    let mix: MixFn = Box::new(move |mixer: &Mixer, writer: &mut WavFile| {
+        mixer.mix_into(writer, |_, _, _| {});
+        if let Some(clipping) = mixer.clipping() {
+            let what = if clipping.limited {
+                "limited"
+            } else {
+                "clipped"
+            };
+            let _ = report.send(format!(
+                "{} {} samples, peak {:.2}",
+                what, clipping.clipped_samples, clipping.peak
+            ));
+        }
+        for overrun in mixer.overruns() {
+            let _ = report.send(format!(
+                "{:?} lost {} frames",
+                overrun.source, overrun.dropped_frames
+            ));
+        }
+    });
+
+    let mut recorder = recorder_core::Recorder::default();
+    recorder.start(&args.out, &args.options, system, Box::new(mic), mix)?;
+    let printer = std::thread::spawn(move || {
+        for line in reports {
+            eprintln!("{}", line);
+        }
+    });
+    wait(args.duration, &interrupted);
+    let path = recorder.stop()?;
+    // The mix callback went with the mixing thread, and with it the sender
+    let _ = printer.join();
+
+    if let Some(target) = args.normalize {
+        eprintln!("Normalizing to {} LUFS", target);
+        match loudness::normalize(&path, target, |_| {})? {
+            Some(normalization) => eprintln!(
+                "Measured {:.1} LUFS, turned by {:+.1} dB",
+                normalization.measured_lufs, normalization.gain_db
+            ),
+            None => eprintln!("Nothing but silence, left as is"),
+        }
+    }
+    println!("{}", path.display());
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match record(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn defaults_to_a_stereo_float_recording() {
+        let args = parse(&["record"]).unwrap().unwrap();
+        assert_eq!(args.out, PathBuf::from("recording.wav"));
+        assert_eq!(args.duration, None);
+        assert_eq!(args.options.sample_rate, 48000);
+        assert_eq!(args.options.file_bit_depth(), BitDepth::Float32);
+        assert_eq!(args.normalize, None);
+    }
+
+    #[test]
+    fn reads_every_option() {
+        let args = parse(&[
+            "record",
+            "--out",
+            "call.wav",
+            "--duration",
+            "1.5",
+            "--sample-rate",
+            "44100",
+            "--bit-depth",
+            "pcm24",
+            "--mono",
+            "--limiter",
+            "--normalize",
+            "-23",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(args.out, PathBuf::from("call.wav"));
+        assert_eq!(args.duration, Some(Duration::from_millis(1500)));
+        assert_eq!(args.options.sample_rate, 44100);
+        assert_eq!(args.options.bit_depth, Some(BitDepth::Pcm24));
+        assert_eq!(args.options.channels, Channels::Mono);
+        assert!(args.options.limiter);
+        assert_eq!(args.normalize, Some(-23.0));
+    }
+
+    #[test]
+    fn normalize_target_is_optional() {
+        let args = parse(&["record", "--normalize", "--mono"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(args.normalize, Some(loudness::DEFAULT_TARGET_LUFS));
+        assert_eq!(args.options.channels, Channels::Mono);
+
+        let args = parse(&["record", "--normalize"]).unwrap().unwrap();
+        assert_eq!(args.normalize, Some(loudness::DEFAULT_TARGET_LUFS));
+    }
+
+    #[test]
+    fn help_only_asks_for_the_usage() {
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["record", "--out", "x.wav", "-h"])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_what_it_doesnt_know() {
+        assert_eq!(parse(&[]).err().unwrap(), "Missing a command");
+        assert_eq!(parse(&["play"]).err().unwrap(), "Unknown command play");
+        assert_eq!(
+            parse(&["record", "--loud"]).err().unwrap(),
+            "Unknown option --loud"
+        );
+        assert_eq!(
+            parse(&["record", "--out"]).err().unwrap(),
+            "--out needs a value"
+        );
+        assert_eq!(
+            parse(&["record", "--bit-depth", "pcm8"]).err().unwrap(),
+            "Unknown bit depth pcm8"
+        );
+        assert!(parse(&["record", "--duration", "-1"]).is_err());
+        assert!(parse(&["record", "--sample-rate", "22050"]).is_err());
+    }
+}
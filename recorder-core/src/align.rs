@@ -0,0 +1,223 @@
+//! Lining the sources up. Each reaches the mixer with its own latency, the
+//! mic's usually well behind system audio, so the mixer delays whichever is
+//! quicker by the difference. `calibrate` measures it by playing a clap
+//! through the speakers, which system audio hears straight away and the mic
+//! only once it's in the room.
+
+use crate::capture::{CaptureSource, SampleCallback};
+use crate::{CHANNELS, SAMPLE_RATE};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How late each source's audio reaches the mixer, in ms.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Latency {
+    pub system_ms: f32,
+    pub mic_ms: f32,
+}
+
+impl Latency {
+    /// Frames of silence to put ahead of system and mic audio at
+    /// `sample_rate` so the two line up.
+    pub fn delays(&self, sample_rate: u32) -> (usize, usize) {
+        let frames = |ms: f32| (ms.max(0.0) * sample_rate as f32 / 1000.0).round() as usize;
+        (
+            frames(self.mic_ms - self.system_ms),
+            frames(self.system_ms - self.mic_ms),
+        )
+    }
+}
+
+/// Listening before the clap, so both sources are running, and after it.
+const LEAD_IN: Duration = Duration::from_millis(500);
+const LISTEN: Duration = Duration::from_millis(1500);
+const CLAP: Duration = Duration::from_millis(20);
+/// The furthest apart the sources are searched for the clap.
+const MAX_LAG_MS: usize = 500;
+/// How closely the mic's recording has to match system audio's to count as
+/// having heard the clap, rather than room noise.
+const MIN_CORRELATION: f32 = 0.3;
+
+/// Energy of each millisecond of interleaved `samples`, which is as fine as
+/// alignment needs to be.
+fn envelope(samples: &[f32]) -> Vec<f32> {
+    let block = (SAMPLE_RATE / 1000) as usize * CHANNELS as usize;
+    samples
+        .chunks_exact(block)
+        .map(|chunk| chunk.iter().map(|s| s * s).sum::<f32>())
+        .collect()
+}
+
+/// How much later in ms `mic` has the clap than `system`, both interleaved
+/// `SAMPLE_RATE` stereo from the same moment on. Negative when the mic is
+/// ahead, `None` when the mic didn't hear it.
+pub fn estimate_mic_lag(system: &[f32], mic: &[f32]) -> Option<f32> {
+    let normalize = |mut envelope: Vec<f32>| {
+        let mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+        envelope.iter_mut().for_each(|e| *e -= mean);
+        let norm = envelope.iter().map(|e| e * e).sum::<f32>().sqrt();
+        (norm > 0.0).then(|| envelope.into_iter().map(|e| e / norm).collect::<Vec<_>>())
+    };
+    let system = normalize(envelope(system))?;
+    let mic = normalize(envelope(mic))?;
+
+    let correlation = |lag: isize| -> f32 {
+        system
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| Some(s * mic.get(usize::try_from(i as isize + lag).ok()?)?))
+            .sum()
+    };
+    let max_lag = MAX_LAG_MS as isize;
+    let (lag, best) = (-max_lag..=max_lag)
+        .map(|lag| (lag, correlation(lag)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    (best >= MIN_CORRELATION).then_some(lag as f32)
+}
+
+/// Collects what a source delivers.
+fn collect(into: &Arc<Mutex<Vec<f32>>>) -> SampleCallback {
+    let into = into.clone();
+    Box::new(move |samples| into.lock().extend_from_slice(samples))
+}
+
+/// A short burst of decaying noise on the default output device, kept
+/// playing until dropped.
+fn play_clap() -> Result<cpal::Stream, String> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or("No output device available")?;
+    let config = device.default_output_config().map_err(|e| e.to_string())?;
+    if config.sample_format() != cpal::SampleFormat::F32 {
+        return Err("The output device doesn't take float samples".to_string());
+    }
+    let channels = config.channels() as usize;
+    let clap_frames = (config.sample_rate() as f32 * CLAP.as_secs_f32()) as usize;
+
+    let mut frame = 0usize;
+    let mut noise = 0x2545_f491u32;
+    let stream = device
+        .build_output_stream(
+            &config.config(),
+            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                for out in data.chunks_mut(channels) {
+                    let sample = if frame < clap_frames {
+                        noise ^= noise << 13;
+                        noise ^= noise >> 17;
+                        noise ^= noise << 5;
+                        let decay = 1.0 - frame as f32 / clap_frames as f32;
+                        (noise as f32 / u32::MAX as f32 * 2.0 - 1.0) * decay * 0.8
+                    } else {
+                        0.0
+                    };
+                    out.fill(sample);
+                    frame += 1;
+                }
+            },
+            |err| eprintln!("Clap stream error: {}", err),
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+    stream.play().map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+/// Plays a clap and measures how much later the mic hears it than `system`
+/// does. Blocks for about two seconds, and needs the speakers on. Both
+/// sources need opening at `SAMPLE_RATE`.
+pub fn calibrate(
+    mut system: Box<dyn CaptureSource>,
+    mut mic: Box<dyn CaptureSource>,
+) -> Result<Latency, String> {
+    let system_audio = Arc::new(Mutex::new(Vec::new()));
+    let mic_audio = Arc::new(Mutex::new(Vec::new()));
+    system.on_samples(collect(&system_audio));
+    mic.on_samples(collect(&mic_audio));
+    system.start()?;
+    if let Err(e) = mic.start() {
+        system.stop();
+        return Err(e);
+    }
+
+    std::thread::sleep(LEAD_IN);
+    // The sources started at different times, only count from here
+    let system_from = system_audio.lock().len();
+    let mic_from = mic_audio.lock().len();
+    let clap = play_clap();
+    if clap.is_ok() {
+        std::thread::sleep(LISTEN);
+    }
+    system.stop();
+    mic.stop();
+    drop(clap?);
+
+    let system_audio = system_audio.lock();
+    let mic_audio = mic_audio.lock();
+    let lag = estimate_mic_lag(&system_audio[system_from..], &mic_audio[mic_from..])
+        .ok_or("The microphone didn't hear the clap, turn the speakers up")?;
+    Ok(Latency {
+        system_ms: (-lag).max(0.0),
+        mic_ms: lag.max(0.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A second of stereo silence with a 20 ms burst `at_ms` in.
+    fn clap(at_ms: usize) -> Vec<f32> {
+        let ms = (SAMPLE_RATE / 1000) as usize * CHANNELS as usize;
+        let mut samples = vec![0.0; 1000 * ms];
+        let mut noise = 0x2545_f491u32;
+        for sample in &mut samples[at_ms * ms..(at_ms + 20) * ms] {
+            noise ^= noise << 13;
+            noise ^= noise >> 17;
+            noise ^= noise << 5;
+            *sample = noise as f32 / u32::MAX as f32 * 2.0 - 1.0;
+        }
+        samples
+    }
+
+    #[test]
+    fn finds_a_late_mic() {
+        assert_eq!(estimate_mic_lag(&clap(200), &clap(320)), Some(120.0));
+    }
+
+    #[test]
+    fn finds_an_early_mic() {
+        assert_eq!(estimate_mic_lag(&clap(400), &clap(350)), Some(-50.0));
+    }
+
+    #[test]
+    fn survives_a_quieter_noisier_mic() {
+        let mut mic = clap(300);
+        for (i, sample) in mic.iter_mut().enumerate() {
+            *sample = *sample * 0.2 + if i % 7 == 0 { 0.01 } else { -0.01 };
+        }
+        assert_eq!(estimate_mic_lag(&clap(250), &mic), Some(50.0));
+    }
+
+    #[test]
+    fn silence_is_not_heard() {
+        assert_eq!(estimate_mic_lag(&clap(200), &vec![0.0; 96000]), None);
+        assert_eq!(estimate_mic_lag(&[], &clap(200)), None);
+    }
+
+    #[test]
+    fn delays_hold_back_the_quicker_source() {
+        let latency = Latency {
+            system_ms: 10.0,
+            mic_ms: 60.0,
+        };
+        assert_eq!(latency.delays(48000), (2400, 0));
+        let latency = Latency {
+            system_ms: 30.0,
+            mic_ms: 0.0,
+        };
+        assert_eq!(latency.delays(44100), (0, 1323));
+    }
+}
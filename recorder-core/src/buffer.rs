@@ -0,0 +1,247 @@
+//! Where a source's samples wait to be mixed. Mixing goes at the pace of the
+//! slower source, so when one stalls the other's audio would pile up for as
+//! long as it lasts. These are fixed ring buffers instead, and what happens
+//! once one is full is up to the `OverflowPolicy`.
+
+use crate::SAMPLE_RATE;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowPolicy {
+    /// Keep the latest audio, losing what hasn't been mixed yet
+    #[default]
+    DropOldest,
+    /// Keep what's waiting, losing what comes in
+    DropNewest,
+    /// Like `DropNewest`, and the app pauses the recording until the user
+    /// resumes it
+    AutoPause,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BufferLimits {
+    /// How much of one source may wait for the other
+    pub max_ms: u32,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for BufferLimits {
+    fn default() -> Self {
+        Self {
+            max_ms: 2000,
+            policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+impl BufferLimits {
+    /// In samples, stereo at `sample_rate`.
+    pub fn capacity(&self, sample_rate: u32) -> usize {
+        (self.max_ms as u64 * sample_rate as u64 / 1000) as usize * 2
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    System,
+    Mic,
+}
+
+/// Interleaved stereo samples, always a whole number of frames.
+pub struct SampleBuffer {
+    samples: Box<[f32]>,
+    head: usize,
+    len: usize,
+}
+
+impl Default for SampleBuffer {
+    fn default() -> Self {
+        Self::new(BufferLimits::default().capacity(SAMPLE_RATE))
+    }
+}
+
+impl SampleBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: vec![0.0; capacity.max(2) & !1].into_boxed_slice(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn index(&self, offset: usize) -> usize {
+        (self.head + offset) % self.samples.len()
+    }
+
+    fn discard(&mut self, count: usize) {
+        let count = count.min(self.len);
+        self.head = self.index(count);
+        self.len -= count;
+    }
+
+    /// Empties it and makes room for `capacity` samples.
+    pub fn reset(&mut self, capacity: usize) {
+        if capacity.max(2) & !1 != self.capacity() {
+            *self = Self::new(capacity);
+        }
+        self.head = 0;
+        self.len = 0;
+    }
+
+    /// Adds `samples` as far as `policy` lets them in, returning how many
+    /// were lost to make them fit.
+    pub fn push(&mut self, samples: &[f32], policy: OverflowPolicy) -> usize {
+        let capacity = self.capacity();
+        let dropped = (self.len + samples.len()).saturating_sub(capacity);
+        let samples = match policy {
+            OverflowPolicy::DropOldest => {
+                let samples = &samples[samples.len().saturating_sub(capacity)..];
+                self.discard((self.len + samples.len()).saturating_sub(capacity));
+                samples
+            }
+            OverflowPolicy::DropNewest | OverflowPolicy::AutoPause => {
+                &samples[..samples.len().min(capacity - self.len)]
+            }
+        };
+        for &sample in samples {
+            let i = self.index(self.len);
+            self.samples[i] = sample;
+            self.len += 1;
+        }
+        dropped
+    }
+
+    /// Adds `frames` frames of silence, as far as they fit.
+    pub fn push_silence(&mut self, frames: usize) {
+        let count = (frames * 2).min(self.capacity() - self.len);
+        for _ in 0..count {
+            let i = self.index(self.len);
+            self.samples[i] = 0.0;
+            self.len += 1;
+        }
+    }
+
+    pub fn pop_frame(&mut self) -> Option<[f32; 2]> {
+        if self.len < 2 {
+            return None;
+        }
+        let frame = [self.samples[self.head], self.samples[self.index(1)]];
+        self.discard(2);
+        Some(frame)
+    }
+
+    /// Folds the two oldest frames into one.
+    pub fn drop_frame(&mut self) {
+        if self.len < 4 {
+            return;
+        }
+        let [left, right] = self.pop_frame().unwrap();
+        let (first, second) = (self.head, self.index(1));
+        self.samples[first] = (self.samples[first] + left) / 2.0;
+        self.samples[second] = (self.samples[second] + right) / 2.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(buffer: &mut SampleBuffer) -> Vec<f32> {
+        std::iter::from_fn(|| buffer.pop_frame())
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn capacity_is_whole_frames() {
+        assert_eq!(SampleBuffer::new(7).capacity(), 6);
+        assert_eq!(SampleBuffer::new(0).capacity(), 2);
+        assert_eq!(BufferLimits::default().capacity(48000), 192_000);
+    }
+
+    #[test]
+    fn pops_in_order_across_the_wrap() {
+        let mut buffer = SampleBuffer::new(6);
+        assert_eq!(
+            buffer.push(&[1.0, 2.0, 3.0, 4.0], OverflowPolicy::DropOldest),
+            0
+        );
+        assert_eq!(buffer.pop_frame(), Some([1.0, 2.0]));
+        assert_eq!(
+            buffer.push(&[5.0, 6.0, 7.0, 8.0], OverflowPolicy::DropOldest),
+            0
+        );
+        assert_eq!(drain(&mut buffer), [3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.pop_frame(), None);
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_latest() {
+        let mut buffer = SampleBuffer::new(4);
+        buffer.push(&[1.0, 2.0, 3.0, 4.0], OverflowPolicy::DropOldest);
+        assert_eq!(buffer.push(&[5.0, 6.0], OverflowPolicy::DropOldest), 2);
+        assert_eq!(drain(&mut buffer), [3.0, 4.0, 5.0, 6.0]);
+
+        // More than fits at once
+        assert_eq!(
+            buffer.push(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], OverflowPolicy::DropOldest),
+            2
+        );
+        assert_eq!(drain(&mut buffer), [3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn drop_newest_keeps_what_waits() {
+        for policy in [OverflowPolicy::DropNewest, OverflowPolicy::AutoPause] {
+            let mut buffer = SampleBuffer::new(4);
+            buffer.push(&[1.0, 2.0], policy);
+            assert_eq!(buffer.push(&[3.0, 4.0, 5.0, 6.0], policy), 2);
+            assert_eq!(drain(&mut buffer), [1.0, 2.0, 3.0, 4.0]);
+        }
+    }
+
+    #[test]
+    fn silence_only_fills_what_is_free() {
+        let mut buffer = SampleBuffer::new(6);
+        buffer.push(&[1.0, 2.0], OverflowPolicy::DropOldest);
+        buffer.push_silence(5);
+        assert_eq!(drain(&mut buffer), [1.0, 2.0, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn drop_frame_averages_the_oldest_two() {
+        let mut buffer = SampleBuffer::new(8);
+        buffer.push(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], OverflowPolicy::DropOldest);
+        buffer.drop_frame();
+        assert_eq!(drain(&mut buffer), [2.0, 3.0, 5.0, 6.0]);
+
+        // A lone frame is left alone
+        buffer.push(&[1.0, 2.0], OverflowPolicy::DropOldest);
+        buffer.drop_frame();
+        assert_eq!(buffer.len(), 2);
+    }
+
+    #[test]
+    fn reset_empties_and_resizes() {
+        let mut buffer = SampleBuffer::new(4);
+        buffer.push(&[1.0, 2.0, 3.0, 4.0], OverflowPolicy::DropOldest);
+        buffer.reset(8);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), 8);
+    }
+}
@@ -0,0 +1,316 @@
+//! Audio sources behind one `CaptureSource` trait: for system audio
+//! ScreenCaptureKit on macOS, WASAPI loopback on Windows and the PulseAudio
+//! monitor on Linux, and cpal for the mic. They deliver stereo at the rate
+//! they were opened for, resampling what the hardware can't do itself. Apps
+//! only decide what happens to the samples.
+
+use crate::resample::Resampler;
+use crate::CHANNELS;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// What `CpalSource::default_input` fails with when there's no mic at all,
+/// for apps that word it their own way.
+pub const NO_INPUT_DEVICE: &str = "No input device available";
+
+/// Receives interleaved samples on the source's capture thread, which is
+/// often real-time, so it mustn't block.
+pub type SampleCallback = Box<dyn FnMut(&[f32]) + Send>;
+
+pub trait CaptureSource: Send {
+    /// Where audio goes once started. Set it before every `start`, which
+    /// takes it.
+    fn on_samples(&mut self, callback: SampleCallback);
+    fn start(&mut self) -> Result<(), String>;
+    /// Stops delivering audio, harmless when not started.
+    fn stop(&mut self);
+}
+
+/// A source the app keeps a handle to while the `Recorder` owns it, e.g. to
+/// point a screen capture at another window mid-recording.
+impl<S: CaptureSource> CaptureSource for Arc<Mutex<S>> {
+    fn on_samples(&mut self, callback: SampleCallback) {
+        self.lock().on_samples(callback);
+    }
+
+    fn start(&mut self) -> Result<(), String> {
+        self.lock().start()
+    }
+
+    fn stop(&mut self) {
+        self.lock().stop();
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub use screencapture::ScreenCaptureSource;
+
+#[cfg(target_os = "linux")]
+mod pulse;
+#[cfg(target_os = "linux")]
+pub use pulse::MonitorSource;
+
+#[cfg(target_os = "windows")]
+mod wasapi;
+#[cfg(target_os = "windows")]
+pub use wasapi::LoopbackSource;
+
+#[cfg(target_os = "macos")]
+mod screencapture {
+    use super::{CaptureSource, SampleCallback};
+    use crate::resample::Resampler;
+    use crate::CHANNELS;
+    use parking_lot::Mutex;
+    use screencapturekit::prelude::*;
+    use std::sync::Arc;
+
+    struct AudioHandler {
+        callback: Arc<Mutex<Option<SampleCallback>>>,
+    }
+
+    impl SCStreamOutputTrait for AudioHandler {
+        fn did_output_sample_buffer(&self, sample: CMSampleBuffer, of_type: SCStreamOutputType) {
+            if let SCStreamOutputType::Audio = of_type {
+                if let Some(buffer_list) = sample.audio_buffer_list() {
+                    let mut samples = Vec::new();
+
+                    // For ScreenCaptureKit audio, it's usually either:
+                    // 1. One buffer with interleaved samples (if number_channels > 1)
+                    // 2. Multiple buffers with one channel each (non-interleaved)
+                    let num_buffers = buffer_list.num_buffers();
+                    if num_buffers == 1 {
+                        let buffer = buffer_list.get(0).unwrap();
+                        let data = buffer.data();
+                        let f32_samples: &[f32] = unsafe {
+                            std::slice::from_raw_parts(data.as_ptr() as *const f32, data.len() / 4)
+                        };
+                        samples.extend_from_slice(f32_samples);
+                    } else {
+                        let mut channel_data = Vec::new();
+                        for i in 0..num_buffers {
+                            let buffer = buffer_list.get(i).unwrap();
+                            let data = buffer.data();
+                            let f32_samples: &[f32] = unsafe {
+                                std::slice::from_raw_parts(
+                                    data.as_ptr() as *const f32,
+                                    data.len() / 4,
+                                )
+                            };
+                            channel_data.push(f32_samples);
+                        }
+                        if !channel_data.is_empty() {
+                            let len = channel_data[0].len();
+                            for i in 0..len {
+                                for channel in &channel_data {
+                                    if i < channel.len() {
+                                        samples.push(channel[i]);
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    if !samples.is_empty() {
+                        if let Some(callback) = self.callback.lock().as_mut() {
+                            callback(&samples);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The rates ScreenCaptureKit can deliver audio at.
+    const NATIVE_RATES: [u32; 4] = [8000, 16000, 24000, 48000];
+
+    fn native_rate(sample_rate: u32) -> u32 {
+        if NATIVE_RATES.contains(&sample_rate) {
+            sample_rate
+        } else {
+            48000
+        }
+    }
+
+    /// System audio at the rate it was created for, stereo.
+    pub struct ScreenCaptureSource {
+        stream: SCStream,
+        sample_rate: u32,
+        callback: Arc<Mutex<Option<SampleCallback>>>,
+        capturing: bool,
+    }
+
+    impl ScreenCaptureSource {
+        /// `config` with stereo audio on, at `sample_rate` if ScreenCaptureKit
+        /// has it and otherwise 48 kHz to be resampled.
+        pub fn configure(config: SCStreamConfiguration, sample_rate: u32) -> SCStreamConfiguration {
+            config
+                .with_captures_audio(true)
+                .with_sample_rate(native_rate(sample_rate) as _)
+                .with_channel_count(CHANNELS as _)
+        }
+
+        /// Audio at `sample_rate` from a stream whose `config` went through
+        /// `configure` with it.
+        pub fn new(
+            filter: &SCContentFilter,
+            config: &SCStreamConfiguration,
+            sample_rate: u32,
+        ) -> Self {
+            let callback = Arc::new(Mutex::new(None));
+            let mut stream = SCStream::new(filter, config);
+            stream.add_output_handler(
+                AudioHandler {
+                    callback: callback.clone(),
+                },
+                SCStreamOutputType::Audio,
+            );
+            Self {
+                stream,
+                sample_rate,
+                callback,
+                capturing: false,
+            }
+        }
+
+        /// Also hands the stream's `of_type` output to `handler`, e.g. the
+        /// screen for a video.
+        pub fn add_output_handler(
+            &mut self,
+            handler: impl SCStreamOutputTrait + 'static,
+            of_type: SCStreamOutputType,
+        ) {
+            self.stream.add_output_handler(handler, of_type);
+        }
+
+        /// Captures what `filter` covers from now on, without a restart.
+        pub fn update_content_filter(&self, filter: &SCContentFilter) -> Result<(), String> {
+            self.stream
+                .update_content_filter(filter)
+                .map_err(|e| e.to_string())
+        }
+
+        /// What the samples come at, after any resampling.
+        pub fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+    }
+
+    impl CaptureSource for ScreenCaptureSource {
+        fn on_samples(&mut self, mut callback: SampleCallback) {
+            let native = native_rate(self.sample_rate);
+            if native != self.sample_rate {
+                let mut resampler = Resampler::new(native, self.sample_rate, CHANNELS, CHANNELS);
+                let mut resampled = Vec::new();
+                callback = Box::new(move |samples| {
+                    resampled.clear();
+                    resampler.process(samples, &mut resampled);
+                    callback(&resampled);
+                });
+            }
+            *self.callback.lock() = Some(callback);
+        }
+
+        fn start(&mut self) -> Result<(), String> {
+            self.stream.start_capture().map_err(|e| e.to_string())?;
+            self.capturing = true;
+            Ok(())
+        }
+
+        fn stop(&mut self) {
+            if std::mem::take(&mut self.capturing) {
+                let _ = self.stream.stop_capture();
+            }
+        }
+    }
+}
+
+/// The default input device, resampled to the rate it was opened for,
+/// stereo.
+pub struct CpalSource {
+    device: cpal::Device,
+    config: cpal::StreamConfig,
+    sample_rate: u32,
+    callback: Option<SampleCallback>,
+    stream: Option<cpal::Stream>,
+}
+
+impl CpalSource {
+    pub fn default_input(sample_rate: u32) -> Result<Self, String> {
+        let host = cpal::default_host();
+        let device = host.default_input_device().ok_or(NO_INPUT_DEVICE)?;
+
+        let supported_configs = device
+            .supported_input_configs()
+            .map_err(|e| e.to_string())?;
+        let config_support = supported_configs
+            .filter(|c| c.sample_format() == cpal::SampleFormat::F32)
+            .find(|c| c.min_sample_rate() <= sample_rate && c.max_sample_rate() >= sample_rate)
+            .or_else(|| device.supported_input_configs().ok()?.next())
+            .ok_or("Could not find any suitable input config")?;
+
+        let source_sr = if config_support.min_sample_rate() <= sample_rate
+            && config_support.max_sample_rate() >= sample_rate
+        {
+            sample_rate
+        } else {
+            config_support.max_sample_rate()
+        };
+        eprintln!(
+            "Selected Mic: {} channels, {} Hz",
+            config_support.channels(),
+            source_sr
+        );
+
+        Ok(Self {
+            device,
+            config: config_support.with_sample_rate(source_sr).into(),
+            sample_rate,
+            callback: None,
+            stream: None,
+        })
+    }
+}
+
+impl CaptureSource for CpalSource {
+    fn on_samples(&mut self, callback: SampleCallback) {
+        self.callback = Some(callback);
+    }
+
+    fn start(&mut self) -> Result<(), String> {
+        let mut callback = self.callback.take().ok_or("No sample callback set")?;
+        let mut resampler = Resampler::new(
+            self.config.sample_rate,
+            self.sample_rate,
+            self.config.channels,
+            CHANNELS,
+        );
+        let mut samples = Vec::new();
+
+        let stream = self
+            .device
+            .build_input_stream(
+                &self.config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    samples.clear();
+                    resampler.process(data, &mut samples);
+                    callback(&samples);
+                },
+                move |err| {
+                    eprintln!("Mic stream error: {}", err);
+                },
+                None,
+            )
+            .map_err(|e| e.to_string())?;
+
+        stream.play().map_err(|e| e.to_string())?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.pause();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! System audio on Linux: the default sink's monitor source, read with the
+//! PulseAudio simple API. PipeWire serves it as well through pipewire-pulse.
+//! The connection can't move between threads, so it's opened on the thread
+//! that reads it.
+
+use super::{CaptureSource, SampleCallback};
+use crate::{RecorderError, CHANNELS};
+use libpulse_binding::sample::{Format as SampleFormat, Spec};
+use libpulse_binding::stream::Direction;
+use libpulse_simple_binding::Simple;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+
+const MONITOR: &str = "@DEFAULT_MONITOR@";
+/// Reads per second, so a read is 20 ms. Also how long `stop` may wait for
+/// the thread.
+const READS_PER_SECOND: u32 = 50;
+
+/// Pulse resamples and downmixes to whatever is asked for, so this is
+/// stereo at the rate it was opened for.
+pub struct MonitorSource {
+    /// What the stream is listed under in the mixer
+    app_name: String,
+    sample_rate: u32,
+    callback: Option<SampleCallback>,
+    running: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl MonitorSource {
+    pub fn new(app_name: &str, sample_rate: u32) -> Self {
+        Self {
+            app_name: app_name.to_string(),
+            sample_rate,
+            callback: None,
+            running: None,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+fn connect(app_name: &str, sample_rate: u32) -> Result<Simple, RecorderError> {
+    let spec = Spec {
+        format: SampleFormat::FLOAT32NE,
+        channels: CHANNELS as u8,
+        rate: sample_rate,
+    };
+    Simple::new(
+        None,
+        app_name,
+        Direction::Record,
+        Some(MONITOR),
+        "System audio",
+        &spec,
+        None,
+        None,
+    )
+    .map_err(|e| RecorderError::DeviceNotFound(format!("No monitor source to record: {}", e)))
+}
+
+fn read_until(
+    simple: Simple,
+    sample_rate: u32,
+    stopping: &AtomicBool,
+    mut callback: SampleCallback,
+) {
+    let frames = (sample_rate / READS_PER_SECOND) as usize;
+    let mut bytes = vec![0u8; frames * CHANNELS as usize * 4];
+    while !stopping.load(Ordering::Relaxed) {
+        if let Err(e) = simple.read(&mut bytes) {
+            eprintln!("Monitor source read failed: {}", e);
+            return;
+        }
+        let samples: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|sample| f32::from_ne_bytes([sample[0], sample[1], sample[2], sample[3]]))
+            .collect();
+        callback(&samples);
+    }
+}
+
+impl CaptureSource for MonitorSource {
+    fn on_samples(&mut self, callback: SampleCallback) {
+        self.callback = Some(callback);
+    }
+
+    fn start(&mut self) -> Result<(), String> {
+        let callback = self.callback.take().ok_or("No sample callback set")?;
+        let stopping = Arc::new(AtomicBool::new(false));
+        let app_name = self.app_name.clone();
+        let sample_rate = self.sample_rate;
+        let (started, connected) = mpsc::channel();
+
+        let thread_stopping = stopping.clone();
+        let thread = std::thread::Builder::new()
+            .name("monitor-source".to_string())
+            .spawn(move || match connect(&app_name, sample_rate) {
+                Ok(simple) => {
+                    let _ = started.send(Ok(()));
+                    read_until(simple, sample_rate, &thread_stopping, callback);
+                }
+                Err(e) => {
+                    let _ = started.send(Err(e.to_string()));
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        connected
+            .recv()
+            .map_err(|_| "The monitor thread exited".to_string())??;
+        self.running = Some((stopping, thread));
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some((stopping, thread)) = self.running.take() {
+            stopping.store(true, Ordering::Relaxed);
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! System audio on Windows: WASAPI loopback of the default output device,
+//! which cpal opens when an input stream is built on an output device. cpal
+//! streams can't move between threads, so each lives on a thread of its own.
+
+use super::{CaptureSource, SampleCallback};
+use crate::resample::Resampler;
+use crate::{RecorderError, CHANNELS};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+fn capture_error(e: impl std::fmt::Display) -> RecorderError {
+    RecorderError::CaptureFailed(e.to_string())
+}
+
+pub struct LoopbackSource {
+    device: cpal::Device,
+    // Loopback captures in the output's own mix format
+    config: cpal::StreamConfig,
+    /// What `config.sample_rate` is resampled to
+    sample_rate: u32,
+    /// What `config.channels` are mixed to
+    channels: u16,
+    callback: Option<SampleCallback>,
+    /// Dropping the sender ends the stream's thread
+    running: Option<(mpsc::Sender<()>, JoinHandle<()>)>,
+}
+
+impl LoopbackSource {
+    /// Stereo at `sample_rate`, like the other sources.
+    pub fn new(sample_rate: u32) -> Result<Self, RecorderError> {
+        Self::open(sample_rate, Some(CHANNELS))
+    }
+
+    /// At `sample_rate`, but with as many channels as the output mixes,
+    /// which `channels` tells.
+    pub fn native(sample_rate: u32) -> Result<Self, RecorderError> {
+        Self::open(sample_rate, None)
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn open(sample_rate: u32, channels: Option<u16>) -> Result<Self, RecorderError> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| RecorderError::DeviceNotFound("No output device found".to_string()))?;
+        let config = device.default_output_config().map_err(capture_error)?;
+        if config.sample_format() != cpal::SampleFormat::F32 {
+            return Err(RecorderError::CaptureFailed(format!(
+                "The output device mixes in {}, not float",
+                config.sample_format()
+            )));
+        }
+        let config: cpal::StreamConfig = config.into();
+        Ok(Self {
+            device,
+            channels: channels.unwrap_or(config.channels),
+            config,
+            sample_rate,
+            callback: None,
+            running: None,
+        })
+    }
+}
+
+fn open_stream(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut callback: SampleCallback,
+) -> Result<cpal::Stream, String> {
+    let stream = device
+        .build_input_stream(
+            config,
+            move |data: &[f32], _: &_| callback(data),
+            |err| eprintln!("Loopback stream error: {}", err),
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+    stream.play().map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+impl CaptureSource for LoopbackSource {
+    fn on_samples(&mut self, callback: SampleCallback) {
+        self.callback = Some(callback);
+    }
+
+    fn start(&mut self) -> Result<(), String> {
+        let mut callback = self.callback.take().ok_or("No sample callback set")?;
+        let mut resampler = Resampler::new(
+            self.config.sample_rate,
+            self.sample_rate,
+            self.config.channels,
+            self.channels,
+        );
+        if !resampler.is_passthrough() {
+            let mut resampled = Vec::new();
+            callback = Box::new(move |samples| {
+                resampled.clear();
+                resampler.process(samples, &mut resampled);
+                callback(&resampled);
+            });
+        }
+        let device = self.device.clone();
+        let config = self.config.clone();
+        let (started, opened) = mpsc::channel();
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let thread = std::thread::Builder::new()
+            .name("loopback".to_string())
+            .spawn(move || match open_stream(&device, &config, callback) {
+                Ok(_stream) => {
+                    let _ = started.send(Ok(()));
+                    let _ = stopped.recv();
+                }
+                Err(e) => {
+                    let _ = started.send(Err(e));
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        opened
+            .recv()
+            .map_err(|_| "The loopback thread exited".to_string())??;
+        self.running = Some((stop, thread));
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some((stop, thread)) = self.running.take() {
+            drop(stop);
+            let _ = thread.join();
+        }
+    }
+}
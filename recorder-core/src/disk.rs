@@ -0,0 +1,29 @@
+//! How much room is left for a recording, for the apps that warn or stop
+//! before the disk fills up.
+
+use std::path::Path;
+
+/// Bytes available to the app on the volume holding `dir`, `None` where
+/// that can't be told.
+#[cfg(unix)]
+pub fn free_space(dir: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL terminated and statvfs fills `stats` on success
+    if unsafe { libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stats = unsafe { stats.assume_init() };
+    // The fields are narrower on macOS
+    #[allow(clippy::unnecessary_cast)]
+    let bytes = stats.f_bavail as u64 * stats.f_frsize as u64;
+    Some(bytes)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_dir: &Path) -> Option<u64> {
+    None
+}
@@ -0,0 +1,118 @@
+//! Errors returned by the recorder commands. They reach the frontend as
+//! `{ kind, code, message }`, plus `resource` and `settings_url` for a
+//! missing permission, so it can branch on `kind` and show `message` as is.
+//! `code` is stable for logs and bug reports.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum RecorderError {
+    AlreadyRecording,
+    NotRecording,
+    /// Another recorder holds the recording lock
+    Busy(String),
+    /// No microphone, display or window to capture
+    DeviceNotFound(String),
+    /// `resource` is e.g. `microphone`, fixed in System Settings at `settings_url`
+    PermissionDenied {
+        resource: String,
+        message: String,
+        settings_url: Option<String>,
+    },
+    InvalidArgument(String),
+    /// A recording, profile or other saved item that doesn't exist
+    NotFound(String),
+    IoError(String),
+    /// The audio or screen capture failed
+    CaptureFailed(String),
+    Other(String),
+}
+
+impl RecorderError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RecorderError::AlreadyRecording => "AlreadyRecording",
+            RecorderError::NotRecording => "NotRecording",
+            RecorderError::Busy(_) => "Busy",
+            RecorderError::DeviceNotFound(_) => "DeviceNotFound",
+            RecorderError::PermissionDenied { .. } => "PermissionDenied",
+            RecorderError::InvalidArgument(_) => "InvalidArgument",
+            RecorderError::NotFound(_) => "NotFound",
+            RecorderError::IoError(_) => "IoError",
+            RecorderError::CaptureFailed(_) => "CaptureFailed",
+            RecorderError::Other(_) => "Other",
+        }
+    }
+
+    pub fn code(&self) -> u16 {
+        match self {
+            RecorderError::AlreadyRecording => 1,
+            RecorderError::NotRecording => 2,
+            RecorderError::Busy(_) => 3,
+            RecorderError::DeviceNotFound(_) => 4,
+            RecorderError::PermissionDenied { .. } => 5,
+            RecorderError::InvalidArgument(_) => 6,
+            RecorderError::NotFound(_) => 7,
+            RecorderError::IoError(_) => 8,
+            RecorderError::CaptureFailed(_) => 9,
+            RecorderError::Other(_) => 99,
+        }
+    }
+}
+
+impl fmt::Display for RecorderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecorderError::AlreadyRecording => f.write_str("Already recording"),
+            RecorderError::NotRecording => f.write_str("Not recording"),
+            RecorderError::Busy(message)
+            | RecorderError::DeviceNotFound(message)
+            | RecorderError::PermissionDenied { message, .. }
+            | RecorderError::InvalidArgument(message)
+            | RecorderError::NotFound(message)
+            | RecorderError::IoError(message)
+            | RecorderError::CaptureFailed(message)
+            | RecorderError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for RecorderError {}
+
+impl Serialize for RecorderError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("RecorderError", 5)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("code", &self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        if let RecorderError::PermissionDenied {
+            resource,
+            settings_url,
+            ..
+        } = self
+        {
+            error.serialize_field("resource", resource)?;
+            error.serialize_field("settings_url", settings_url)?;
+        }
+        error.end()
+    }
+}
+
+impl From<std::io::Error> for RecorderError {
+    fn from(e: std::io::Error) -> Self {
+        RecorderError::IoError(e.to_string())
+    }
+}
+
+impl From<String> for RecorderError {
+    fn from(message: String) -> Self {
+        RecorderError::Other(message)
+    }
+}
+
+impl From<&str> for RecorderError {
+    fn from(message: &str) -> Self {
+        RecorderError::Other(message.to_string())
+    }
+}
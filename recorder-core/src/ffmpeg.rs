@@ -0,0 +1,32 @@
+//! Finding the ffmpeg binary, for the apps that encode or export through it.
+
+use std::path::PathBuf;
+
+// Where GUI apps won't look on their own, `PATH` is minimal outside a shell
+const KNOWN_LOCATIONS: &[&str] = &["/opt/homebrew/bin/ffmpeg", "/usr/local/bin/ffmpeg"];
+
+/// A sidecar next to the app binary, then the usual install locations and
+/// `PATH`.
+pub fn locate() -> Option<PathBuf> {
+    let name = if cfg!(windows) {
+        "ffmpeg.exe"
+    } else {
+        "ffmpeg"
+    };
+    let sidecar = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(name)));
+    let on_path = std::env::var_os("PATH")
+        .map(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join(name))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    sidecar
+        .into_iter()
+        .chain(KNOWN_LOCATIONS.iter().map(PathBuf::from))
+        .chain(on_path)
+        .find(|candidate| candidate.is_file())
+}
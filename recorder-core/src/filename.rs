@@ -0,0 +1,184 @@
+//! File names for new recordings, rendered from a template. Tokens are
+//! `{date}`, `{time}`, `{profile}` (whatever the app groups recordings by)
+//! and `{counter}`, the lowest number not yet taken in the folder.
+
+use chrono::Local;
+use std::path::{Path, PathBuf};
+
+const TOKENS: &[&str] = &["date", "time", "profile", "counter"];
+
+// Separators, and what Finder shows as a slash
+const FORBIDDEN: &[char] = &['/', '\\', ':'];
+
+/// Whether `template` renders to a usable file name: not empty or hidden,
+/// no path separators and only known tokens.
+pub fn is_valid(template: &str) -> bool {
+    if template.trim().is_empty() || template.contains(FORBIDDEN) || template.starts_with('.') {
+        return false;
+    }
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| end + start) else {
+            return false;
+        };
+        if !TOKENS.contains(&&rest[start + 1..end]) {
+            return false;
+        }
+        rest = &rest[end + 1..];
+    }
+    true
+}
+
+fn render(template: &str, profile: &str, counter: u32) -> String {
+    let now = Local::now();
+    let name = template
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H-%M-%S").to_string())
+        .replace("{profile}", &profile.replace(FORBIDDEN, "-"))
+        .replace("{counter}", &format!("{:03}", counter));
+    // An empty `{profile}` shouldn't leave a dangling separator
+    let name = name.trim_matches(|c: char| c == '-' || c == '_' || c.is_whitespace());
+    match name.is_empty() {
+        true => "recording".to_string(),
+        false => name.to_string(),
+    }
+}
+
+/// Path for a new WAV recording in `dir`, skipping every path `taken` says
+/// is in use. Without `{counter}` in the template a name that's already
+/// taken gets `-2`, `-3`, ... appended instead.
+pub fn next_path(
+    dir: &Path,
+    template: &str,
+    profile: &str,
+    taken: impl Fn(&Path) -> bool,
+) -> PathBuf {
+    let counted = template.contains("{counter}");
+    let base = render(template, profile, 1);
+    let mut n = 1;
+    loop {
+        let name = match (counted, n) {
+            (true, _) => render(template, profile, n),
+            (false, 1) => base.clone(),
+            (false, _) => format!("{}-{}", base, n),
+        };
+        // Not `with_extension`, a dot in a profile isn't one
+        let path = dir.join(format!("{}.wav", name));
+        if !taken(&path) {
+            return path;
+        }
+        n += 1;
+    }
+}
+
+/// `name` in `dir`, or with `-2`, `-3`, ... before the extension when a file
+/// of that name already exists.
+pub fn unique_path(dir: &Path, name: &Path) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = name.extension().map(|ext| ext.to_string_lossy());
+    (2..)
+        .map(|n| match &extension {
+            Some(ext) => dir.join(format!("{}-{}.{}", stem, n, ext)),
+            None => dir.join(format!("{}-{}", stem, n)),
+        })
+        .find(|path| !path.exists())
+        .expect("some suffix is free")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn taken(names: &[&str]) -> impl Fn(&Path) -> bool {
+        let names: HashSet<PathBuf> = names
+            .iter()
+            .map(|name| Path::new("out").join(name))
+            .collect();
+        move |path| names.contains(path)
+    }
+
+    #[test]
+    fn accepts_known_tokens_only() {
+        assert!(is_valid("{profile}-{date}-{time}"));
+        assert!(is_valid("take {counter}"));
+        assert!(!is_valid("{when}"));
+        assert!(!is_valid("open {date"));
+        assert!(!is_valid("  "));
+        assert!(!is_valid(".hidden"));
+        assert!(!is_valid("a/b"));
+        assert!(!is_valid("a:b"));
+    }
+
+    #[test]
+    fn renders_date_and_time() {
+        let path = next_path(Path::new("out"), "{date}_{time}", "", |_| false);
+        let name = path.file_name().unwrap().to_str().unwrap();
+        // 2026-10-18_09-30-00.wav
+        assert_eq!(name.len(), "2026-10-18_09-30-00.wav".len());
+        assert!(name.ends_with(".wav"));
+        assert_eq!(name.matches('-').count(), 4);
+    }
+
+    #[test]
+    fn counts_past_taken_names() {
+        let dir = Path::new("out");
+        assert_eq!(
+            next_path(dir, "take-{counter}", "", taken(&[])),
+            dir.join("take-001.wav")
+        );
+        assert_eq!(
+            next_path(
+                dir,
+                "take-{counter}",
+                "",
+                taken(&["take-001.wav", "take-002.wav"])
+            ),
+            dir.join("take-003.wav")
+        );
+    }
+
+    #[test]
+    fn suffixes_a_taken_name_without_counter() {
+        let dir = Path::new("out");
+        assert_eq!(
+            next_path(dir, "call", "", taken(&["call.wav", "call-2.wav"])),
+            dir.join("call-3.wav")
+        );
+    }
+
+    #[test]
+    fn keeps_profiles_inside_the_folder() {
+        let path = next_path(Path::new("out"), "{profile}", "com.app/x:y", |_| false);
+        assert_eq!(path, Path::new("out").join("com.app-x-y.wav"));
+    }
+
+    #[test]
+    fn trims_what_an_empty_profile_leaves() {
+        let dir = Path::new("out");
+        assert_eq!(
+            next_path(dir, "{profile}-call", "", |_| false),
+            dir.join("call.wav")
+        );
+        assert_eq!(
+            next_path(dir, "{profile}", "", |_| false),
+            dir.join("recording.wav")
+        );
+    }
+
+    #[test]
+    fn unique_path_keeps_the_extension() {
+        let dir = std::env::temp_dir().join(format!("filename-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let name = Path::new("call.wav");
+        assert_eq!(unique_path(&dir, name), dir.join("call.wav"));
+        std::fs::write(dir.join("call.wav"), b"").unwrap();
+        std::fs::write(dir.join("call-2.wav"), b"").unwrap();
+        assert_eq!(unique_path(&dir, name), dir.join("call-3.wav"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+//! Meters for the UI: the RMS of each source's latest buffer and of the mix
+//! since the last report, and the reports of the last few seconds for
+//! windows that open mid-recording.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How often `LevelMeter::report` hands out levels.
+pub const REPORT_INTERVAL: Duration = Duration::from_millis(50);
+/// How far back `LevelMeter::history` goes.
+pub const HISTORY_LENGTH: Duration = Duration::from_secs(10);
+const HISTORY_REPORTS: usize = (HISTORY_LENGTH.as_millis() / REPORT_INTERVAL.as_millis()) as usize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioLevels {
+    pub mic_level: f32,
+    pub system_level: f32,
+    pub mixed_level: f32,
+}
+
+#[derive(Default)]
+struct Meter {
+    system: f32,
+    mic: f32,
+    // Sum of squares of the mix and the frames it covers
+    mixed_sum: f32,
+    mixed_frames: u32,
+    last_report: Option<Instant>,
+    // Every report within `HISTORY_LENGTH`, oldest first
+    history: VecDeque<AudioLevels>,
+}
+
+#[derive(Default)]
+pub struct LevelMeter(Mutex<Meter>);
+
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum: f32 = samples.iter().map(|s| s * s).sum();
+    (sum / samples.len() as f32).sqrt()
+}
+
+impl LevelMeter {
+    pub fn set_system(&self, samples: &[f32]) {
+        self.0.lock().system = rms(samples);
+    }
+
+    pub fn set_mic(&self, samples: &[f32]) {
+        self.0.lock().mic = rms(samples);
+    }
+
+    /// The RMS of each source's latest buffer, system then mic, whether or
+    /// not a report is due.
+    pub fn sources(&self) -> (f32, f32) {
+        let meter = self.0.lock();
+        (meter.system, meter.mic)
+    }
+
+    /// Adds `frames` stereo frames of mix whose squares sum to `sum`.
+    pub fn add_mixed(&self, sum: f32, frames: u32) {
+        let mut meter = self.0.lock();
+        meter.mixed_sum += sum;
+        meter.mixed_frames += frames;
+    }
+
+    /// The current levels, once `REPORT_INTERVAL` has passed since the last
+    /// report and there's new mix to measure.
+    pub fn report(&self) -> Option<AudioLevels> {
+        let mut meter = self.0.lock();
+        let due = meter
+            .last_report
+            .is_none_or(|at| at.elapsed() >= REPORT_INTERVAL);
+        if !due || meter.mixed_frames == 0 {
+            return None;
+        }
+        let levels = AudioLevels {
+            mic_level: meter.mic,
+            system_level: meter.system,
+            mixed_level: (meter.mixed_sum / (meter.mixed_frames * 2) as f32).sqrt(),
+        };
+        meter.mixed_sum = 0.0;
+        meter.mixed_frames = 0;
+        meter.last_report = Some(Instant::now());
+        if meter.history.len() == HISTORY_REPORTS {
+            meter.history.pop_front();
+        }
+        meter.history.push_back(levels.clone());
+        Some(levels)
+    }
+
+    /// The reports of the last `HISTORY_LENGTH`, oldest first and
+    /// `REPORT_INTERVAL` apart.
+    pub fn history(&self) -> Vec<AudioLevels> {
+        self.0.lock().history.iter().cloned().collect()
+    }
+
+    /// Back to silence with no history, for when a recording stops.
+    pub fn reset(&self) {
+        *self.0.lock() = Meter::default();
+    }
+}
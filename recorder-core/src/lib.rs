@@ -0,0 +1,46 @@
+//! The recording pipeline the recorder apps share: capture sources, the
+//! mixer that lines system and mic audio up, level metering and the WAV file
+//! the mix goes into, and the lock that keeps two of them off the mic at
+//! once. Apps keep their commands and whatever state is theirs alone, and
+//! decide what happens to each mixed frame.
+
+pub mod align;
+pub mod buffer;
+pub mod capture;
+pub mod disk;
+pub mod error;
+pub mod ffmpeg;
+pub mod filename;
+pub mod levels;
+pub mod limiter;
+pub mod loudness;
+pub mod mixer;
+pub mod options;
+pub mod recording_lock;
+pub mod resample;
+pub mod state;
+pub mod tray_icon;
+pub mod writer;
+
+pub use align::Latency;
+pub use buffer::{BufferLimits, OverflowPolicy};
+#[cfg(target_os = "windows")]
+pub use capture::LoopbackSource;
+#[cfg(target_os = "linux")]
+pub use capture::MonitorSource;
+#[cfg(target_os = "macos")]
+pub use capture::ScreenCaptureSource;
+pub use capture::{CaptureSource, CpalSource, SampleCallback};
+pub use error::RecorderError;
+pub use levels::{AudioLevels, LevelMeter};
+pub use limiter::Limiter;
+pub use loudness::{LoudnessMeter, Normalization};
+pub use mixer::{BufferOverrun, Clipping, Mixer};
+pub use options::{Channels, FinalizeOptions, RecordingOptions};
+pub use state::{AppState, MixFn, Output, Recorder};
+pub use writer::{read_samples, BitDepth, SampleEncoder, WavFile};
+
+/// What recordings are made at unless `RecordingOptions` say otherwise.
+pub const SAMPLE_RATE: u32 = 48000;
+/// What the sources deliver and the mixer works in, whatever the file has.
+pub const CHANNELS: u16 = 2;
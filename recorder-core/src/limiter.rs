@@ -0,0 +1,121 @@
+//! A soft-knee peak limiter for the mix. Loud system audio plus the mic can
+//! go past full scale, which the file would clip into audible distortion;
+//! this turns the gain down just enough instead, easing in over the knee so
+//! quiet passages stay untouched and it doesn't pump.
+
+/// The most the mix comes out at, just under full scale.
+const CEILING_DB: f32 = -1.0;
+/// How far below the ceiling gain reduction starts easing in.
+const KNEE_DB: f32 = 6.0;
+/// How long the gain takes to recover once peaks are gone, roughly.
+const RELEASE_MS: f32 = 100.0;
+/// Reduction small enough to count as none, where the release would
+/// otherwise creep towards zero forever.
+const INAUDIBLE_DB: f32 = 0.001;
+
+fn to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-9).log10()
+}
+
+fn from_db(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// How many dB to take off a peak of `peak_db`. Nothing below the knee,
+/// everything over the ceiling above it, and a quadratic blend between.
+fn reduction_db(peak_db: f32) -> f32 {
+    let over = peak_db - (CEILING_DB - KNEE_DB / 2.0);
+    if over <= 0.0 {
+        0.0
+    } else if over < KNEE_DB {
+        over * over / (2.0 * KNEE_DB)
+    } else {
+        peak_db - CEILING_DB
+    }
+}
+
+pub struct Limiter {
+    /// Per-frame factor the reduction recovers by
+    release: f32,
+    /// Current reduction in dB, positive
+    reduction: f32,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            release: (-1.0 / (RELEASE_MS / 1000.0 * sample_rate as f32)).exp(),
+            reduction: 0.0,
+        }
+    }
+
+    /// `frame` turned down as far as it needs to be. Peaks are caught on the
+    /// frame they arrive, so nothing leaves above `CEILING_DB`.
+    pub fn process(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        let peak = frame[0].abs().max(frame[1].abs());
+        let target = reduction_db(to_db(peak));
+        self.reduction = if target > self.reduction {
+            target
+        } else {
+            target + (self.reduction - target) * self.release
+        };
+        if self.reduction < INAUDIBLE_DB {
+            self.reduction = 0.0;
+            return frame;
+        }
+        let gain = from_db(-self.reduction);
+        [frame[0] * gain, frame[1] * gain]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_frames_pass_untouched() {
+        let mut limiter = Limiter::new(48000);
+        for frame in [[0.0, 0.0], [0.5, -0.5], [0.1, 0.6]] {
+            assert_eq!(limiter.process(frame), frame);
+        }
+    }
+
+    #[test]
+    fn nothing_leaves_above_the_ceiling() {
+        let mut limiter = Limiter::new(48000);
+        let ceiling = from_db(CEILING_DB) + 1e-5;
+        for peak in [0.9, 1.0, 1.5, 4.0, 100.0] {
+            let [left, right] = limiter.process([peak, -peak]);
+            assert!(
+                left <= ceiling && -right <= ceiling,
+                "{} came out at {}",
+                peak,
+                left
+            );
+            assert!(left > 0.5, "{} was turned down to {}", peak, left);
+        }
+    }
+
+    #[test]
+    fn the_knee_eases_in() {
+        assert_eq!(reduction_db(CEILING_DB - KNEE_DB), 0.0);
+        let in_knee = reduction_db(CEILING_DB);
+        assert!(in_knee > 0.0 && in_knee < KNEE_DB / 2.0);
+        assert_eq!(reduction_db(CEILING_DB + 6.0), 6.0);
+    }
+
+    #[test]
+    fn gain_recovers_after_a_peak() {
+        let mut limiter = Limiter::new(48000);
+        limiter.process([2.0, 2.0]);
+        // Held down right after the peak
+        let [held, _] = limiter.process([0.5, 0.5]);
+        assert!(held < 0.5);
+        // And back to none a second later
+        let mut frame = [0.0, 0.0];
+        for _ in 0..48000 {
+            frame = limiter.process([0.5, 0.5]);
+        }
+        assert_eq!(frame, [0.5, 0.5]);
+    }
+}
@@ -0,0 +1,311 @@
+//! EBU R128 loudness: the integrated loudness of a finished recording, as
+//! ITU-R BS.1770 measures it, and a pass that turns the file up or down to a
+//! target so recordings play back at the same level.
+//!
+//! The measurement K-weights each channel, a high shelf for the head and a
+//! high-pass for the ear, then averages the power of 400 ms blocks. Blocks
+//! of near silence and blocks well under the rest are gated out so pauses
+//! don't drag the result down.
+
+use crate::writer::{read_samples, BitDepth, SampleEncoder};
+use hound::{WavReader, WavWriter};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// What podcasts and streaming services mostly aim for.
+pub const DEFAULT_TARGET_LUFS: f32 = -16.0;
+/// Gain is held back so no sample ends up louder than this.
+const PEAK_CEILING_DB: f32 = -1.0;
+/// Blocks quieter than this are silence, whatever the rest does.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks this far under the ungated loudness are pauses.
+const RELATIVE_GATE_LU: f64 = 10.0;
+/// Blocks are 400 ms and start every 100 ms, so four steps each.
+const STEP_MS: u32 = 100;
+const STEPS_PER_BLOCK: usize = 4;
+/// Frames read between progress reports.
+const PROGRESS_FRAMES: u32 = 48000;
+
+/// One biquad stage, direct form I.
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    /// a1 and a2, a0 is normalized to 1
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// The two K-weighting stages at `sample_rate`. BS.1770 only gives them at
+/// 48 kHz, these are the analogue prototypes they come from.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new(
+        [1.0, -2.0, 1.0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    [shelf, high_pass]
+}
+
+fn to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.max(1e-20).log10()
+}
+
+/// Integrated loudness of interleaved audio fed in any number of pieces.
+pub struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+    step_frames: usize,
+    /// Sum of squares of the current step over all channels, and its frames
+    step_sum: f64,
+    step_len: usize,
+    /// Mean power of the last few steps, enough for a block
+    steps: VecDeque<f64>,
+    /// Mean power of every block so far
+    blocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        let channels = channels.max(1) as usize;
+        Self {
+            channels,
+            filters: vec![k_weighting(sample_rate); channels],
+            step_frames: (sample_rate * STEP_MS / 1000) as usize,
+            step_sum: 0.0,
+            step_len: 0,
+            steps: VecDeque::with_capacity(STEPS_PER_BLOCK),
+            blocks: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (sample, filters) in frame.iter().zip(&mut self.filters) {
+                let weighted = filters
+                    .iter_mut()
+                    .fold(*sample as f64, |x, stage| stage.process(x));
+                self.step_sum += weighted * weighted;
+            }
+            self.step_len += 1;
+            if self.step_len == self.step_frames {
+                self.end_step();
+            }
+        }
+    }
+
+    fn end_step(&mut self) {
+        if self.steps.len() == STEPS_PER_BLOCK {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(self.step_sum / self.step_len as f64);
+        self.step_sum = 0.0;
+        self.step_len = 0;
+        if self.steps.len() == STEPS_PER_BLOCK {
+            self.blocks
+                .push(self.steps.iter().sum::<f64>() / STEPS_PER_BLOCK as f64);
+        }
+    }
+
+    /// In LUFS, `None` for less than a block or nothing but silence.
+    pub fn integrated(&self) -> Option<f32> {
+        let mean = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+        let audible: Vec<f64> = self
+            .blocks
+            .iter()
+            .copied()
+            .filter(|power| to_lufs(*power) > ABSOLUTE_GATE_LUFS)
+            .collect();
+        if audible.is_empty() {
+            return None;
+        }
+        let relative_gate = to_lufs(mean(&audible)) - RELATIVE_GATE_LU;
+        let gated: Vec<f64> = audible
+            .into_iter()
+            .filter(|power| to_lufs(*power) > relative_gate)
+            .collect();
+        Some(to_lufs(mean(&gated)) as f32)
+    }
+}
+
+/// What `normalize` found and did.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Normalization {
+    pub measured_lufs: f32,
+    /// What the file was turned up by, negative for down. Less than the
+    /// target asks for when peaks would have gone past `PEAK_CEILING_DB`.
+    pub gain_db: f32,
+}
+
+/// Measures the recording at `path` and rewrites it at `target_lufs`, in the
+/// format it has. `progress` is told how far along it is, from 0 to 1.
+/// Silent recordings are left alone and measure `None`.
+pub fn normalize(
+    path: &Path,
+    target_lufs: f32,
+    mut progress: impl FnMut(f32),
+) -> Result<Option<Normalization>, String> {
+    let reader = WavReader::open(path).map_err(|e| e.to_string())?;
+    let spec = reader.spec();
+    let bit_depth = BitDepth::from_spec(spec)
+        .ok_or_else(|| format!("Can't normalize {}-bit audio", spec.bits_per_sample))?;
+    let channels = spec.channels.max(1) as usize;
+    let total = reader.duration().max(1) as f32;
+    let report_every = PROGRESS_FRAMES as usize * channels;
+
+    // Measuring is the first half of the work, rewriting the second
+    let mut meter = LoudnessMeter::new(spec.sample_rate, spec.channels);
+    let mut peak = 0.0f32;
+    let mut chunk = Vec::with_capacity(report_every);
+    let mut read = 0usize;
+    for sample in read_samples(reader) {
+        let sample = sample.map_err(|e| e.to_string())?;
+        peak = peak.max(sample.abs());
+        chunk.push(sample);
+        if chunk.len() == report_every {
+            meter.add(&chunk);
+            chunk.clear();
+            read += report_every / channels;
+            progress(read as f32 / total * 0.5);
+        }
+    }
+    meter.add(&chunk);
+
+    let Some(measured_lufs) = meter.integrated() else {
+        progress(1.0);
+        return Ok(None);
+    };
+    let headroom = PEAK_CEILING_DB - 20.0 * peak.max(1e-9).log10();
+    let gain_db = (target_lufs - measured_lufs).min(headroom);
+    let gain = 10f32.powf(gain_db / 20.0);
+
+    // Written beside the original and swapped in, so a failure leaves it be
+    let normalized = path.with_extension("normalizing.wav");
+    let mut writer = WavWriter::create(&normalized, spec).map_err(|e| e.to_string())?;
+    let mut encoder = SampleEncoder::new(bit_depth);
+    let reader = WavReader::open(path).map_err(|e| e.to_string())?;
+    let result = read_samples(reader)
+        .enumerate()
+        .try_for_each(|(i, sample)| {
+            encoder.write(&mut writer, sample? * gain)?;
+            if (i + 1) % report_every == 0 {
+                progress(0.5 + (i + 1) as f32 / channels as f32 / total * 0.5);
+            }
+            Ok::<_, hound::Error>(())
+        })
+        .and_then(|_| writer.finalize());
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&normalized);
+        return Err(e.to_string());
+    }
+    std::fs::rename(&normalized, path).map_err(|e| e.to_string())?;
+    progress(1.0);
+
+    Ok(Some(Normalization {
+        measured_lufs,
+        gain_db,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `seconds` of a 997 Hz sine at `amplitude` in both channels, the tone
+    /// BS.1770 is specified with.
+    fn sine(amplitude: f32, seconds: f32) -> Vec<f32> {
+        let frames = (48000.0 * seconds) as usize;
+        (0..frames)
+            .flat_map(|n| {
+                let sample = (2.0 * std::f32::consts::PI * 997.0 * n as f32 / 48000.0).sin();
+                [sample * amplitude; 2]
+            })
+            .collect()
+    }
+
+    fn measure(samples: &[f32]) -> Option<f32> {
+        let mut meter = LoudnessMeter::new(48000, 2);
+        meter.add(samples);
+        meter.integrated()
+    }
+
+    #[test]
+    fn full_scale_stereo_sine_is_zero_lufs() {
+        let lufs = measure(&sine(1.0, 2.0)).unwrap();
+        assert!(lufs.abs() < 0.1, "{} LUFS", lufs);
+    }
+
+    #[test]
+    fn follows_the_level() {
+        let lufs = measure(&sine(0.1, 2.0)).unwrap();
+        assert!((lufs + 20.0).abs() < 0.1, "{} LUFS", lufs);
+    }
+
+    #[test]
+    fn silence_and_short_audio_measure_nothing() {
+        assert_eq!(measure(&vec![0.0; 96000 * 2]), None);
+        // Less than one 400 ms block
+        assert_eq!(measure(&sine(1.0, 0.3)), None);
+    }
+
+    #[test]
+    fn pauses_are_gated_out() {
+        let mut samples = sine(0.1, 2.0);
+        samples.extend(vec![0.0; 96000 * 2]);
+        samples.extend(sine(0.1, 2.0));
+        let lufs = measure(&samples).unwrap();
+        // The blocks straddling the pause still count, averaged in it'd be
+        // almost -22
+        assert!((lufs + 20.0).abs() < 0.5, "{} LUFS", lufs);
+    }
+
+    #[test]
+    fn pieces_measure_like_the_whole() {
+        let samples = sine(0.5, 2.0);
+        let mut meter = LoudnessMeter::new(48000, 2);
+        for piece in samples.chunks(1234 * 2) {
+            meter.add(piece);
+        }
+        assert_eq!(meter.integrated(), measure(&samples));
+    }
+}
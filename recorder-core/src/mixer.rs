@@ -0,0 +1,386 @@
+//! Lines up system and mic audio, both stereo at the recording's rate, frame
+//! by frame and averages them into the recording.
+//!
+//! The two come from different clocks, so over hours one delivers slightly
+//! more than the other. Mixing is paced by the slower one and the faster
+//! one's leftovers pile up, so once that backlog has grown past
+//! `DRIFT_TOLERANCE_MS` the mixer folds pairs of its frames into one until
+//! it's back. That's a frame every few thousand at worst, not audible.
+//!
+//! Their latencies differ too, see `align`. That's a fixed offset, applied as
+//! silence ahead of the quicker source whenever the buffers are cleared.
+//!
+//! Each source waits in a bounded `SampleBuffer`. Whatever a full one loses
+//! is counted and handed out by `overruns` about once a second.
+//!
+//! A recording may also capture only one of them, the other then counts as
+//! silence and the one captured sets the pace.
+//!
+//! The mix can also go past full scale. That's counted the same way and
+//! handed out by `clipping`, and with `set_limiter` a `Limiter` turns it
+//! down before it reaches the file.
+
+use crate::align::Latency;
+use crate::buffer::{BufferLimits, OverflowPolicy, SampleBuffer, Source};
+use crate::levels::LevelMeter;
+use crate::limiter::Limiter;
+use crate::writer::WavFile;
+use crate::SAMPLE_RATE;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::time::{Duration, Instant};
+
+/// How far one source may drift ahead.
+const DRIFT_TOLERANCE_MS: f32 = 20.0;
+/// How long after the first mix the backlog is taken as the starting point.
+/// Sources start at different times, and that offset isn't drift.
+const DRIFT_WARMUP: Duration = Duration::from_secs(5);
+const WARMUP_SMOOTHING: f32 = 0.05;
+/// Per drain, callbacks come in every 10-20 ms so this evens out their
+/// jitter over tens of seconds.
+const DRIFT_SMOOTHING: f32 = 0.001;
+
+struct DriftTracker {
+    /// `DRIFT_TOLERANCE_MS` in frames
+    tolerance: f32,
+    started: Option<Instant>,
+    /// Smoothed system minus mic frames left over after a drain
+    backlog: f32,
+    /// Where `backlog` settled during `DRIFT_WARMUP`
+    baseline: Option<f32>,
+}
+
+impl DriftTracker {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            tolerance: sample_rate as f32 * DRIFT_TOLERANCE_MS / 1000.0,
+            started: None,
+            backlog: 0.0,
+            baseline: None,
+        }
+    }
+
+    /// Takes the backlog left after a drain and says which source should
+    /// lose a frame: `Greater` system, `Less` mic.
+    fn update(&mut self, backlog: f32) -> Ordering {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let Some(baseline) = self.baseline else {
+            self.backlog += (backlog - self.backlog) * WARMUP_SMOOTHING;
+            if started.elapsed() >= DRIFT_WARMUP {
+                self.baseline = Some(self.backlog);
+            }
+            return Ordering::Equal;
+        };
+
+        self.backlog += (backlog - self.backlog) * DRIFT_SMOOTHING;
+        let drift = self.backlog - baseline;
+        // Counted right away so the lag of the smoothing doesn't overshoot
+        if drift > self.tolerance {
+            self.backlog -= 1.0;
+            Ordering::Greater
+        } else if drift < -self.tolerance {
+            self.backlog += 1.0;
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+/// How often `Mixer::overruns` and `Mixer::clipping` report what happened
+/// since.
+const OVERRUN_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferOverrun {
+    pub source: Source,
+    pub dropped_frames: u64,
+    pub policy: OverflowPolicy,
+}
+
+#[derive(Default)]
+struct Overruns {
+    // Samples dropped since the last report
+    system: usize,
+    mic: usize,
+    last_report: Option<Instant>,
+}
+
+/// Mixed samples past full scale. With `limited` the limiter caught them
+/// before the file, otherwise they were written clipped.
+#[derive(Debug, Clone, Serialize)]
+pub struct Clipping {
+    pub clipped_samples: u64,
+    /// Loudest sample, as an amplitude over 1.0
+    pub peak: f32,
+    pub limited: bool,
+}
+
+#[derive(Default)]
+struct Clips {
+    // Since the last report
+    samples: usize,
+    peak: f32,
+    last_report: Option<Instant>,
+}
+
+/// Which sources are captured.
+#[derive(Clone, Copy)]
+struct Capturing {
+    system: bool,
+    mic: bool,
+}
+
+impl Default for Capturing {
+    fn default() -> Self {
+        Self {
+            system: true,
+            mic: true,
+        }
+    }
+}
+
+pub struct Mixer {
+    system: Mutex<SampleBuffer>,
+    mic: Mutex<SampleBuffer>,
+    capturing: Mutex<Capturing>,
+    sample_rate: Mutex<u32>,
+    drift: Mutex<DriftTracker>,
+    latency: Mutex<Latency>,
+    limits: Mutex<BufferLimits>,
+    overruns: Mutex<Overruns>,
+    clips: Mutex<Clips>,
+    limiter: Mutex<Option<Limiter>>,
+    levels: LevelMeter,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self {
+            system: Mutex::default(),
+            mic: Mutex::default(),
+            capturing: Mutex::default(),
+            sample_rate: Mutex::new(SAMPLE_RATE),
+            drift: Mutex::new(DriftTracker::new(SAMPLE_RATE)),
+            latency: Mutex::default(),
+            limits: Mutex::default(),
+            overruns: Mutex::default(),
+            clips: Mutex::default(),
+            limiter: Mutex::default(),
+            levels: LevelMeter::default(),
+        }
+    }
+}
+
+/// Simple mixing: averages the two sources.
+pub fn mix(system: [f32; 2], mic: [f32; 2]) -> [f32; 2] {
+    [(system[0] + mic[0]) / 2.0, (system[1] + mic[1]) / 2.0]
+}
+
+impl Mixer {
+    pub fn push(&self, source: Source, samples: &[f32]) {
+        let policy = self.limits.lock().policy;
+        let dropped = match source {
+            Source::System => {
+                self.levels.set_system(samples);
+                self.system.lock().push(samples, policy)
+            }
+            Source::Mic => {
+                self.levels.set_mic(samples);
+                self.mic.lock().push(samples, policy)
+            }
+        };
+        self.count_dropped(source, dropped);
+    }
+
+    /// Counts `samples` of `source` as lost before they reached the mixer.
+    pub fn count_dropped(&self, source: Source, samples: usize) {
+        if samples == 0 {
+            return;
+        }
+        let mut overruns = self.overruns.lock();
+        match source {
+            Source::System => overruns.system += samples,
+            Source::Mic => overruns.mic += samples,
+        }
+    }
+
+    /// What the sources deliver at. Takes effect from the next `clear`,
+    /// like `set_latency`.
+    pub fn set_sample_rate(&self, sample_rate: u32) {
+        *self.sample_rate.lock() = sample_rate;
+    }
+
+    /// Which sources deliver anything, the other is mixed as silence.
+    pub fn set_sources(&self, system: bool, mic: bool) {
+        *self.capturing.lock() = Capturing { system, mic };
+    }
+
+    /// Takes effect from the next `clear`, like `set_latency`.
+    pub fn set_buffer_limits(&self, limits: BufferLimits) {
+        *self.limits.lock() = limits;
+    }
+
+    /// What each source lost to a full buffer since the last report, at
+    /// most once per `OVERRUN_REPORT_INTERVAL`. Usually nothing.
+    pub fn overruns(&self) -> Vec<BufferOverrun> {
+        let mut overruns = self.overruns.lock();
+        let due = overruns
+            .last_report
+            .is_none_or(|at| at.elapsed() >= OVERRUN_REPORT_INTERVAL);
+        if !due || overruns.system + overruns.mic == 0 {
+            return Vec::new();
+        }
+        let policy = self.limits.lock().policy;
+        let reports = [
+            (Source::System, std::mem::take(&mut overruns.system)),
+            (Source::Mic, std::mem::take(&mut overruns.mic)),
+        ]
+        .into_iter()
+        .filter(|(_, dropped)| *dropped > 0)
+        .map(|(source, dropped)| BufferOverrun {
+            source,
+            dropped_frames: dropped as u64 / 2,
+            policy,
+        })
+        .collect();
+        overruns.last_report = Some(Instant::now());
+        reports
+    }
+
+    /// Whether the mix goes through a `Limiter` before the file. Takes
+    /// effect from the next `clear`, like `set_latency`.
+    pub fn set_limiter(&self, enabled: bool) {
+        let sample_rate = *self.sample_rate.lock();
+        *self.limiter.lock() = enabled.then(|| Limiter::new(sample_rate));
+    }
+
+    /// How much of the mix went past full scale since the last report, at
+    /// most once per `OVERRUN_REPORT_INTERVAL`. Usually nothing.
+    pub fn clipping(&self) -> Option<Clipping> {
+        let mut clips = self.clips.lock();
+        let due = clips
+            .last_report
+            .is_none_or(|at| at.elapsed() >= OVERRUN_REPORT_INTERVAL);
+        if !due || clips.samples == 0 {
+            return None;
+        }
+        let clipping = Clipping {
+            clipped_samples: std::mem::take(&mut clips.samples) as u64,
+            peak: std::mem::take(&mut clips.peak),
+            limited: self.limiter.lock().is_some(),
+        };
+        clips.last_report = Some(Instant::now());
+        Some(clipping)
+    }
+
+    /// Takes effect from the next `clear`, recordings clear as they start.
+    pub fn set_latency(&self, latency: Latency) {
+        *self.latency.lock() = latency;
+    }
+
+    pub fn latency(&self) -> Latency {
+        *self.latency.lock()
+    }
+
+    pub fn levels(&self) -> &LevelMeter {
+        &self.levels
+    }
+
+    /// Hands every frame both sources have delivered so far to `frame`, as
+    /// its system and mic halves.
+    pub fn drain(&self, mut frame: impl FnMut([f32; 2], [f32; 2])) {
+        let capturing = *self.capturing.lock();
+        let mut sys = self.system.lock();
+        let mut mic = self.mic.lock();
+        if !(capturing.system && capturing.mic) {
+            if !capturing.system && !capturing.mic {
+                return;
+            }
+            // Nothing to line up or drift against
+            let take = |buffer: &mut SampleBuffer, on: bool| match on {
+                true => buffer.pop_frame(),
+                false => Some([0.0; 2]),
+            };
+            loop {
+                let system_frame = take(&mut sys, capturing.system);
+                let mic_frame = take(&mut mic, capturing.mic);
+                let (Some(system_frame), Some(mic_frame)) = (system_frame, mic_frame) else {
+                    return;
+                };
+                frame(system_frame, mic_frame);
+            }
+        }
+        while sys.len() >= 2 && mic.len() >= 2 {
+            frame(sys.pop_frame().unwrap(), mic.pop_frame().unwrap());
+        }
+
+        let backlog = (sys.len() as f32 - mic.len() as f32) / 2.0;
+        match self.drift.lock().update(backlog) {
+            Ordering::Greater => sys.drop_frame(),
+            Ordering::Less => mic.drop_frame(),
+            Ordering::Equal => {}
+        }
+    }
+
+    /// Mixes every frame both sources have delivered into `writer`, showing
+    /// each to `each` as system, mic and mix on the way. Clipping is counted
+    /// before the limiter, the mix shown is what's written.
+    pub fn mix_into(
+        &self,
+        writer: &mut WavFile,
+        mut each: impl FnMut([f32; 2], [f32; 2], [f32; 2]),
+    ) {
+        let mut sum = 0.0f32;
+        let mut frames = 0u32;
+        let mut clipped = 0usize;
+        let mut peak = 0.0f32;
+        let mut limiter = self.limiter.lock();
+        self.drain(|system, mic| {
+            let mut mixed = mix(system, mic);
+            for sample in mixed {
+                if sample.abs() > 1.0 {
+                    clipped += 1;
+                    peak = peak.max(sample.abs());
+                }
+            }
+            if let Some(limiter) = limiter.as_mut() {
+                mixed = limiter.process(mixed);
+            }
+            sum += mixed[0] * mixed[0] + mixed[1] * mixed[1];
+            frames += 1;
+            each(system, mic, mixed);
+
+            let _ = writer.write_frame(mixed);
+        });
+        self.levels.add_mixed(sum, frames);
+        if clipped > 0 {
+            let mut clips = self.clips.lock();
+            clips.samples += clipped;
+            clips.peak = clips.peak.max(peak);
+        }
+    }
+
+    /// Drops audio not mixed yet and lines the sources up again. Drift is
+    /// measured afresh from here.
+    pub fn clear(&self) {
+        let sample_rate = *self.sample_rate.lock();
+        let (system_delay, mic_delay) = self.latency().delays(sample_rate);
+        let capacity = self.limits.lock().capacity(sample_rate);
+        // Taken before the buffers, as in `mix_into`
+        if let Some(limiter) = self.limiter.lock().as_mut() {
+            *limiter = Limiter::new(sample_rate);
+        }
+        let mut sys = self.system.lock();
+        let mut mic = self.mic.lock();
+        // The delay comes on top, it's not backlog
+        sys.reset(capacity + system_delay * 2);
+        mic.reset(capacity + mic_delay * 2);
+        sys.push_silence(system_delay);
+        mic.push_silence(mic_delay);
+        *self.drift.lock() = DriftTracker::new(sample_rate);
+        *self.overruns.lock() = Overruns::default();
+        *self.clips.lock() = Clips::default();
+    }
+}
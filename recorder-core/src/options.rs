@@ -0,0 +1,91 @@
+//! What the callers of `start_recording` and `stop_recording` get to choose
+//! about the file.
+
+use crate::loudness::DEFAULT_TARGET_LUFS;
+use crate::writer::BitDepth;
+use crate::SAMPLE_RATE;
+use serde::{Deserialize, Serialize};
+
+/// The rates recordings can be made at.
+pub const SAMPLE_RATES: [u32; 3] = [44100, 48000, 96000];
+
+/// Sources are mixed in stereo either way, mono is downmixed as it's written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Channels {
+    /// Both sources averaged into one channel, for voice memos
+    Mono,
+    #[default]
+    Stereo,
+}
+
+impl Channels {
+    pub fn count(self) -> u16 {
+        match self {
+            Channels::Mono => 1,
+            Channels::Stereo => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct RecordingOptions {
+    /// Unset, see `file_bit_depth`
+    pub bit_depth: Option<BitDepth>,
+    /// One of `SAMPLE_RATES`
+    pub sample_rate: u32,
+    pub channels: Channels,
+    /// Runs the mix through a `Limiter` so it can't clip
+    pub limiter: bool,
+}
+
+impl Default for RecordingOptions {
+    fn default() -> Self {
+        Self {
+            bit_depth: None,
+            sample_rate: SAMPLE_RATE,
+            channels: Channels::default(),
+            limiter: false,
+        }
+    }
+}
+
+impl RecordingOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !SAMPLE_RATES.contains(&self.sample_rate) {
+            return Err(format!(
+                "Recordings can be made at 44.1, 48 or 96 kHz, not {} Hz",
+                self.sample_rate
+            ));
+        }
+        Ok(())
+    }
+
+    /// `bit_depth`, or when it's unset 16-bit for mono, which is all a voice
+    /// needs and a quarter the size of float stereo, and float otherwise.
+    pub fn file_bit_depth(&self) -> BitDepth {
+        self.bit_depth.unwrap_or(match self.channels {
+            Channels::Mono => BitDepth::Pcm16,
+            Channels::Stereo => BitDepth::default(),
+        })
+    }
+}
+
+/// What happens to a recording once it's stopped.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct FinalizeOptions {
+    /// Runs `loudness::normalize` over the file
+    pub normalize: bool,
+    pub target_lufs: f32,
+}
+
+impl Default for FinalizeOptions {
+    fn default() -> Self {
+        Self {
+            normalize: false,
+            target_lufs: DEFAULT_TARGET_LUFS,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Lock file shared by every playground recorder, so two apps don't fight
+//! over the microphone. Holds the owner's PID and session info while a
+//! recording runs and is removed when the guard drops.
+
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LOCK_FILE: &str = "tauri-playground-recording.lock";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub pid: u32,
+    pub app: String,
+    pub started_at: u64,
+    pub output: String,
+}
+
+pub struct RecordingLock {
+    path: PathBuf,
+}
+
+impl Drop for RecordingLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn lock_path() -> PathBuf {
+    std::env::temp_dir().join(LOCK_FILE)
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks that the process exists
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // An exited process can be opened while handles to it remain, so its
+    // exit code is what tells
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return false;
+        }
+        let mut code = 0;
+        let running = GetExitCodeProcess(process, &mut code) != 0 && code == STILL_ACTIVE as u32;
+        CloseHandle(process);
+        running
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Who holds the lock right now, ignoring locks left behind by a crash.
+pub fn holder() -> Option<LockInfo> {
+    let json = std::fs::read_to_string(lock_path()).ok()?;
+    let info: LockInfo = serde_json::from_str(&json).ok()?;
+    process_alive(info.pid).then_some(info)
+}
+
+fn create(path: &Path, info: &LockInfo) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(serde_json::to_string(info)?.as_bytes())
+}
+
+/// Claims the recording lock for this process, or explains who has it.
+pub fn acquire(app: &str, output: &Path) -> Result<RecordingLock, String> {
+    let path = lock_path();
+    let info = LockInfo {
+        pid: std::process::id(),
+        app: app.to_string(),
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        output: output.to_string_lossy().to_string(),
+    };
+
+    match create(&path, &info) {
+        Ok(()) => return Ok(RecordingLock { path }),
+        Err(e) if e.kind() != ErrorKind::AlreadyExists => return Err(e.to_string()),
+        Err(_) => {}
+    }
+
+    if let Some(owner) = holder() {
+        return Err(format!(
+            "{} (pid {}) is already recording, stop it first",
+            owner.app, owner.pid
+        ));
+    }
+
+    // Stale lock from a recorder that didn't shut down cleanly
+    let _ = std::fs::remove_file(&path);
+    create(&path, &info).map_err(|e| e.to_string())?;
+    Ok(RecordingLock { path })
+}
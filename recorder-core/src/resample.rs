@@ -0,0 +1,141 @@
+//! Converting a source's audio to the recording's rate and channel count
+//! when the hardware can't deliver it as is. Linear interpolation, which is
+//! plenty for voices and for hearing what the system plays.
+
+pub struct Resampler {
+    /// Source frames per output frame
+    step: f64,
+    in_channels: usize,
+    out_channels: usize,
+    /// Where the next output frame falls between `previous` and the next
+    /// source frame, 0 at `previous`
+    position: f64,
+    previous: Option<Vec<f32>>,
+    frame: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32, to_rate: u32, in_channels: u16, out_channels: u16) -> Self {
+        Self {
+            step: from_rate as f64 / to_rate as f64,
+            in_channels: in_channels.max(1) as usize,
+            out_channels: out_channels.max(1) as usize,
+            position: 0.0,
+            previous: None,
+            frame: vec![0.0; out_channels.max(1) as usize],
+        }
+    }
+
+    /// Whether `process` only copies.
+    pub fn is_passthrough(&self) -> bool {
+        self.step == 1.0 && self.in_channels == self.out_channels
+    }
+
+    // Mono is the average of every channel, anything else takes the first
+    // channels and repeats the last one as needed
+    fn map_channels(&mut self, input: &[f32]) {
+        if self.out_channels == 1 {
+            self.frame[0] = input.iter().sum::<f32>() / input.len() as f32;
+            return;
+        }
+        for (channel, out) in self.frame.iter_mut().enumerate() {
+            *out = input[channel.min(input.len() - 1)];
+        }
+    }
+
+    /// Converts interleaved `input`, appending the result to `output`.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.is_passthrough() {
+            output.extend_from_slice(input);
+            return;
+        }
+        for input in input.chunks_exact(self.in_channels) {
+            self.map_channels(input);
+            let Some(previous) = self.previous.as_mut() else {
+                self.previous = Some(self.frame.clone());
+                continue;
+            };
+            while self.position < 1.0 {
+                let t = self.position as f32;
+                output.extend(
+                    previous
+                        .iter()
+                        .zip(&self.frame)
+                        .map(|(from, to)| from + (to - from) * t),
+                );
+                self.position += self.step;
+            }
+            self.position -= 1.0;
+            previous.copy_from_slice(&self.frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(resampler: &mut Resampler, input: &[f32]) -> Vec<f32> {
+        let mut output = Vec::new();
+        resampler.process(input, &mut output);
+        output
+    }
+
+    #[test]
+    fn same_format_is_copied() {
+        let mut resampler = Resampler::new(48000, 48000, 2, 2);
+        assert!(resampler.is_passthrough());
+        assert_eq!(process(&mut resampler, &[0.1, 0.2, 0.3]), [0.1, 0.2, 0.3]);
+    }
+
+    #[test]
+    fn upsampling_interpolates() {
+        let mut resampler = Resampler::new(24000, 48000, 1, 1);
+        assert!(!resampler.is_passthrough());
+        assert_eq!(
+            process(&mut resampler, &[0.0, 1.0, 0.0]),
+            [0.0, 0.5, 1.0, 0.5]
+        );
+    }
+
+    #[test]
+    fn downsampling_skips_frames() {
+        let mut resampler = Resampler::new(96000, 48000, 1, 1);
+        assert_eq!(
+            process(&mut resampler, &[0.0, 0.1, 0.2, 0.3, 0.4]),
+            [0.0, 0.2]
+        );
+    }
+
+    #[test]
+    fn rate_holds_across_calls() {
+        let mut resampler = Resampler::new(44100, 48000, 2, 2);
+        let input = vec![0.0; 441 * 2];
+        let frames: usize = (0..100)
+            .map(|_| process(&mut resampler, &input).len() / 2)
+            .sum();
+        // A second of input, less the frame still waiting for its successor
+        assert!((47_998..=48_000).contains(&frames), "{} frames", frames);
+    }
+
+    #[test]
+    fn mono_is_the_average() {
+        let mut resampler = Resampler::new(48000, 48000, 2, 1);
+        process(&mut resampler, &[0.2, 0.4]);
+        assert_eq!(process(&mut resampler, &[0.6, 1.0]), [0.3]);
+    }
+
+    #[test]
+    fn mono_is_repeated_to_stereo() {
+        let mut resampler = Resampler::new(48000, 48000, 1, 2);
+        process(&mut resampler, &[0.5]);
+        assert_eq!(process(&mut resampler, &[0.25]), [0.5, 0.5]);
+    }
+
+    #[test]
+    fn surround_keeps_the_front_pair() {
+        let mut resampler = Resampler::new(48000, 48000, 6, 2);
+        process(&mut resampler, &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+        assert_eq!(process(&mut resampler, &[0.0; 6]), [0.1, 0.2]);
+    }
+}
@@ -0,0 +1,300 @@
+//! What a recording holds on to: its two sources, the mixer they feed and
+//! the thread mixing into the file.
+//!
+//! The capture callbacks only copy their samples into a lock-free ring
+//! buffer and wake that thread, which does the mixing, the writing and
+//! whatever the app reports. Nothing a real-time audio thread does can wait
+//! on a lock or an allocation, so the UI being busy can't cause a dropout.
+
+use crate::buffer::Source;
+use crate::capture::{CaptureSource, SampleCallback};
+use crate::mixer::Mixer;
+use crate::options::RecordingOptions;
+use crate::writer::WavFile;
+use crate::CHANNELS;
+use parking_lot::Mutex;
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{JoinHandle, Thread};
+use std::time::Duration;
+
+/// Runs on the mixing thread after each delivery, with the file still open.
+/// Usually `Mixer::mix_into` plus whatever the app reports.
+pub type MixFn = Box<dyn FnMut(&Mixer, &mut WavFile) + Send>;
+
+/// Where the mixing thread puts the audio, for apps that write something
+/// other than the one WAV `Recorder::start` makes: other formats, a file per
+/// source, processing of their own. Both run on the mixing thread.
+pub trait Output: Send {
+    /// Takes what the sources delivered, usually through `Mixer::drain`.
+    fn mix(&mut self, mixer: &Mixer);
+    /// Closes the files once the last delivery is mixed.
+    fn finish(self: Box<Self>) -> Result<(), String>;
+}
+
+/// What `Recorder::start` records into.
+struct WavOutput {
+    writer: WavFile,
+    mix: MixFn,
+}
+
+impl Output for WavOutput {
+    fn mix(&mut self, mixer: &Mixer) {
+        (self.mix)(mixer, &mut self.writer);
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        self.writer.finalize()
+    }
+}
+
+/// The thread is woken for every delivery, this is in case one is missed.
+const IDLE_WAIT: Duration = Duration::from_millis(10);
+
+/// The mixing thread's end of one source's ring buffer.
+struct Queue {
+    source: Source,
+    consumer: Consumer<f32>,
+    // Samples that didn't fit, counted by the callback
+    dropped: Arc<AtomicUsize>,
+}
+
+impl Queue {
+    /// Moves everything waiting into the mixer, returning whether there was
+    /// anything.
+    fn drain_into(&mut self, mixer: &Mixer, scratch: &mut Vec<f32>) -> bool {
+        mixer.count_dropped(self.source, self.dropped.swap(0, Ordering::Relaxed));
+        let Ok(chunk) = self.consumer.read_chunk(self.consumer.slots()) else {
+            return false;
+        };
+        if chunk.is_empty() {
+            return false;
+        }
+        // One push, the level is measured over what's pushed
+        let (first, second) = chunk.as_slices();
+        scratch.clear();
+        scratch.extend_from_slice(first);
+        scratch.extend_from_slice(second);
+        chunk.commit_all();
+        mixer.push(self.source, scratch);
+        true
+    }
+}
+
+/// Copies what a source delivers into its ring buffer and wakes the mixing
+/// thread. What doesn't fit is dropped and counted.
+fn feed(mut producer: Producer<f32>, dropped: Arc<AtomicUsize>, mixing: Thread) -> SampleCallback {
+    Box::new(move |samples| {
+        // Whole frames only
+        let fits = samples.len().min(producer.slots()) & !1;
+        if let Ok(chunk) = producer.write_chunk_uninit(fits) {
+            chunk.fill_from_iter(samples.iter().copied());
+        }
+        if fits < samples.len() {
+            dropped.fetch_add(samples.len() - fits, Ordering::Relaxed);
+        }
+        mixing.unpark();
+    })
+}
+
+/// The mixing thread, which hands the output back once stopped.
+struct Mixing {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Box<dyn Output>>,
+}
+
+impl Mixing {
+    /// Starts the thread and returns it with the callbacks for system and
+    /// mic audio.
+    fn spawn(
+        mixer: Arc<Mixer>,
+        sample_rate: u32,
+        mut output: Box<dyn Output>,
+    ) -> (Self, SampleCallback, SampleCallback) {
+        // A second per source, far more than the thread ever falls behind
+        let capacity = sample_rate as usize * CHANNELS as usize;
+        let (system_producer, system) = RingBuffer::new(capacity);
+        let (mic_producer, mic) = RingBuffer::new(capacity);
+        let system_dropped = Arc::new(AtomicUsize::new(0));
+        let mic_dropped = Arc::new(AtomicUsize::new(0));
+        let mut queues = [
+            Queue {
+                source: Source::System,
+                consumer: system,
+                dropped: system_dropped.clone(),
+            },
+            Queue {
+                source: Source::Mic,
+                consumer: mic,
+                dropped: mic_dropped.clone(),
+            },
+        ];
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = std::thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let mut scratch = Vec::with_capacity(capacity);
+                loop {
+                    // Checked first so what came before the stop still gets mixed
+                    let stopping = stop.load(Ordering::Acquire);
+                    let mut delivered = false;
+                    for queue in &mut queues {
+                        delivered |= queue.drain_into(&mixer, &mut scratch);
+                    }
+                    if delivered {
+                        output.mix(&mixer);
+                    }
+                    if stopping {
+                        return output;
+                    }
+                    std::thread::park_timeout(IDLE_WAIT);
+                }
+            }
+        });
+
+        let system = feed(system_producer, system_dropped, thread.thread().clone());
+        let mic = feed(mic_producer, mic_dropped, thread.thread().clone());
+        (Self { stop, thread }, system, mic)
+    }
+
+    /// Mixes what was delivered so far and finishes the output.
+    fn finish(self) -> Result<(), String> {
+        self.stop.store(true, Ordering::Release);
+        self.thread.thread().unpark();
+        let output = self
+            .thread
+            .join()
+            .map_err(|_| "The mixing thread panicked".to_string())?;
+        output.finish()
+    }
+}
+
+#[derive(Default)]
+pub struct Recorder {
+    pub system_source: Option<Box<dyn CaptureSource>>,
+    pub mic_source: Option<Box<dyn CaptureSource>>,
+    /// Of the current or last recording
+    pub file_path: Option<PathBuf>,
+    pub mixer: Arc<Mixer>,
+    mixing: Option<Mixing>,
+}
+
+impl Recorder {
+    pub fn is_recording(&self) -> bool {
+        self.system_source.is_some() || self.mic_source.is_some()
+    }
+
+    /// Creates `file_path` as `options` say and starts `system` and `mic`
+    /// feeding it through the mixer, see `MixFn`. Both need opening at
+    /// `options.sample_rate`.
+    pub fn start(
+        &mut self,
+        file_path: &Path,
+        options: &RecordingOptions,
+        system: Box<dyn CaptureSource>,
+        mic: Box<dyn CaptureSource>,
+        mix: MixFn,
+    ) -> Result<(), String> {
+        if self.is_recording() {
+            return Err("Already recording".to_string());
+        }
+        options.validate()?;
+        self.mixer.set_sample_rate(options.sample_rate);
+        self.mixer.set_limiter(options.limiter);
+        let output = WavOutput {
+            writer: WavFile::create(file_path, options)?,
+            mix,
+        };
+        self.start_output(
+            file_path,
+            options.sample_rate,
+            Some(system),
+            Some(mic),
+            Box::new(output),
+        )
+    }
+
+    /// Like `start`, with the app's own `output` in place of the WAV. A
+    /// source left out counts as silence. `file_path` is only what `stop`
+    /// hands back, the output has its files open already.
+    pub fn start_output(
+        &mut self,
+        file_path: &Path,
+        sample_rate: u32,
+        mut system: Option<Box<dyn CaptureSource>>,
+        mut mic: Option<Box<dyn CaptureSource>>,
+        output: Box<dyn Output>,
+    ) -> Result<(), String> {
+        if self.is_recording() {
+            return Err("Already recording".to_string());
+        }
+        if system.is_none() && mic.is_none() {
+            return Err("Nothing to record".to_string());
+        }
+        self.mixer.set_sample_rate(sample_rate);
+        self.mixer.set_sources(system.is_some(), mic.is_some());
+        self.mixer.clear();
+        let (mixing, system_feed, mic_feed) =
+            Mixing::spawn(self.mixer.clone(), sample_rate, output);
+
+        if let Some(system) = system.as_mut() {
+            system.on_samples(system_feed);
+        }
+        if let Some(mic) = mic.as_mut() {
+            mic.on_samples(mic_feed);
+        }
+        if let Some(Err(e)) = system.as_mut().map(|system| system.start()) {
+            let _ = mixing.finish();
+            return Err(e);
+        }
+        if let Some(Err(e)) = mic.as_mut().map(|mic| mic.start()) {
+            if let Some(system) = system.as_mut() {
+                system.stop();
+            }
+            let _ = mixing.finish();
+            return Err(e);
+        }
+
+        self.system_source = system;
+        self.mic_source = mic;
+        self.file_path = Some(file_path.to_path_buf());
+        self.mixing = Some(mixing);
+        Ok(())
+    }
+
+    /// Stops the sources and finishes the output, returning its path.
+    pub fn stop(&mut self) -> Result<PathBuf, String> {
+        for mut source in [self.system_source.take(), self.mic_source.take()]
+            .into_iter()
+            .flatten()
+        {
+            source.stop();
+        }
+        if let Some(mixing) = self.mixing.take() {
+            mixing.finish()?;
+        }
+
+        self.mixer.clear();
+        self.mixer.levels().reset();
+        self.file_path
+            .clone()
+            .ok_or_else(|| "Not recording".to_string())
+    }
+}
+
+/// Tauri state for apps that keep nothing beside the `Recorder`.
+#[derive(Default)]
+pub struct AppState(pub Mutex<Recorder>);
+
+impl AppState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.0.lock().is_recording()
+    }
+}
@@ -0,0 +1,26 @@
+//! The red dot the apps put in the menu bar while recording, as raw RGBA so
+//! each app can wrap it in whatever image type its tray takes.
+
+/// Edge length in pixels of the tray icons.
+pub const ICON_SIZE: u32 = 32;
+const RECORDING_COLOR: [u8; 3] = [0xff, 0x3b, 0x30];
+
+/// Renders a filled red dot, `ICON_SIZE` square, with `scale` 1.0 the full
+/// size. It's meant as a non-template image, so macOS keeps the color
+/// instead of matching the menu bar.
+pub fn recording_dot(scale: f32) -> Vec<u8> {
+    let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+    let radius = ICON_SIZE as f32 * 0.4 * scale;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let distance = ((x as f32 - center).powi(2) + (y as f32 - center).powi(2)).sqrt();
+            // One pixel of falloff keeps the edge smooth
+            let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+            rgba.extend_from_slice(&RECORDING_COLOR);
+            rgba.push((coverage * 255.0) as u8);
+        }
+    }
+    rgba
+}
@@ -0,0 +1,144 @@
+//! The WAV file a mix is written to, as float or as integer PCM. Going down
+//! to integers adds TPDF dither, a little noise that turns the rounding
+//! error into an even hiss instead of distortion on quiet passages.
+
+use crate::options::{Channels, RecordingOptions};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BitDepth {
+    /// 16-bit integer, half the size of float
+    Pcm16,
+    /// 24-bit integer
+    Pcm24,
+    /// 32-bit float, what the sources deliver
+    #[default]
+    Float32,
+}
+
+impl BitDepth {
+    pub fn bits(self) -> u16 {
+        match self {
+            BitDepth::Pcm16 => 16,
+            BitDepth::Pcm24 => 24,
+            BitDepth::Float32 => 32,
+        }
+    }
+
+    /// The depth a file was written at, if it's one of these.
+    pub fn from_spec(spec: WavSpec) -> Option<Self> {
+        match (spec.sample_format, spec.bits_per_sample) {
+            (SampleFormat::Int, 16) => Some(BitDepth::Pcm16),
+            (SampleFormat::Int, 24) => Some(BitDepth::Pcm24),
+            (SampleFormat::Float, 32) => Some(BitDepth::Float32),
+            _ => None,
+        }
+    }
+
+    pub fn spec(self, sample_rate: u32, channels: u16) -> WavSpec {
+        WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: self.bits(),
+            sample_format: match self {
+                BitDepth::Float32 => SampleFormat::Float,
+                BitDepth::Pcm16 | BitDepth::Pcm24 => SampleFormat::Int,
+            },
+        }
+    }
+}
+
+/// Writes float samples at a `BitDepth`, for apps with their own writers.
+pub struct SampleEncoder {
+    bit_depth: BitDepth,
+    max: f32,
+    noise: u32,
+}
+
+impl SampleEncoder {
+    pub fn new(bit_depth: BitDepth) -> Self {
+        Self {
+            bit_depth,
+            max: ((1i64 << (bit_depth.bits() - 1)) - 1) as f32,
+            noise: 0x2545_f491,
+        }
+    }
+
+    // Uniform in [0, 1)
+    fn next_noise(&mut self) -> f32 {
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        self.noise as f32 / (u32::MAX as f32 + 1.0)
+    }
+
+    pub fn write<W: Write + Seek>(
+        &mut self,
+        writer: &mut WavWriter<W>,
+        sample: f32,
+    ) -> hound::Result<()> {
+        if self.bit_depth == BitDepth::Float32 {
+            return writer.write_sample(sample);
+        }
+        // Two uniform draws make triangular noise of up to one step either way
+        let dither = self.next_noise() - self.next_noise();
+        let scaled = sample.clamp(-1.0, 1.0) * self.max + dither;
+        writer.write_sample(scaled.round().clamp(-self.max - 1.0, self.max) as i32)
+    }
+}
+
+/// Samples of a WAV at any `BitDepth`, as floats.
+pub fn read_samples<R: Read + 'static>(
+    reader: WavReader<R>,
+) -> Box<dyn Iterator<Item = hound::Result<f32>>> {
+    let spec = reader.spec();
+    match spec.sample_format {
+        SampleFormat::Float => Box::new(reader.into_samples::<f32>()),
+        SampleFormat::Int => {
+            let max = ((1i64 << (spec.bits_per_sample - 1)) - 1) as f32;
+            Box::new(
+                reader
+                    .into_samples::<i32>()
+                    .map(move |sample| sample.map(|s| s as f32 / max)),
+            )
+        }
+    }
+}
+
+pub struct WavFile {
+    writer: WavWriter<BufWriter<File>>,
+    encoder: SampleEncoder,
+    mono: bool,
+}
+
+impl WavFile {
+    pub fn create(path: &Path, options: &RecordingOptions) -> Result<Self, String> {
+        let bit_depth = options.file_bit_depth();
+        let spec = bit_depth.spec(options.sample_rate, options.channels.count());
+        Ok(Self {
+            writer: WavWriter::create(path, spec).map_err(|e| e.to_string())?,
+            encoder: SampleEncoder::new(bit_depth),
+            mono: options.channels == Channels::Mono,
+        })
+    }
+
+    /// Writes a stereo frame, as its average in a mono file.
+    pub fn write_frame(&mut self, frame: [f32; 2]) -> hound::Result<()> {
+        if self.mono {
+            return self
+                .encoder
+                .write(&mut self.writer, (frame[0] + frame[1]) / 2.0);
+        }
+        self.encoder.write(&mut self.writer, frame[0])?;
+        self.encoder.write(&mut self.writer, frame[1])
+    }
+
+    pub fn finalize(self) -> Result<(), String> {
+        self.writer.finalize().map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,9 @@
+// Release builds ship with `createUpdaterArtifacts`, and an updater without
+// the key would accept no update at all, so they need it to build.
+fn main() {
+    println!("cargo:rerun-if-env-changed=TAURI_UPDATER_PUBKEY");
+    let has_key = std::env::var("TAURI_UPDATER_PUBKEY").is_ok_and(|key| !key.trim().is_empty());
+    if !has_key && std::env::var("PROFILE").as_deref() == Ok("release") {
+        panic!("TAURI_UPDATER_PUBKEY must hold the updater's public key for release builds");
+    }
+}
@@ -0,0 +1,156 @@
+//! Updates for the recorder apps. Each app checks its own release feed on a
+//! channel, keeps the update it found, and installs it once no recording is
+//! running. The public key comes from `TAURI_UPDATER_PUBKEY` at build time,
+//! release builds fail without it.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Url, Wry};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+const RELEASES_URL: &str = "https://github.com/cod3hulk/tauri-playground/releases/download";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+
+    fn endpoint(self, app_name: &str) -> String {
+        format!(
+            "{}/{}-{}/latest.json",
+            RELEASES_URL,
+            app_name,
+            self.as_str()
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct PendingUpdate {
+    update: Mutex<Option<Update>>,
+    // Set when an install was requested mid-recording
+    install_deferred: Mutex<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateAvailable {
+    pub version: String,
+    pub channel: UpdateChannel,
+    pub notes: Option<String>,
+}
+
+pub fn plugin() -> tauri::plugin::TauriPlugin<Wry> {
+    let mut builder = tauri_plugin_updater::Builder::new();
+    if let Some(pubkey) = option_env!("TAURI_UPDATER_PUBKEY") {
+        builder = builder.pubkey(pubkey);
+    }
+    builder.build()
+}
+
+/// Checks `app_name`'s feed on `channel` and remembers what it found, so
+/// the app only has to announce it. Needs `PendingUpdate` managed.
+pub async fn check(
+    app: &AppHandle,
+    app_name: &str,
+    channel: UpdateChannel,
+) -> Result<Option<UpdateAvailable>, String> {
+    let endpoint = Url::parse(&channel.endpoint(app_name)).map_err(|e| e.to_string())?;
+    let update = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .map_err(|e| e.to_string())?
+        .build()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let available = update.as_ref().map(|update| UpdateAvailable {
+        version: update.version.clone(),
+        channel,
+        notes: update.body.clone(),
+    });
+    *app.state::<PendingUpdate>().update.lock() = update;
+    Ok(available)
+}
+
+/// Checks `app_name`'s stable feed in the background, for apps without a
+/// channel setting, and emits `update-available` with the `UpdateAvailable`
+/// when there's an update.
+pub fn check_in_background(app: &AppHandle, app_name: &'static str) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match check(&app, app_name, UpdateChannel::Stable).await {
+            Ok(Some(update)) => {
+                let _ = app.emit("update-available", &update);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Update check failed: {}", e),
+        }
+    });
+}
+
+/// Version of the update waiting to be installed, if any.
+pub fn pending_version(app: &AppHandle) -> Option<String> {
+    app.state::<PendingUpdate>()
+        .update
+        .lock()
+        .as_ref()
+        .map(|update| update.version.clone())
+}
+
+async fn install(app: AppHandle) -> Result<(), String> {
+    let update = app
+        .state::<PendingUpdate>()
+        .update
+        .lock()
+        .take()
+        .ok_or("No update is pending")?;
+
+    update
+        .download_and_install(|_, _| {}, || {})
+        .await
+        .map_err(|e| e.to_string())?;
+    app.restart();
+}
+
+fn spawn_install(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = install(app).await {
+            eprintln!("Update install failed: {}", e);
+        }
+    });
+}
+
+/// Installs the pending update now, or once the current recording stops,
+/// see `resume_deferred_install`. Returns whether the install started right
+/// away.
+pub fn install_when_idle(app: &AppHandle, recording: bool) -> bool {
+    if recording {
+        *app.state::<PendingUpdate>().install_deferred.lock() = true;
+        return false;
+    }
+
+    spawn_install(app.clone());
+    true
+}
+
+/// Called after a recording stops to pick up an install that was put off.
+/// Doesn't look at the recorder state since the caller may still hold its lock.
+pub fn resume_deferred_install(app: &AppHandle) {
+    let deferred = std::mem::take(&mut *app.state::<PendingUpdate>().install_deferred.lock());
+    if deferred {
+        spawn_install(app.clone());
+    }
+}
@@ -1,17 +1,25 @@
+mod output;
+#[cfg(target_os = "macos")]
+mod screencapture;
+mod segments;
+mod system_audio;
+
 use anyhow::Result;
-use hound::{WavSpec, WavWriter};
 use parking_lot::Mutex;
-use screencapturekit::prelude::*;
-use std::fs::File;
-use std::io::BufWriter;
+use recorder_core::resample::Resampler;
+use recorder_core::{CaptureSource, RecorderError, RecordingOptions};
+use segments::SegmentedWriter;
 use std::path::PathBuf;
 use std::sync::Arc;
+use system_audio::SystemAudioSource;
 use tauri::{AppHandle, Manager, State};
 
 struct Recorder {
-    stream: Option<SCStream>,
+    source: Option<Box<dyn SystemAudioSource>>,
     file_path: Option<PathBuf>,
-    writer: Option<Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>>,
+    writer: Option<Arc<Mutex<Option<SegmentedWriter>>>>,
+    // Files finalized so far by the current or last recording
+    segments: Arc<Mutex<Vec<PathBuf>>>,
 }
 
 pub struct AppState(Mutex<Recorder>);
@@ -19,130 +27,79 @@ pub struct AppState(Mutex<Recorder>);
 impl AppState {
     pub fn new() -> Self {
         Self(Mutex::new(Recorder {
-            stream: None,
+            source: None,
             file_path: None,
             writer: None,
+            segments: Arc::new(Mutex::new(Vec::new())),
         }))
     }
 }
 
-struct AudioOutputHandler {
-    writer: Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>,
-}
-
-impl SCStreamOutputTrait for AudioOutputHandler {
-    fn did_output_sample_buffer(&self, sample: CMSampleBuffer, of_type: SCStreamOutputType) {
-        if let SCStreamOutputType::Audio = of_type {
-            if let Some(buffer_list) = sample.audio_buffer_list() {
-                let mut samples_to_write = Vec::new();
-                
-                // For ScreenCaptureKit audio, it's usually either:
-                // 1. One buffer with interleaved samples (if number_channels > 1)
-                // 2. Multiple buffers with one channel each (non-interleaved)
-                
-                let num_buffers = buffer_list.num_buffers();
-                if num_buffers == 1 {
-                    let buffer = buffer_list.get(0).unwrap();
-                    let data = buffer.data();
-                    let f32_samples: &[f32] = unsafe {
-                        std::slice::from_raw_parts(
-                            data.as_ptr() as *const f32,
-                            data.len() / 4
-                        )
-                    };
-                    samples_to_write.extend_from_slice(f32_samples);
-                } else {
-                    // Non-interleaved: multiple buffers. We need to interleave them for WAV.
-                    // This is more complex, but let's handle the common case (1 buffer interleaved) first.
-                    // If we have multiple, let's just take the first one for now or try to interleave.
-                    let mut channel_data = Vec::new();
-                    for i in 0..num_buffers {
-                        let buffer = buffer_list.get(i).unwrap();
-                        let data = buffer.data();
-                        let f32_samples: &[f32] = unsafe {
-                            std::slice::from_raw_parts(
-                                data.as_ptr() as *const f32,
-                                data.len() / 4
-                            )
-                        };
-                        channel_data.push(f32_samples);
-                    }
-                    
-                    if !channel_data.is_empty() {
-                        let len = channel_data[0].len();
-                        for i in 0..len {
-                            for channel in &channel_data {
-                                if i < channel.len() {
-                                    samples_to_write.push(channel[i]);
-                                }
-                            }
-                        }
-                    }
-                }
-
-                if !samples_to_write.is_empty() {
-                    let mut writer_lock = self.writer.lock();
-                    if let Some(writer) = writer_lock.as_mut() {
-                        for &s in &samples_to_write {
-                            let _ = writer.write_sample(s);
-                        }
-                    }
-                }
-            }
-        }
-    }
-}
-
+/// Starts recording system audio. With `bundle_id` only that app is heard,
+/// which needs macOS, otherwise everything the system plays. With `segment_minutes` a new file
+/// is started every that many minutes. `options` picks the bit depth, sample
+/// rate and channels, 32-bit float 48 kHz stereo by default and 16-bit for
+/// mono. Returns the first file.
 #[tauri::command]
-async fn start_recording(app: AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+async fn start_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    bundle_id: Option<String>,
+    segment_minutes: Option<u32>,
+    options: Option<RecordingOptions>,
+) -> Result<String, RecorderError> {
     let mut recorder = state.0.lock();
-    if recorder.stream.is_some() {
-        return Err("Already recording".to_string());
+    if recorder.source.is_some() {
+        return Err(RecorderError::AlreadyRecording);
+    }
+    if segment_minutes == Some(0) {
+        return Err(RecorderError::InvalidArgument(
+            "Segments must be at least a minute long".to_string(),
+        ));
     }
 
-    let content = SCShareableContent::get().map_err(|e| e.to_string())?;
-    let display = content
-        .displays()
-        .first()
-        .cloned()
-        .ok_or_else(|| "No display found".to_string())?;
-
-    let filter = SCContentFilter::create()
-        .with_display(&display)
-        .with_excluding_windows(&[])
-        .build();
-
-    let config = SCStreamConfiguration::new()
-        .with_captures_audio(true)
-        .with_sample_rate(48000)
-        .with_channel_count(2);
-
-    let audio_dir = app
-        .path()
-        .app_data_dir()
-        .unwrap_or_else(|_| PathBuf::from("."));
-    std::fs::create_dir_all(&audio_dir).map_err(|e| e.to_string())?;
-    let file_path = audio_dir.join("system_audio.wav");
-
-    let spec = WavSpec {
-        channels: 2,
-        sample_rate: 48000,
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
-    };
-
-    let writer = WavWriter::create(&file_path, spec).map_err(|e| e.to_string())?;
+    let options = options.unwrap_or_default();
+    options.validate().map_err(RecorderError::InvalidArgument)?;
+
+    let mut source = system_audio::open(bundle_id.clone(), options.sample_rate)?;
+    let format = source.format();
+    // Names the file after what's heard
+    let profile = bundle_id.unwrap_or_else(|| "system".to_string());
+
+    let file_path = app.state::<output::OutputStore>().next_path(&app, &profile)?;
+
+    let writer = SegmentedWriter::create(
+        &file_path,
+        format.sample_rate,
+        options.channels.count(),
+        options.file_bit_depth(),
+        segment_minutes,
+        recorder.segments.clone(),
+    )?;
+    let file_path = writer.current_path();
     let writer = Arc::new(Mutex::new(Some(writer)));
 
-    let handler = AudioOutputHandler {
-        writer: writer.clone(),
-    };
-
-    let mut stream = SCStream::new(&filter, &config);
-    stream.add_output_handler(handler, SCStreamOutputType::Audio);
-    stream.start_capture().map_err(|e| e.to_string())?;
+    let sink_writer = writer.clone();
+    // Only maps channels, the source already delivers at the file's rate
+    let mut downmix = Resampler::new(
+        format.sample_rate,
+        format.sample_rate,
+        format.channels,
+        options.channels.count(),
+    );
+    let mut mapped = Vec::new();
+    source.on_samples(Box::new(move |samples| {
+        mapped.clear();
+        downmix.process(samples, &mut mapped);
+        if let Some(writer) = sink_writer.lock().as_mut() {
+            if let Err(e) = writer.write(&mapped) {
+                eprintln!("Failed to write audio: {}", e);
+            }
+        }
+    }));
+    source.start().map_err(RecorderError::CaptureFailed)?;
 
-    recorder.stream = Some(stream);
+    recorder.source = Some(source);
     recorder.file_path = Some(file_path.clone());
     recorder.writer = Some(writer);
 
@@ -150,31 +107,76 @@ async fn start_recording(app: AppHandle, state: State<'_, AppState>) -> Result<S
 }
 
 #[tauri::command]
-async fn stop_recording(state: State<'_, AppState>) -> Result<String, String> {
+async fn stop_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, RecorderError> {
     let mut recorder = state.0.lock();
-    if let Some(stream) = recorder.stream.take() {
-        stream.stop_capture().map_err(|e| e.to_string())?;
+    if let Some(mut source) = recorder.source.take() {
+        source.stop();
         
         if let Some(writer_arc) = recorder.writer.take() {
             let mut writer_lock = writer_arc.lock();
             if let Some(writer) = writer_lock.take() {
-                writer.finalize().map_err(|e| e.to_string())?;
+                writer.finish()?;
             }
         }
+        recorder_updater::resume_deferred_install(&app);
 
         if let Some(path) = &recorder.file_path {
             return Ok(path.to_string_lossy().to_string());
         }
     }
-    Err("Not recording".to_string())
+    Err(RecorderError::NotRecording)
+}
+
+/// Files of the current or last recording, in order. While recording only
+/// the segments already finalized are listed.
+#[tauri::command]
+fn list_segments(state: State<'_, AppState>) -> Vec<String> {
+    state
+        .0
+        .lock()
+        .segments
+        .lock()
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+}
+
+/// Installs the update announced by `update-available`, once the recording
+/// stops if one is running. Returns whether the install started right away.
+#[tauri::command]
+fn install_update(app: AppHandle, state: State<'_, AppState>) -> Result<bool, RecorderError> {
+    if recorder_updater::pending_version(&app).is_none() {
+        return Err(RecorderError::NotFound("No update is pending".to_string()));
+    }
+    let recording = state.0.lock().source.is_some();
+    Ok(recorder_updater::install_when_idle(&app, recording))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .manage(AppState::new())
+        .manage(recorder_updater::PendingUpdate::default())
+        .setup(|app| {
+            app.manage(output::OutputStore::load(app.handle()));
+            recorder_updater::check_in_background(app.handle(), "system-audio-recorder");
+            Ok(())
+        })
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![start_recording, stop_recording])
+        .plugin(tauri_plugin_window_state::Builder::default().build())
+        .plugin(recorder_updater::plugin())
+        .invoke_handler(tauri::generate_handler![
+            start_recording,
+            stop_recording,
+            system_audio::list_capturable_apps,
+            list_segments,
+            output::get_output_settings,
+            output::set_output_settings,
+            install_update
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,126 @@
+//! Where recordings go and what they're called, kept in `output.json` in the
+//! app data dir. File names come from a template with the tokens `{date}`,
+//! `{time}`, `{profile}` (the captured app's bundle id, `system` for
+//! everything) and `{counter}`, the lowest number not yet taken in the folder.
+
+use crate::segments;
+use parking_lot::Mutex;
+use recorder_core::{filename, RecorderError};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputSettings {
+    /// The app data dir when unset
+    pub output_dir: Option<PathBuf>,
+    pub filename_template: String,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            output_dir: None,
+            filename_template: "system-audio-{date}-{time}".to_string(),
+        }
+    }
+}
+
+fn validate(template: &str) -> Result<(), RecorderError> {
+    match filename::is_valid(template) {
+        true => Ok(()),
+        false => Err(RecorderError::InvalidArgument(format!(
+            "Not a usable file name template: {}",
+            template
+        ))),
+    }
+}
+
+// Also taken when a segmented recording already starts with that name
+fn taken(path: &Path) -> bool {
+    path.is_file() || segments::segment_path(path, 0).is_file()
+}
+
+pub struct OutputStore {
+    store: PathBuf,
+    settings: Mutex<OutputSettings>,
+}
+
+impl OutputStore {
+    pub fn load(app: &AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+            .join("output.json");
+        let settings = std::fs::read_to_string(&store)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            store,
+            settings: Mutex::new(settings),
+        }
+    }
+
+    fn save(&self, settings: &OutputSettings) -> Result<(), RecorderError> {
+        if let Some(dir) = self.store.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+        Ok(std::fs::write(&self.store, json)?)
+    }
+
+    /// Path for a new recording, creating the folder if needed. Without
+    /// `{counter}` in the template a name that's already taken gets `-2`,
+    /// `-3`, ... appended instead.
+    pub fn next_path(&self, app: &AppHandle, profile: &str) -> Result<PathBuf, RecorderError> {
+        let settings = self.settings.lock().clone();
+        let dir = match settings.output_dir {
+            Some(dir) => dir,
+            None => app
+                .path()
+                .app_data_dir()
+                .unwrap_or_else(|_| PathBuf::from(".")),
+        };
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(filename::next_path(
+            &dir,
+            &settings.filename_template,
+            profile,
+            taken,
+        ))
+    }
+}
+
+#[tauri::command]
+pub fn get_output_settings(store: State<'_, OutputStore>) -> OutputSettings {
+    store.settings.lock().clone()
+}
+
+/// Sets the folder and file name template for the next recordings.
+#[tauri::command]
+pub fn set_output_settings(
+    store: State<'_, OutputStore>,
+    output_dir: Option<PathBuf>,
+    filename_template: String,
+) -> Result<(), RecorderError> {
+    validate(&filename_template)?;
+    if let Some(dir) = &output_dir {
+        if !dir.is_dir() {
+            return Err(RecorderError::NotFound(
+                "The output folder doesn't exist".to_string(),
+            ));
+        }
+    }
+
+    let settings = OutputSettings {
+        output_dir,
+        filename_template,
+    };
+    store.save(&settings)?;
+    *store.settings.lock() = settings;
+    Ok(())
+}
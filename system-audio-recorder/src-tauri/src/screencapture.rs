@@ -0,0 +1,72 @@
+//! System audio through ScreenCaptureKit, the only backend that can record a
+//! single app.
+
+use crate::system_audio::{CapturableApp, Format, SystemAudioSource};
+use recorder_core::{RecorderError, ScreenCaptureSource};
+use screencapturekit::prelude::*;
+
+fn capture_error(e: impl std::fmt::Display) -> RecorderError {
+    RecorderError::CaptureFailed(e.to_string())
+}
+
+pub fn capturable_apps() -> Result<Vec<CapturableApp>, RecorderError> {
+    let content = SCShareableContent::get().map_err(capture_error)?;
+    let mut apps: Vec<CapturableApp> = content
+        .applications()
+        .into_iter()
+        .map(|running| CapturableApp {
+            bundle_id: running.bundle_identifier(),
+            name: running.application_name(),
+        })
+        // Background helpers show up without a bundle id or name
+        .filter(|app| !app.bundle_id.is_empty() && !app.name.is_empty())
+        .collect();
+    apps.sort_by_key(|app| app.name.to_lowercase());
+    apps.dedup_by(|a, b| a.bundle_id == b.bundle_id);
+    Ok(apps)
+}
+
+/// With `bundle_id` only that app is heard, otherwise everything the display
+/// plays. Delivers at `sample_rate`.
+pub fn open(
+    bundle_id: Option<String>,
+    sample_rate: u32,
+) -> Result<ScreenCaptureSource, RecorderError> {
+    let content = SCShareableContent::get().map_err(capture_error)?;
+    let display = content
+        .displays()
+        .first()
+        .cloned()
+        .ok_or_else(|| RecorderError::DeviceNotFound("No display found".to_string()))?;
+
+    let filter = match bundle_id {
+        Some(bundle_id) => {
+            let target = content
+                .applications()
+                .into_iter()
+                .find(|running| running.bundle_identifier() == bundle_id)
+                .ok_or_else(|| {
+                    RecorderError::DeviceNotFound(format!("{} isn't running", bundle_id))
+                })?;
+            SCContentFilter::create()
+                .with_display(&display)
+                .with_including_applications(&[&target], &[])
+                .build()
+        }
+        None => SCContentFilter::create()
+            .with_display(&display)
+            .with_excluding_windows(&[])
+            .build(),
+    };
+    let config = ScreenCaptureSource::configure(SCStreamConfiguration::new(), sample_rate);
+    Ok(ScreenCaptureSource::new(&filter, &config, sample_rate))
+}
+
+impl SystemAudioSource for ScreenCaptureSource {
+    fn format(&self) -> Format {
+        Format {
+            sample_rate: self.sample_rate(),
+            channels: recorder_core::CHANNELS,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Dashcam-style recording: a long take is split into fixed-length WAV
+//! files, each finalized as soon as the next one starts, so a crash or a
+//! full disk only costs the segment being written.
+
+use hound::{WavSpec, WavWriter};
+use parking_lot::Mutex;
+use recorder_core::{BitDepth, RecorderError, SampleEncoder};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub struct SegmentedWriter {
+    /// Path of a single-file recording, or the base the segments are named after
+    base: PathBuf,
+    spec: WavSpec,
+    encoder: SampleEncoder,
+    /// Frames per file, `None` keeps everything in one
+    segment_frames: Option<u64>,
+    writer: Option<WavWriter<BufWriter<File>>>,
+    /// Frames in the current file, and samples of a frame not yet complete
+    frames: u64,
+    pending: u16,
+    /// Finalized files, shared so they can be listed while recording
+    finished: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+/// A WAV write or finalize that failed, as the commands report it.
+pub fn io_error(e: hound::Error) -> RecorderError {
+    RecorderError::IoError(e.to_string())
+}
+
+/// The `index`th file of a recording split into segments.
+pub fn segment_path(base: &Path, index: usize) -> PathBuf {
+    let stem = base.file_stem().unwrap_or_default().to_string_lossy();
+    base.with_file_name(format!("{}-{:03}.wav", stem, index + 1))
+}
+
+impl SegmentedWriter {
+    /// Opens the first file. With `segment_minutes` the files are
+    /// `<stem>-001.wav`, `<stem>-002.wav`, ... next to `base`.
+    pub fn create(
+        base: &Path,
+        sample_rate: u32,
+        channels: u16,
+        bit_depth: BitDepth,
+        segment_minutes: Option<u32>,
+        finished: Arc<Mutex<Vec<PathBuf>>>,
+    ) -> Result<Self, RecorderError> {
+        finished.lock().clear();
+        let mut writer = Self {
+            base: base.to_path_buf(),
+            spec: bit_depth.spec(sample_rate, channels),
+            encoder: SampleEncoder::new(bit_depth),
+            segment_frames: segment_minutes.map(|minutes| minutes as u64 * 60 * sample_rate as u64),
+            writer: None,
+            frames: 0,
+            pending: 0,
+            finished,
+        };
+        writer.open()?;
+        Ok(writer)
+    }
+
+    /// Path of the file being written.
+    pub fn current_path(&self) -> PathBuf {
+        match self.segment_frames {
+            Some(_) => segment_path(&self.base, self.finished.lock().len()),
+            None => self.base.clone(),
+        }
+    }
+
+    fn open(&mut self) -> Result<(), RecorderError> {
+        let writer = WavWriter::create(self.current_path(), self.spec).map_err(io_error)?;
+        self.writer = Some(writer);
+        self.frames = 0;
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), RecorderError> {
+        let path = self.current_path();
+        if let Some(writer) = self.writer.take() {
+            writer.finalize().map_err(io_error)?;
+            self.finished.lock().push(path);
+        }
+        Ok(())
+    }
+
+    /// Writes interleaved samples, moving to the next segment on the frame
+    /// boundary where the current one is full.
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), RecorderError> {
+        for &sample in samples {
+            if self.pending == 0 && self.segment_frames == Some(self.frames) {
+                self.close()?;
+                self.open()?;
+            }
+            let Some(writer) = self.writer.as_mut() else {
+                return Ok(());
+            };
+            self.encoder.write(writer, sample).map_err(io_error)?;
+            self.pending += 1;
+            if self.pending == self.spec.channels {
+                self.pending = 0;
+                self.frames += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finalizes the last file.
+    pub fn finish(mut self) -> Result<(), RecorderError> {
+        self.close()
+    }
+}
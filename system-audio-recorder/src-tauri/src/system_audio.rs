@@ -0,0 +1,87 @@
+//! Where system audio comes from on each platform: ScreenCaptureKit on
+//! macOS, WASAPI loopback of the default output on Windows, and the default
+//! sink's monitor source on Linux, which PulseAudio and PipeWire both offer.
+//! Only ScreenCaptureKit can single out one app.
+
+use recorder_core::{CaptureSource, RecorderError};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Format {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// A `CaptureSource` at the rate it was opened for, but whose samples may
+/// come with as many channels as the system has rather than stereo.
+pub trait SystemAudioSource: CaptureSource {
+    /// What `start` delivers, known before it runs so the file can be opened.
+    fn format(&self) -> Format;
+}
+
+/// Everything the system plays, or with `bundle_id` only that app, at
+/// `sample_rate`.
+#[cfg(target_os = "macos")]
+pub fn open(
+    bundle_id: Option<String>,
+    sample_rate: u32,
+) -> Result<Box<dyn SystemAudioSource>, RecorderError> {
+    Ok(Box::new(crate::screencapture::open(bundle_id, sample_rate)?))
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn open(
+    bundle_id: Option<String>,
+    sample_rate: u32,
+) -> Result<Box<dyn SystemAudioSource>, RecorderError> {
+    if bundle_id.is_some() {
+        return Err(RecorderError::InvalidArgument(
+            "Recording a single app needs macOS".to_string(),
+        ));
+    }
+    #[cfg(target_os = "windows")]
+    let source = recorder_core::LoopbackSource::native(sample_rate)?;
+    #[cfg(target_os = "linux")]
+    let source = recorder_core::MonitorSource::new("system-audio-recorder", sample_rate);
+    Ok(Box::new(source))
+}
+
+#[cfg(target_os = "windows")]
+impl SystemAudioSource for recorder_core::LoopbackSource {
+    fn format(&self) -> Format {
+        Format {
+            sample_rate: self.sample_rate(),
+            channels: self.channels(),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl SystemAudioSource for recorder_core::MonitorSource {
+    fn format(&self) -> Format {
+        Format {
+            sample_rate: self.sample_rate(),
+            channels: recorder_core::CHANNELS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturableApp {
+    pub bundle_id: String,
+    pub name: String,
+}
+
+/// Running apps whose audio can be recorded on its own, sorted by name.
+#[cfg(target_os = "macos")]
+#[tauri::command]
+pub fn list_capturable_apps() -> Result<Vec<CapturableApp>, RecorderError> {
+    crate::screencapture::capturable_apps()
+}
+
+/// None outside macOS, see `open`.
+#[cfg(not(target_os = "macos"))]
+#[tauri::command]
+pub fn list_capturable_apps() -> Result<Vec<CapturableApp>, RecorderError> {
+    Ok(Vec::new())
+}
@@ -1,20 +1,55 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import "./App.css";
 
+interface CapturableApp {
+  bundle_id: string;
+  name: string;
+}
+
+interface RecorderError {
+  kind: string;
+  code: number;
+  message: string;
+}
+
+function errorMessage(e: unknown) {
+  return (e as RecorderError)?.message ?? String(e);
+}
+
 function App() {
   const [isRecording, setIsRecording] = useState(false);
   const [filePath, setFilePath] = useState("");
   const [error, setError] = useState("");
+  const [apps, setApps] = useState<CapturableApp[]>([]);
+  const [bundleId, setBundleId] = useState("");
+  const [segmentMinutes, setSegmentMinutes] = useState(0);
+  const [segments, setSegments] = useState<string[]>([]);
+
+  async function refreshApps() {
+    try {
+      setApps(await invoke<CapturableApp[]>("list_capturable_apps"));
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  }
+
+  useEffect(() => {
+    refreshApps();
+  }, []);
 
   async function startRecording() {
     try {
       setError("");
-      const path = await invoke<string>("start_recording");
+      const path = await invoke<string>("start_recording", {
+        bundleId: bundleId || null,
+        segmentMinutes: segmentMinutes || null,
+      });
       setIsRecording(true);
       setFilePath(path);
+      setSegments([]);
     } catch (e) {
-      setError(String(e));
+      setError(errorMessage(e));
     }
   }
 
@@ -23,8 +58,9 @@ function App() {
       const path = await invoke<string>("stop_recording");
       setIsRecording(false);
       setFilePath(path);
+      setSegments(await invoke<string[]>("list_segments"));
     } catch (e) {
-      setError(String(e));
+      setError(errorMessage(e));
     }
   }
 
@@ -42,17 +78,53 @@ function App() {
             Start Recording
           </button>
         )}
+
+        <select
+          className="app-select"
+          value={bundleId}
+          disabled={isRecording}
+          onFocus={refreshApps}
+          onChange={(e) => setBundleId(e.currentTarget.value)}
+        >
+          <option value="">All system audio</option>
+          {apps.map((app) => (
+            <option key={app.bundle_id} value={app.bundle_id}>
+              {app.name}
+            </option>
+          ))}
+        </select>
+
+        <select
+          className="app-select"
+          value={segmentMinutes}
+          disabled={isRecording}
+          onChange={(e) => setSegmentMinutes(Number(e.currentTarget.value))}
+        >
+          <option value={0}>Single file</option>
+          <option value={5}>New file every 5 min</option>
+          <option value={15}>New file every 15 min</option>
+          <option value={60}>New file every hour</option>
+        </select>
       </div>
 
       {isRecording && <p className="recording-status">🔴 Recording...</p>}
 
-      {filePath && (
+      {filePath && segments.length <= 1 && (
         <div className="file-info">
           <p>File saved at:</p>
           <code>{filePath}</code>
         </div>
       )}
 
+      {segments.length > 1 && (
+        <div className="file-info">
+          <p>{segments.length} segments saved:</p>
+          {segments.map((segment) => (
+            <code key={segment}>{segment}</code>
+          ))}
+        </div>
+      )}
+
       {error && <p className="error">{error}</p>}
     </main>
   );